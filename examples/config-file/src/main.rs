@@ -89,7 +89,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Create and run the server
     let mut server = Server::new(Some("file_config"), &args.socket)?;
-    server.register_plugin(Plugin::config(config_plugin));
+    server.register_plugin(Plugin::config(config_plugin))?;
 
     if args.verbose {
         info!("Config plugin started");
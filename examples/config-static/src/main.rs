@@ -78,7 +78,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Create and run the server
     let mut server = Server::new(Some("static_config"), &args.socket)?;
-    server.register_plugin(Plugin::config(FileEventsConfigPlugin));
+    server.register_plugin(Plugin::config(FileEventsConfigPlugin))?;
 
     if args.verbose {
         info!("File events config plugin started");
@@ -173,7 +173,10 @@ fn main() {
         std::process::exit(1);
     });
 
-    server.register_plugin(Plugin::logger(logger));
+    if let Err(e) = server.register_plugin(Plugin::logger(logger)) {
+        eprintln!("Failed to register logger: {e}");
+        std::process::exit(1);
+    }
 
     if args.verbose {
         info!("Running server");
@@ -233,12 +236,7 @@ mod tests {
         let temp_file = NamedTempFile::new().expect("create temp file");
         let logger = FileLoggerPlugin::new(temp_file.path().to_path_buf()).expect("create logger");
 
-        let status = LogStatus {
-            severity: LogSeverity::Warning,
-            filename: "test.rs".to_string(),
-            line: 42,
-            message: "warning message".to_string(),
-        };
+        let status = LogStatus::new(LogSeverity::Warning, "test.rs", 42, "warning message");
 
         let result = logger.log_status(&status);
         assert!(result.is_ok());
@@ -255,12 +253,7 @@ mod tests {
         let temp_file = NamedTempFile::new().expect("create temp file");
         let logger = FileLoggerPlugin::new(temp_file.path().to_path_buf()).expect("create logger");
 
-        let status = LogStatus {
-            severity: LogSeverity::Info,
-            filename: "info.rs".to_string(),
-            line: 1,
-            message: "info message".to_string(),
-        };
+        let status = LogStatus::new(LogSeverity::Info, "info.rs", 1, "info message");
 
         logger.log_status(&status).expect("log status");
 
@@ -273,12 +266,7 @@ mod tests {
         let temp_file = NamedTempFile::new().expect("create temp file");
         let logger = FileLoggerPlugin::new(temp_file.path().to_path_buf()).expect("create logger");
 
-        let status = LogStatus {
-            severity: LogSeverity::Error,
-            filename: "error.rs".to_string(),
-            line: 99,
-            message: "error message".to_string(),
-        };
+        let status = LogStatus::new(LogSeverity::Error, "error.rs", 99, "error message");
 
         logger.log_status(&status).expect("log status");
 
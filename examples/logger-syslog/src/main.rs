@@ -2,7 +2,7 @@ mod cli;
 
 use clap::Parser;
 use log::info;
-use osquery_rust_ng::plugin::{LogSeverity, LogStatus, LoggerPlugin, Plugin};
+use osquery_rust_ng::plugin::{LogSeverity, LogStatus, LoggerPlugin, Plugin, SyslogFacility};
 use osquery_rust_ng::prelude::*;
 use std::sync::Mutex;
 use syslog::{Facility, Formatter3164, LoggerBackend};
@@ -38,29 +38,11 @@ impl SyslogLoggerPlugin {
     }
 
     fn parse_facility(s: &str) -> Result<Facility, String> {
-        match s.to_lowercase().as_str() {
-            "kern" => Ok(Facility::LOG_KERN),
-            "user" => Ok(Facility::LOG_USER),
-            "mail" => Ok(Facility::LOG_MAIL),
-            "daemon" => Ok(Facility::LOG_DAEMON),
-            "auth" => Ok(Facility::LOG_AUTH),
-            "syslog" => Ok(Facility::LOG_SYSLOG),
-            "lpr" => Ok(Facility::LOG_LPR),
-            "news" => Ok(Facility::LOG_NEWS),
-            "uucp" => Ok(Facility::LOG_UUCP),
-            "cron" => Ok(Facility::LOG_CRON),
-            "authpriv" => Ok(Facility::LOG_AUTHPRIV),
-            "ftp" => Ok(Facility::LOG_FTP),
-            "local0" => Ok(Facility::LOG_LOCAL0),
-            "local1" => Ok(Facility::LOG_LOCAL1),
-            "local2" => Ok(Facility::LOG_LOCAL2),
-            "local3" => Ok(Facility::LOG_LOCAL3),
-            "local4" => Ok(Facility::LOG_LOCAL4),
-            "local5" => Ok(Facility::LOG_LOCAL5),
-            "local6" => Ok(Facility::LOG_LOCAL6),
-            "local7" => Ok(Facility::LOG_LOCAL7),
-            _ => Err(format!("Unknown syslog facility: {s}")),
-        }
+        let facility: SyslogFacility = s.parse().map_err(|e| format!("{e}"))?;
+        facility
+            .as_str()
+            .parse()
+            .map_err(|()| format!("Unknown syslog facility: {s}"))
     }
 }
 
@@ -183,7 +165,10 @@ fn main() {
         std::process::exit(1);
     });
 
-    server.register_plugin(Plugin::logger(logger));
+    if let Err(e) = server.register_plugin(Plugin::logger(logger)) {
+        eprintln!("Failed to register logger: {e}");
+        std::process::exit(1);
+    }
 
     if args.verbose {
         info!("Running server");
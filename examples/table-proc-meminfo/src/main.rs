@@ -1,9 +1,11 @@
 mod cli;
 
-use clap::crate_name;
 use clap::Parser;
+use clap::crate_name;
 use log::info;
-use osquery_rust_ng::plugin::{ColumnDef, ColumnOptions, ColumnType, Plugin, ReadOnlyTable};
+use osquery_rust_ng::plugin::{
+    ColumnDef, ColumnOptions, ColumnType, Plugin, QueryConstraints, ReadOnlyTable,
+};
 use osquery_rust_ng::prelude::*;
 use std::collections::BTreeMap;
 use std::fs::File;
@@ -124,11 +126,22 @@ fn main() -> std::io::Result<()> {
 
         let mut manager = Server::new(Some(crate_name!()), socket.as_str())?;
 
-        manager.register_plugin(Plugin::readonly_table(ProcMemInfoTable {}));
+        manager
+            .register_plugin(Plugin::readonly_table(ProcMemInfoTable {}))
+            .map_err(Error::other)?;
 
         manager.run().map_err(Error::other)?;
     } else {
-        todo!("standalone mode has not been implemented");
+        let harness = StandaloneHarness::new([Plugin::readonly_table(ProcMemInfoTable {})])
+            .map_err(Error::other)?;
+
+        let response = harness
+            .query_table("proc_meminfo", &QueryConstraints::new())
+            .map_err(Error::other)?;
+
+        for row in response.response.unwrap_or_default() {
+            println!("{row:?}");
+        }
     }
 
     Ok(())
@@ -6,10 +6,24 @@ use crate::cli::Args;
 use crate::t1::Table1;
 use crate::t2::Table2;
 use clap::{Parser, crate_name};
-use osquery_rust_ng::Server;
 use osquery_rust_ng::plugin::Plugin;
+use osquery_rust_ng::plugin::QueryConstraints;
+use osquery_rust_ng::{Server, StandaloneHarness};
 use std::io::{Error, ErrorKind};
 
+/// Print every row of `table`, one per line.
+fn print_rows(harness: &StandaloneHarness<Plugin>, table: &str) -> std::io::Result<()> {
+    let response = harness
+        .query_table(table, &QueryConstraints::new())
+        .map_err(Error::other)?;
+
+    for row in response.response.unwrap_or_default() {
+        println!("{table}: {row:?}");
+    }
+
+    Ok(())
+}
+
 fn main() -> std::io::Result<()> {
     env_logger::init();
 
@@ -22,12 +36,24 @@ fn main() -> std::io::Result<()> {
 
         let mut manager = Server::new(Some(crate_name!()), socket.as_str())?;
 
-        manager.register_plugin(Plugin::readonly_table(Table1::new()));
-        manager.register_plugin(Plugin::table(Table2::new()));
-
-        manager.run().map_err(Error::other)?;
+        manager
+            .register_plugins([
+                Plugin::readonly_table(Table1::new()),
+                Plugin::table(Table2::new()),
+            ])
+            .map_err(Error::other)?
+            .run()
+            .map_err(Error::other)?;
     } else {
-        todo!("standalone mode has not been implemented");
+        let harness = StandaloneHarness::new([
+            Plugin::readonly_table(Table1::new()),
+            Plugin::table(Table2::new()),
+        ])
+        .map_err(Error::other)?;
+
+        for table in ["t1", "t2"] {
+            print_rows(&harness, table)?;
+        }
     }
 
     Ok(())
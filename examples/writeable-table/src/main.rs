@@ -3,10 +3,13 @@ mod cli;
 use crate::cli::Args;
 use clap::{Parser, crate_name};
 use log::info;
-use osquery_rust_ng::plugin::{ColumnDef, ColumnOptions, ColumnType, Plugin, Table};
+use osquery_rust_ng::plugin::{
+    ColumnDef, ColumnOptions, ColumnType, Plugin, QueryConstraints, Row, Table,
+};
 use osquery_rust_ng::plugin::{DeleteResult, InsertResult, UpdateResult};
-use osquery_rust_ng::{ExtensionPluginRequest, ExtensionResponse, ExtensionStatus, Server};
-use serde_json::Value;
+use osquery_rust_ng::{
+    ExtensionPluginRequest, ExtensionResponse, ExtensionStatus, Server, StandaloneHarness,
+};
 use std::collections::BTreeMap;
 use std::io::{Error, ErrorKind};
 
@@ -59,27 +62,15 @@ impl Table for WriteableTable {
         ExtensionResponse::new(ExtensionStatus::default(), resp)
     }
 
-    fn update(&mut self, rowid: u64, row: &Value) -> UpdateResult {
-        log::info!("updating item at {rowid} = {row:?}");
+    fn update_row(&mut self, rowid: u64, row: &Row) -> UpdateResult {
+        log::info!("updating item at {rowid} = {:?}", row.as_json());
 
-        let Some(row) = row.as_array() else {
-            return UpdateResult::Err("Could not parse row as array".to_string());
+        let (Some(name), Some(lastname)) = (row.get_text("name"), row.get_text("lastname")) else {
+            return UpdateResult::Err("name and lastname can't be NULL".to_string());
         };
 
-        let &[
-            Value::Number(rowid),
-            Value::String(name),
-            Value::String(lastname),
-        ] = &row.as_slice()
-        else {
-            return UpdateResult::Err("Could not parse row update".to_string());
-        };
-
-        let Some(rowid) = rowid.as_u64() else {
-            return UpdateResult::Err("Could not parse rowid as u64".to_string());
-        };
-
-        self.items.insert(rowid, (name.clone(), lastname.clone()));
+        self.items
+            .insert(rowid, (name.to_string(), lastname.to_string()));
 
         UpdateResult::Success
     }
@@ -93,41 +84,32 @@ impl Table for WriteableTable {
         }
     }
 
-    fn insert(&mut self, _auto_rowid: bool, row: &Value) -> InsertResult {
-        log::info!("inserting item: {row:?}");
+    fn insert_row(&mut self, auto_rowid: bool, row: &Row) -> InsertResult {
+        log::info!("inserting item: {:?}", row.as_json());
 
-        let Some(row) = row.as_array() else {
-            return InsertResult::Err("Could not parse row as array".to_string());
+        let (Some(name), Some(lastname)) = (row.get_text("name"), row.get_text("lastname")) else {
+            return InsertResult::Constraint;
         };
 
-        let rowid = match &row.as_slice() {
-            [Value::Null, Value::String(name), Value::String(lastname)] => {
-                // TODO: figure out what auto_rowid means here
-                let rowid = self.items.keys().next_back().unwrap_or(&0u64) + 1;
-                log::info!("rowid: {rowid}");
-
-                self.items.insert(rowid, (name.clone(), lastname.clone()));
-
-                rowid
-            }
-            [
-                Value::Number(rowid),
-                Value::String(name),
-                Value::String(lastname),
-            ] => {
-                let Some(rowid) = rowid.as_u64() else {
-                    return InsertResult::Err("Could not parse rowid as u64".to_string());
-                };
-
-                self.items.insert(rowid, (name.clone(), lastname.clone()));
-
-                rowid
-            }
-            _ => {
+        let rowid = if auto_rowid {
+            // No rowid in the INSERT: pick the next free one
+            let rowid = self.items.keys().next_back().map_or(0, |rowid| rowid + 1);
+            log::info!("rowid: {rowid}");
+            rowid
+        } else {
+            let Some(rowid) = row.rowid() else {
+                return InsertResult::Err("INSERT chose no rowid".to_string());
+            };
+            // The INSERT chose a rowid that is already in use
+            if self.items.contains_key(&rowid) {
                 return InsertResult::Constraint;
             }
+            rowid
         };
 
+        self.items
+            .insert(rowid, (name.to_string(), lastname.to_string()));
+
         InsertResult::Success(rowid)
     }
     fn shutdown(&self) {
@@ -147,11 +129,22 @@ fn main() -> std::io::Result<()> {
 
         let mut manager = Server::new(Some(crate_name!()), socket.as_str())?;
 
-        manager.register_plugin(Plugin::table(WriteableTable::new()));
+        manager
+            .register_plugin(Plugin::table(WriteableTable::new()))
+            .map_err(Error::other)?;
 
         manager.run().map_err(Error::other)?;
     } else {
-        todo!("standalone mode has not been implemented");
+        let harness =
+            StandaloneHarness::new([Plugin::table(WriteableTable::new())]).map_err(Error::other)?;
+
+        let response = harness
+            .query_table("writeable_table", &QueryConstraints::new())
+            .map_err(Error::other)?;
+
+        for row in response.response.unwrap_or_default() {
+            println!("{row:?}");
+        }
     }
 
     Ok(())
@@ -166,7 +159,44 @@ fn main() -> std::io::Result<()> {
 )]
 mod tests {
     use super::*;
-    use serde_json::json;
+    use osquery_rust_ng::plugin::Registry;
+    use serde_json::{Value, json};
+
+    fn harness() -> StandaloneHarness<Plugin> {
+        StandaloneHarness::new([Plugin::table(WriteableTable::new())])
+            .expect("harness should build")
+    }
+
+    /// Send a write the way osquery does: the rowid an INSERT chose goes in
+    /// "id", with "auto_rowid" false
+    fn write(
+        harness: &StandaloneHarness<Plugin>,
+        action: &str,
+        id: Option<u64>,
+        values: Value,
+    ) -> BTreeMap<String, String> {
+        let mut request = BTreeMap::from([
+            ("action".to_string(), action.to_string()),
+            ("auto_rowid".to_string(), id.is_none().to_string()),
+            ("json_value_array".to_string(), values.to_string()),
+        ]);
+        if let Some(id) = id {
+            request.insert("id".to_string(), id.to_string());
+        }
+
+        let response = harness
+            .call(Registry::Table, "writeable_table", request)
+            .expect("call should be dispatched");
+        response.response.unwrap_or_default().remove(0)
+    }
+
+    fn rows(harness: &StandaloneHarness<Plugin>) -> Vec<BTreeMap<String, String>> {
+        harness
+            .query_table("writeable_table", &QueryConstraints::new())
+            .expect("query should be dispatched")
+            .response
+            .unwrap_or_default()
+    }
 
     #[test]
     fn test_table_name() {
@@ -196,61 +226,60 @@ mod tests {
 
     #[test]
     fn test_insert_with_auto_rowid() {
-        let mut table = WriteableTable::new();
+        let harness = harness();
 
-        // Insert with null rowid (auto-assign)
-        let row = json!([null, "alice", "smith"]);
-        let result = table.insert(true, &row);
-
-        let InsertResult::Success(rowid) = result else {
-            panic!("Expected InsertResult::Success");
-        };
-        assert_eq!(rowid, 3); // Next after 0, 1, 2
+        // INSERT INTO writeable_table (name, lastname) VALUES ('alice', 'smith')
+        let result = write(&harness, "insert", None, json!([null, "alice", "smith"]));
 
-        // Verify the row was added
-        let response = table.generate(ExtensionPluginRequest::default());
-        let rows = response.response.expect("should have rows");
-        assert_eq!(rows.len(), 4);
+        assert_eq!(result.get("id"), Some(&"3".to_string())); // Next after 0, 1, 2
+        assert_eq!(rows(&harness).len(), 4);
     }
 
     #[test]
     fn test_insert_with_explicit_rowid() {
-        let mut table = WriteableTable::new();
+        let harness = harness();
 
-        // Insert with explicit rowid
-        let row = json!([100, "bob", "jones"]);
-        let result = table.insert(false, &row);
+        // The rowid comes from "id", whatever the rowid column holds
+        let result = write(&harness, "insert", Some(100), json!([null, "bob", "jones"]));
 
-        let InsertResult::Success(rowid) = result else {
-            panic!("Expected InsertResult::Success");
-        };
-        assert_eq!(rowid, 100);
+        assert_eq!(result.get("id"), Some(&"100".to_string()));
     }
 
     #[test]
-    fn test_insert_invalid_row_returns_constraint() {
-        let mut table = WriteableTable::new();
+    fn test_insert_with_taken_rowid_returns_constraint() {
+        let harness = harness();
 
-        // Invalid row format
-        let row = json!(["invalid"]);
-        let result = table.insert(false, &row);
+        // Rowid 0 already holds "foo"
+        let result = write(&harness, "insert", Some(0), json!([0, "bob", "jones"]));
 
-        assert!(matches!(result, InsertResult::Constraint));
+        assert_eq!(result.get("status"), Some(&"constraint".to_string()));
+    }
+
+    #[test]
+    fn test_insert_invalid_row_is_rejected() {
+        let harness = harness();
+
+        let result = write(&harness, "insert", None, json!(["invalid"]));
+
+        assert_eq!(result.get("status"), Some(&"failure".to_string()));
+        assert_eq!(rows(&harness).len(), 3);
     }
 
     #[test]
     fn test_update_existing_row() {
-        let mut table = WriteableTable::new();
+        let harness = harness();
 
         // Update row 0 (foo -> updated)
-        let row = json!([0, "updated_name", "updated_lastname"]);
-        let result = table.update(0, &row);
-
-        assert!(matches!(result, UpdateResult::Success));
+        let result = write(
+            &harness,
+            "update",
+            Some(0),
+            json!([0, "updated_name", "updated_lastname"]),
+        );
+        assert_eq!(result.get("status"), Some(&"success".to_string()));
 
         // Verify the update
-        let response = table.generate(ExtensionPluginRequest::default());
-        let rows = response.response.expect("should have rows");
+        let rows = rows(&harness);
         let row0 = rows
             .iter()
             .find(|r| r.get("rowid") == Some(&"0".to_string()));
@@ -259,13 +288,12 @@ mod tests {
 
     #[test]
     fn test_update_invalid_row_returns_error() {
-        let mut table = WriteableTable::new();
+        let harness = harness();
 
         // Invalid row (not an array)
-        let row = json!({"name": "test"});
-        let result = table.update(0, &row);
+        let result = write(&harness, "update", Some(0), json!({"name": "test"}));
 
-        assert!(matches!(result, UpdateResult::Err(_)));
+        assert_eq!(result.get("status"), Some(&"failure".to_string()));
     }
 
     #[test]
@@ -294,32 +322,43 @@ mod tests {
 
     #[test]
     fn test_full_crud_workflow() {
-        let mut table = WriteableTable::new();
+        let harness = harness();
 
         // Create
-        let row = json!([null, "new_user", "new_lastname"]);
-        let InsertResult::Success(new_rowid) = table.insert(true, &row) else {
-            panic!("Insert failed");
-        };
+        let result = write(
+            &harness,
+            "insert",
+            None,
+            json!([null, "new_user", "new_lastname"]),
+        );
+        let new_rowid: u64 = result.get("id").unwrap().parse().unwrap();
 
         // Read (verify exists)
-        let response = table.generate(ExtensionPluginRequest::default());
-        let rows = response.response.expect("should have rows");
-        assert_eq!(rows.len(), 4);
+        assert_eq!(rows(&harness).len(), 4);
 
         // Update
-        let updated = json!([new_rowid, "modified", "user"]);
-        assert!(matches!(
-            table.update(new_rowid, &updated),
-            UpdateResult::Success
-        ));
+        let result = write(
+            &harness,
+            "update",
+            Some(new_rowid),
+            json!([new_rowid, "modified", "user"]),
+        );
+        assert_eq!(result.get("status"), Some(&"success".to_string()));
 
         // Delete
-        assert!(matches!(table.delete(new_rowid), DeleteResult::Success));
+        let result = harness
+            .call(
+                Registry::Table,
+                "writeable_table",
+                BTreeMap::from([
+                    ("action".to_string(), "delete".to_string()),
+                    ("id".to_string(), new_rowid.to_string()),
+                ]),
+            )
+            .unwrap();
+        assert_eq!(result.status.and_then(|s| s.code), Some(0));
 
         // Verify final state
-        let response = table.generate(ExtensionPluginRequest::default());
-        let rows = response.response.expect("should have rows");
-        assert_eq!(rows.len(), 3); // Back to original count
+        assert_eq!(rows(&harness).len(), 3); // Back to original count
     }
 }
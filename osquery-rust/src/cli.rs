@@ -0,0 +1,103 @@
+//! Command line flags osquery passes to every extension it starts.
+//!
+//! osquery launches autoloaded extensions with `--socket`, `--timeout` and
+//! `--interval` (plus `--verbose` when osqueryd is verbose). Flatten
+//! [`OsqueryArgs`] into your own `clap` parser to accept them next to the
+//! extension's own flags:
+//!
+//! ```
+//! use clap::Parser;
+//! use osquery_rust_ng::cli::OsqueryArgs;
+//!
+//! #[derive(Parser)]
+//! struct Args {
+//!     #[command(flatten)]
+//!     osquery: OsqueryArgs,
+//!
+//!     /// Where to write logs.
+//!     #[arg(long)]
+//!     log_file: Option<String>,
+//! }
+//!
+//! let args = Args::parse_from(["ext", "--socket", "/var/osquery/osquery.em", "--log-file", "x.log"]);
+//! assert_eq!(args.osquery.socket.as_deref(), Some("/var/osquery/osquery.em"));
+//! ```
+//!
+//! Declaring one of these flags again in the outer parser is reported by
+//! clap's debug assertions (`Command::debug_assert()`, run automatically in
+//! debug builds and tests).
+
+/// Flags osquery passes when it starts an extension.
+#[derive(clap::Args, Debug, Clone)]
+pub struct OsqueryArgs {
+    /// Path to the osquery extension socket.
+    #[arg(long, value_name = "PATH_TO_SOCKET")]
+    pub socket: Option<String>,
+
+    /// Delay in seconds between connectivity checks.
+    #[arg(long, default_value_t = 30)]
+    pub interval: u32,
+
+    /// Time in seconds to wait for autoloaded extensions until connection times out.
+    #[arg(long, default_value_t = 30)]
+    pub timeout: u32,
+
+    /// Enable verbose informational messages.
+    #[arg(long)]
+    pub verbose: bool,
+}
+
+#[cfg(test)]
+#[allow(clippy::panic)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, Parser};
+
+    #[derive(Parser, Debug)]
+    struct MergedArgs {
+        #[command(flatten)]
+        osquery: OsqueryArgs,
+
+        #[arg(long)]
+        log_file: Option<String>,
+    }
+
+    #[derive(Parser, Debug)]
+    struct ConflictingArgs {
+        #[command(flatten)]
+        osquery: OsqueryArgs,
+
+        #[arg(long)]
+        socket: Option<String>,
+    }
+
+    #[test]
+    fn test_user_flags_merge_with_osquery_flags() {
+        MergedArgs::command().debug_assert();
+
+        let args = MergedArgs::try_parse_from([
+            "ext",
+            "--socket",
+            "/tmp/osquery.em",
+            "--interval",
+            "5",
+            "--log-file",
+            "/tmp/ext.log",
+        ]);
+        let Ok(args) = args else {
+            panic!("arguments should parse: {args:?}");
+        };
+
+        assert_eq!(args.osquery.socket.as_deref(), Some("/tmp/osquery.em"));
+        assert_eq!(args.osquery.interval, 5);
+        assert_eq!(args.osquery.timeout, 30);
+        assert!(!args.osquery.verbose);
+        assert_eq!(args.log_file.as_deref(), Some("/tmp/ext.log"));
+    }
+
+    #[test]
+    #[should_panic(expected = "socket")]
+    fn test_conflicting_osquery_flag_is_reported() {
+        ConflictingArgs::command().debug_assert();
+    }
+}
@@ -1,8 +1,12 @@
 use crate::_osquery as osquery;
+use crate::log_target;
+use crate::protocol::{BoundedInputProtocol, Protocol, DEFAULT_MAX_FRAME_SIZE};
+use crate::transport::{self, BufferSizes};
 use std::io::Error;
-use std::os::unix::net::UnixStream;
 use std::time::Duration;
-use thrift::protocol::{TBinaryInputProtocol, TBinaryOutputProtocol};
+use thrift::protocol::{
+    TBinaryOutputProtocol, TCompactOutputProtocol, TInputProtocol, TOutputProtocol,
+};
 
 /// Trait for osquery daemon communication - enables mocking in tests.
 ///
@@ -32,30 +36,174 @@ pub trait OsqueryClient: Send {
 
     /// Get column information for a SQL query without executing it.
     fn get_query_columns(&mut self, sql: String) -> thrift::Result<crate::ExtensionResponse>;
+
+    /// Call a plugin through osquery, which routes the request to whichever
+    /// process (osquery itself or an extension) owns `item` in `registry`.
+    ///
+    /// The default implementation reports that calls aren't supported.
+    fn call(
+        &mut self,
+        registry: String,
+        item: String,
+        _request: osquery::ExtensionPluginRequest,
+    ) -> thrift::Result<crate::ExtensionResponse> {
+        Err(thrift::Error::Application(thrift::ApplicationError::new(
+            thrift::ApplicationErrorKind::UnknownMethod,
+            format!("This client can't call {registry} plugin {item}"),
+        )))
+    }
+
+    /// Open a new connection to osquery after the old one was lost, e.g.
+    /// before [`Server::run_with_restart`](crate::Server::run_with_restart)
+    /// registers again.
+    ///
+    /// The default does nothing, for clients without a connection to renew.
+    fn reconnect(&mut self) -> thrift::Result<()> {
+        Ok(())
+    }
+}
+
+type ManagerClient = osquery::ExtensionManagerSyncClient<
+    Box<dyn TInputProtocol + Send>,
+    Box<dyn TOutputProtocol + Send>,
+>;
+
+/// How a [`ThriftClient`] talks to osquery, for
+/// [`ThriftClient::with_options`].
+///
+/// The defaults match osquery's: the binary protocol, 4KB buffers and
+/// responses of up to 100MB.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClientOptions {
+    max_frame_size: usize,
+    protocol: Protocol,
+    buffer_sizes: BufferSizes,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        ClientOptions {
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            protocol: Protocol::Binary,
+            buffer_sizes: BufferSizes::default(),
+        }
+    }
+}
+
+impl ClientOptions {
+    /// Reject any response that announces more than `max_frame_size` bytes
+    /// of strings and container elements with a `SizeLimit` protocol error.
+    pub fn set_max_frame_size(&mut self, max_frame_size: usize) -> &mut Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Speak `protocol` instead of the binary protocol; it must match the
+    /// osquery build, see [`Protocol`].
+    pub fn set_protocol(&mut self, protocol: Protocol) -> &mut Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Buffer reads and writes on the socket in buffers of `buffer_sizes`,
+    /// see [`BufferSizes`].
+    pub fn set_buffer_sizes(&mut self, buffer_sizes: BufferSizes) -> &mut Self {
+        self.buffer_sizes = buffer_sizes;
+        self
+    }
 }
 
-/// Production implementation of [`OsqueryClient`] using Thrift over Unix sockets.
+/// Production implementation of [`OsqueryClient`] using Thrift over Unix sockets
+/// (or named pipes on Windows).
 pub struct ThriftClient {
-    client: osquery::ExtensionManagerSyncClient<
-        TBinaryInputProtocol<UnixStream>,
-        TBinaryOutputProtocol<UnixStream>,
-    >,
+    client: ManagerClient,
+    socket_path: String,
+    timeout: Duration,
+    options: ClientOptions,
 }
 
 impl ThriftClient {
-    pub fn new(socket_path: &str, _timeout: Duration) -> Result<Self, Error> {
-        // todo: error handling, socket could be unable to connect to
-        // todo: use timeout
-        let socket_tx = UnixStream::connect(socket_path)?;
-        let socket_rx = socket_tx.try_clone()?;
-
-        let in_proto = TBinaryInputProtocol::new(socket_tx, true);
-        let out_proto = TBinaryOutputProtocol::new(socket_rx, true);
+    /// Connect to osquery with the default [`ClientOptions`].
+    ///
+    /// Reads and writes on the connection fail with a transport error after
+    /// `timeout`; a zero timeout (e.g. `Default::default()`) waits forever.
+    /// Named pipes on Windows ignore it.
+    pub fn new(socket_path: &str, timeout: Duration) -> Result<Self, Error> {
+        Self::with_options(socket_path, timeout, &ClientOptions::default())
+    }
 
+    /// Connect to osquery like `new()`, as configured by `options`, e.g.
+    ///
+    /// ```no_run
+    /// # use osquery_rust_ng::{ClientOptions, Protocol, ThriftClient};
+    /// # use std::time::Duration;
+    /// let client = ThriftClient::with_options(
+    ///     "/var/osquery/osquery.em",
+    ///     Duration::from_secs(1),
+    ///     ClientOptions::default().set_protocol(Protocol::Compact),
+    /// );
+    /// ```
+    pub fn with_options(
+        socket_path: &str,
+        timeout: Duration,
+        options: &ClientOptions,
+    ) -> Result<Self, Error> {
         Ok(ThriftClient {
-            client: osquery::ExtensionManagerSyncClient::new(in_proto, out_proto),
+            client: Self::connect(socket_path, timeout, options)?,
+            socket_path: socket_path.to_string(),
+            timeout,
+            options: *options,
         })
     }
+
+    /// Drop the current connection and connect again to the same socket.
+    ///
+    /// Use this to recover after osquery restarts or the connection drops,
+    /// instead of building a new client.
+    pub fn reconnect(&mut self) -> thrift::Result<()> {
+        self.client = Self::connect(&self.socket_path, self.timeout, &self.options)?;
+        Ok(())
+    }
+
+    fn connect(
+        socket_path: &str,
+        timeout: Duration,
+        options: &ClientOptions,
+    ) -> Result<ManagerClient, Error> {
+        let (socket_tx, socket_rx) = transport::connect(socket_path, timeout).map_err(|e| {
+            // Keep the kind so callers can still tell NotFound from ConnectionRefused
+            Error::new(e.kind(), format!("{socket_path}: {} ({e})", e.kind()))
+        })?;
+        let (read, write) = transport::buffered(socket_tx, socket_rx, options.buffer_sizes);
+
+        let in_proto: Box<dyn TInputProtocol + Send> = Box::new(BoundedInputProtocol::new(
+            read,
+            options.protocol,
+            options.max_frame_size,
+        ));
+        let out_proto: Box<dyn TOutputProtocol + Send> = match options.protocol {
+            Protocol::Binary => Box::new(TBinaryOutputProtocol::new(write, true)),
+            Protocol::Compact => Box::new(TCompactOutputProtocol::new(write)),
+        };
+
+        Ok(osquery::ExtensionManagerSyncClient::new(
+            in_proto, out_proto,
+        ))
+    }
+}
+
+/// Prefix transport errors with the socket they happened on.
+///
+/// Thrift turns read and write failures into transport errors carrying only
+/// the I/O error's text, which doesn't say which osquery instance went away.
+fn with_socket_path<T>(socket_path: &str, result: thrift::Result<T>) -> thrift::Result<T> {
+    result.map_err(|e| match e {
+        thrift::Error::Transport(mut e) => {
+            e.message = format!("{socket_path}: {}", e.message);
+            thrift::Error::Transport(e)
+        }
+        other => other,
+    })
 }
 
 //
@@ -104,11 +252,11 @@ impl osquery::TExtensionSyncClient for ThriftClient {
 
     fn call(
         &mut self,
-        _registry: String,
-        _item: String,
-        _request: osquery::ExtensionPluginRequest,
+        registry: String,
+        item: String,
+        request: osquery::ExtensionPluginRequest,
     ) -> thrift::Result<osquery::ExtensionResponse> {
-        todo!()
+        self.client.call(registry, item, request)
     }
 
     fn shutdown(&mut self) -> thrift::Result<()> {
@@ -120,31 +268,229 @@ impl osquery::TExtensionSyncClient for ThriftClient {
 // ThriftClient implements our custom OsqueryClient trait
 //
 impl OsqueryClient for ThriftClient {
+    fn reconnect(&mut self) -> thrift::Result<()> {
+        ThriftClient::reconnect(self)
+    }
+
+    fn register_extension(
+        &mut self,
+        info: osquery::InternalExtensionInfo,
+        registry: osquery::ExtensionRegistry,
+    ) -> thrift::Result<osquery::ExtensionStatus> {
+        with_socket_path(
+            &self.socket_path,
+            osquery::TExtensionManagerSyncClient::register_extension(
+                &mut self.client,
+                info,
+                registry,
+            ),
+        )
+    }
+
+    fn deregister_extension(
+        &mut self,
+        uuid: osquery::ExtensionRouteUUID,
+    ) -> thrift::Result<osquery::ExtensionStatus> {
+        with_socket_path(
+            &self.socket_path,
+            osquery::TExtensionManagerSyncClient::deregister_extension(&mut self.client, uuid),
+        )
+    }
+
+    fn ping(&mut self) -> thrift::Result<osquery::ExtensionStatus> {
+        with_socket_path(
+            &self.socket_path,
+            osquery::TExtensionSyncClient::ping(&mut self.client),
+        )
+    }
+
+    fn query(&mut self, sql: String) -> thrift::Result<crate::ExtensionResponse> {
+        with_socket_path(
+            &self.socket_path,
+            osquery::TExtensionManagerSyncClient::query(&mut self.client, sql),
+        )
+    }
+
+    fn get_query_columns(&mut self, sql: String) -> thrift::Result<crate::ExtensionResponse> {
+        with_socket_path(
+            &self.socket_path,
+            osquery::TExtensionManagerSyncClient::get_query_columns(&mut self.client, sql),
+        )
+    }
+
+    fn call(
+        &mut self,
+        registry: String,
+        item: String,
+        request: osquery::ExtensionPluginRequest,
+    ) -> thrift::Result<crate::ExtensionResponse> {
+        with_socket_path(
+            &self.socket_path,
+            osquery::TExtensionSyncClient::call(&mut self.client, registry, item, request),
+        )
+    }
+}
+
+/// An [`OsqueryClient`] that reconnects and retries when the connection to
+/// osquery drops.
+///
+/// Any Thrift transport error (broken pipe, connection reset, unexpected EOF)
+/// is treated as transient: the client reconnects to the same socket and
+/// repeats the call, waiting `backoff` before the first retry and doubling the
+/// wait after each failed attempt. Protocol and application errors are
+/// returned as-is. Calls may be sent twice if the connection drops after
+/// osquery received them.
+pub struct ReconnectingClient {
+    client: ThriftClient,
+    max_attempts: u32,
+    backoff: Duration,
+}
+
+impl ReconnectingClient {
+    /// Default number of attempts per call, including the first one.
+    pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+    /// Default wait before the first retry.
+    pub const DEFAULT_BACKOFF: Duration = Duration::from_millis(100);
+
+    /// Wrap `client`, making up to three attempts per call.
+    pub fn new(client: ThriftClient) -> Self {
+        Self::with_retry(client, Self::DEFAULT_MAX_ATTEMPTS, Self::DEFAULT_BACKOFF)
+    }
+
+    /// Wrap `client`, making up to `max_attempts` attempts per call (at least
+    /// one) and waiting `backoff` before the first retry.
+    pub fn with_retry(client: ThriftClient, max_attempts: u32, backoff: Duration) -> Self {
+        ReconnectingClient {
+            client,
+            max_attempts: max_attempts.max(1),
+            backoff,
+        }
+    }
+
+    fn with_reconnect<T>(
+        &mut self,
+        mut call: impl FnMut(&mut ThriftClient) -> thrift::Result<T>,
+    ) -> thrift::Result<T> {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+
+        loop {
+            let err = match call(&mut self.client) {
+                Err(err @ thrift::Error::Transport(_)) => err,
+                result => return result,
+            };
+            if attempt >= self.max_attempts {
+                return Err(err);
+            }
+
+            log::debug!(
+                target: log_target::CLIENT,
+                "osquery connection lost ({err}), reconnecting (attempt {attempt})"
+            );
+            // A failed reconnect is retried on the next attempt like any other
+            // transport error
+            loop {
+                attempt += 1;
+                std::thread::sleep(backoff);
+                backoff = backoff.saturating_mul(2);
+
+                match self.client.reconnect() {
+                    Ok(()) => break,
+                    Err(e) if attempt >= self.max_attempts => return Err(e),
+                    Err(_) => {}
+                }
+            }
+        }
+    }
+}
+
+impl OsqueryClient for ReconnectingClient {
+    fn reconnect(&mut self) -> thrift::Result<()> {
+        self.client.reconnect()
+    }
+
+    fn register_extension(
+        &mut self,
+        info: osquery::InternalExtensionInfo,
+        registry: osquery::ExtensionRegistry,
+    ) -> thrift::Result<osquery::ExtensionStatus> {
+        self.with_reconnect(|c| {
+            OsqueryClient::register_extension(c, info.clone(), registry.clone())
+        })
+    }
+
+    fn deregister_extension(
+        &mut self,
+        uuid: osquery::ExtensionRouteUUID,
+    ) -> thrift::Result<osquery::ExtensionStatus> {
+        self.with_reconnect(|c| OsqueryClient::deregister_extension(c, uuid))
+    }
+
+    fn ping(&mut self) -> thrift::Result<osquery::ExtensionStatus> {
+        self.with_reconnect(OsqueryClient::ping)
+    }
+
+    fn query(&mut self, sql: String) -> thrift::Result<crate::ExtensionResponse> {
+        self.with_reconnect(|c| OsqueryClient::query(c, sql.clone()))
+    }
+
+    fn get_query_columns(&mut self, sql: String) -> thrift::Result<crate::ExtensionResponse> {
+        self.with_reconnect(|c| OsqueryClient::get_query_columns(c, sql.clone()))
+    }
+
+    fn call(
+        &mut self,
+        registry: String,
+        item: String,
+        request: osquery::ExtensionPluginRequest,
+    ) -> thrift::Result<crate::ExtensionResponse> {
+        self.with_reconnect(|c| {
+            OsqueryClient::call(c, registry.clone(), item.clone(), request.clone())
+        })
+    }
+}
+
+/// Lets `Server` take a `Box<dyn OsqueryClient>`, see
+/// [`Server::with_boxed_client`](crate::Server::with_boxed_client).
+impl<C: OsqueryClient + ?Sized> OsqueryClient for Box<C> {
+    fn reconnect(&mut self) -> thrift::Result<()> {
+        (**self).reconnect()
+    }
+
     fn register_extension(
         &mut self,
         info: osquery::InternalExtensionInfo,
         registry: osquery::ExtensionRegistry,
     ) -> thrift::Result<osquery::ExtensionStatus> {
-        osquery::TExtensionManagerSyncClient::register_extension(&mut self.client, info, registry)
+        (**self).register_extension(info, registry)
     }
 
     fn deregister_extension(
         &mut self,
         uuid: osquery::ExtensionRouteUUID,
     ) -> thrift::Result<osquery::ExtensionStatus> {
-        osquery::TExtensionManagerSyncClient::deregister_extension(&mut self.client, uuid)
+        (**self).deregister_extension(uuid)
     }
 
     fn ping(&mut self) -> thrift::Result<osquery::ExtensionStatus> {
-        osquery::TExtensionSyncClient::ping(&mut self.client)
+        (**self).ping()
     }
 
     fn query(&mut self, sql: String) -> thrift::Result<crate::ExtensionResponse> {
-        osquery::TExtensionManagerSyncClient::query(&mut self.client, sql)
+        (**self).query(sql)
     }
 
     fn get_query_columns(&mut self, sql: String) -> thrift::Result<crate::ExtensionResponse> {
-        osquery::TExtensionManagerSyncClient::get_query_columns(&mut self.client, sql)
+        (**self).get_query_columns(sql)
+    }
+
+    fn call(
+        &mut self,
+        registry: String,
+        item: String,
+        request: osquery::ExtensionPluginRequest,
+    ) -> thrift::Result<crate::ExtensionResponse> {
+        (**self).call(registry, item, request)
     }
 }
 
@@ -152,3 +498,246 @@ impl OsqueryClient for ThriftClient {
 ///
 /// Existing code using `Client` will continue to work unchanged.
 pub type Client = ThriftClient;
+
+#[cfg(all(test, unix))]
+#[allow(clippy::expect_used, clippy::panic)] // Tests are allowed to panic on setup failures
+mod tests {
+    use super::*;
+    use crate::ServerError;
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixListener;
+
+    #[test]
+    fn test_reconnect_after_listener_restart() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("osquery.em");
+        let socket_path = path.to_string_lossy().to_string();
+
+        let listener = UnixListener::bind(&path).expect("failed to bind test socket");
+        let mut client =
+            ThriftClient::new(&socket_path, Duration::from_secs(1)).expect("initial connect");
+
+        // osquery goes away: nothing is listening any more
+        drop(listener);
+        std::fs::remove_file(&path).expect("failed to remove socket");
+        assert!(client.reconnect().is_err());
+
+        // ...and comes back on the same path
+        let _listener = UnixListener::bind(&path).expect("failed to rebind test socket");
+        assert!(client.reconnect().is_ok());
+    }
+
+    #[test]
+    fn test_connect_error_names_socket_path() {
+        let Err(e) = ThriftClient::new("/no/such.sock", Duration::from_secs(1)) else {
+            panic!("connecting to a missing socket should fail");
+        };
+
+        assert_eq!(e.kind(), std::io::ErrorKind::NotFound);
+        assert!(e.to_string().contains("/no/such.sock"), "{e}");
+        assert!(ServerError::from(e).to_string().contains("/no/such.sock"));
+    }
+
+    #[test]
+    fn test_transport_error_names_socket_path() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("osquery.em");
+        let socket_path = path.to_string_lossy().to_string();
+        let listener = UnixListener::bind(&path).expect("failed to bind test socket");
+
+        let mut client =
+            ThriftClient::new(&socket_path, Duration::from_secs(1)).expect("failed to connect");
+        // osquery hangs up without answering
+        drop(listener.accept().expect("failed to accept"));
+
+        let Err(e) = OsqueryClient::ping(&mut client) else {
+            panic!("ping should fail once the connection is closed");
+        };
+        let message = ServerError::from(e).to_string();
+        assert!(message.contains(&socket_path), "{message}");
+    }
+
+    #[test]
+    fn test_reads_time_out() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("osquery.em");
+        let listener = UnixListener::bind(&path).expect("failed to bind test socket");
+
+        let mut client = ThriftClient::new(&path.to_string_lossy(), Duration::from_millis(50))
+            .expect("failed to connect");
+        // osquery accepts the connection but never answers
+        let _stream = listener.accept().expect("failed to accept");
+
+        let start = std::time::Instant::now();
+        let result = OsqueryClient::ping(&mut client);
+        assert!(
+            matches!(result, Err(thrift::Error::Transport(_))),
+            "{result:?}"
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_client_keeps_buffer_sizes_across_reconnect() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("osquery.em");
+        let _listener = UnixListener::bind(&path).expect("failed to bind test socket");
+
+        let sizes = BufferSizes {
+            read: 12_345,
+            write: 54_321,
+        };
+        let mut client = ThriftClient::with_options(
+            &path.to_string_lossy(),
+            Duration::from_secs(1),
+            ClientOptions::default().set_buffer_sizes(sizes),
+        )
+        .expect("failed to connect");
+        assert_eq!(client.options.buffer_sizes, sizes);
+
+        client.reconnect().expect("failed to reconnect");
+        assert_eq!(client.options.buffer_sizes, sizes);
+    }
+
+    #[test]
+    fn test_oversized_response_is_rejected() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("osquery.em");
+        let listener = UnixListener::bind(&path).expect("failed to bind test socket");
+
+        let peer = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("failed to accept");
+            let mut request = [0u8; 64];
+            let _ = stream.read(&mut request);
+
+            // Reply header for "ping", sequence 1
+            let mut reply = vec![0x80, 0x01, 0x00, 0x02, 0, 0, 0, 4];
+            reply.extend_from_slice(b"ping");
+            reply.extend_from_slice(&[0, 0, 0, 1]);
+            // success: ExtensionStatus (struct, field 0) whose message (string,
+            // field 2) claims to be 0xFFFFFFFF bytes long
+            reply.extend_from_slice(&[12, 0, 0, 11, 0, 2, 0xff, 0xff, 0xff, 0xff]);
+            stream.write_all(&reply).expect("failed to write reply");
+        });
+
+        let mut client = ThriftClient::new(&path.to_string_lossy(), Duration::from_secs(1))
+            .expect("failed to connect");
+        let result = OsqueryClient::ping(&mut client);
+        peer.join().expect("peer thread panicked");
+
+        let Err(thrift::Error::Protocol(e)) = result else {
+            panic!("expected a protocol error, got {result:?}");
+        };
+        assert_eq!(e.kind, thrift::ProtocolErrorKind::SizeLimit);
+        assert!(e.message.contains("response frame exceeds max size"));
+    }
+
+    #[test]
+    fn test_oversized_compact_response_is_rejected() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("osquery.em");
+        let listener = UnixListener::bind(&path).expect("failed to bind test socket");
+
+        let peer = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("failed to accept");
+            let mut request = [0u8; 64];
+            let _ = stream.read(&mut request);
+
+            // Compact reply header for "ping", sequence 1
+            let mut reply = vec![0x82, 0x41, 0x01, 4];
+            reply.extend_from_slice(b"ping");
+            // success: ExtensionStatus (struct, field 0) whose message (binary,
+            // field 2) claims to be 0xFFFFFFFF bytes long
+            reply.extend_from_slice(&[0x0c, 0x00, 0x28, 0xff, 0xff, 0xff, 0xff, 0x0f]);
+            stream.write_all(&reply).expect("failed to write reply");
+        });
+
+        let mut client = ThriftClient::with_options(
+            &path.to_string_lossy(),
+            Duration::from_secs(1),
+            ClientOptions::default().set_protocol(Protocol::Compact),
+        )
+        .expect("failed to connect");
+        let result = OsqueryClient::ping(&mut client);
+        peer.join().expect("peer thread panicked");
+
+        let Err(thrift::Error::Protocol(e)) = result else {
+            panic!("expected a protocol error, got {result:?}");
+        };
+        assert_eq!(e.kind, thrift::ProtocolErrorKind::SizeLimit);
+    }
+
+    /// Encode a successful `query` reply returning the single row `{"n": "1"}`.
+    fn query_reply() -> Vec<u8> {
+        let mut reply = vec![0x80, 0x01, 0x00, 0x02, 0, 0, 0, 5];
+        reply.extend_from_slice(b"query");
+        reply.extend_from_slice(&[0, 0, 0, 1]);
+        // success: ExtensionResponse (struct, field 0)
+        reply.extend_from_slice(&[12, 0, 0]);
+        // status: ExtensionStatus { code: 0 }
+        reply.extend_from_slice(&[12, 0, 1, 8, 0, 1, 0, 0, 0, 0, 0]);
+        // response: list<map<string, string>> with one single-entry map
+        reply.extend_from_slice(&[15, 0, 2, 13, 0, 0, 0, 1, 11, 11, 0, 0, 0, 1]);
+        reply.extend_from_slice(&[0, 0, 0, 1, b'n', 0, 0, 0, 1, b'1']);
+        // end of ExtensionResponse, end of result
+        reply.extend_from_slice(&[0, 0]);
+        reply
+    }
+
+    #[test]
+    fn test_reconnecting_client_retries_dropped_connection() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("osquery.em");
+        let listener = UnixListener::bind(&path).expect("failed to bind test socket");
+
+        let peer = std::thread::spawn(move || {
+            // Drop the first connection without answering
+            let (stream, _) = listener.accept().expect("failed to accept");
+            drop(stream);
+
+            let (mut stream, _) = listener.accept().expect("failed to accept reconnect");
+            // The request is written piecewise, so read all of it before
+            // replying: header (17 bytes), sql field (7 + 13 bytes), stop (1)
+            let mut request = [0u8; 38];
+            stream
+                .read_exact(&mut request)
+                .expect("failed to read request");
+            stream
+                .write_all(&query_reply())
+                .expect("failed to write reply");
+        });
+
+        let client = ThriftClient::new(&path.to_string_lossy(), Duration::from_secs(1))
+            .expect("failed to connect");
+        let mut client = ReconnectingClient::with_retry(client, 3, Duration::from_millis(1));
+        let result = OsqueryClient::query(&mut client, "SELECT 1 AS n".to_string());
+        peer.join().expect("peer thread panicked");
+
+        let response = result.expect("query should succeed after reconnecting");
+        assert_eq!(response.status.and_then(|s| s.code), Some(0));
+        let rows = response.response.expect("response rows");
+        assert_eq!(
+            rows.first()
+                .and_then(|row| row.get("n"))
+                .map(String::as_str),
+            Some("1")
+        );
+    }
+
+    #[test]
+    fn test_reconnecting_client_gives_up_after_max_attempts() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("osquery.em");
+        let listener = UnixListener::bind(&path).expect("failed to bind test socket");
+
+        let client = ThriftClient::new(&path.to_string_lossy(), Duration::from_secs(1))
+            .expect("failed to connect");
+        // osquery goes away for good
+        drop(listener);
+        std::fs::remove_file(&path).expect("failed to remove socket");
+
+        let mut client = ReconnectingClient::with_retry(client, 2, Duration::from_millis(1));
+        let result = OsqueryClient::ping(&mut client);
+        assert!(matches!(result, Err(thrift::Error::Transport(_))));
+    }
+}
@@ -0,0 +1,83 @@
+//! The time source the server reads "now" from.
+
+use std::time::SystemTime;
+#[cfg(any(test, feature = "test-util"))]
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Where the server gets the current time for its uptime, idle tracking and
+/// the last-call time reported by the internal table.
+///
+/// The default is [`SystemClock`]; set another with
+/// [`Server::set_clock`](crate::Server::set_clock), e.g. a `MockClock` (with
+/// the `test-util` feature) to make time-dependent tests deterministic.
+pub trait Clock: Send + Sync {
+    /// The current time.
+    fn now(&self) -> SystemTime;
+}
+
+/// A [`Clock`] reading the system's wall clock; the server's default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A [`Clock`] that only moves when told to. Clones share the same time.
+/// Available with the `test-util` feature.
+///
+/// ```
+/// # #[cfg(feature = "test-util")]
+/// # {
+/// use osquery_rust_ng::{Clock, MockClock};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+/// clock.advance(Duration::from_secs(90));
+/// assert_eq!(clock.now(), UNIX_EPOCH + Duration::from_secs(1_700_000_090));
+/// # }
+/// ```
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    now: Arc<Mutex<SystemTime>>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl MockClock {
+    /// A clock stopped at `start`.
+    pub fn new(start: SystemTime) -> Self {
+        MockClock {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    /// Move the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        if let Ok(mut now) = self.now.lock() {
+            *now += duration;
+        }
+    }
+
+    /// Set the clock to `time`, which may be in its past.
+    pub fn set(&self, time: SystemTime) {
+        if let Ok(mut now) = self.now.lock() {
+            *now = time;
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        self.now
+            .lock()
+            .map(|now| *now)
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    }
+}
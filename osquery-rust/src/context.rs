@@ -0,0 +1,405 @@
+//! Access to the running osquery from inside a plugin.
+
+use crate::client::{OsqueryClient, ThriftClient};
+use crate::ExtensionPluginRequest;
+use std::collections::BTreeMap;
+use std::io::Error;
+use std::sync::{Arc, Mutex};
+use thrift::{ApplicationError, ApplicationErrorKind};
+
+/// A connection plugins can use to ask osquery for data while serving a call,
+/// e.g. a table that joins against another table or reads the current config.
+///
+/// The context talks to osquery over its own connection to the extension
+/// socket, separate from the server's, so it is safe to use from inside
+/// `generate()`. Clones share the connection.
+///
+/// ```no_run
+/// use osquery_rust_ng::OsqueryContext;
+///
+/// let context = OsqueryContext::connect("/var/osquery/osquery.em")?;
+/// let config = context.config()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+pub struct OsqueryContext {
+    client: Arc<Mutex<Box<dyn OsqueryClient>>>,
+}
+
+impl OsqueryContext {
+    /// Open a connection to osquery's extension socket at `socket_path`.
+    pub fn connect(socket_path: &str) -> Result<Self, Error> {
+        let client = ThriftClient::new(socket_path, Default::default())?;
+        Ok(Self::with_client(client))
+    }
+
+    /// Use `client` to talk to osquery, e.g. a mock in tests.
+    pub fn with_client<C: OsqueryClient + 'static>(client: C) -> Self {
+        OsqueryContext {
+            client: Arc::new(Mutex::new(Box::new(client))),
+        }
+    }
+
+    /// Run `sql` in osquery and return the resulting rows.
+    ///
+    /// A query osquery rejects (e.g. a syntax error or an unknown table) is
+    /// returned as an application error carrying osquery's message.
+    pub fn query(&self, sql: &str) -> thrift::Result<Vec<BTreeMap<String, String>>> {
+        let response = self.client()?.query(sql.to_string())?;
+        check_status(response)
+    }
+
+    /// The configuration osquery's active config plugin currently generates.
+    ///
+    /// The result is a JSON object keyed by config source, as the plugin's
+    /// `genConfig` reports it (e.g. `{"main": "{\"schedule\": ...}"}` for the
+    /// filesystem plugin); each value is that source's config as a string.
+    pub fn config(&self) -> thrift::Result<String> {
+        let plugin = self
+            .query("SELECT value FROM osquery_flags WHERE name = 'config_plugin'")?
+            .into_iter()
+            .next()
+            .and_then(|mut row| row.remove("value"))
+            .ok_or_else(|| application_error("osquery did not report a config plugin"))?;
+
+        let request =
+            ExtensionPluginRequest::from([("action".to_string(), "genConfig".to_string())]);
+        let response = self.client()?.call("config".to_string(), plugin, request)?;
+        let sources = check_status(response)?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        serde_json::to_string(&sources).map_err(|e| application_error(&e.to_string()))
+    }
+
+    fn client(&self) -> thrift::Result<std::sync::MutexGuard<'_, Box<dyn OsqueryClient>>> {
+        self.client
+            .lock()
+            .map_err(|_| application_error("osquery context connection is poisoned"))
+    }
+}
+
+/// The response's rows, or an error if osquery reported a failure.
+fn check_status(
+    response: crate::ExtensionResponse,
+) -> thrift::Result<Vec<BTreeMap<String, String>>> {
+    let status = response.status.unwrap_or_default();
+    match status.code {
+        None | Some(0) => Ok(response.response.unwrap_or_default()),
+        Some(code) => {
+            Err(application_error(&status.message.unwrap_or_else(|| {
+                format!("osquery returned status {code}")
+            })))
+        }
+    }
+}
+
+/// An [`OsqueryContext`] stand-in for unit tests, answering queries with
+/// canned rows and recording every query it is asked to run.
+///
+/// Queries are matched on their exact SQL; one without a canned response
+/// fails the way osquery reports a bad query. Available with the `test-util`
+/// feature.
+///
+/// ```
+/// # #[cfg(feature = "test-util")]
+/// # {
+/// use osquery_rust_ng::MockOsqueryContext;
+/// use std::collections::BTreeMap;
+///
+/// let mock = MockOsqueryContext::new().with_response(
+///     "SELECT version FROM osquery_info",
+///     vec![BTreeMap::from([("version".to_string(), "5.12.1".to_string())])],
+/// );
+/// let context = mock.context();
+///
+/// let rows = context.query("SELECT version FROM osquery_info")?;
+/// assert_eq!(rows[0]["version"], "5.12.1");
+/// assert_eq!(mock.queries(), vec!["SELECT version FROM osquery_info"]);
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Clone, Default)]
+pub struct MockOsqueryContext {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Default)]
+struct MockState {
+    responses: std::collections::HashMap<String, Vec<BTreeMap<String, String>>>,
+    queries: Vec<String>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl MockOsqueryContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer `sql` with `rows`.
+    pub fn with_response(self, sql: &str, rows: Vec<BTreeMap<String, String>>) -> Self {
+        if let Ok(mut state) = self.state.lock() {
+            state.responses.insert(sql.to_string(), rows);
+        }
+        self
+    }
+
+    /// A context backed by this mock, to hand to the plugin under test.
+    pub fn context(&self) -> OsqueryContext {
+        OsqueryContext::with_client(MockContextClient(self.state.clone()))
+    }
+
+    /// The SQL of every query run through the mock's contexts, in order.
+    pub fn queries(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .map(|state| state.queries.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// The client behind [`MockOsqueryContext::context`].
+#[cfg(any(test, feature = "test-util"))]
+struct MockContextClient(Arc<Mutex<MockState>>);
+
+#[cfg(any(test, feature = "test-util"))]
+impl OsqueryClient for MockContextClient {
+    fn register_extension(
+        &mut self,
+        _info: crate::_osquery::osquery::InternalExtensionInfo,
+        _registry: crate::_osquery::osquery::ExtensionRegistry,
+    ) -> thrift::Result<crate::ExtensionStatus> {
+        Ok(crate::ExtensionStatus::default())
+    }
+
+    fn deregister_extension(
+        &mut self,
+        _uuid: crate::_osquery::osquery::ExtensionRouteUUID,
+    ) -> thrift::Result<crate::ExtensionStatus> {
+        Ok(crate::ExtensionStatus::default())
+    }
+
+    fn ping(&mut self) -> thrift::Result<crate::ExtensionStatus> {
+        Ok(crate::ExtensionStatus::default())
+    }
+
+    fn query(&mut self, sql: String) -> thrift::Result<crate::ExtensionResponse> {
+        let mut state = self
+            .0
+            .lock()
+            .map_err(|_| application_error("mock osquery context is poisoned"))?;
+        state.queries.push(sql.clone());
+
+        Ok(match state.responses.get(&sql) {
+            Some(rows) => {
+                crate::ExtensionResponse::new(crate::ExtensionStatus::default(), rows.clone())
+            }
+            None => crate::ExtensionResponse::new(
+                crate::ExtensionStatus::new(1, format!("no canned response for: {sql}"), None),
+                vec![],
+            ),
+        })
+    }
+
+    fn get_query_columns(&mut self, sql: String) -> thrift::Result<crate::ExtensionResponse> {
+        Err(application_error(&format!(
+            "mock osquery context can't describe: {sql}"
+        )))
+    }
+}
+
+fn application_error(message: &str) -> thrift::Error {
+    thrift::Error::Application(ApplicationError::new(
+        ApplicationErrorKind::InternalError,
+        message.to_string(),
+    ))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)] // Tests are allowed to panic on setup failures
+mod tests {
+    use super::*;
+    use crate::client::MockOsqueryClient;
+    use crate::plugin::{ColumnDef, ColumnOptions, ColumnType, ReadOnlyTable, TablePlugin};
+    use crate::plugin::{OsqueryPlugin, Plugin};
+    use crate::{ExtensionResponse, ExtensionStatus};
+
+    const CONFIG: &str =
+        r#"{"schedule":{"uptime":{"query":"SELECT * FROM uptime","interval":60}}}"#;
+
+    fn rows(row: &[(&str, &str)]) -> Vec<BTreeMap<String, String>> {
+        vec![row
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()]
+    }
+
+    fn mock_osquery() -> MockOsqueryClient {
+        let mut client = MockOsqueryClient::new();
+        client
+            .expect_query()
+            .withf(|sql| sql.contains("config_plugin"))
+            .returning(|_| {
+                Ok(ExtensionResponse::new(
+                    ExtensionStatus::default(),
+                    rows(&[("value", "filesystem")]),
+                ))
+            });
+        client
+            .expect_call()
+            .withf(|registry, item, request| {
+                registry == "config"
+                    && item == "filesystem"
+                    && request.get("action").map(String::as_str) == Some("genConfig")
+            })
+            .returning(|_, _, _| {
+                Ok(ExtensionResponse::new(
+                    ExtensionStatus::default(),
+                    rows(&[("main", CONFIG)]),
+                ))
+            });
+        client
+    }
+
+    /// Reports the configured schedule interval of the `uptime` query.
+    struct ScheduleTable {
+        context: OsqueryContext,
+    }
+
+    impl ReadOnlyTable for ScheduleTable {
+        fn name(&self) -> String {
+            "schedule_interval".to_string()
+        }
+
+        fn columns(&self) -> Vec<ColumnDef> {
+            vec![ColumnDef::new(
+                "interval",
+                ColumnType::Integer,
+                ColumnOptions::DEFAULT,
+            )]
+        }
+
+        fn generate(&self, _request: ExtensionPluginRequest) -> ExtensionResponse {
+            let interval = self
+                .context
+                .config()
+                .ok()
+                .and_then(|config| serde_json::from_str::<serde_json::Value>(&config).ok())
+                .and_then(|sources| {
+                    let main = sources.get("main")?.as_str()?.to_string();
+                    serde_json::from_str::<serde_json::Value>(&main).ok()
+                })
+                .and_then(|config| config.pointer("/schedule/uptime/interval")?.as_u64());
+            let rows = interval
+                .map(|interval| rows(&[("interval", &interval.to_string())]))
+                .unwrap_or_default();
+            ExtensionResponse::new(ExtensionStatus::default(), rows)
+        }
+
+        fn shutdown(&self) {}
+    }
+
+    #[test]
+    fn test_config_returns_sources_from_active_plugin() {
+        let context = OsqueryContext::with_client(mock_osquery());
+
+        let config = context.config().expect("config should be returned");
+        let sources: BTreeMap<String, String> =
+            serde_json::from_str(&config).expect("config should be a JSON object");
+        assert_eq!(sources.get("main").map(String::as_str), Some(CONFIG));
+    }
+
+    #[test]
+    fn test_plugin_reads_config_through_context() {
+        let context = OsqueryContext::with_client(mock_osquery());
+        let plugin = Plugin::Table(TablePlugin::from_readonly_table(ScheduleTable { context }));
+
+        let response = plugin.handle_call(ExtensionPluginRequest::from([(
+            "action".to_string(),
+            "generate".to_string(),
+        )]));
+        assert_eq!(response.response, Some(rows(&[("interval", "60")])));
+    }
+
+    /// Lists the names of running processes that listen on a port.
+    struct ListenerTable {
+        context: OsqueryContext,
+    }
+
+    impl ReadOnlyTable for ListenerTable {
+        fn name(&self) -> String {
+            "listener_names".to_string()
+        }
+
+        fn columns(&self) -> Vec<ColumnDef> {
+            vec![ColumnDef::new(
+                "name",
+                ColumnType::Text,
+                ColumnOptions::DEFAULT,
+            )]
+        }
+
+        fn generate(&self, _request: ExtensionPluginRequest) -> ExtensionResponse {
+            match self.context.query(LISTENERS_SQL) {
+                Ok(rows) => ExtensionResponse::new(ExtensionStatus::default(), rows),
+                Err(e) => {
+                    ExtensionResponse::new(ExtensionStatus::new(1, e.to_string(), None), vec![])
+                }
+            }
+        }
+
+        fn shutdown(&self) {}
+    }
+
+    const LISTENERS_SQL: &str =
+        "SELECT DISTINCT p.name FROM listening_ports l JOIN processes p USING (pid)";
+
+    #[test]
+    fn test_mock_context_answers_table_sub_query() {
+        let mock =
+            MockOsqueryContext::new().with_response(LISTENERS_SQL, rows(&[("name", "sshd")]));
+        let plugin = Plugin::Table(TablePlugin::from_readonly_table(ListenerTable {
+            context: mock.context(),
+        }));
+
+        let response = plugin.handle_call(ExtensionPluginRequest::from([(
+            "action".to_string(),
+            "generate".to_string(),
+        )]));
+        assert_eq!(response.response, Some(rows(&[("name", "sshd")])));
+        assert_eq!(mock.queries(), vec![LISTENERS_SQL]);
+    }
+
+    #[test]
+    fn test_mock_context_fails_unexpected_queries() {
+        let mock = MockOsqueryContext::new();
+
+        let result = mock.context().query("SELECT * FROM users");
+        assert!(matches!(
+            result,
+            Err(thrift::Error::Application(e)) if e.message == "no canned response for: SELECT * FROM users"
+        ));
+        assert_eq!(mock.queries(), vec!["SELECT * FROM users"]);
+    }
+
+    #[test]
+    fn test_query_failure_is_an_error() {
+        let mut client = MockOsqueryClient::new();
+        client.expect_query().returning(|_| {
+            Ok(ExtensionResponse::new(
+                ExtensionStatus::new(1, "no such table: bogus".to_string(), None),
+                vec![],
+            ))
+        });
+        let context = OsqueryContext::with_client(client);
+
+        let result = context.query("SELECT * FROM bogus");
+        assert!(matches!(
+            result,
+            Err(thrift::Error::Application(e)) if e.message == "no such table: bogus"
+        ));
+    }
+}
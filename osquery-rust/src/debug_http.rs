@@ -0,0 +1,205 @@
+//! A development-only HTTP endpoint for calling plugins without osquery.
+//!
+//! Enabled with the `debug-http` feature and
+//! [`Server::set_debug_http`](crate::Server::set_debug_http). Requests are
+//! dispatched through the same [`Handler`] osquery's calls go through:
+//!
+//! - `GET /tables`: every table's column routes, keyed by table name
+//! - `POST /tables/{name}/generate`: the table's rows; the body is the query
+//!   context osquery would send, e.g. `{"constraints":[...]}` (may be empty)
+//! - `GET /config`: the output of every config plugin's `genConfig`
+//!
+//! Responses are JSON. This is a plain HTTP/1.1 server handling one request
+//! per connection, and the server refuses to start it on anything but a
+//! loopback address.
+
+use crate::_osquery as osquery;
+use crate::log_target;
+use crate::plugin::OsqueryPlugin;
+use crate::server::Handler;
+use osquery::ExtensionSyncHandler;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// How often the accept loop checks for shutdown.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Request bodies larger than this are cut off.
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Serve requests on `listener` until `shutdown_flag` is set.
+///
+/// `listener` must be non-blocking so the shutdown flag is noticed.
+pub(crate) fn serve<P: OsqueryPlugin + Clone + 'static>(
+    listener: TcpListener,
+    handler: Handler<P>,
+    shutdown_flag: Arc<AtomicBool>,
+) {
+    while !shutdown_flag.load(Ordering::Acquire) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if let Err(e) = handle_connection(stream, &handler) {
+                    log::debug!(target: log_target::SERVER, "Debug HTTP request failed: {e}");
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                log::warn!(target: log_target::SERVER, "Debug HTTP listener failed: {e}");
+                break;
+            }
+        }
+    }
+}
+
+fn handle_connection<P: OsqueryPlugin + Clone + 'static>(
+    stream: TcpStream,
+    handler: &Handler<P>,
+) -> io::Result<()> {
+    // Accepted sockets inherit non-blocking mode on some platforms
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length.min(MAX_BODY_SIZE)];
+    reader.read_exact(&mut body)?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    let (status, response) = route(handler, method, path, &String::from_utf8_lossy(&body));
+
+    write_response(&stream, status, &response)
+}
+
+fn route<P: OsqueryPlugin + Clone + 'static>(
+    handler: &Handler<P>,
+    method: &str,
+    path: &str,
+    body: &str,
+) -> (u16, Value) {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        ("GET", ["tables"]) => describe_all(handler, "table", "columns"),
+        ("POST", ["tables", name, "generate"]) => generate(handler, name, body),
+        ("GET", ["config"]) => describe_all(handler, "config", "genConfig"),
+        _ => (
+            404,
+            json!({ "error": format!("No route for {method} {path}") }),
+        ),
+    }
+}
+
+/// Call `action` on every plugin in `registry`, keyed by plugin name.
+fn describe_all<P: OsqueryPlugin + Clone + 'static>(
+    handler: &Handler<P>,
+    registry: &str,
+    action: &str,
+) -> (u16, Value) {
+    let mut plugins = Map::new();
+    for name in handler.plugin_names(registry) {
+        let request = BTreeMap::from([("action".to_string(), action.to_string())]);
+        match call(handler, registry, &name, request) {
+            Ok(rows) => plugins.insert(name, rows),
+            Err(error) => return error,
+        };
+    }
+    (200, Value::Object(plugins))
+}
+
+fn generate<P: OsqueryPlugin + Clone + 'static>(
+    handler: &Handler<P>,
+    table: &str,
+    context: &str,
+) -> (u16, Value) {
+    let context = if context.trim().is_empty() {
+        "{}"
+    } else {
+        context
+    };
+    if let Err(e) = serde_json::from_str::<Value>(context) {
+        return (
+            400,
+            json!({ "error": format!("Invalid query context: {e}") }),
+        );
+    }
+
+    let request = BTreeMap::from([
+        ("action".to_string(), "generate".to_string()),
+        ("context".to_string(), context.to_string()),
+    ]);
+    match call(handler, "table", table, request) {
+        Ok(rows) => (200, rows),
+        Err(error) => error,
+    }
+}
+
+/// Dispatch a plugin call, returning its rows or an HTTP error.
+fn call<P: OsqueryPlugin + Clone + 'static>(
+    handler: &Handler<P>,
+    registry: &str,
+    item: &str,
+    request: osquery::ExtensionPluginRequest,
+) -> Result<Value, (u16, Value)> {
+    let response = handler
+        .handle_call(registry.to_string(), item.to_string(), request)
+        .map_err(|_| {
+            (
+                404,
+                json!({ "error": format!("No {registry} plugin named '{item}'") }),
+            )
+        })?;
+
+    let rows = response.response.unwrap_or_default();
+    match response.status.and_then(|status| status.code) {
+        None | Some(0) => Ok(json!(rows)),
+        Some(code) => {
+            let message = rows
+                .first()
+                .and_then(|row| row.get("message"))
+                .cloned()
+                .unwrap_or_else(|| format!("Plugin returned status {code}"));
+            Err((500, json!({ "error": message, "code": code })))
+        }
+    }
+}
+
+fn write_response(mut stream: &TcpStream, status: u16, body: &Value) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        _ => "Internal Server Error",
+    };
+    let body = body.to_string();
+
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
@@ -0,0 +1,275 @@
+use std::fmt;
+use std::io;
+
+/// Errors returned while creating or running a [`Server`](crate::Server).
+#[derive(Debug)]
+pub enum ServerError {
+    /// Connecting to the osquery extension socket failed.
+    ///
+    /// The wrapped error's `kind()` tells apart a missing socket
+    /// (`NotFound`) from osquery not accepting connections (`ConnectionRefused`).
+    Connect(io::Error),
+    /// A Thrift protocol or transport error occurred while talking to osquery.
+    Thrift(thrift::Error),
+    /// Setting up the socket osquery calls the extension on failed, e.g.
+    /// because the mode from
+    /// [`Server::set_socket_mode`](crate::Server::set_socket_mode) couldn't be
+    /// applied, or the debug HTTP endpoint couldn't listen on its address.
+    Listen(io::Error),
+    /// The server has already been started.
+    AlreadyStarted,
+    /// No plugins were registered before starting the server.
+    NoPlugins,
+    /// A plugin with the same name is already registered in the same registry.
+    DuplicatePlugin { registry: String, name: String },
+    /// A plugin can't be registered, e.g. because it panicked describing its
+    /// routes. See [`Server::allow_partial_registration`](crate::Server::allow_partial_registration)
+    /// to register the other plugins anyway.
+    InvalidPlugin {
+        registry: String,
+        name: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerError::Connect(e) => write!(f, "Failed to connect to osquery: {e}"),
+            // Transport errors only display their kind; the detail is in the message
+            ServerError::Thrift(thrift::Error::Transport(e)) => {
+                write!(f, "Thrift error: {e}: {}", e.message)
+            }
+            ServerError::Thrift(e) => write!(f, "Thrift error: {e}"),
+            ServerError::Listen(e) => write!(f, "Failed to set up extension socket: {e}"),
+            ServerError::AlreadyStarted => write!(f, "Server has already been started"),
+            ServerError::NoPlugins => write!(f, "No plugins registered"),
+            ServerError::DuplicatePlugin { registry, name } => {
+                write!(f, "Duplicate {registry} plugin name '{name}'")
+            }
+            ServerError::InvalidPlugin {
+                registry,
+                name,
+                reason,
+            } => write!(f, "Can't register {registry} plugin '{name}': {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ServerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ServerError::Connect(e) | ServerError::Listen(e) => Some(e),
+            ServerError::Thrift(e) => Some(e),
+            ServerError::AlreadyStarted
+            | ServerError::NoPlugins
+            | ServerError::DuplicatePlugin { .. }
+            | ServerError::InvalidPlugin { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ServerError {
+    fn from(e: io::Error) -> Self {
+        ServerError::Connect(e)
+    }
+}
+
+impl From<thrift::Error> for ServerError {
+    fn from(e: thrift::Error) -> Self {
+        ServerError::Thrift(e)
+    }
+}
+
+/// Allows `?` on server results in functions returning `std::io::Result`.
+impl From<ServerError> for io::Error {
+    fn from(e: ServerError) -> Self {
+        match e {
+            ServerError::Connect(e) | ServerError::Listen(e) => e,
+            other => io::Error::other(other),
+        }
+    }
+}
+
+/// Any error this crate produces, so code mixing socket I/O, Thrift calls,
+/// server setup and plugin failures can use `?` throughout.
+#[derive(Debug)]
+pub enum Error {
+    /// An I/O error, e.g. from a socket.
+    Io(io::Error),
+    /// A Thrift protocol or transport error.
+    Thrift(thrift::Error),
+    /// Creating or running a [`Server`](crate::Server) failed.
+    Server(ServerError),
+    /// A plugin failed with the message it returned.
+    Plugin(String),
+}
+
+/// `Result` with [`Error`] as the default error type.
+///
+/// The error type can still be given, so `Result<T, String>` in plugin code
+/// keeps working with the prelude imported.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {e}"),
+            Error::Thrift(thrift::Error::Transport(e)) => {
+                write!(f, "Thrift error: {e}: {}", e.message)
+            }
+            Error::Thrift(e) => write!(f, "Thrift error: {e}"),
+            Error::Server(e) => e.fmt(f),
+            Error::Plugin(message) => write!(f, "Plugin error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Thrift(e) => Some(e),
+            Error::Server(e) => Some(e),
+            Error::Plugin(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<thrift::Error> for Error {
+    fn from(e: thrift::Error) -> Self {
+        Error::Thrift(e)
+    }
+}
+
+impl From<ServerError> for Error {
+    fn from(e: ServerError) -> Self {
+        Error::Server(e)
+    }
+}
+
+/// Plugin methods report failures as `Result<_, String>`.
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Plugin(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::Plugin(message.to_string())
+    }
+}
+
+/// Allows `?` on crate results in functions returning `std::io::Result`.
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e,
+            Error::Server(e) => e.into(),
+            other => io::Error::other(other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_io_error_is_connect() {
+        let err: ServerError = io::Error::from(io::ErrorKind::ConnectionRefused).into();
+        assert!(matches!(
+            err,
+            ServerError::Connect(ref e) if e.kind() == io::ErrorKind::ConnectionRefused
+        ));
+    }
+
+    #[test]
+    fn test_from_thrift_error_is_thrift() {
+        let err: ServerError = thrift::Error::from(io::Error::other("boom")).into();
+        assert!(matches!(err, ServerError::Thrift(_)));
+    }
+
+    #[test]
+    fn test_into_io_error_preserves_kind() {
+        let err = ServerError::Connect(io::Error::from(io::ErrorKind::NotFound));
+        let io_err: io::Error = err.into();
+        assert_eq!(io_err.kind(), io::ErrorKind::NotFound);
+
+        let io_err: io::Error = ServerError::NoPlugins.into();
+        assert_eq!(io_err.kind(), io::ErrorKind::Other);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            ServerError::AlreadyStarted.to_string(),
+            "Server has already been started"
+        );
+        assert_eq!(ServerError::NoPlugins.to_string(), "No plugins registered");
+    }
+
+    #[test]
+    fn test_error_from_conversions() {
+        let err: Error = io::Error::from(io::ErrorKind::NotFound).into();
+        assert!(matches!(err, Error::Io(ref e) if e.kind() == io::ErrorKind::NotFound));
+
+        let err: Error = thrift::Error::from(io::Error::other("boom")).into();
+        assert!(matches!(err, Error::Thrift(_)));
+
+        let err: Error = ServerError::NoPlugins.into();
+        assert!(matches!(err, Error::Server(ServerError::NoPlugins)));
+        assert_eq!(err.to_string(), "No plugins registered");
+
+        let err: Error = "table is empty".to_string().into();
+        assert!(matches!(err, Error::Plugin(ref m) if m == "table is empty"));
+        assert_eq!(err.to_string(), "Plugin error: table is empty");
+    }
+
+    #[test]
+    fn test_question_mark_mixes_error_sources() {
+        fn plugin_step() -> Result<(), String> {
+            Err("config missing".to_string())
+        }
+        fn server_step() -> std::result::Result<(), ServerError> {
+            Err(ServerError::AlreadyStarted)
+        }
+        fn run(step: u8) -> Result<()> {
+            match step {
+                0 => Err(io::Error::from(io::ErrorKind::ConnectionRefused))?,
+                1 => server_step()?,
+                _ => plugin_step()?,
+            }
+            Ok(())
+        }
+
+        assert!(matches!(run(0), Err(Error::Io(_))));
+        assert!(matches!(
+            run(1),
+            Err(Error::Server(ServerError::AlreadyStarted))
+        ));
+        assert!(matches!(run(2), Err(Error::Plugin(_))));
+
+        // Existing `io::Result` callers can still use `?` on both error types
+        fn io_caller() -> io::Result<()> {
+            server_step()?;
+            Ok(())
+        }
+        fn io_caller_unified() -> io::Result<()> {
+            run(0)?;
+            Ok(())
+        }
+        assert_eq!(io_caller().map_err(|e| e.kind()), Err(io::ErrorKind::Other));
+        assert_eq!(
+            io_caller_unified().map_err(|e| e.kind()),
+            Err(io::ErrorKind::ConnectionRefused)
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! The optional `<extension>_internal` table reporting the server's own stats.
+
+use crate::clock::Clock;
+use crate::plugin::{ColumnDef, ColumnOptions, ColumnType, ColumnValue, ReadOnlyTable};
+use crate::{ExtensionPluginRequest, ExtensionResponse, ExtensionStatus};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Counters the server keeps for its internal table.
+#[derive(Default)]
+pub(crate) struct ServerStats {
+    /// When the server registered with osquery
+    started_at: Mutex<Option<SystemTime>>,
+    /// Successful pings to osquery
+    ping_count: AtomicU64,
+}
+
+impl ServerStats {
+    pub(crate) fn record_start(&self, now: SystemTime) {
+        if let Ok(mut started_at) = self.started_at.lock() {
+            *started_at = Some(now);
+        }
+    }
+
+    pub(crate) fn record_ping(&self) {
+        self.ping_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn uptime_seconds(&self, now: SystemTime) -> u64 {
+        self.started_at
+            .lock()
+            .ok()
+            .and_then(|started_at| *started_at)
+            .and_then(|started_at| now.duration_since(started_at).ok())
+            .map_or(0, |uptime| uptime.as_secs())
+    }
+}
+
+/// A one-row table with the server's uptime, ping count and last call time.
+pub(crate) struct InternalTable {
+    name: String,
+    stats: Arc<ServerStats>,
+    /// Time of the last call from osquery, in milliseconds since the Unix epoch
+    last_call: Arc<AtomicU64>,
+    clock: Arc<dyn Clock>,
+}
+
+impl InternalTable {
+    /// The table for the extension called `extension`.
+    pub(crate) fn new(
+        extension: &str,
+        stats: Arc<ServerStats>,
+        last_call: Arc<AtomicU64>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        InternalTable {
+            name: format!("{extension}_internal"),
+            stats,
+            last_call,
+            clock,
+        }
+    }
+}
+
+impl ReadOnlyTable for InternalTable {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn columns(&self) -> Vec<ColumnDef> {
+        vec![
+            ColumnDef::new("uptime_seconds", ColumnType::BigInt, ColumnOptions::DEFAULT)
+                .with_description("Seconds since the extension registered with osquery"),
+            ColumnDef::new("ping_count", ColumnType::BigInt, ColumnOptions::DEFAULT)
+                .with_description("Successful pings to osquery"),
+            ColumnDef::new("last_call_time", ColumnType::BigInt, ColumnOptions::DEFAULT)
+                .with_description("Unix time of the last call from osquery"),
+        ]
+    }
+
+    fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+        let last_call_ms = self.last_call.load(Ordering::Acquire);
+        let row = ColumnValue::typed_row([
+            (
+                "uptime_seconds",
+                ColumnValue::UnsignedBigInt(self.stats.uptime_seconds(self.clock.now())),
+            ),
+            (
+                "ping_count",
+                ColumnValue::UnsignedBigInt(self.stats.ping_count.load(Ordering::Relaxed)),
+            ),
+            (
+                "last_call_time",
+                ColumnValue::UnsignedBigInt(last_call_ms / 1000),
+            ),
+        ]);
+        ExtensionResponse::new(ExtensionStatus::default(), vec![row])
+    }
+
+    fn shutdown(&self) {}
+}
@@ -3,19 +3,68 @@
 // Restrict access to osquery API to osquery-rust
 // Users of osquery-rust are not allowed to access osquery API directly
 pub(crate) mod _osquery;
+pub mod cli;
 mod client;
+mod clock;
+mod context;
+#[cfg(feature = "debug-http")]
+mod debug_http;
+mod error;
+mod internal_table;
+mod local_query;
+mod macros;
+mod metrics;
 pub mod plugin;
+#[cfg(feature = "prometheus")]
+mod prometheus;
+mod protocol;
+mod request;
 mod server;
+mod standalone;
+mod transport;
 mod util;
 
-pub use crate::client::{Client, OsqueryClient, ThriftClient};
-pub use crate::server::{Server, ServerStopHandle};
+pub use crate::client::{Client, ClientOptions, OsqueryClient, ReconnectingClient, ThriftClient};
+#[cfg(any(test, feature = "test-util"))]
+pub use crate::clock::MockClock;
+pub use crate::clock::{Clock, SystemClock};
+#[cfg(any(test, feature = "test-util"))]
+pub use crate::context::MockOsqueryContext;
+pub use crate::context::OsqueryContext;
+pub use crate::error::{Error, Result, ServerError};
+pub use crate::metrics::{MetricsSink, NoopMetricsSink};
+#[cfg(feature = "prometheus")]
+pub use crate::prometheus::PrometheusMetrics;
+pub use crate::protocol::Protocol;
+pub use crate::request::{ExtensionPluginRequestExt, PluginRequest};
+pub use crate::server::{
+    OptionType, PluginFailure, PollOutcome, RestartPolicy, Server, ServerStopHandle, ShutdownReason,
+};
+pub use crate::standalone::StandaloneHarness;
+pub use crate::transport::BufferSizes;
+
+/// Targets the crate logs under with the `log` crate, one per component, so
+/// they can be filtered separately (e.g. `RUST_LOG=osquery_rust::client=debug`
+/// with `env_logger`). These strings are stable across releases.
+pub mod log_target {
+    /// Registration, the ping loop, the listener and shutdown.
+    pub const SERVER: &str = "osquery_rust::server";
+    /// The connection to osquery's extension manager.
+    pub const CLIENT: &str = "osquery_rust::client";
+    /// The table, config, logger and event plugin wrappers.
+    pub const PLUGIN: &str = "osquery_rust::plugin";
+}
 
 // Re-exports
+/// Used by [`plugin::OsqueryPlugin::min_osquery_version`], re-exported so
+/// extensions don't need to depend on a matching version of it.
+pub use semver;
 pub type ExtensionResponse = _osquery::osquery::ExtensionResponse;
 pub type ExtensionPluginRequest = _osquery::osquery::ExtensionPluginRequest;
 pub type ExtensionPluginResponse = _osquery::osquery::ExtensionPluginResponse;
 pub type ExtensionStatus = _osquery::osquery::ExtensionStatus;
+pub type InternalOptionInfo = _osquery::osquery::InternalOptionInfo;
+pub type InternalOptionList = _osquery::osquery::InternalOptionList;
 
 ///
 /// Expose all structures required in virtually any osquery extension
@@ -24,12 +73,17 @@ pub type ExtensionStatus = _osquery::osquery::ExtensionStatus;
 /// use osquery_rust_ng::prelude::*;
 /// ```
 pub mod prelude {
+    pub use crate::OsqueryContext;
     pub use crate::Server;
+    pub use crate::ServerError;
     pub use crate::ServerStopHandle;
+    pub use crate::StandaloneHarness;
+    pub use crate::{Error, Result};
     pub use crate::{
-        ExtensionPluginRequest, ExtensionPluginResponse, ExtensionResponse, ExtensionStatus,
+        ExtensionPluginRequest, ExtensionPluginRequestExt, ExtensionPluginResponse,
+        ExtensionResponse, ExtensionStatus, PluginRequest,
     };
 }
 
-#[cfg(test)]
+#[cfg(all(test, unix))]
 mod server_tests;
@@ -0,0 +1,362 @@
+//! A tiny `SELECT` parser backing [`Server::local_query`](crate::Server::local_query).
+//!
+//! Understands `SELECT <*|col, ...> FROM <table> [WHERE <col> <op> <literal>
+//! [AND ...]]`, which is enough for an extension to query its own tables in
+//! self-tests. Anything fancier (joins, functions, `OR`) is rejected.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::plugin::table::query_constraint;
+use crate::plugin::{
+    ColumnType, ConstraintList, ExtensionResponseEnum, Operator, OsqueryPlugin, QueryConstraints,
+    Registry,
+};
+use crate::ExtensionResponse;
+
+/// A parsed `SELECT` statement.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct LocalQuery {
+    pub(crate) table: String,
+    /// The selected columns, or `None` for `*`
+    pub(crate) columns: Option<Vec<String>>,
+    /// `(column, operator, value)` for each `AND`ed `WHERE` term
+    pub(crate) predicates: Vec<(String, Operator, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Literal(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 9] = ["<=", ">=", "==", "*", ",", ";", "=", "<", ">"];
+
+fn tokenize(sql: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = sql.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        if c == '\'' || c == '"' {
+            let (text, remainder) = quoted(rest, c)?;
+            // Double quotes delimit identifiers, single quotes strings
+            tokens.push(if c == '"' {
+                Token::Word(text)
+            } else {
+                Token::Literal(text)
+            });
+            rest = remainder;
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = rest.get(symbol.len()..).unwrap_or_default();
+        } else if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '.'))
+                .unwrap_or(rest.len());
+            let (word, remainder) = rest.split_at(end);
+            tokens.push(Token::Word(word.to_string()));
+            rest = remainder;
+        } else {
+            return Err(format!("unrecognized token: \"{c}\""));
+        }
+        rest = rest.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// Split a `quote`-delimited string off the front of `input`, unescaping
+/// doubled quotes.
+fn quoted(input: &str, quote: char) -> Result<(String, &str), String> {
+    let mut text = String::new();
+    let mut chars = input.char_indices().skip(1).peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c != quote {
+            text.push(c);
+        } else if chars.peek().is_some_and(|(_, next)| *next == quote) {
+            text.push(quote);
+            chars.next();
+        } else {
+            return Ok((text, input.get(i + 1..).unwrap_or_default()));
+        }
+    }
+
+    Err(format!("unterminated string: {input}"))
+}
+
+struct Parser {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser {
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let matched = matches!(
+            self.tokens.peek(),
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword)
+        );
+        if matched {
+            self.tokens.next();
+        }
+        matched
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.keyword(keyword) {
+            Ok(())
+        } else {
+            Err(format!("expected {keyword} near {}", self.near()))
+        }
+    }
+
+    fn symbol(&mut self, symbol: &'static str) -> bool {
+        let matched = self.tokens.peek() == Some(&Token::Symbol(symbol));
+        if matched {
+            self.tokens.next();
+        }
+        matched
+    }
+
+    fn identifier(&mut self) -> Result<String, String> {
+        match self.tokens.next() {
+            Some(Token::Word(word)) => Ok(word),
+            other => Err(format!("expected a name near {}", describe(other.as_ref()))),
+        }
+    }
+
+    fn near(&mut self) -> String {
+        describe(self.tokens.peek())
+    }
+
+    fn columns(&mut self) -> Result<Option<Vec<String>>, String> {
+        if self.symbol("*") {
+            return Ok(None);
+        }
+
+        let mut columns = vec![self.identifier()?];
+        while self.symbol(",") {
+            columns.push(self.identifier()?);
+        }
+        Ok(Some(columns))
+    }
+
+    fn predicate(&mut self) -> Result<(String, Operator, String), String> {
+        let column = self.identifier()?;
+
+        let op = match self.tokens.next() {
+            Some(Token::Symbol("=" | "==")) => Operator::Equals,
+            Some(Token::Symbol("<")) => Operator::LessThan,
+            Some(Token::Symbol("<=")) => Operator::LessThanOrEquals,
+            Some(Token::Symbol(">")) => Operator::GreaterThan,
+            Some(Token::Symbol(">=")) => Operator::GreaterThanOrEquals,
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("LIKE") => Operator::Like,
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("GLOB") => Operator::Glob,
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("REGEXP") => Operator::Regexp,
+            other => {
+                return Err(format!(
+                    "expected an operator near {}",
+                    describe(other.as_ref())
+                ))
+            }
+        };
+
+        let value = match self.tokens.next() {
+            Some(Token::Literal(value)) => value,
+            Some(Token::Word(value)) if value.parse::<f64>().is_ok() => value,
+            other => {
+                return Err(format!(
+                    "expected a value near {}",
+                    describe(other.as_ref())
+                ))
+            }
+        };
+
+        Ok((column, op, value))
+    }
+}
+
+fn describe(token: Option<&Token>) -> String {
+    match token {
+        Some(Token::Word(word)) => format!("\"{word}\""),
+        Some(Token::Literal(literal)) => format!("'{literal}'"),
+        Some(Token::Symbol(symbol)) => format!("\"{symbol}\""),
+        None => "end of input".to_string(),
+    }
+}
+
+/// Parse `sql` into the table, projection and predicates it names.
+pub(crate) fn parse(sql: &str) -> Result<LocalQuery, String> {
+    let mut parser = Parser {
+        tokens: tokenize(sql)?.into_iter().peekable(),
+    };
+
+    parser.expect_keyword("SELECT")?;
+    let columns = parser.columns()?;
+    parser.expect_keyword("FROM")?;
+    let table = parser.identifier()?;
+
+    let mut predicates = Vec::new();
+    if parser.keyword("WHERE") {
+        predicates.push(parser.predicate()?);
+        while parser.keyword("AND") {
+            predicates.push(parser.predicate()?);
+        }
+    }
+
+    parser.symbol(";");
+    if parser.tokens.peek().is_some() {
+        return Err(format!("unsupported SQL near {}", parser.near()));
+    }
+
+    Ok(LocalQuery {
+        table,
+        columns,
+        predicates,
+    })
+}
+
+/// Run `sql` against the matching table in `plugins`.
+///
+/// The `WHERE` terms are passed to the table as constraints, the way osquery
+/// would push them down, and then applied to the generated rows since there
+/// is no SQLite to filter them afterwards.
+pub(crate) fn run<P: OsqueryPlugin>(plugins: &[P], sql: &str) -> ExtensionResponse {
+    let query = match parse(sql) {
+        Ok(query) => query,
+        Err(e) => return ExtensionResponseEnum::Failure(e).into(),
+    };
+
+    let Some(table) = plugins
+        .iter()
+        .find(|plugin| plugin.registry() == Registry::Table && plugin.name() == query.table)
+    else {
+        return ExtensionResponseEnum::Failure(format!("no such table: {}", query.table)).into();
+    };
+
+    let column_types: HashMap<String, ColumnType> = table
+        .routes()
+        .iter()
+        .filter_map(|route| {
+            let column_type = route.get("type")?.parse().unwrap_or(ColumnType::Text);
+            Some((route.get("name")?.clone(), column_type))
+        })
+        .collect();
+
+    let mut constraints = QueryConstraints::new();
+    for (column, op, value) in &query.predicates {
+        let Some(column_type) = column_types.get(column) else {
+            return ExtensionResponseEnum::Failure(format!("no such column: {column}")).into();
+        };
+        constraints
+            .entry(column.clone())
+            .or_insert_with(|| ConstraintList::new(column_type.clone()))
+            .add_constraint(*op, value.clone());
+    }
+    if let Some(column) = query
+        .columns
+        .iter()
+        .flatten()
+        .find(|column| !column_types.contains_key(*column))
+    {
+        return ExtensionResponseEnum::Failure(format!("no such column: {column}")).into();
+    }
+    let matchers = match constraints
+        .iter()
+        .map(|(column, list)| list.compile().map(|matcher| (column, matcher)))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(matchers) => matchers,
+        Err(e) => return ExtensionResponseEnum::Failure(e).into(),
+    };
+
+    let context = query_constraint::constraints_to_context(&constraints);
+    let mut response = table.handle_call(BTreeMap::from([
+        ("action".to_string(), "generate".to_string()),
+        ("context".to_string(), context.to_string()),
+    ]));
+    if response.status.as_ref().and_then(|s| s.code).unwrap_or(0) != 0 {
+        return response;
+    }
+
+    response.response = response.response.map(|rows| {
+        rows.into_iter()
+            .filter(|row| {
+                matchers
+                    .iter()
+                    .all(|(column, matcher)| row.get(*column).is_some_and(|v| matcher.matches(v)))
+            })
+            .map(|row| match &query.columns {
+                Some(columns) => row
+                    .into_iter()
+                    .filter(|(column, _)| columns.contains(column))
+                    .collect(),
+                None => row,
+            })
+            .collect()
+    });
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_select_with_predicates() {
+        let query = parse(
+            "select path, size FROM files WHERE path = '/etc/it''s' AND size >= 10 and name LIKE 'a%';",
+        );
+
+        assert_eq!(
+            query,
+            Ok(LocalQuery {
+                table: "files".to_string(),
+                columns: Some(vec!["path".to_string(), "size".to_string()]),
+                predicates: vec![
+                    (
+                        "path".to_string(),
+                        Operator::Equals,
+                        "/etc/it's".to_string()
+                    ),
+                    (
+                        "size".to_string(),
+                        Operator::GreaterThanOrEquals,
+                        "10".to_string()
+                    ),
+                    ("name".to_string(), Operator::Like, "a%".to_string()),
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_select_star() {
+        let query = parse("SELECT * FROM \"my table\"");
+
+        assert_eq!(
+            query,
+            Ok(LocalQuery {
+                table: "my table".to_string(),
+                columns: None,
+                predicates: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_unsupported_sql() {
+        assert_eq!(
+            parse("SELECT * FROM a WHERE x = 1 OR y = 2"),
+            Err("unsupported SQL near \"OR\"".to_string())
+        );
+        assert_eq!(
+            parse("SELECT * FROM a WHERE x = y"),
+            Err("expected a value near \"y\"".to_string())
+        );
+        assert_eq!(
+            parse("SELECT count(*) FROM a"),
+            Err("unrecognized token: \"(\"".to_string())
+        );
+        assert!(parse("DELETE FROM a").is_err());
+    }
+}
@@ -0,0 +1,102 @@
+//! Macros for wiring up extensions.
+
+/// Register every listed plugin on a [`Server`](crate::Server), in order.
+///
+/// Expands to a `register_plugin()` call per plugin and evaluates to
+/// `Result<(), ServerError>`: registration stops at the first plugin whose
+/// name is already taken in its registry, and the error names it. Plugin
+/// names come from `name()` at runtime, so duplicates can't be caught at
+/// compile time.
+///
+/// ```no_run
+/// use osquery_rust_ng::plugin::{ColumnDef, ColumnOptions, ColumnType, Plugin};
+/// use osquery_rust_ng::{register_all, Server};
+///
+/// fn table(name: &str) -> Plugin {
+///     let columns = vec![ColumnDef::new("value", ColumnType::Text, ColumnOptions::DEFAULT)];
+///     Plugin::static_table(name, columns, Vec::new)
+/// }
+///
+/// let mut server = Server::new(Some("suite"), "/var/osquery/osquery.em")?;
+/// register_all!(server, table("users_ext"), table("groups_ext"), table("hosts_ext"))?;
+/// server.run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[macro_export]
+macro_rules! register_all {
+    ($server:expr, $($plugin:expr),+ $(,)?) => {{
+        let server = &mut $server;
+        let mut result: ::std::result::Result<(), $crate::ServerError> = Ok(());
+        $(
+            if result.is_ok() {
+                result = server.register_plugin($plugin).map(|_| ());
+            }
+        )+
+        result
+    }};
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)] // Tests are allowed to panic on setup failures
+mod tests {
+    use crate::client::MockOsqueryClient;
+    use crate::plugin::{ColumnDef, ColumnOptions, ColumnType, Plugin};
+    use crate::{Server, ServerError};
+
+    fn table(name: &str) -> Plugin {
+        let columns = vec![ColumnDef::new(
+            "value",
+            ColumnType::Text,
+            ColumnOptions::DEFAULT,
+        )];
+        Plugin::static_table(name, columns, Vec::new)
+    }
+
+    fn table_names(server: &Server<Plugin, MockOsqueryClient>) -> Vec<String> {
+        server
+            .describe()
+            .remove("table")
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn test_register_all_registers_in_order() {
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("suite"), "/tmp/test.sock", MockOsqueryClient::new());
+
+        register_all!(
+            server,
+            table("users_ext"),
+            table("groups_ext"),
+            table("hosts_ext"),
+        )
+        .expect("plugins should register");
+
+        assert_eq!(
+            table_names(&server),
+            vec!["users_ext", "groups_ext", "hosts_ext"]
+        );
+    }
+
+    #[test]
+    fn test_register_all_stops_at_duplicate() {
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("suite"), "/tmp/test.sock", MockOsqueryClient::new());
+
+        let result = register_all!(
+            &mut server,
+            table("users_ext"),
+            table("users_ext"),
+            table("hosts_ext")
+        );
+
+        assert!(matches!(
+            result,
+            Err(ServerError::DuplicatePlugin { name, .. }) if name == "users_ext"
+        ));
+        assert_eq!(table_names(&server), vec!["users_ext"]);
+    }
+}
@@ -0,0 +1,24 @@
+//! Hooks for observing plugin calls made by osquery.
+
+use std::time::Duration;
+
+/// Receives a measurement for every plugin call osquery makes.
+///
+/// Set one with [`Server::set_metrics_sink`](crate::Server::set_metrics_sink)
+/// to bridge call counts and latencies to Prometheus, statsd and the like.
+/// `on_call` runs on the listener thread right after each call, so it should
+/// be quick; hand expensive work off to another thread.
+pub trait MetricsSink: Send + Sync {
+    /// Called after `registry`/`item` (e.g. `"table"`/`"users"`) handled a
+    /// request in `duration`. `ok` is false when the plugin returned a
+    /// non-zero status.
+    fn on_call(&self, registry: &str, item: &str, duration: Duration, ok: bool);
+}
+
+/// A [`MetricsSink`] that discards every measurement; the server's default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopMetricsSink;
+
+impl MetricsSink for NoopMetricsSink {
+    fn on_call(&self, _registry: &str, _item: &str, _duration: Duration, _ok: bool) {}
+}
@@ -1,10 +1,13 @@
 use crate::_osquery as osquery;
 use crate::_osquery::{ExtensionPluginRequest, ExtensionResponse};
 use crate::plugin::config::{ConfigPlugin, ConfigPluginWrapper};
+use crate::plugin::event::{EventHandle, EventSubscriberPlugin, EventSubscriberWrapper};
 use crate::plugin::logger::{LoggerPlugin, LoggerPluginWrapper};
+use crate::plugin::table::static_table::StaticTable;
 use crate::plugin::table::{ReadOnlyTable, TablePlugin};
 use crate::plugin::Registry;
-use crate::plugin::{OsqueryPlugin, Table};
+use crate::plugin::{CancellationToken, ColumnDef, OsqueryPlugin, Table};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -12,6 +15,7 @@ pub enum Plugin {
     Config(Arc<dyn OsqueryPlugin>),
     Logger(Arc<dyn OsqueryPlugin>),
     Table(TablePlugin),
+    EventSubscriber(Arc<dyn OsqueryPlugin>),
 }
 
 impl Plugin {
@@ -23,6 +27,14 @@ impl Plugin {
         Plugin::Table(TablePlugin::from_readonly_table(t))
     }
 
+    /// A read-only table whose rows come from `generator`, see [`StaticTable`].
+    pub fn static_table<F>(name: &str, columns: Vec<ColumnDef>, generator: F) -> Self
+    where
+        F: Fn() -> Vec<BTreeMap<String, String>> + Send + Sync + 'static,
+    {
+        Self::readonly_table(StaticTable::new(name, columns, generator))
+    }
+
     pub fn config<C: ConfigPlugin + 'static>(c: C) -> Self {
         Plugin::Config(Arc::new(ConfigPluginWrapper::new(c)))
     }
@@ -30,6 +42,13 @@ impl Plugin {
     pub fn logger<L: LoggerPlugin + 'static>(l: L) -> Self {
         Plugin::Logger(Arc::new(LoggerPluginWrapper::new(l)))
     }
+
+    /// An event-backed table, plus the handle used to push events into it.
+    pub fn event_subscriber<S: EventSubscriberPlugin + 'static>(s: S) -> (Self, EventHandle) {
+        let wrapper = EventSubscriberWrapper::new(s);
+        let handle = wrapper.handle();
+        (Plugin::EventSubscriber(Arc::new(wrapper)), handle)
+    }
 }
 
 impl OsqueryPlugin for Plugin {
@@ -40,6 +59,7 @@ impl OsqueryPlugin for Plugin {
             Plugin::Config(c) => c.name(),
             Plugin::Logger(l) => l.name(),
             Plugin::Table(t) => t.name(),
+            Plugin::EventSubscriber(e) => e.name(),
         }
     }
 
@@ -49,6 +69,7 @@ impl OsqueryPlugin for Plugin {
             Plugin::Config(_) => Registry::Config,
             Plugin::Logger(_) => Registry::Logger,
             Plugin::Table(_) => Registry::Table,
+            Plugin::EventSubscriber(e) => e.registry(),
         }
     }
 
@@ -60,6 +81,7 @@ impl OsqueryPlugin for Plugin {
             Plugin::Config(c) => c.routes(),
             Plugin::Logger(l) => l.routes(),
             Plugin::Table(t) => t.routes(),
+            Plugin::EventSubscriber(e) => e.routes(),
         }
     }
 
@@ -70,6 +92,18 @@ impl OsqueryPlugin for Plugin {
             Plugin::Config(c) => c.ping(),
             Plugin::Logger(l) => l.ping(),
             Plugin::Table(t) => t.ping(),
+            Plugin::EventSubscriber(e) => e.ping(),
+        }
+    }
+
+    // Health reports whether the plugin is able to serve requests. It is
+    // aggregated across plugins when osquery pings the extension.
+    fn health(&self) -> osquery::ExtensionStatus {
+        match self {
+            Plugin::Config(c) => c.health(),
+            Plugin::Logger(l) => l.health(),
+            Plugin::Table(t) => t.health(),
+            Plugin::EventSubscriber(e) => e.health(),
         }
     }
 
@@ -80,6 +114,39 @@ impl OsqueryPlugin for Plugin {
             Plugin::Config(c) => c.handle_call(request),
             Plugin::Logger(l) => l.handle_call(request),
             Plugin::Table(t) => t.handle_call(request),
+            Plugin::EventSubscriber(e) => e.handle_call(request),
+        }
+    }
+
+    fn handle_call_cancellable(
+        &self,
+        request: ExtensionPluginRequest,
+        ctx: &CancellationToken,
+    ) -> ExtensionResponse {
+        match self {
+            Plugin::Config(c) => c.handle_call_cancellable(request, ctx),
+            Plugin::Logger(l) => l.handle_call_cancellable(request, ctx),
+            Plugin::Table(t) => t.handle_call_cancellable(request, ctx),
+            Plugin::EventSubscriber(e) => e.handle_call_cancellable(request, ctx),
+        }
+    }
+
+    fn min_osquery_version(&self) -> Option<semver::Version> {
+        match self {
+            Plugin::Config(c) => c.min_osquery_version(),
+            Plugin::Logger(l) => l.min_osquery_version(),
+            Plugin::Table(t) => t.min_osquery_version(),
+            Plugin::EventSubscriber(e) => e.min_osquery_version(),
+        }
+    }
+
+    // Flush asks the plugin to write out buffered data before shutdown.
+    fn flush(&self) -> Result<(), String> {
+        match self {
+            Plugin::Config(c) => c.flush(),
+            Plugin::Logger(l) => l.flush(),
+            Plugin::Table(t) => t.flush(),
+            Plugin::EventSubscriber(e) => e.flush(),
         }
     }
 
@@ -89,6 +156,7 @@ impl OsqueryPlugin for Plugin {
             Plugin::Config(c) => c.shutdown(),
             Plugin::Logger(l) => l.shutdown(),
             Plugin::Table(t) => t.shutdown(),
+            Plugin::EventSubscriber(e) => e.shutdown(),
         }
     }
 }
@@ -290,7 +358,7 @@ mod tests {
         let (logger, _flag) = TestLoggerPlugin::new();
         let plugin = Plugin::logger(logger);
         let mut request: BTreeMap<String, String> = BTreeMap::new();
-        request.insert("action".to_string(), "init".to_string());
+        request.insert("init".to_string(), "test_logger".to_string());
 
         let response = plugin.handle_call(request);
         let status = response.status.as_ref();
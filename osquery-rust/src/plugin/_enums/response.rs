@@ -1,13 +1,65 @@
 use crate::ExtensionResponse;
 use crate::_osquery::ExtensionStatus;
+use serde::Serialize;
+use serde_json::{Map, Value};
 use std::collections::BTreeMap;
+use std::fmt;
+
+/// Status codes a plugin can report in `ExtensionStatus.code`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusCode {
+    /// The request succeeded (code 0)
+    Success,
+    /// The request failed (code 1)
+    Failure,
+    /// The request failed but may succeed if retried later (code 2)
+    Transient,
+    /// Any other plugin-specific code
+    Other(i32),
+}
+
+impl StatusCode {
+    pub fn code(&self) -> i32 {
+        match self {
+            StatusCode::Success => 0,
+            StatusCode::Failure => 1,
+            StatusCode::Transient => 2,
+            StatusCode::Other(code) => *code,
+        }
+    }
+}
+
+impl From<i32> for StatusCode {
+    fn from(code: i32) -> Self {
+        match code {
+            0 => StatusCode::Success,
+            1 => StatusCode::Failure,
+            2 => StatusCode::Transient,
+            other => StatusCode::Other(other),
+        }
+    }
+}
+
+impl From<StatusCode> for i32 {
+    fn from(code: StatusCode) -> Self {
+        code.code()
+    }
+}
 
 pub enum ExtensionResponseEnum {
     Success(),
     SuccessWithId(u64),
     SuccessWithCode(i32),
     Failure(String),
+    FailureWithCode(i32, String),
     Constraint(),
+    /// The table doesn't accept writes.
+    ///
+    /// Answered with status code 0 and a `{"status": "readonly"}` row, the
+    /// same reply osquery's own read-only tables give: osquery only reads the
+    /// row when the call succeeded, and turns it into SQLite's
+    /// `SQLITE_READONLY` so the query fails with "attempt to write a readonly
+    /// database" instead of a generic error.
     Readonly(),
 }
 
@@ -18,12 +70,12 @@ impl From<ExtensionResponseEnum> for ExtensionResponse {
         let code = match value {
             ExtensionResponseEnum::Success() => {
                 resp.insert("status".to_string(), "success".to_string());
-                0
+                StatusCode::Success.code()
             }
             ExtensionResponseEnum::SuccessWithId(id) => {
                 resp.insert("status".to_string(), "success".to_string());
                 resp.insert("id".to_string(), id.to_string());
-                0
+                StatusCode::Success.code()
             }
             ExtensionResponseEnum::SuccessWithCode(code) => {
                 resp.insert("status".to_string(), "success".to_string());
@@ -32,15 +84,20 @@ impl From<ExtensionResponseEnum> for ExtensionResponse {
             ExtensionResponseEnum::Failure(msg) => {
                 resp.insert("status".to_string(), "failure".to_string());
                 resp.insert("message".to_string(), msg.to_string());
-                1
+                StatusCode::Failure.code()
+            }
+            ExtensionResponseEnum::FailureWithCode(code, msg) => {
+                resp.insert("status".to_string(), "failure".to_string());
+                resp.insert("message".to_string(), msg.to_string());
+                code
             }
             ExtensionResponseEnum::Constraint() => {
                 resp.insert("status".to_string(), "constraint".to_string());
-                1
+                StatusCode::Failure.code()
             }
             ExtensionResponseEnum::Readonly() => {
                 resp.insert("status".to_string(), "readonly".to_string());
-                1
+                StatusCode::Success.code()
             }
         };
 
@@ -48,6 +105,114 @@ impl From<ExtensionResponseEnum> for ExtensionResponse {
     }
 }
 
+/// A JSON row value that can't be represented as an osquery column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonRowError {
+    /// Index of the offending row in the input
+    pub row: usize,
+    /// Key of the nested array or object
+    pub column: String,
+}
+
+impl fmt::Display for JsonRowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Row {}: column '{}' is a nested array or object",
+            self.row, self.column
+        )
+    }
+}
+
+impl std::error::Error for JsonRowError {}
+
+impl ExtensionResponse {
+    /// Build a response from JSON objects, e.g. rows fetched from an upstream API.
+    ///
+    /// Strings are used as-is, numbers and booleans are stringified and `null`
+    /// becomes an empty string (osquery's NULL). Nested arrays and objects
+    /// have no column representation; every one of them is reported, so a
+    /// bad row is never dropped silently.
+    pub fn from_json_rows(
+        status: ExtensionStatus,
+        rows: impl IntoIterator<Item = Map<String, Value>>,
+    ) -> Result<Self, Vec<JsonRowError>> {
+        let mut response = Vec::new();
+        let mut errors = Vec::new();
+
+        for (index, row) in rows.into_iter().enumerate() {
+            let mut columns = BTreeMap::new();
+            for (column, value) in row {
+                let value = match value {
+                    Value::String(s) => s,
+                    Value::Null => String::new(),
+                    Value::Bool(_) | Value::Number(_) => value.to_string(),
+                    Value::Array(_) | Value::Object(_) => {
+                        errors.push(JsonRowError { row: index, column });
+                        continue;
+                    }
+                };
+                columns.insert(column, value);
+            }
+            response.push(columns);
+        }
+
+        if errors.is_empty() {
+            Ok(ExtensionResponse::new(status, response))
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// A successful table response with one row per serializable value, so a
+    /// `generate()` body can be a single expression:
+    ///
+    /// ```
+    /// use osquery_rust_ng::ExtensionResponse;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct User {
+    ///     username: String,
+    ///     uid: u32,
+    /// }
+    ///
+    /// let users = vec![User { username: "root".to_string(), uid: 0 }];
+    /// let rows = ExtensionResponse::ok_rows(&users).response.unwrap_or_default();
+    /// assert_eq!(rows[0]["uid"], "0");
+    /// ```
+    ///
+    /// Each value must serialize to a JSON object (e.g. a struct or map),
+    /// whose fields become columns as in [`from_json_rows`](Self::from_json_rows).
+    /// Values that don't fit a column turn the whole response into a failure
+    /// naming them. No rows is a success with an empty result.
+    pub fn ok_rows<T: Serialize>(rows: impl IntoIterator<Item = T>) -> Self {
+        let rows: Result<Vec<_>, String> = rows
+            .into_iter()
+            .enumerate()
+            .map(|(index, row)| match serde_json::to_value(row) {
+                Ok(Value::Object(row)) => Ok(row),
+                Ok(_) => Err(format!("Row {index}: not a JSON object")),
+                Err(e) => Err(format!("Row {index}: {e}")),
+            })
+            .collect();
+
+        let response = rows.and_then(|rows| {
+            ExtensionResponse::from_json_rows(ExtensionStatus::new(0, None, None), rows).map_err(
+                |errors| {
+                    errors
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                },
+            )
+        });
+
+        response.unwrap_or_else(|message| ExtensionResponseEnum::Failure(message).into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,8 +313,9 @@ mod tests {
     fn test_readonly_response() {
         let resp: ExtensionResponse = ExtensionResponseEnum::Readonly().into();
 
+        // osquery ignores the row of a failed call, so the code must be 0
         let status = resp.status.as_ref();
-        assert_eq!(status.and_then(|s| s.code), Some(1));
+        assert_eq!(status.and_then(|s| s.code), Some(0));
 
         let row = get_first_row(&resp);
         assert!(row.is_some());
@@ -158,4 +324,191 @@ mod tests {
             Some("readonly")
         );
     }
+
+    #[test]
+    fn test_failure_with_code_response() {
+        let resp: ExtensionResponse = ExtensionResponseEnum::FailureWithCode(
+            StatusCode::Transient.into(),
+            "try again".to_string(),
+        )
+        .into();
+
+        let status = resp.status.as_ref();
+        assert_eq!(status.and_then(|s| s.code), Some(2));
+
+        let row = get_first_row(&resp);
+        assert_eq!(
+            row.and_then(|r| r.get("status")).map(|s| s.as_str()),
+            Some("failure")
+        );
+        assert_eq!(
+            row.and_then(|r| r.get("message")).map(|s| s.as_str()),
+            Some("try again")
+        );
+    }
+
+    #[test]
+    fn test_variants_map_to_status_codes() {
+        let cases: Vec<(ExtensionResponseEnum, StatusCode)> = vec![
+            (ExtensionResponseEnum::Success(), StatusCode::Success),
+            (ExtensionResponseEnum::SuccessWithId(1), StatusCode::Success),
+            (
+                ExtensionResponseEnum::SuccessWithCode(7),
+                StatusCode::Other(7),
+            ),
+            (
+                ExtensionResponseEnum::Failure("x".to_string()),
+                StatusCode::Failure,
+            ),
+            (
+                ExtensionResponseEnum::FailureWithCode(2, "x".to_string()),
+                StatusCode::Transient,
+            ),
+            (ExtensionResponseEnum::Constraint(), StatusCode::Failure),
+            (ExtensionResponseEnum::Readonly(), StatusCode::Success),
+        ];
+
+        for (variant, expected) in cases {
+            let resp: ExtensionResponse = variant.into();
+            let code = resp.status.and_then(|s| s.code).map(StatusCode::from);
+            assert_eq!(code, Some(expected));
+        }
+    }
+
+    fn json_rows(value: Value) -> Vec<Map<String, Value>> {
+        match value {
+            Value::Array(rows) => rows
+                .into_iter()
+                .filter_map(|row| match row {
+                    Value::Object(row) => Some(row),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_from_json_rows_stringifies_scalars() {
+        let rows = json_rows(serde_json::json!([
+            {"name": "eth0", "mtu": 1500, "up": true, "ratio": 0.5, "alias": null},
+            {"name": "lo", "mtu": 65536, "up": false},
+        ]));
+
+        let resp = ExtensionResponse::from_json_rows(ExtensionStatus::new(0, None, None), rows);
+        let rows = resp.ok().and_then(|resp| resp.response).unwrap_or_default();
+
+        let expected: Vec<BTreeMap<String, String>> = vec![
+            [
+                ("alias", ""),
+                ("mtu", "1500"),
+                ("name", "eth0"),
+                ("ratio", "0.5"),
+                ("up", "true"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+            [("mtu", "65536"), ("name", "lo"), ("up", "false")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        ];
+        assert_eq!(rows, expected);
+    }
+
+    #[test]
+    fn test_from_json_rows_reports_nested_values() {
+        let rows = json_rows(serde_json::json!([
+            {"name": "eth0"},
+            {"name": "eth1", "addresses": {"ipv4": "10.0.0.1"}, "tags": ["a"]},
+        ]));
+
+        let resp = ExtensionResponse::from_json_rows(ExtensionStatus::new(0, None, None), rows);
+        let errors = resp.err().unwrap_or_default();
+
+        assert_eq!(
+            errors,
+            vec![
+                JsonRowError {
+                    row: 1,
+                    column: "addresses".to_string()
+                },
+                JsonRowError {
+                    row: 1,
+                    column: "tags".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            errors.first().map(ToString::to_string).as_deref(),
+            Some("Row 1: column 'addresses' is a nested array or object")
+        );
+    }
+
+    #[derive(Serialize)]
+    struct Process {
+        pid: u32,
+        name: String,
+        parent: Option<u32>,
+    }
+
+    #[test]
+    fn test_ok_rows_serializes_structs() {
+        let processes = [
+            Process {
+                pid: 1,
+                name: "launchd".to_string(),
+                parent: None,
+            },
+            Process {
+                pid: 42,
+                name: "osqueryd".to_string(),
+                parent: Some(1),
+            },
+        ];
+
+        let resp = ExtensionResponse::ok_rows(&processes);
+
+        assert_eq!(resp.status.as_ref().and_then(|s| s.code), Some(0));
+        let row = |pid: &str, name: &str, parent: &str| {
+            BTreeMap::from([
+                ("pid".to_string(), pid.to_string()),
+                ("name".to_string(), name.to_string()),
+                ("parent".to_string(), parent.to_string()),
+            ])
+        };
+        assert_eq!(
+            resp.response,
+            Some(vec![row("1", "launchd", ""), row("42", "osqueryd", "1")])
+        );
+    }
+
+    #[test]
+    fn test_ok_rows_empty_is_success_without_rows() {
+        let resp = ExtensionResponse::ok_rows(Vec::<Process>::new());
+
+        assert_eq!(resp.status.as_ref().and_then(|s| s.code), Some(0));
+        assert_eq!(resp.response, Some(vec![]));
+    }
+
+    #[test]
+    fn test_ok_rows_rejects_values_that_are_not_rows() {
+        let resp = ExtensionResponse::ok_rows(["not a row"]);
+
+        assert_eq!(resp.status.as_ref().and_then(|s| s.code), Some(1));
+        assert_eq!(
+            get_first_row(&resp)
+                .and_then(|r| r.get("message"))
+                .map(String::as_str),
+            Some("Row 0: not a JSON object")
+        );
+    }
+
+    #[test]
+    fn test_status_code_round_trip() {
+        for code in [0, 1, 2, 42, -1] {
+            assert_eq!(StatusCode::from(code).code(), code);
+        }
+    }
 }
@@ -1,13 +1,39 @@
-use crate::plugin::Registry;
+use crate::plugin::{CancellationToken, Registry};
 
 pub trait OsqueryPlugin: Send + Sync {
     fn name(&self) -> String;
     fn registry(&self) -> Registry;
     fn routes(&self) -> crate::_osquery::ExtensionPluginResponse;
     fn ping(&self) -> crate::_osquery::ExtensionStatus;
+    /// Report the plugin's health. Aggregated into the extension's ping response,
+    /// so a non-zero code lets osquery's watchdog notice an unhealthy plugin.
+    fn health(&self) -> crate::_osquery::ExtensionStatus {
+        crate::_osquery::ExtensionStatus::new(0, None, None)
+    }
     fn handle_call(
         &self,
         request: crate::_osquery::ExtensionPluginRequest,
     ) -> crate::_osquery::ExtensionResponse;
+    /// Like `handle_call`, with a token that is cancelled once the extension
+    /// starts shutting down. The server calls this; the default ignores the
+    /// token.
+    fn handle_call_cancellable(
+        &self,
+        request: crate::_osquery::ExtensionPluginRequest,
+        _ctx: &CancellationToken,
+    ) -> crate::_osquery::ExtensionResponse {
+        self.handle_call(request)
+    }
+    /// The oldest osquery this plugin works with. When set, `start()` asks
+    /// osquery for its version and leaves the plugin out of the registration
+    /// on older daemons. The default, `None`, works with any version.
+    fn min_osquery_version(&self) -> Option<semver::Version> {
+        None
+    }
+    /// Write out anything the plugin still buffers. Called on every plugin
+    /// before any of them is shut down; failures are logged.
+    fn flush(&self) -> Result<(), String> {
+        Ok(())
+    }
     fn shutdown(&self);
 }
@@ -1,8 +1,13 @@
 use crate::_osquery::{ExtensionPluginResponse, ExtensionResponse, ExtensionStatus};
 use crate::plugin::{ExtensionResponseEnum, OsqueryPlugin, Registry};
+use crate::PluginRequest;
 use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
+mod validate;
+
+pub use validate::{validate_config, ConfigValidationError};
+
 /// Trait for implementing configuration plugins in osquery-rust.
 ///
 /// Configuration plugins provide osquery with its configuration data,
@@ -16,6 +21,12 @@ pub trait ConfigPlugin: Send + Sync + 'static {
     ///
     /// Returns a map of config source names to JSON-encoded configuration strings.
     /// The map typically contains a "main" key with the primary configuration.
+    ///
+    /// Return `Ok(HashMap::new())` when the source has nothing to offer yet,
+    /// e.g. a remote backend that is still starting up. The wrapper answers
+    /// with a successful response carrying no rows, which osquery treats as
+    /// "no update" and keeps its current config. `Err` is reported as a
+    /// failure instead.
     fn gen_config(&self) -> Result<HashMap<String, String>, String>;
 
     /// Generate pack configuration.
@@ -26,6 +37,19 @@ pub trait ConfigPlugin: Send + Sync + 'static {
         Err(format!("Pack '{name}' not found"))
     }
 
+    /// Generate several packs at once, keyed by pack name.
+    ///
+    /// Called for bulk `genPack` requests, which list the packs in a `names`
+    /// JSON array instead of a single `name`. Override this when the backend
+    /// can resolve a pack and its dependencies in one round trip. The default
+    /// calls `gen_pack` for each name and fails if any of them fails.
+    fn gen_packs(&self, names: &[&str]) -> Result<HashMap<String, String>, String> {
+        names
+            .iter()
+            .map(|name| Ok((name.to_string(), self.gen_pack(name, "")?)))
+            .collect()
+    }
+
     /// Called when the plugin is shutting down.
     fn shutdown(&self) {}
 }
@@ -64,12 +88,27 @@ impl OsqueryPlugin for ConfigPluginWrapper {
 
     fn handle_call(&self, request: crate::_osquery::ExtensionPluginRequest) -> ExtensionResponse {
         // Config plugins handle two actions: genConfig and genPack
-        let action = request.get("action").map(|s| s.as_str()).unwrap_or("");
+        let request = PluginRequest::new(&request);
+        let action = request.action();
 
         match action {
             "genConfig" => {
                 match self.plugin.gen_config() {
+                    // No rows tells osquery to keep the config it already has
+                    Ok(config_map) if config_map.is_empty() => {
+                        ExtensionResponse::new(ExtensionStatus::new(0, None, None), vec![])
+                    }
                     Ok(config_map) => {
+                        #[cfg(feature = "validate")]
+                        for (source, config) in &config_map {
+                            if let Err(e) = validate_config(config) {
+                                return ExtensionResponseEnum::Failure(format!(
+                                    "Config source '{source}' is invalid: {e}"
+                                ))
+                                .into();
+                            }
+                        }
+
                         let mut response = ExtensionPluginResponse::new();
                         let mut row = BTreeMap::new();
 
@@ -85,11 +124,32 @@ impl OsqueryPlugin for ConfigPluginWrapper {
                     Err(e) => ExtensionResponseEnum::Failure(e).into(),
                 }
             }
+            "genPack" if request.names().is_some() => {
+                let names = match request.names() {
+                    Some(Ok(names)) => names,
+                    _ => {
+                        return ExtensionResponseEnum::Failure(
+                            "Could not parse the pack names".to_string(),
+                        )
+                        .into()
+                    }
+                };
+                let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+                match self.plugin.gen_packs(&names) {
+                    Ok(packs) => {
+                        let row: BTreeMap<String, String> = packs.into_iter().collect();
+                        let status = ExtensionStatus::new(0, None, None);
+                        ExtensionResponse::new(status, vec![row])
+                    }
+                    Err(e) => ExtensionResponseEnum::Failure(e).into(),
+                }
+            }
             "genPack" => {
-                let name = request.get("name").cloned().unwrap_or_default();
-                let value = request.get("value").cloned().unwrap_or_default();
+                let name = request.name().unwrap_or_default();
+                let value = request.value().unwrap_or_default();
 
-                match self.plugin.gen_pack(&name, &value) {
+                match self.plugin.gen_pack(name, value) {
                     Ok(pack_content) => {
                         let mut response = ExtensionPluginResponse::new();
                         let mut row = BTreeMap::new();
@@ -202,6 +262,28 @@ mod tests {
         assert!(row.map(|r| r.contains_key("main")).unwrap_or(false));
     }
 
+    #[cfg(feature = "validate")]
+    #[test]
+    fn test_gen_config_rejects_invalid_config() {
+        let mut config = TestConfig::new();
+        config
+            .config
+            .insert("main".to_string(), r#"{"options":[]}"#.to_string());
+        let wrapper = ConfigPluginWrapper::new(config);
+
+        let mut request: BTreeMap<String, String> = BTreeMap::new();
+        request.insert("action".to_string(), "genConfig".to_string());
+
+        let response = wrapper.handle_call(request);
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(1));
+        assert_eq!(
+            get_first_row(&response)
+                .and_then(|r| r.get("message"))
+                .map(|s| s.as_str()),
+            Some("Config source 'main' is invalid: Config section 'options' must be an object")
+        );
+    }
+
     #[test]
     fn test_gen_config_failure_returns_error() {
         let config = TestConfig::failing();
@@ -227,7 +309,7 @@ mod tests {
     }
 
     #[test]
-    fn test_gen_config_empty_map_returns_empty_response() {
+    fn test_gen_config_empty_map_returns_no_update() {
         let config = TestConfig::empty();
         let wrapper = ConfigPluginWrapper::new(config);
 
@@ -240,13 +322,34 @@ mod tests {
         let status = response.status.as_ref();
         assert_eq!(status.and_then(|s| s.code), Some(0));
 
-        // Response should have one row but it's empty
-        let empty_vec = vec![];
-        let rows = response.response.as_ref().unwrap_or(&empty_vec);
-        assert_eq!(rows.len(), 1);
-        let row = get_first_row(&response);
-        assert!(row.is_some());
-        assert!(row.map(|r| r.is_empty()).unwrap_or(false));
+        // No rows at all, so osquery keeps its current config
+        assert_eq!(response.response.as_ref().map(Vec::len), Some(0));
+    }
+
+    #[test]
+    fn test_gen_config_distinguishes_empty_populated_and_error() {
+        let mut request: BTreeMap<String, String> = BTreeMap::new();
+        request.insert("action".to_string(), "genConfig".to_string());
+
+        let outcomes: Vec<(Option<i32>, Option<usize>)> = [
+            TestConfig::empty(),
+            TestConfig::new(),
+            TestConfig::failing(),
+        ]
+        .into_iter()
+        .map(|config| {
+            let response = ConfigPluginWrapper::new(config).handle_call(request.clone());
+            (
+                response.status.as_ref().and_then(|s| s.code),
+                response.response.as_ref().map(Vec::len),
+            )
+        })
+        .collect();
+
+        assert_eq!(
+            outcomes,
+            vec![(Some(0), Some(0)), (Some(0), Some(1)), (Some(1), Some(1))]
+        );
     }
 
     #[test]
@@ -294,6 +397,87 @@ mod tests {
         );
     }
 
+    /// Resolves packs together with the packs they depend on.
+    struct BundledPacks;
+
+    impl ConfigPlugin for BundledPacks {
+        fn name(&self) -> String {
+            "bundled".to_string()
+        }
+
+        fn gen_config(&self) -> Result<HashMap<String, String>, String> {
+            Ok(HashMap::new())
+        }
+
+        fn gen_pack(&self, name: &str, _value: &str) -> Result<String, String> {
+            Err(format!("Pack '{name}' is only served in bulk"))
+        }
+
+        fn gen_packs(&self, names: &[&str]) -> Result<HashMap<String, String>, String> {
+            let mut packs = HashMap::new();
+            for name in names {
+                packs.insert(name.to_string(), format!(r#"{{"name":"{name}"}}"#));
+                packs.insert("base".to_string(), r#"{"name":"base"}"#.to_string());
+            }
+            Ok(packs)
+        }
+    }
+
+    fn bulk_pack_request(names: &str) -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("action".to_string(), "genPack".to_string()),
+            ("names".to_string(), names.to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_gen_packs_returns_all_packs() {
+        let wrapper = ConfigPluginWrapper::new(BundledPacks);
+
+        let response = wrapper.handle_call(bulk_pack_request(r#"["security"]"#));
+
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(0));
+        let row = get_first_row(&response);
+        assert_eq!(
+            row.and_then(|r| r.get("security")).map(String::as_str),
+            Some(r#"{"name":"security"}"#)
+        );
+        assert_eq!(
+            row.and_then(|r| r.get("base")).map(String::as_str),
+            Some(r#"{"name":"base"}"#)
+        );
+    }
+
+    #[test]
+    fn test_gen_packs_default_calls_gen_pack_per_name() {
+        let config = TestConfig::new()
+            .with_pack("security", r#"{"queries":{}}"#)
+            .with_pack("compliance", r#"{"discovery":[]}"#);
+        let wrapper = ConfigPluginWrapper::new(config);
+
+        let response = wrapper.handle_call(bulk_pack_request(r#"["security","compliance"]"#));
+
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(0));
+        let row = get_first_row(&response).cloned().unwrap_or_default();
+        assert_eq!(
+            row,
+            BTreeMap::from([
+                ("compliance".to_string(), r#"{"discovery":[]}"#.to_string()),
+                ("security".to_string(), r#"{"queries":{}}"#.to_string()),
+            ])
+        );
+
+        // One missing pack fails the whole request
+        let response = wrapper.handle_call(bulk_pack_request(r#"["security","missing"]"#));
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(1));
+        assert_eq!(
+            get_first_row(&response)
+                .and_then(|r| r.get("message"))
+                .map(String::as_str),
+            Some("Pack 'missing' not found")
+        );
+    }
+
     #[test]
     fn test_unknown_action_returns_error() {
         let config = TestConfig::new();
@@ -0,0 +1,193 @@
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// Why a configuration was rejected by [`validate_config`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigValidationError {
+    /// The configuration is not valid JSON.
+    InvalidJson(String),
+    /// The configuration is valid JSON but not a JSON object.
+    NotAnObject,
+    /// A top-level section that osquery expects to be an object is not one.
+    SectionNotObject { section: String },
+    /// A scheduled query is not an object or has no `query` string.
+    InvalidQuery { name: String },
+    /// A scheduled query's `interval` is not a positive integer.
+    InvalidInterval { name: String, interval: String },
+    /// A pack is neither a path/URL string nor an inline pack object.
+    InvalidPack { name: String },
+}
+
+impl fmt::Display for ConfigValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigValidationError::InvalidJson(e) => write!(f, "Config is not valid JSON: {e}"),
+            ConfigValidationError::NotAnObject => write!(f, "Config must be a JSON object"),
+            ConfigValidationError::SectionNotObject { section } => {
+                write!(f, "Config section '{section}' must be an object")
+            }
+            ConfigValidationError::InvalidQuery { name } => {
+                write!(
+                    f,
+                    "Scheduled query '{name}' must be an object with a 'query' string"
+                )
+            }
+            ConfigValidationError::InvalidInterval { name, interval } => write!(
+                f,
+                "Scheduled query '{name}' interval must be a positive integer, got {interval}"
+            ),
+            ConfigValidationError::InvalidPack { name } => {
+                write!(f, "Pack '{name}' must be a path string or an object")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigValidationError {}
+
+/// Check that `json` has the top-level shape osquery requires of a config.
+///
+/// This is a dry run of the checks osquery performs when loading the output of
+/// [`ConfigPlugin::gen_config`](crate::plugin::ConfigPlugin::gen_config):
+/// `options`, `schedule`, `file_paths` and `packs` must be objects, every
+/// scheduled query (including those in inline packs) needs a `query` string and
+/// a positive integer `interval`, and packs are either a path/URL string or an
+/// inline object. Unknown sections are allowed.
+pub fn validate_config(json: &str) -> Result<(), ConfigValidationError> {
+    let config: Value = serde_json::from_str(json)
+        .map_err(|e| ConfigValidationError::InvalidJson(e.to_string()))?;
+    let Value::Object(config) = config else {
+        return Err(ConfigValidationError::NotAnObject);
+    };
+
+    for section in ["options", "file_paths"] {
+        section_object(&config, section)?;
+    }
+
+    if let Some(schedule) = section_object(&config, "schedule")? {
+        validate_schedule(schedule)?;
+    }
+
+    if let Some(packs) = section_object(&config, "packs")? {
+        for (name, pack) in packs {
+            match pack {
+                Value::String(_) => {}
+                Value::Object(pack) => {
+                    if let Some(queries) = section_object(pack, "queries")? {
+                        validate_schedule(queries)?;
+                    }
+                }
+                _ => return Err(ConfigValidationError::InvalidPack { name: name.clone() }),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Get `section` from `config` if present, failing if it is not an object.
+fn section_object<'a>(
+    config: &'a Map<String, Value>,
+    section: &str,
+) -> Result<Option<&'a Map<String, Value>>, ConfigValidationError> {
+    match config.get(section) {
+        None => Ok(None),
+        Some(Value::Object(map)) => Ok(Some(map)),
+        Some(_) => Err(ConfigValidationError::SectionNotObject {
+            section: section.to_string(),
+        }),
+    }
+}
+
+fn validate_schedule(schedule: &Map<String, Value>) -> Result<(), ConfigValidationError> {
+    for (name, query) in schedule {
+        let has_query = query.get("query").is_some_and(Value::is_string);
+        if !query.is_object() || !has_query {
+            return Err(ConfigValidationError::InvalidQuery { name: name.clone() });
+        }
+
+        if let Some(interval) = query.get("interval") {
+            if interval.as_u64().is_none_or(|i| i == 0) {
+                return Err(ConfigValidationError::InvalidInterval {
+                    name: name.clone(),
+                    interval: interval.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_config() {
+        let config = r#"{
+            "options": {"host_identifier": "hostname"},
+            "schedule": {
+                "uptime": {"query": "SELECT * FROM uptime;", "interval": 60}
+            },
+            "file_paths": {"etc": ["/etc/%%"]},
+            "packs": {
+                "external": "/etc/osquery/packs/external.conf",
+                "inline": {"queries": {"users": {"query": "SELECT * FROM users;", "interval": 3600}}}
+            }
+        }"#;
+        assert_eq!(validate_config(config), Ok(()));
+        assert_eq!(validate_config("{}"), Ok(()));
+    }
+
+    #[test]
+    fn test_non_integer_interval() {
+        let config = r#"{"schedule": {"uptime": {"query": "SELECT 1;", "interval": "60s"}}}"#;
+        assert_eq!(
+            validate_config(config),
+            Err(ConfigValidationError::InvalidInterval {
+                name: "uptime".to_string(),
+                interval: "\"60s\"".to_string(),
+            })
+        );
+
+        let config = r#"{"schedule": {"uptime": {"query": "SELECT 1;", "interval": 0}}}"#;
+        assert!(matches!(
+            validate_config(config),
+            Err(ConfigValidationError::InvalidInterval { .. })
+        ));
+    }
+
+    #[test]
+    fn test_non_object_options() {
+        let result = validate_config(r#"{"options": ["verbose"]}"#);
+        assert_eq!(
+            result,
+            Err(ConfigValidationError::SectionNotObject {
+                section: "options".to_string(),
+            })
+        );
+        assert_eq!(
+            result.map_err(|e| e.to_string()),
+            Err("Config section 'options' must be an object".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_json_and_shape() {
+        assert!(matches!(
+            validate_config("{"),
+            Err(ConfigValidationError::InvalidJson(_))
+        ));
+        assert_eq!(
+            validate_config("[]"),
+            Err(ConfigValidationError::NotAnObject)
+        );
+        assert_eq!(
+            validate_config(r#"{"packs": {"bad": 1}}"#),
+            Err(ConfigValidationError::InvalidPack {
+                name: "bad".to_string()
+            })
+        );
+    }
+}
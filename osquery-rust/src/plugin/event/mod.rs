@@ -0,0 +1,475 @@
+//! Event subscriber plugins: tables fed by events pushed from other threads.
+//!
+//! osquery builds `*_events` tables from its internal publisher/subscriber
+//! framework, which extensions cannot register into. This module provides the
+//! same model for extensions: the subscriber describes its columns, events are
+//! pushed into a buffer through an [`EventHandle`] (e.g. from a file watcher
+//! thread), and each query returns the events still in the buffer.
+//!
+//! As in osquery's event tables, every row gets a `time` (when it was pushed,
+//! in seconds since the epoch) and an `eid` column, and events are kept
+//! across queries until they are older than the expiry (an hour by default,
+//! like `--events_expiry`) or pushed out by newer ones once the buffer is
+//! full (50,000 events, like `--events_max`). Scheduled queries use `time` to
+//! pick up only the events since their last run. The subscriber is
+//! registered in osquery's `table` registry.
+//!
+//! ```
+//! use osquery_rust_ng::plugin::{ColumnDef, ColumnOptions, ColumnType};
+//! use osquery_rust_ng::plugin::{EventSubscriberPlugin, Plugin};
+//! use std::collections::BTreeMap;
+//!
+//! struct FileEvents;
+//!
+//! impl EventSubscriberPlugin for FileEvents {
+//!     fn name(&self) -> String {
+//!         "rust_file_events".to_string()
+//!     }
+//!
+//!     fn columns(&self) -> Vec<ColumnDef> {
+//!         vec![ColumnDef::new("path", ColumnType::Text, ColumnOptions::DEFAULT)]
+//!     }
+//! }
+//!
+//! let (plugin, events) = Plugin::event_subscriber(FileEvents);
+//! events.push(BTreeMap::from([("path".to_string(), "/etc/hosts".to_string())]));
+//! ```
+
+use crate::_osquery::{
+    ExtensionPluginRequest, ExtensionPluginResponse, ExtensionResponse, ExtensionStatus,
+};
+use crate::plugin::table::{column_routes, query_constraint};
+use crate::plugin::{
+    ColumnDef, ColumnOptions, ColumnType, ExtensionResponseEnum, OsqueryPlugin, QueryConstraints,
+    Registry,
+};
+use crate::{log_target, ExtensionPluginRequestExt, PluginRequest};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Events kept in the buffer before the oldest ones are dropped.
+const DEFAULT_MAX_EVENTS: usize = 50_000;
+
+/// How long an event is kept before it expires.
+const DEFAULT_EXPIRY: Duration = Duration::from_secs(3600);
+
+/// Seconds since the epoch.
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Trait for implementing event-backed tables.
+pub trait EventSubscriberPlugin: Send + Sync + 'static {
+    /// The name of the table the events are exposed as
+    fn name(&self) -> String;
+
+    /// The columns of each event row
+    fn columns(&self) -> Vec<ColumnDef>;
+
+    /// Apply a subscription configuration sent with a `configure` action.
+    ///
+    /// `subscription` is passed through as sent, typically JSON describing
+    /// what to watch.
+    fn configure(&self, _subscription: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Turn the buffered events into rows.
+    ///
+    /// Each event has its `time` and `eid` columns filled in. The default
+    /// returns the events unchanged; override it to filter on
+    /// `constraints` or to enrich rows.
+    fn generate(
+        &self,
+        _constraints: &QueryConstraints,
+        events: Vec<BTreeMap<String, String>>,
+    ) -> Vec<BTreeMap<String, String>> {
+        events
+    }
+
+    /// Called when the plugin is shutting down.
+    fn shutdown(&self) {}
+}
+
+/// Buffered events, oldest first, with the time each was pushed.
+#[derive(Default)]
+struct EventBuffer {
+    events: VecDeque<(u64, BTreeMap<String, String>)>,
+    next_eid: u64,
+}
+
+impl EventBuffer {
+    /// Drop the events pushed before `cutoff`.
+    fn expire(&mut self, cutoff: u64) {
+        while self.events.front().is_some_and(|(time, _)| *time < cutoff) {
+            self.events.pop_front();
+        }
+    }
+}
+
+/// Handle for pushing events to a subscriber's buffer from any thread.
+#[derive(Clone)]
+pub struct EventHandle {
+    buffer: Arc<Mutex<EventBuffer>>,
+    max_events: usize,
+    expiry: Duration,
+}
+
+impl EventHandle {
+    /// Buffer an event row until it expires.
+    ///
+    /// The row's `time` is set to now and its `eid` to the next event id,
+    /// replacing any values it already had. When the buffer is full the
+    /// oldest event is dropped.
+    pub fn push(&self, event: BTreeMap<String, String>) {
+        self.push_at(unix_now(), event);
+    }
+
+    fn push_at(&self, time: u64, mut event: BTreeMap<String, String>) {
+        let Ok(mut buffer) = self.buffer.lock() else {
+            log::error!(target: log_target::PLUGIN, "Event buffer was unavailable, dropping event");
+            return;
+        };
+
+        let eid = buffer.next_eid;
+        buffer.next_eid += 1;
+        event.insert("time".to_string(), time.to_string());
+        event.insert("eid".to_string(), format!("{eid:010}"));
+
+        if buffer.events.len() >= self.max_events {
+            buffer.events.pop_front();
+        }
+        buffer.events.push_back((time, event));
+    }
+
+    /// Number of events in the buffer, including any that have expired
+    /// since the last query.
+    pub fn len(&self) -> usize {
+        self.buffer.lock().map(|b| b.events.len()).unwrap_or(0)
+    }
+
+    /// Whether the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Expire old events and copy out the rest, as of `now`.
+    fn events(&self, now: u64) -> Vec<BTreeMap<String, String>> {
+        let Ok(mut buffer) = self.buffer.lock() else {
+            return Vec::new();
+        };
+
+        buffer.expire(now.saturating_sub(self.expiry.as_secs()));
+        buffer.events.iter().map(|(_, e)| e.clone()).collect()
+    }
+}
+
+/// Wrapper that adapts an EventSubscriberPlugin to the OsqueryPlugin interface.
+#[derive(Clone)]
+pub struct EventSubscriberWrapper {
+    subscriber: Arc<dyn EventSubscriberPlugin>,
+    events: EventHandle,
+}
+
+impl EventSubscriberWrapper {
+    pub fn new<S: EventSubscriberPlugin>(subscriber: S) -> Self {
+        Self::with_capacity(subscriber, DEFAULT_MAX_EVENTS)
+    }
+
+    /// Create a wrapper that buffers at most `max_events` events.
+    pub fn with_capacity<S: EventSubscriberPlugin>(subscriber: S, max_events: usize) -> Self {
+        Self::with_limits(subscriber, max_events, DEFAULT_EXPIRY)
+    }
+
+    /// Create a wrapper that buffers at most `max_events` events and expires
+    /// each one `expiry` after it was pushed.
+    pub fn with_limits<S: EventSubscriberPlugin>(
+        subscriber: S,
+        max_events: usize,
+        expiry: Duration,
+    ) -> Self {
+        Self {
+            subscriber: Arc::new(subscriber),
+            events: EventHandle {
+                buffer: Arc::new(Mutex::new(EventBuffer::default())),
+                max_events,
+                expiry,
+            },
+        }
+    }
+
+    /// A handle for pushing events into this subscriber's buffer.
+    pub fn handle(&self) -> EventHandle {
+        self.events.clone()
+    }
+
+    /// The subscriber's columns, plus `time` and `eid` unless it declares
+    /// them itself.
+    fn columns(&self) -> Vec<ColumnDef> {
+        let mut columns = self.subscriber.columns();
+        for (name, t, o) in [
+            ("time", ColumnType::BigInt, ColumnOptions::DEFAULT),
+            ("eid", ColumnType::Text, ColumnOptions::HIDDEN),
+        ] {
+            if !columns.iter().any(|c| c.name() == name) {
+                columns.push(ColumnDef::new(name, t, o));
+            }
+        }
+        columns
+    }
+
+    fn generate(&self, request: &ExtensionPluginRequest) -> ExtensionResponse {
+        self.generate_at(request, unix_now())
+    }
+
+    fn generate_at(&self, request: &ExtensionPluginRequest, now: u64) -> ExtensionResponse {
+        let constraints = request
+            .context()
+            .as_ref()
+            .map(query_constraint::parse_constraints)
+            .unwrap_or_default();
+
+        let rows = self
+            .subscriber
+            .generate(&constraints, self.events.events(now));
+        ExtensionResponse::new(ExtensionStatus::new(0, None, None), rows)
+    }
+}
+
+impl OsqueryPlugin for EventSubscriberWrapper {
+    fn name(&self) -> String {
+        self.subscriber.name()
+    }
+
+    fn registry(&self) -> Registry {
+        Registry::Table
+    }
+
+    fn routes(&self) -> ExtensionPluginResponse {
+        column_routes(&self.columns())
+    }
+
+    fn ping(&self) -> ExtensionStatus {
+        ExtensionStatus::new(0, None, None)
+    }
+
+    fn handle_call(&self, request: ExtensionPluginRequest) -> ExtensionResponse {
+        let action = PluginRequest::new(&request).action();
+
+        match action {
+            "columns" => ExtensionResponse::new(ExtensionStatus::new(0, None, None), self.routes()),
+            "generate" => self.generate(&request),
+            "configure" => {
+                let subscription = request
+                    .get("subscription")
+                    .map(|s| s.as_str())
+                    .unwrap_or("");
+                match self.subscriber.configure(subscription) {
+                    Ok(()) => ExtensionResponseEnum::Success().into(),
+                    Err(e) => ExtensionResponseEnum::Failure(e).into(),
+                }
+            }
+            _ => ExtensionResponseEnum::Failure(format!(
+                "Invalid event subscriber action:{action:?}"
+            ))
+            .into(),
+        }
+    }
+
+    fn shutdown(&self) {
+        self.subscriber.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::{ColumnOptions, ColumnType};
+
+    struct PathEvents {
+        configured: Arc<Mutex<Option<String>>>,
+    }
+
+    impl EventSubscriberPlugin for PathEvents {
+        fn name(&self) -> String {
+            "path_events".to_string()
+        }
+
+        fn columns(&self) -> Vec<ColumnDef> {
+            vec![ColumnDef::new(
+                "path",
+                ColumnType::Text,
+                ColumnOptions::DEFAULT,
+            )]
+        }
+
+        fn configure(&self, subscription: &str) -> Result<(), String> {
+            if subscription.is_empty() {
+                return Err("empty subscription".to_string());
+            }
+            if let Ok(mut configured) = self.configured.lock() {
+                *configured = Some(subscription.to_string());
+            }
+            Ok(())
+        }
+
+        fn generate(
+            &self,
+            constraints: &QueryConstraints,
+            events: Vec<BTreeMap<String, String>>,
+        ) -> Vec<BTreeMap<String, String>> {
+            // Report how many path constraints were seen alongside each event
+            let count = constraints.get("path").map(|l| l.len()).unwrap_or(0);
+            events
+                .into_iter()
+                .map(|mut e| {
+                    e.insert("constraints".to_string(), count.to_string());
+                    e
+                })
+                .collect()
+        }
+    }
+
+    fn wrapper() -> (EventSubscriberWrapper, Arc<Mutex<Option<String>>>) {
+        let configured = Arc::new(Mutex::new(None));
+        let wrapper = EventSubscriberWrapper::new(PathEvents {
+            configured: configured.clone(),
+        });
+        (wrapper, configured)
+    }
+
+    fn path_event(path: &str) -> BTreeMap<String, String> {
+        BTreeMap::from([("path".to_string(), path.to_string())])
+    }
+
+    #[test]
+    fn test_configure_action() {
+        let (wrapper, configured) = wrapper();
+
+        let request = BTreeMap::from([
+            ("action".to_string(), "configure".to_string()),
+            (
+                "subscription".to_string(),
+                r#"{"paths":["/etc"]}"#.to_string(),
+            ),
+        ]);
+        let response = wrapper.handle_call(request);
+        assert_eq!(response.status.and_then(|s| s.code), Some(0));
+        assert_eq!(
+            configured.lock().ok().and_then(|c| c.clone()).as_deref(),
+            Some(r#"{"paths":["/etc"]}"#)
+        );
+
+        let request = BTreeMap::from([("action".to_string(), "configure".to_string())]);
+        let response = wrapper.handle_call(request);
+        assert_eq!(response.status.and_then(|s| s.code), Some(1));
+    }
+
+    fn generate_request() -> ExtensionPluginRequest {
+        BTreeMap::from([
+            ("action".to_string(), "generate".to_string()),
+            (
+                "context".to_string(),
+                r#"{"constraints":[{"name":"path","affinity":"TEXT","list":[{"op":2,"expr":"/etc/hosts"}]}]}"#
+                    .to_string(),
+            ),
+        ])
+    }
+
+    fn paths(rows: &[BTreeMap<String, String>]) -> Vec<&str> {
+        rows.iter()
+            .filter_map(|r| r.get("path"))
+            .map(|s| s.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_generate_keeps_buffered_events() {
+        let (wrapper, _) = wrapper();
+        let events = wrapper.handle();
+        events.push_at(1000, path_event("/etc/hosts"));
+        events.push_at(1001, path_event("/etc/passwd"));
+        assert_eq!(events.len(), 2);
+
+        let response = wrapper.generate_at(&generate_request(), 1002);
+
+        let rows = response.response.unwrap_or_default();
+        assert_eq!(paths(&rows), vec!["/etc/hosts", "/etc/passwd"]);
+        assert_eq!(
+            rows.first()
+                .and_then(|r| r.get("constraints"))
+                .map(|s| s.as_str()),
+            Some("1")
+        );
+        assert_eq!(
+            rows.iter()
+                .map(|r| (r.get("time").cloned(), r.get("eid").cloned()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some("1000".to_string()), Some("0000000000".to_string())),
+                (Some("1001".to_string()), Some("0000000001".to_string())),
+            ]
+        );
+
+        // Events are reported again until they expire
+        let response = wrapper.generate_at(&generate_request(), 1003);
+        assert_eq!(response.response.map(|r| r.len()), Some(2));
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn test_generate_expires_old_events() {
+        let wrapper = EventSubscriberWrapper::with_limits(
+            PathEvents {
+                configured: Arc::new(Mutex::new(None)),
+            },
+            10,
+            Duration::from_secs(60),
+        );
+        let events = wrapper.handle();
+        events.push_at(1000, path_event("old"));
+        events.push_at(1050, path_event("new"));
+
+        let response = wrapper.generate_at(&generate_request(), 1070);
+        assert_eq!(paths(&response.response.unwrap_or_default()), vec!["new"]);
+        assert_eq!(events.len(), 1);
+
+        let response = wrapper.generate_at(&generate_request(), 1200);
+        assert_eq!(response.response.map(|r| r.len()), Some(0));
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_buffer_drops_oldest_when_full() {
+        let wrapper = EventSubscriberWrapper::with_capacity(
+            PathEvents {
+                configured: Arc::new(Mutex::new(None)),
+            },
+            2,
+        );
+        let events = wrapper.handle();
+        events.push(path_event("a"));
+        events.push(path_event("b"));
+        events.push(path_event("c"));
+
+        assert_eq!(paths(&events.events(unix_now())), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn test_routes_describe_columns() {
+        let (wrapper, _) = wrapper();
+        assert_eq!(wrapper.registry(), Registry::Table);
+
+        let routes = wrapper.routes();
+        let names: Vec<&str> = routes
+            .iter()
+            .filter_map(|r| r.get("name"))
+            .map(|s| s.as_str())
+            .collect();
+        assert_eq!(names, vec!["path", "time", "eid"]);
+    }
+}
@@ -0,0 +1,377 @@
+//! Local batching for loggers that forward logs over the network.
+
+use super::{LogStatus, LoggerPlugin};
+use crate::log_target;
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Flush after this many entries unless configured otherwise.
+const DEFAULT_MAX_ENTRIES: usize = 1000;
+/// Flush entries older than this unless configured otherwise.
+const DEFAULT_MAX_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A logger adapter that batches entries and hands them to the inner
+/// logger's [`LoggerPlugin::flush`] in one call.
+///
+/// Query results, status logs, snapshots and raw strings are buffered as one
+/// line each (query results as compact JSON). The buffer is flushed once it
+/// holds `max_entries`, when an entry arrives and the oldest buffered one is
+/// older than `max_interval`, on health checks past the interval, and on
+/// shutdown. With the `gzip` feature the batch can be gzip-compressed first.
+///
+/// ```no_run
+/// use osquery_rust_ng::plugin::{BufferingLogger, LoggerPlugin, Plugin};
+/// use std::time::Duration;
+///
+/// struct HttpLogger;
+///
+/// impl LoggerPlugin for HttpLogger {
+///     fn name(&self) -> String {
+///         "http_logger".to_string()
+///     }
+///
+///     fn log_string(&self, _message: &str) -> Result<(), String> {
+///         Err("HttpLogger only accepts batches".to_string())
+///     }
+///
+///     fn flush(&self, batch: &[u8]) -> Result<(), String> {
+///         // POST `batch` to the collector
+///         Ok(())
+///     }
+/// }
+///
+/// let logger = BufferingLogger::new(HttpLogger)
+///     .max_entries(500)
+///     .max_interval(Duration::from_secs(30));
+/// let plugin = Plugin::logger(logger);
+/// ```
+pub struct BufferingLogger<L: LoggerPlugin> {
+    inner: L,
+    max_entries: usize,
+    max_interval: Duration,
+    #[cfg(feature = "gzip")]
+    gzip: bool,
+    clock: Box<dyn Fn() -> Instant + Send + Sync>,
+    buffer: Mutex<Buffer>,
+}
+
+#[derive(Default)]
+struct Buffer {
+    entries: Vec<String>,
+    /// When the oldest buffered entry arrived
+    oldest: Option<Instant>,
+}
+
+impl<L: LoggerPlugin> BufferingLogger<L> {
+    pub fn new(inner: L) -> Self {
+        BufferingLogger {
+            inner,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_interval: DEFAULT_MAX_INTERVAL,
+            #[cfg(feature = "gzip")]
+            gzip: false,
+            clock: Box::new(Instant::now),
+            buffer: Mutex::new(Buffer::default()),
+        }
+    }
+
+    /// Flush once this many entries are buffered.
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    /// Flush once the oldest buffered entry is this old.
+    ///
+    /// Checked when entries arrive and on health checks; there is no
+    /// background timer, so a quiet logger holds entries until then.
+    pub fn max_interval(mut self, max_interval: Duration) -> Self {
+        self.max_interval = max_interval;
+        self
+    }
+
+    /// Gzip-compress each batch before passing it to the inner logger.
+    #[cfg(feature = "gzip")]
+    pub fn gzip(mut self, gzip: bool) -> Self {
+        self.gzip = gzip;
+        self
+    }
+
+    /// Read the time from `clock` instead of [`Instant::now`], for tests.
+    pub fn with_clock<F: Fn() -> Instant + Send + Sync + 'static>(mut self, clock: F) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// The wrapped logger.
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+
+    /// Hand everything buffered to the inner logger now.
+    pub fn flush_buffer(&self) -> Result<(), String> {
+        let entries = {
+            let mut buffer = self.lock()?;
+            buffer.oldest = None;
+            std::mem::take(&mut buffer.entries)
+        };
+        self.flush_entries(entries)
+    }
+
+    fn push(&self, entry: String) -> Result<(), String> {
+        let now = (self.clock)();
+        let entries = {
+            let mut buffer = self.lock()?;
+            buffer.entries.push(entry);
+            let oldest = *buffer.oldest.get_or_insert(now);
+
+            if buffer.entries.len() < self.max_entries
+                && now.saturating_duration_since(oldest) < self.max_interval
+            {
+                return Ok(());
+            }
+            buffer.oldest = None;
+            std::mem::take(&mut buffer.entries)
+        };
+        self.flush_entries(entries)
+    }
+
+    /// Flush if the oldest buffered entry has outlived `max_interval`.
+    fn flush_if_stale(&self) -> Result<(), String> {
+        let now = (self.clock)();
+        let stale = self
+            .lock()?
+            .oldest
+            .is_some_and(|oldest| now.saturating_duration_since(oldest) >= self.max_interval);
+        if stale {
+            self.flush_buffer()?;
+        }
+        Ok(())
+    }
+
+    fn flush_entries(&self, entries: Vec<String>) -> Result<(), String> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut batch = entries.join("\n").into_bytes();
+        batch.push(b'\n');
+
+        #[cfg(feature = "gzip")]
+        if self.gzip {
+            batch = gzip(&batch)?;
+        }
+
+        self.inner.flush(&batch)
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Buffer>, String> {
+        self.buffer
+            .lock()
+            .map_err(|e| format!("Failed to lock log buffer: {e}"))
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn gzip(batch: &[u8]) -> Result<Vec<u8>, String> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(batch)
+        .and_then(|()| encoder.finish())
+        .map_err(|e| format!("Failed to compress log batch: {e}"))
+}
+
+impl<L: LoggerPlugin> LoggerPlugin for BufferingLogger<L> {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn log_string(&self, message: &str) -> Result<(), String> {
+        self.push(message.to_string())
+    }
+
+    fn log_query_result(&self, value: &Value) -> Result<(), String> {
+        self.push(value.to_string())
+    }
+
+    fn log_status(&self, status: &LogStatus) -> Result<(), String> {
+        self.push(status.to_string())
+    }
+
+    fn log_snapshot(&self, snapshot: &str) -> Result<(), String> {
+        self.push(snapshot.to_string())
+    }
+
+    fn flush(&self, batch: &[u8]) -> Result<(), String> {
+        self.inner.flush(batch)
+    }
+
+    fn init(&self, name: &str) -> Result<(), String> {
+        self.inner.init(name)
+    }
+
+    fn health(&self) -> Result<(), String> {
+        self.flush_if_stale()?;
+        self.inner.health()
+    }
+
+    fn features(&self) -> i32 {
+        self.inner.features()
+    }
+
+    fn flush_pending(&self) -> Result<(), String> {
+        self.flush_buffer()?;
+        self.inner.flush_pending()
+    }
+
+    fn shutdown(&self) {
+        if let Err(e) = self.flush_buffer() {
+            log::warn!(
+                target: log_target::PLUGIN,
+                "Failed to flush buffered logs on shutdown: {e}"
+            );
+        }
+        self.inner.shutdown();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)] // Tests are allowed to panic on setup failures
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Records every batch it is handed.
+    #[derive(Clone, Default)]
+    struct BatchLogger {
+        batches: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    impl BatchLogger {
+        fn batches(&self) -> Vec<String> {
+            self.batches
+                .lock()
+                .expect("batches lock poisoned")
+                .iter()
+                .map(|batch| String::from_utf8_lossy(batch).into_owned())
+                .collect()
+        }
+    }
+
+    impl LoggerPlugin for BatchLogger {
+        fn name(&self) -> String {
+            "batch_logger".to_string()
+        }
+
+        fn log_string(&self, _message: &str) -> Result<(), String> {
+            Err("unbatched".to_string())
+        }
+
+        fn flush(&self, batch: &[u8]) -> Result<(), String> {
+            self.batches
+                .lock()
+                .map_err(|e| e.to_string())?
+                .push(batch.to_vec());
+            Ok(())
+        }
+    }
+
+    /// A clock the test advances by hand.
+    fn fake_clock() -> (Arc<Mutex<Instant>>, impl Fn() -> Instant + Send + Sync) {
+        let now = Arc::new(Mutex::new(Instant::now()));
+        let clock = now.clone();
+        (now, move || *clock.lock().expect("clock lock poisoned"))
+    }
+
+    fn advance(now: &Mutex<Instant>, by: Duration) {
+        let mut now = now.lock().expect("clock lock poisoned");
+        *now += by;
+    }
+
+    #[test]
+    fn test_flush_on_count() {
+        let inner = BatchLogger::default();
+        let logger = BufferingLogger::new(inner.clone()).max_entries(3);
+
+        logger.log_string("one").expect("log should succeed");
+        logger
+            .log_query_result(&serde_json::json!({"name": "uptime"}))
+            .expect("log should succeed");
+        assert!(inner.batches().is_empty());
+
+        logger.log_snapshot("three").expect("log should succeed");
+        assert_eq!(inner.batches(), vec!["one\n{\"name\":\"uptime\"}\nthree\n"]);
+
+        logger.log_string("four").expect("log should succeed");
+        assert_eq!(inner.batches().len(), 1);
+    }
+
+    #[test]
+    fn test_flush_on_interval() {
+        let (now, clock) = fake_clock();
+        let inner = BatchLogger::default();
+        let logger = BufferingLogger::new(inner.clone())
+            .max_interval(Duration::from_secs(5))
+            .with_clock(clock);
+
+        logger.log_string("first").expect("log should succeed");
+        advance(&now, Duration::from_secs(4));
+        logger.log_string("second").expect("log should succeed");
+        assert!(inner.batches().is_empty());
+
+        advance(&now, Duration::from_secs(1));
+        logger.log_string("third").expect("log should succeed");
+        assert_eq!(inner.batches(), vec!["first\nsecond\nthird\n"]);
+
+        // The interval restarts with the next entry; health checks flush it
+        logger.log_string("fourth").expect("log should succeed");
+        logger.health().expect("health should succeed");
+        assert_eq!(inner.batches().len(), 1);
+        advance(&now, Duration::from_secs(5));
+        logger.health().expect("health should succeed");
+        assert_eq!(inner.batches().last().map(String::as_str), Some("fourth\n"));
+    }
+
+    #[test]
+    fn test_shutdown_flushes_remaining_entries() {
+        let inner = BatchLogger::default();
+        let logger = BufferingLogger::new(inner.clone());
+
+        logger.log_string("pending").expect("log should succeed");
+        logger.shutdown();
+        assert_eq!(inner.batches(), vec!["pending\n"]);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_batches() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let inner = BatchLogger::default();
+        let logger = BufferingLogger::new(inner.clone())
+            .max_entries(2)
+            .gzip(true);
+
+        logger.log_string("one").expect("log should succeed");
+        logger.log_string("two").expect("log should succeed");
+
+        let batch = inner
+            .batches
+            .lock()
+            .expect("batches lock poisoned")
+            .first()
+            .cloned()
+            .expect("a batch should be flushed");
+        let mut decoded = String::new();
+        GzDecoder::new(batch.as_slice())
+            .read_to_string(&mut decoded)
+            .expect("batch should be gzip");
+        assert_eq!(decoded, "one\ntwo\n");
+    }
+}
@@ -0,0 +1,199 @@
+//! A logger that remembers what it was given, for tests.
+
+use super::{LogStatus, LoggerPlugin};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+/// A [`LoggerPlugin`] that records every call instead of writing anywhere.
+///
+/// Clones share the same record, so keep one and hand the other to the code
+/// under test, e.g. a [`LoggerPluginWrapper`](super::LoggerPluginWrapper) or
+/// your own logger adapter. Available with the `test-util` feature.
+///
+/// ```
+/// # #[cfg(feature = "test-util")]
+/// # {
+/// use osquery_rust_ng::plugin::{CapturingLogger, LoggerPluginWrapper, OsqueryPlugin};
+/// use std::collections::BTreeMap;
+///
+/// let logger = CapturingLogger::new("capture");
+/// let wrapper = LoggerPluginWrapper::new(logger.clone());
+///
+/// wrapper.handle_call(BTreeMap::from([("snapshot".to_string(), "{}".to_string())]));
+/// assert_eq!(logger.snapshots(), vec!["{}"]);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CapturingLogger {
+    name: String,
+    captured: Arc<Mutex<Captured>>,
+}
+
+#[derive(Default)]
+struct Captured {
+    strings: Vec<String>,
+    query_results: Vec<Value>,
+    statuses: Vec<LogStatus>,
+    snapshots: Vec<String>,
+    inits: Vec<String>,
+    health_checks: usize,
+    shutdowns: usize,
+}
+
+impl CapturingLogger {
+    /// A logger registered as `name`.
+    pub fn new(name: &str) -> Self {
+        CapturingLogger {
+            name: name.to_string(),
+            captured: Arc::new(Mutex::new(Captured::default())),
+        }
+    }
+
+    /// Messages passed to `log_string`.
+    pub fn strings(&self) -> Vec<String> {
+        self.read(|captured| captured.strings.clone())
+    }
+
+    /// Results passed to `log_query_result`.
+    pub fn query_results(&self) -> Vec<Value> {
+        self.read(|captured| captured.query_results.clone())
+    }
+
+    /// Entries passed to `log_status`.
+    pub fn statuses(&self) -> Vec<LogStatus> {
+        self.read(|captured| captured.statuses.clone())
+    }
+
+    /// Snapshots passed to `log_snapshot`.
+    pub fn snapshots(&self) -> Vec<String> {
+        self.read(|captured| captured.snapshots.clone())
+    }
+
+    /// Names passed to `init`.
+    pub fn inits(&self) -> Vec<String> {
+        self.read(|captured| captured.inits.clone())
+    }
+
+    /// How many times `health` was called.
+    pub fn health_checks(&self) -> usize {
+        self.read(|captured| captured.health_checks)
+    }
+
+    /// How many times `shutdown` was called.
+    pub fn shutdowns(&self) -> usize {
+        self.read(|captured| captured.shutdowns)
+    }
+
+    fn read<T: Default>(&self, f: impl FnOnce(&Captured) -> T) -> T {
+        self.captured
+            .lock()
+            .map(|captured| f(&captured))
+            .unwrap_or_default()
+    }
+
+    fn record(&self, f: impl FnOnce(&mut Captured)) -> Result<(), String> {
+        let mut captured = self
+            .captured
+            .lock()
+            .map_err(|e| format!("Failed to lock captured logs: {e}"))?;
+        f(&mut captured);
+        Ok(())
+    }
+}
+
+impl LoggerPlugin for CapturingLogger {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn log_string(&self, message: &str) -> Result<(), String> {
+        self.record(|captured| captured.strings.push(message.to_string()))
+    }
+
+    fn log_query_result(&self, value: &Value) -> Result<(), String> {
+        self.record(|captured| captured.query_results.push(value.clone()))
+    }
+
+    fn log_status(&self, status: &LogStatus) -> Result<(), String> {
+        self.record(|captured| captured.statuses.push(status.clone()))
+    }
+
+    fn log_snapshot(&self, snapshot: &str) -> Result<(), String> {
+        self.record(|captured| captured.snapshots.push(snapshot.to_string()))
+    }
+
+    fn init(&self, name: &str) -> Result<(), String> {
+        self.record(|captured| captured.inits.push(name.to_string()))
+    }
+
+    fn health(&self) -> Result<(), String> {
+        self.record(|captured| captured.health_checks += 1)
+    }
+
+    fn shutdown(&self) {
+        let _ = self.record(|captured| captured.shutdowns += 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::{LogSeverity, LoggerPluginWrapper, OsqueryPlugin};
+    use std::collections::BTreeMap;
+
+    fn request(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_records_status_entry_through_wrapper() {
+        let logger = CapturingLogger::new("capture");
+        let wrapper = LoggerPluginWrapper::new(logger.clone());
+
+        let response = wrapper.handle_call(request(&[
+            ("status", "true"),
+            (
+                "log",
+                r#"[{"s":1,"f":"watcher.cpp","i":42,"m":"Memory limits exceeded","u":1700000000}]"#,
+            ),
+        ]));
+
+        assert_eq!(response.status.and_then(|s| s.code), Some(0));
+        assert_eq!(
+            logger.statuses(),
+            vec![LogStatus::new(
+                LogSeverity::Warning,
+                "watcher.cpp",
+                42,
+                "Memory limits exceeded"
+            )
+            .with_unix_time(1_700_000_000)]
+        );
+        assert!(logger.strings().is_empty());
+    }
+
+    #[test]
+    fn test_records_snapshot_and_lifecycle_through_wrapper() {
+        let logger = CapturingLogger::new("capture");
+        let wrapper = LoggerPluginWrapper::new(logger.clone());
+
+        wrapper.handle_call(request(&[("init", "capture")]));
+        wrapper.handle_call(request(&[(
+            "snapshot",
+            r#"{"name":"uptime","snapshot":[{"days":"1"}]}"#,
+        )]));
+        wrapper.handle_call(request(&[("health", "")]));
+        wrapper.shutdown();
+
+        assert_eq!(logger.inits(), vec!["capture"]);
+        assert_eq!(
+            logger.snapshots(),
+            vec![r#"{"name":"uptime","snapshot":[{"days":"1"}]}"#]
+        );
+        assert_eq!(logger.health_checks(), 1);
+        assert_eq!(logger.shutdowns(), 1);
+    }
+}
@@ -0,0 +1,221 @@
+//! Rewriting log entries, e.g. to redact secrets, before a logger sees them.
+
+use super::{LogStatus, LoggerPlugin};
+use regex::Regex;
+use serde_json::Value;
+
+/// Rewrites log entries before [`FilteringLogger`] hands them on.
+pub trait LogFilter: Send + Sync + 'static {
+    /// Rewrite a log line, query result value or snapshot in place.
+    fn transform_string(&self, s: &mut String);
+
+    /// Rewrite a status log in place. The default rewrites its message.
+    fn transform(&self, entry: &mut LogStatus) {
+        self.transform_string(&mut entry.message);
+    }
+}
+
+/// A [`LogFilter`] replacing every match of its patterns, in order.
+///
+/// Replacements may refer to capture groups as `$1` or `$name`.
+///
+/// ```
+/// use osquery_rust_ng::plugin::{LogFilter, RegexFilter};
+///
+/// let filter = RegexFilter::new(&[
+///     (r"[\w.+-]+@[\w-]+\.[\w.]+", "<email>"),
+///     (r"(token=)\S+", "${1}<redacted>"),
+/// ])
+/// .expect("patterns are valid");
+///
+/// let mut line = "login by alice@example.com with token=abc123".to_string();
+/// filter.transform_string(&mut line);
+/// assert_eq!(line, "login by <email> with token=<redacted>");
+/// ```
+pub struct RegexFilter {
+    rules: Vec<(Regex, String)>,
+}
+
+impl RegexFilter {
+    /// A filter replacing matches of each `(pattern, replacement)` pair.
+    pub fn new(rules: &[(&str, &str)]) -> Result<Self, regex::Error> {
+        let rules = rules
+            .iter()
+            .map(|(pattern, replacement)| Ok((Regex::new(pattern)?, replacement.to_string())))
+            .collect::<Result<_, regex::Error>>()?;
+        Ok(RegexFilter { rules })
+    }
+}
+
+impl LogFilter for RegexFilter {
+    fn transform_string(&self, s: &mut String) {
+        for (pattern, replacement) in &self.rules {
+            if let std::borrow::Cow::Owned(replaced) = pattern.replace_all(s, replacement.as_str())
+            {
+                *s = replaced;
+            }
+        }
+    }
+}
+
+/// A logger adapter that runs every entry through a [`LogFilter`] before
+/// passing it to the inner logger.
+///
+/// Query results are filtered string by string, leaving their JSON structure
+/// (and object keys) intact. Batches from a [`BufferingLogger`] may be
+/// compressed and are passed through as-is, so wrap the `FilteringLogger`
+/// in the `BufferingLogger` rather than the other way round.
+///
+/// ```no_run
+/// use osquery_rust_ng::plugin::{FilteringLogger, Plugin, RegexFilter, StreamLogger};
+///
+/// let filter = RegexFilter::new(&[(r"[\w.+-]+@[\w-]+\.[\w.]+", "<email>")])
+///     .expect("pattern is valid");
+/// let plugin = Plugin::logger(FilteringLogger::new(StreamLogger::stdout("stdout"), filter));
+/// ```
+///
+/// [`BufferingLogger`]: super::BufferingLogger
+pub struct FilteringLogger<L: LoggerPlugin, F: LogFilter> {
+    inner: L,
+    filter: F,
+}
+
+impl<L: LoggerPlugin, F: LogFilter> FilteringLogger<L, F> {
+    pub fn new(inner: L, filter: F) -> Self {
+        FilteringLogger { inner, filter }
+    }
+
+    fn filter_value(&self, value: &mut Value) {
+        match value {
+            Value::String(s) => self.filter.transform_string(s),
+            Value::Array(values) => values.iter_mut().for_each(|v| self.filter_value(v)),
+            Value::Object(map) => map.values_mut().for_each(|v| self.filter_value(v)),
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+        }
+    }
+}
+
+impl<L: LoggerPlugin, F: LogFilter> LoggerPlugin for FilteringLogger<L, F> {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn log_string(&self, message: &str) -> Result<(), String> {
+        let mut message = message.to_string();
+        self.filter.transform_string(&mut message);
+        self.inner.log_string(&message)
+    }
+
+    fn log_query_result(&self, value: &Value) -> Result<(), String> {
+        let mut value = value.clone();
+        self.filter_value(&mut value);
+        self.inner.log_query_result(&value)
+    }
+
+    fn log_status(&self, status: &LogStatus) -> Result<(), String> {
+        let mut status = status.clone();
+        self.filter.transform(&mut status);
+        self.inner.log_status(&status)
+    }
+
+    fn log_snapshot(&self, snapshot: &str) -> Result<(), String> {
+        let mut snapshot = snapshot.to_string();
+        self.filter.transform_string(&mut snapshot);
+        self.inner.log_snapshot(&snapshot)
+    }
+
+    fn flush(&self, batch: &[u8]) -> Result<(), String> {
+        self.inner.flush(batch)
+    }
+
+    fn init(&self, name: &str) -> Result<(), String> {
+        self.inner.init(name)
+    }
+
+    fn health(&self) -> Result<(), String> {
+        self.inner.health()
+    }
+
+    fn features(&self) -> i32 {
+        self.inner.features()
+    }
+
+    fn flush_pending(&self) -> Result<(), String> {
+        self.inner.flush_pending()
+    }
+
+    fn shutdown(&self) {
+        self.inner.shutdown();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::plugin::{CapturingLogger, LogSeverity, LoggerPluginWrapper, OsqueryPlugin};
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    fn redacting() -> RegexFilter {
+        RegexFilter::new(&[
+            (r"[\w.+-]+@[\w-]+\.[\w.]+", "<email>"),
+            (r"(token=)\S+", "${1}<redacted>"),
+        ])
+        .expect("patterns should compile")
+    }
+
+    #[test]
+    fn test_status_and_string_are_redacted_through_wrapper() {
+        let logger = CapturingLogger::new("capture");
+        let wrapper = LoggerPluginWrapper::new(FilteringLogger::new(logger.clone(), redacting()));
+
+        wrapper.handle_call(BTreeMap::from([
+            ("status".to_string(), "true".to_string()),
+            (
+                "log".to_string(),
+                r#"[{"s":0,"f":"auth.cpp","i":7,"m":"login by alice@example.com"}]"#.to_string(),
+            ),
+        ]));
+        wrapper.handle_call(BTreeMap::from([(
+            "string".to_string(),
+            "GET /api?token=s3cr3t&x=1".to_string(),
+        )]));
+
+        assert_eq!(
+            logger.statuses(),
+            vec![LogStatus::new(
+                LogSeverity::Info,
+                "auth.cpp",
+                7,
+                "login by <email>"
+            )]
+        );
+        assert_eq!(logger.strings(), vec!["GET /api?token=<redacted>"]);
+    }
+
+    #[test]
+    fn test_query_result_strings_are_redacted() {
+        let logger = CapturingLogger::new("capture");
+        let filtered = FilteringLogger::new(logger.clone(), redacting());
+
+        let result = filtered.log_query_result(&json!({
+            "name": "users",
+            "columns": {"email": "bob@example.org", "uid": 501},
+        }));
+
+        assert!(result.is_ok());
+        assert_eq!(
+            logger.query_results(),
+            vec![json!({
+                "name": "users",
+                "columns": {"email": "<email>", "uid": 501},
+            })]
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_an_error() {
+        assert!(RegexFilter::new(&[("(", "")]).is_err());
+    }
+}
@@ -0,0 +1,322 @@
+//! A file logger writing one JSON object per line (NDJSON).
+
+use super::{LogStatus, LoggerPlugin};
+use serde_json::{json, Map, Value};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Keep this many rotated files unless configured otherwise.
+const DEFAULT_MAX_FILES: usize = 5;
+
+/// A [`LoggerPlugin`] that appends each entry to a file as one JSON object
+/// per line, ready for `jq` or a log shipper.
+///
+/// Every line has a `"type"` of `"status"`, `"result"`, `"snapshot"` or
+/// `"string"`. Status lines carry `severity`, `filename`, `line` and
+/// `message` (plus `calendar_time`, `unix_time` and `decorations` when osquery
+/// sent them); results and snapshots are embedded as JSON under `"result"` and
+/// `"snapshot"`.
+///
+/// With [`max_bytes`](Self::max_bytes) set, a write that would grow the file
+/// past the limit first rotates it: `osquery.log` becomes `osquery.log.1`,
+/// `osquery.log.1` becomes `osquery.log.2` and so on, keeping
+/// [`max_files`](Self::max_files) old files.
+///
+/// ```no_run
+/// use osquery_rust_ng::plugin::{JsonLinesLogger, Plugin};
+///
+/// let logger = JsonLinesLogger::new("jsonl", "/var/log/osquery/ext.jsonl")
+///     .max_bytes(10 * 1024 * 1024)
+///     .max_files(3);
+/// let plugin = Plugin::logger(logger);
+/// ```
+pub struct JsonLinesLogger {
+    name: String,
+    path: PathBuf,
+    max_bytes: Option<u64>,
+    max_files: usize,
+    output: Mutex<Output>,
+}
+
+#[derive(Default)]
+struct Output {
+    /// Opened on the first write
+    file: Option<File>,
+    /// Size of the current file
+    written: u64,
+}
+
+impl JsonLinesLogger {
+    /// A logger registered as `name`, appending to the file at `path`.
+    ///
+    /// The file is created on the first write; failures to open or write it
+    /// are returned from the logging call.
+    pub fn new(name: &str, path: impl AsRef<Path>) -> Self {
+        JsonLinesLogger {
+            name: name.to_string(),
+            path: path.as_ref().to_path_buf(),
+            max_bytes: None,
+            max_files: DEFAULT_MAX_FILES,
+            output: Mutex::new(Output::default()),
+        }
+    }
+
+    /// Rotate the file before it grows past `max_bytes`. Off by default.
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Keep this many rotated files, deleting older ones. Defaults to 5.
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    fn write_line(&self, entry: &Value) -> Result<(), String> {
+        let mut line = entry.to_string();
+        line.push('\n');
+
+        let mut output = self
+            .output
+            .lock()
+            .map_err(|e| format!("Failed to lock {}: {e}", self.path.display()))?;
+        self.write_locked(&mut output, line.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {e}", self.path.display()))
+    }
+
+    fn write_locked(&self, output: &mut Output, line: &[u8]) -> io::Result<()> {
+        if output.file.is_none() {
+            let file = self.open()?;
+            output.written = file.metadata()?.len();
+            output.file = Some(file);
+        }
+
+        let len = line.len() as u64;
+        let full = self
+            .max_bytes
+            .is_some_and(|max| output.written > 0 && output.written + len > max);
+        if full {
+            output.file = None;
+            self.rotate()?;
+            output.file = Some(self.open()?);
+            output.written = 0;
+        }
+
+        if let Some(file) = output.file.as_mut() {
+            file.write_all(line)?;
+            output.written += len;
+        }
+        Ok(())
+    }
+
+    fn open(&self) -> io::Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+    }
+
+    /// The path of the `index`th rotated file, e.g. `osquery.log.1`.
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        PathBuf::from(path)
+    }
+
+    /// Shift each rotated file up one, dropping the oldest, then move the
+    /// current file to `.1`.
+    fn rotate(&self) -> io::Result<()> {
+        if self.max_files == 0 {
+            return fs::remove_file(&self.path);
+        }
+
+        match fs::remove_file(self.rotated(self.max_files)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        for index in (1..self.max_files).rev() {
+            match fs::rename(self.rotated(index), self.rotated(index + 1)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))
+    }
+}
+
+/// A status log as a `"type": "status"` JSON object.
+pub(super) fn status_entry(status: &LogStatus) -> Value {
+    let mut entry = Map::new();
+    entry.insert("type".to_string(), json!("status"));
+    entry.insert("severity".to_string(), json!(status.severity.to_string()));
+    entry.insert("filename".to_string(), json!(status.filename));
+    entry.insert("line".to_string(), json!(status.line));
+    entry.insert("message".to_string(), json!(status.message));
+    if let Some(calendar_time) = &status.calendar_time {
+        entry.insert("calendar_time".to_string(), json!(calendar_time));
+    }
+    if let Some(unix_time) = status.unix_time {
+        entry.insert("unix_time".to_string(), json!(unix_time));
+    }
+    if !status.decorations.is_empty() {
+        entry.insert("decorations".to_string(), json!(status.decorations));
+    }
+    Value::Object(entry)
+}
+
+/// A snapshot as a `"type": "snapshot"` JSON object.
+pub(super) fn snapshot_entry(snapshot: &str) -> Value {
+    // osquery sends snapshots as JSON; keep anything else as a string
+    let snapshot = serde_json::from_str(snapshot).unwrap_or_else(|_| json!(snapshot));
+    json!({"type": "snapshot", "snapshot": snapshot})
+}
+
+impl LoggerPlugin for JsonLinesLogger {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn log_string(&self, message: &str) -> Result<(), String> {
+        self.write_line(&json!({"type": "string", "message": message}))
+    }
+
+    fn log_query_result(&self, value: &Value) -> Result<(), String> {
+        self.write_line(&json!({"type": "result", "result": value}))
+    }
+
+    fn log_status(&self, status: &LogStatus) -> Result<(), String> {
+        self.write_line(&status_entry(status))
+    }
+
+    fn log_snapshot(&self, snapshot: &str) -> Result<(), String> {
+        self.write_line(&snapshot_entry(snapshot))
+    }
+
+    fn flush_pending(&self) -> Result<(), String> {
+        let mut output = self
+            .output
+            .lock()
+            .map_err(|e| format!("Failed to lock {}: {e}", self.path.display()))?;
+        match output.file.as_mut() {
+            Some(file) => file
+                .flush()
+                .map_err(|e| format!("Failed to flush {}: {e}", self.path.display())),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::plugin::LogSeverity;
+    use std::collections::BTreeMap;
+
+    fn lines(path: &Path) -> Vec<Value> {
+        fs::read_to_string(path)
+            .expect("log file should be readable")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("line should be JSON"))
+            .collect()
+    }
+
+    #[test]
+    fn test_status_and_snapshot_are_json_lines() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join("osquery.jsonl");
+        let logger = JsonLinesLogger::new("jsonl", &path);
+
+        logger
+            .log_status(
+                &LogStatus::new(
+                    LogSeverity::Warning,
+                    "watcher.cpp",
+                    42,
+                    "Memory limits exceeded",
+                )
+                .with_unix_time(1_700_000_000)
+                .with_decorations(BTreeMap::from([(
+                    "hostname".to_string(),
+                    "box".to_string(),
+                )])),
+            )
+            .expect("status should be written");
+        logger
+            .log_snapshot(r#"{"name":"uptime","snapshot":[{"days":"1"}]}"#)
+            .expect("snapshot should be written");
+
+        assert_eq!(
+            lines(&path),
+            vec![
+                json!({
+                    "type": "status",
+                    "severity": "WARNING",
+                    "filename": "watcher.cpp",
+                    "line": 42,
+                    "message": "Memory limits exceeded",
+                    "unix_time": 1_700_000_000,
+                    "decorations": {"hostname": "box"},
+                }),
+                json!({
+                    "type": "snapshot",
+                    "snapshot": {"name": "uptime", "snapshot": [{"days": "1"}]},
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rotates_at_max_bytes() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join("osquery.jsonl");
+        let logger = JsonLinesLogger::new("jsonl", &path)
+            .max_bytes(100)
+            .max_files(2);
+        let message = "x".repeat(40);
+        // {"message":"xxx...","type":"string"} plus a newline
+        let line_len = json!({"type": "string", "message": message})
+            .to_string()
+            .len() as u64
+            + 1;
+        assert!(line_len * 2 > 100 && line_len <= 100);
+
+        for _ in 0..4 {
+            logger.log_string(&message).expect("line should be written");
+        }
+
+        // Each line rotated the previous one away; only two old files are kept
+        let rotated = |index: usize| logger.rotated(index);
+        assert_eq!(lines(&path).len(), 1);
+        assert_eq!(lines(&rotated(1)).len(), 1);
+        assert_eq!(lines(&rotated(2)).len(), 1);
+        assert!(!rotated(3).exists());
+        assert!(fs::metadata(&path).expect("log file should exist").len() <= 100);
+    }
+
+    #[test]
+    fn test_appends_to_existing_file_and_counts_its_size() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join("osquery.jsonl");
+        fs::write(
+            &path,
+            format!("{}\n", json!({"type": "string", "message": "old"})),
+        )
+        .expect("seed file should be written");
+        let logger = JsonLinesLogger::new("jsonl", &path).max_bytes(50);
+
+        logger.log_string("new").expect("line should be written");
+
+        assert_eq!(
+            lines(&path),
+            vec![json!({"type": "string", "message": "new"})]
+        );
+        assert_eq!(
+            lines(&logger.rotated(1)),
+            vec![json!({"type": "string", "message": "old"})]
+        );
+    }
+}
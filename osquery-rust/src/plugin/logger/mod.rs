@@ -31,7 +31,7 @@
 //!
 //! # fn main() -> Result<(), Box<dyn std::error::Error>> {
 //! let mut server = Server::new(None, "/path/to/socket").unwrap();
-//! server.register_plugin(Plugin::logger(ConsoleLogger));
+//! server.register_plugin(Plugin::logger(ConsoleLogger))?;
 //! # Ok(())
 //! # }
 //! ```
@@ -56,9 +56,30 @@ use crate::_osquery::osquery::{ExtensionPluginRequest, ExtensionPluginResponse};
 use crate::_osquery::osquery::{ExtensionResponse, ExtensionStatus};
 use crate::plugin::OsqueryPlugin;
 use crate::plugin::_enums::response::ExtensionResponseEnum;
+use crate::{log_target, PluginRequest};
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::fmt;
 
+mod buffering;
+#[cfg(any(test, feature = "test-util"))]
+mod capturing;
+mod filtering;
+mod json_lines;
+mod request_counts;
+mod stream;
+#[cfg(feature = "syslog-helpers")]
+mod syslog;
+pub use buffering::BufferingLogger;
+#[cfg(any(test, feature = "test-util"))]
+pub use capturing::CapturingLogger;
+pub use filtering::{FilteringLogger, LogFilter, RegexFilter};
+pub use json_lines::JsonLinesLogger;
+pub use request_counts::{LogRequestCounter, LogRequestCounts};
+pub use stream::StreamLogger;
+#[cfg(feature = "syslog-helpers")]
+pub use syslog::{ParseFacilityError, SyslogFacility};
+
 /// Trait that logger plugins must implement.
 ///
 /// # Example
@@ -85,9 +106,55 @@ pub trait LoggerPlugin: Send + Sync + 'static {
 
     /// Log a raw string message.
     ///
-    /// This is called for general log entries and query results.
+    /// This is called for general log entries that are not valid JSON.
     fn log_string(&self, message: &str) -> Result<(), String>;
 
+    /// Log a structured query result.
+    ///
+    /// Called when the logged payload parses as JSON, which is the case for
+    /// scheduled query results. By default differential results (those with
+    /// a `diffResults` object) are split into `log_added` and `log_removed`,
+    /// skipping empty sides; anything else, such as a `snapshot` result, is
+    /// pretty-printed and passed to `log_string`.
+    fn log_query_result(&self, value: &Value) -> Result<(), String> {
+        if let Some(diff) = value.get("diffResults") {
+            let rows = |side: &str| {
+                diff.get(side)
+                    .and_then(Value::as_array)
+                    .filter(|rows| !rows.is_empty())
+                    .cloned()
+            };
+            if let Some(added) = rows("added") {
+                self.log_added(&added)?;
+            }
+            if let Some(removed) = rows("removed") {
+                self.log_removed(&removed)?;
+            }
+            return Ok(());
+        }
+
+        let formatted = serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string());
+        self.log_string(&formatted)
+    }
+
+    /// Log rows that appeared since the query last ran.
+    ///
+    /// Called by the default `log_query_result` with the `added` rows of a
+    /// differential result. The default passes `{"added": rows}`,
+    /// pretty-printed, to `log_string`.
+    fn log_added(&self, rows: &[Value]) -> Result<(), String> {
+        let formatted = serde_json::to_string_pretty(&serde_json::json!({ "added": rows }))
+            .map_err(|e| e.to_string())?;
+        self.log_string(&formatted)
+    }
+
+    /// Log rows that disappeared since the query last ran, like `log_added`.
+    fn log_removed(&self, rows: &[Value]) -> Result<(), String> {
+        let formatted = serde_json::to_string_pretty(&serde_json::json!({ "removed": rows }))
+            .map_err(|e| e.to_string())?;
+        self.log_string(&formatted)
+    }
+
     /// Log structured status information.
     ///
     /// Called when osquery sends status logs with severity, file, line, and message.
@@ -103,6 +170,15 @@ pub trait LoggerPlugin: Send + Sync + 'static {
         self.log_string(snapshot)
     }
 
+    /// Forward a batch of log entries collected by [`BufferingLogger`].
+    ///
+    /// The batch is newline-separated entries, gzip-compressed if the
+    /// `BufferingLogger` was configured to. Only called for loggers wrapped in
+    /// a `BufferingLogger`; the default rejects the batch.
+    fn flush(&self, _batch: &[u8]) -> Result<(), String> {
+        Err(format!("Logger {} does not accept batches", self.name()))
+    }
+
     /// Initialize the logger.
     ///
     /// Called when the logger is first registered with osquery.
@@ -143,6 +219,15 @@ pub trait LoggerPlugin: Send + Sync + 'static {
         LoggerFeatures::LOG_STATUS
     }
 
+    /// Write out any log entries the logger still holds, e.g. ones queued
+    /// for a network collector.
+    ///
+    /// Called when the extension shuts down, before `shutdown()` and before
+    /// any plugin is shut down; an `Err` is logged. The default does nothing.
+    fn flush_pending(&self) -> Result<(), String> {
+        Ok(())
+    }
+
     /// Shutdown the logger.
     ///
     /// Called when the extension is shutting down.
@@ -153,7 +238,11 @@ pub trait LoggerPlugin: Send + Sync + 'static {
 ///
 /// Status logs contain structured information about osquery's internal state,
 /// including error messages, warnings, and informational messages.
-#[derive(Debug, Clone)]
+///
+/// Fields may be added as osquery sends more; build one with
+/// [`LogStatus::new`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct LogStatus {
     /// The severity level of the log message
     pub severity: LogSeverity,
@@ -163,6 +252,43 @@ pub struct LogStatus {
     pub line: u32,
     /// The log message text
     pub message: String,
+    /// Human readable time the entry was logged, if osquery sent one
+    pub calendar_time: Option<String>,
+    /// Seconds since the epoch when the entry was logged, if osquery sent one
+    pub unix_time: Option<u64>,
+    /// Host metadata osquery attached to the entry (e.g. `hostname`)
+    pub decorations: BTreeMap<String, String>,
+}
+
+impl LogStatus {
+    /// A status entry with no timestamps or decorations.
+    pub fn new(severity: LogSeverity, filename: &str, line: u32, message: &str) -> Self {
+        LogStatus {
+            severity,
+            filename: filename.to_string(),
+            line,
+            message: message.to_string(),
+            ..LogStatus::default()
+        }
+    }
+
+    /// Set the human readable time the entry was logged.
+    pub fn with_calendar_time(mut self, calendar_time: &str) -> Self {
+        self.calendar_time = Some(calendar_time.to_string());
+        self
+    }
+
+    /// Set the seconds since the epoch when the entry was logged.
+    pub fn with_unix_time(mut self, unix_time: u64) -> Self {
+        self.unix_time = Some(unix_time);
+        self
+    }
+
+    /// Set the host metadata attached to the entry.
+    pub fn with_decorations(mut self, decorations: BTreeMap<String, String>) -> Self {
+        self.decorations = decorations;
+        self
+    }
 }
 
 impl fmt::Display for LogStatus {
@@ -211,9 +337,10 @@ impl LoggerFeatures {
 /// Log severity levels used by osquery.
 ///
 /// These map directly to osquery's internal severity levels.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum LogSeverity {
     /// Informational messages (severity 0)
+    #[default]
     Info = 0,
     /// Warning messages (severity 1)
     Warning = 1,
@@ -244,6 +371,29 @@ impl TryFrom<i64> for LogSeverity {
     }
 }
 
+impl From<LogSeverity> for log::Level {
+    fn from(severity: LogSeverity) -> Self {
+        match severity {
+            LogSeverity::Info => log::Level::Info,
+            LogSeverity::Warning => log::Level::Warn,
+            LogSeverity::Error => log::Level::Error,
+        }
+    }
+}
+
+/// osquery has no severity below `Info`, so `Debug` and `Trace` map down to
+/// [`LogSeverity::Info`]. Being infallible, this also provides
+/// `LogSeverity::try_from(level)`.
+impl From<log::Level> for LogSeverity {
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => LogSeverity::Error,
+            log::Level::Warn => LogSeverity::Warning,
+            log::Level::Info | log::Level::Debug | log::Level::Trace => LogSeverity::Info,
+        }
+    }
+}
+
 /// Types of log requests that can be received from osquery.
 ///
 /// This enum represents the different types of logging operations
@@ -264,6 +414,8 @@ enum LogRequestType {
     Health,
     /// Features query - osquery asks what log types we support
     Features,
+    /// A request with none of the keys we know, e.g. from a newer osquery
+    Unknown(BTreeMap<String, String>),
 }
 
 /// A single status log entry from osquery
@@ -273,6 +425,79 @@ struct StatusEntry {
     filename: String,
     line: u32,
     message: String,
+    calendar_time: Option<String>,
+    unix_time: Option<u64>,
+    decorations: BTreeMap<String, String>,
+}
+
+/// Parse the JSON array a status log request carries.
+fn parse_status_array(log_data: &str) -> Result<Vec<Value>, String> {
+    serde_json::from_str(log_data).map_err(|e| format!("Failed to parse status log array: {e}"))
+}
+
+/// Parse one status log entry. It must be an object with a string message
+/// (`"m"`) and, if it has one, a known severity (`"s"`); the other fields
+/// fall back to defaults.
+fn parse_status_entry(entry: &Value) -> Result<StatusEntry, String> {
+    let obj = entry
+        .as_object()
+        .ok_or_else(|| format!("expected an object, got {entry}"))?;
+
+    let severity = match obj.get("s") {
+        None => LogSeverity::Info,
+        Some(s) => s
+            .as_i64()
+            .ok_or_else(|| format!("invalid severity {s}"))?
+            .try_into()?,
+    };
+
+    let filename = obj
+        .get("f")
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    let line = obj.get("i").and_then(|v| v.as_i64()).unwrap_or(0) as u32;
+
+    let message = obj
+        .get("m")
+        .and_then(|v| v.as_str())
+        .ok_or("missing message")?
+        .to_string();
+
+    let calendar_time = obj.get("c").and_then(|v| v.as_str()).map(str::to_string);
+
+    let unix_time = obj.get("u").and_then(|v| match v {
+        Value::String(s) => s.parse::<u64>().ok(),
+        other => other.as_u64(),
+    });
+
+    let decorations = obj
+        .get("decorations")
+        .and_then(|v| v.as_object())
+        .map(|decorations| {
+            decorations
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    (key.clone(), value)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(StatusEntry {
+        severity,
+        filename,
+        line,
+        message,
+        calendar_time,
+        unix_time,
+        decorations,
+    })
 }
 
 /// Wrapper that adapts a LoggerPlugin to the OsqueryPlugin interface.
@@ -285,11 +510,44 @@ struct StatusEntry {
 /// `Plugin::logger()` to create plugins.
 pub struct LoggerPluginWrapper<L: LoggerPlugin> {
     logger: L,
+    /// Requests received so far, by type
+    counter: LogRequestCounter,
+    /// Reject a status log batch with any malformed entry instead of
+    /// skipping those entries
+    strict_status_logs: bool,
 }
 
 impl<L: LoggerPlugin> LoggerPluginWrapper<L> {
     pub fn new(logger: L) -> Self {
-        Self { logger }
+        Self {
+            logger,
+            counter: LogRequestCounter::default(),
+            strict_status_logs: false,
+        }
+    }
+
+    /// Treat a status log batch containing a malformed entry (one that isn't
+    /// an object, has no `"m"` message or has an unknown severity) as
+    /// malformed as a whole, as if it weren't a status log at all.
+    ///
+    /// By default such entries are skipped and counted in
+    /// [`LogRequestCounts::dropped_status_entries`], and the batch's valid
+    /// entries are still logged.
+    pub fn strict_status_logs(mut self, strict: bool) -> Self {
+        self.strict_status_logs = strict;
+        self
+    }
+
+    /// How many requests of each type osquery has sent so far.
+    pub fn request_counts(&self) -> LogRequestCounts {
+        self.counter.counts()
+    }
+
+    /// A handle to the request counts that stays live after the wrapper is
+    /// moved into a [`Plugin`](crate::plugin::Plugin), e.g.
+    /// `Plugin::Logger(Arc::new(wrapper))`.
+    pub fn request_counter(&self) -> LogRequestCounter {
+        self.counter.clone()
     }
 
     /// Parse an osquery request into a structured log request type
@@ -298,7 +556,20 @@ impl<L: LoggerPlugin> LoggerPluginWrapper<L> {
         if let Some(log_data) = request.get("log") {
             if request.get("status").map(|s| s == "true").unwrap_or(false) {
                 // Parse status log array
-                if let Ok(entries) = self.parse_status_entries(log_data) {
+                let parsed = if self.strict_status_logs {
+                    self.parse_status_entries(log_data)
+                        .map(|entries| (entries, 0))
+                } else {
+                    self.parse_status_entries_lenient(log_data)
+                };
+                if let Ok((entries, dropped)) = parsed {
+                    if dropped > 0 {
+                        log::warn!(
+                            target: log_target::PLUGIN,
+                            "Dropped {dropped} malformed status log entries"
+                        );
+                        self.counter.record_dropped_status_entries(dropped as u64);
+                    }
                     return LogRequestType::StatusLog(entries);
                 }
             }
@@ -326,11 +597,7 @@ impl<L: LoggerPlugin> LoggerPluginWrapper<L> {
         }
 
         // Check for features query
-        if request
-            .get("action")
-            .map(|a| a == "features")
-            .unwrap_or(false)
-        {
+        if PluginRequest::new(request).action() == "features" {
             return LogRequestType::Features;
         }
 
@@ -339,49 +606,36 @@ impl<L: LoggerPlugin> LoggerPluginWrapper<L> {
             return LogRequestType::RawString(string_log.to_string());
         }
 
-        LogRequestType::RawString(String::new())
+        LogRequestType::Unknown(request.clone())
     }
 
-    /// Parse status entries from JSON array string
+    /// Parse status entries from JSON array string, failing if any entry is
+    /// malformed.
     fn parse_status_entries(&self, log_data: &str) -> Result<Vec<StatusEntry>, String> {
-        let entries: Vec<Value> = serde_json::from_str(log_data)
-            .map_err(|e| format!("Failed to parse status log array: {e}"))?;
-
-        let mut status_entries = Vec::new();
-
-        for entry in entries {
-            if let Some(obj) = entry.as_object() {
-                let severity = obj
-                    .get("s")
-                    .and_then(|v| v.as_i64())
-                    .unwrap_or(0)
-                    .try_into()
-                    .unwrap_or(LogSeverity::Info);
-
-                let filename = obj
-                    .get("f")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("unknown")
-                    .to_string();
-
-                let line = obj.get("i").and_then(|v| v.as_i64()).unwrap_or(0) as u32;
-
-                let message = obj
-                    .get("m")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("")
-                    .to_string();
-
-                status_entries.push(StatusEntry {
-                    severity,
-                    filename,
-                    line,
-                    message,
-                });
-            }
-        }
+        parse_status_array(log_data)?
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                parse_status_entry(entry)
+                    .map_err(|e| format!("Malformed status log entry {index}: {e}"))
+            })
+            .collect()
+    }
 
-        Ok(status_entries)
+    /// Parse status entries from JSON array string, skipping malformed
+    /// entries. Returns the valid entries and how many were skipped.
+    fn parse_status_entries_lenient(
+        &self,
+        log_data: &str,
+    ) -> Result<(Vec<StatusEntry>, usize), String> {
+        let entries = parse_status_array(log_data)?;
+        let total = entries.len();
+        let valid: Vec<StatusEntry> = entries
+            .iter()
+            .filter_map(|entry| parse_status_entry(entry).ok())
+            .collect();
+        let dropped = total - valid.len();
+        Ok((valid, dropped))
     }
 
     /// Handle a parsed log request
@@ -394,22 +648,21 @@ impl<L: LoggerPlugin> LoggerPluginWrapper<L> {
                         filename: entry.filename,
                         line: entry.line,
                         message: entry.message,
+                        calendar_time: entry.calendar_time,
+                        unix_time: entry.unix_time,
+                        decorations: entry.decorations,
                     };
                     self.logger.log_status(&status)?;
                 }
                 Ok(())
             }
-            LogRequestType::QueryResult(value) => {
-                let formatted =
-                    serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string());
-                self.logger.log_string(&formatted)
-            }
+            LogRequestType::QueryResult(value) => self.logger.log_query_result(&value),
             LogRequestType::RawString(s) => self.logger.log_string(&s),
             LogRequestType::Snapshot(s) => self.logger.log_snapshot(&s),
             LogRequestType::Init(name) => self.logger.init(&name),
             LogRequestType::Health => self.logger.health(),
-            // Features is handled specially in handle_call before this is called
-            LogRequestType::Features => Ok(()),
+            // Features and Unknown are handled in handle_call before this is called
+            LogRequestType::Features | LogRequestType::Unknown(_) => Ok(()),
         }
     }
 }
@@ -433,15 +686,39 @@ impl<L: LoggerPlugin> OsqueryPlugin for LoggerPluginWrapper<L> {
         ExtensionStatus::new(0, None, None)
     }
 
+    fn health(&self) -> ExtensionStatus {
+        match self.logger.health() {
+            Ok(()) => ExtensionStatus::new(0, None, None),
+            Err(e) => ExtensionStatus::new(1, e, None),
+        }
+    }
+
     fn handle_call(&self, request: crate::_osquery::ExtensionPluginRequest) -> ExtensionResponse {
         // Parse the request into a structured type
         let request_type = self.parse_request(&request);
+        self.counter.record(&request_type);
 
         // Features request needs special handling - return features as status code
         if matches!(request_type, LogRequestType::Features) {
             return ExtensionResponseEnum::SuccessWithCode(self.logger.features()).into();
         }
 
+        // Don't hand the logger an empty entry for requests we don't understand;
+        // report them instead so the call fails without disturbing osquery
+        if let LogRequestType::Unknown(request) = &request_type {
+            let keys: Vec<&str> = request.keys().map(String::as_str).collect();
+            log::debug!(
+                target: log_target::PLUGIN,
+                "Ignoring unknown logger request with keys: {}",
+                keys.join(", ")
+            );
+            return ExtensionResponseEnum::Failure(format!(
+                "Unknown logger request with keys: {}",
+                keys.join(", ")
+            ))
+            .into();
+        }
+
         // Handle the request and return the appropriate response
         match self.handle_log_request(request_type) {
             Ok(()) => ExtensionResponseEnum::Success().into(),
@@ -449,6 +726,10 @@ impl<L: LoggerPlugin> OsqueryPlugin for LoggerPluginWrapper<L> {
         }
     }
 
+    fn flush(&self) -> Result<(), String> {
+        self.logger.flush_pending()
+    }
+
     fn shutdown(&self) {
         self.logger.shutdown();
     }
@@ -463,18 +744,28 @@ mod tests {
     /// A minimal logger for testing
     struct TestLogger {
         custom_features: Option<i32>,
+        unhealthy: Option<String>,
     }
 
     impl TestLogger {
         fn new() -> Self {
             Self {
                 custom_features: None,
+                unhealthy: None,
             }
         }
 
         fn with_features(features: i32) -> Self {
             Self {
                 custom_features: Some(features),
+                unhealthy: None,
+            }
+        }
+
+        fn unhealthy(reason: &str) -> Self {
+            Self {
+                custom_features: None,
+                unhealthy: Some(reason.to_string()),
             }
         }
     }
@@ -491,6 +782,13 @@ mod tests {
         fn features(&self) -> i32 {
             self.custom_features.unwrap_or(LoggerFeatures::LOG_STATUS)
         }
+
+        fn health(&self) -> Result<(), String> {
+            match &self.unhealthy {
+                Some(reason) => Err(reason.clone()),
+                None => Ok(()),
+            }
+        }
     }
 
     #[test]
@@ -568,8 +866,7 @@ mod tests {
         request.insert("action".to_string(), "unknown".to_string());
 
         let request_type = wrapper.parse_request(&request);
-        // Should fall through to default (RawString)
-        assert!(matches!(request_type, LogRequestType::RawString(_)));
+        assert!(matches!(request_type, LogRequestType::Unknown(_)));
     }
 
     #[test]
@@ -622,6 +919,374 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_status_log_preserves_decorations_and_timestamps() {
+        use std::sync::{Arc, Mutex};
+
+        struct CapturingLogger {
+            statuses: Arc<Mutex<Vec<LogStatus>>>,
+        }
+
+        impl LoggerPlugin for CapturingLogger {
+            fn name(&self) -> String {
+                "capturing".to_string()
+            }
+
+            fn log_string(&self, _message: &str) -> Result<(), String> {
+                Ok(())
+            }
+
+            fn log_status(&self, status: &LogStatus) -> Result<(), String> {
+                self.statuses
+                    .lock()
+                    .map_err(|e| e.to_string())?
+                    .push(status.clone());
+                Ok(())
+            }
+        }
+
+        let statuses = Arc::new(Mutex::new(vec![]));
+        let wrapper = LoggerPluginWrapper::new(CapturingLogger {
+            statuses: statuses.clone(),
+        });
+
+        let mut request: BTreeMap<String, String> = BTreeMap::new();
+        request.insert("status".to_string(), "true".to_string());
+        request.insert(
+            "log".to_string(),
+            r#"[{"s":1,"f":"db.cpp","i":7,"m":"slow","h":"host1","c":"Mon Jan  1 00:00:00 2024 UTC","u":1704067200,"decorations":{"hostname":"host1","uptime":42}}]"#
+                .to_string(),
+        );
+
+        let response = wrapper.handle_call(request);
+        assert_eq!(response.status.and_then(|s| s.code), Some(0));
+
+        let statuses = statuses.lock().map(|s| s.clone()).unwrap_or_default();
+        assert_eq!(statuses.len(), 1);
+        let status = statuses.first();
+        assert_eq!(status.map(|s| s.severity), Some(LogSeverity::Warning));
+        assert_eq!(
+            status.and_then(|s| s.calendar_time.as_deref()),
+            Some("Mon Jan  1 00:00:00 2024 UTC")
+        );
+        assert_eq!(status.and_then(|s| s.unix_time), Some(1704067200));
+        assert_eq!(
+            status.map(|s| s.decorations.clone()),
+            Some(BTreeMap::from([
+                ("hostname".to_string(), "host1".to_string()),
+                ("uptime".to_string(), "42".to_string()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_status_log_without_extras_uses_defaults() {
+        let wrapper = LoggerPluginWrapper::new(TestLogger::new());
+        let entries = wrapper
+            .parse_status_entries(r#"[{"s":0,"f":"a.cpp","i":1,"m":"info"}]"#)
+            .unwrap_or_default();
+
+        let entry = entries.first();
+        assert!(entry.is_some_and(|e| e.calendar_time.is_none()
+            && e.unix_time.is_none()
+            && e.decorations.is_empty()));
+    }
+
+    /// Records which logging method each request was routed to
+    struct RoutingLogger {
+        calls: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl LoggerPlugin for RoutingLogger {
+        fn name(&self) -> String {
+            "routing".to_string()
+        }
+
+        fn log_string(&self, message: &str) -> Result<(), String> {
+            self.calls
+                .lock()
+                .map_err(|e| e.to_string())?
+                .push(format!("string:{message}"));
+            Ok(())
+        }
+
+        fn log_query_result(&self, value: &Value) -> Result<(), String> {
+            self.calls
+                .lock()
+                .map_err(|e| e.to_string())?
+                .push(format!("result:{value}"));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_log_routes_to_log_query_result() {
+        let calls = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let wrapper = LoggerPluginWrapper::new(RoutingLogger {
+            calls: calls.clone(),
+        });
+
+        let mut request: BTreeMap<String, String> = BTreeMap::new();
+        request.insert(
+            "log".to_string(),
+            r#"{"name":"pack_users","action":"added"}"#.to_string(),
+        );
+        wrapper.handle_call(request);
+
+        let mut request: BTreeMap<String, String> = BTreeMap::new();
+        request.insert("log".to_string(), "plain text".to_string());
+        wrapper.handle_call(request);
+
+        assert_eq!(
+            calls.lock().map(|c| c.clone()).unwrap_or_default(),
+            vec![
+                r#"result:{"action":"added","name":"pack_users"}"#.to_string(),
+                "string:plain text".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_request_is_not_logged() {
+        let calls = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let wrapper = LoggerPluginWrapper::new(RoutingLogger {
+            calls: calls.clone(),
+        });
+
+        let mut request: BTreeMap<String, String> = BTreeMap::new();
+        request.insert("telemetry".to_string(), "v2".to_string());
+        let response = wrapper.handle_call(request);
+
+        assert!(calls.lock().map(|c| c.is_empty()).unwrap_or(false));
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(1));
+        let row = response.response.as_ref().and_then(|rows| rows.first());
+        assert_eq!(
+            row.and_then(|r| r.get("message")).map(String::as_str),
+            Some("Unknown logger request with keys: telemetry")
+        );
+    }
+
+    #[test]
+    fn test_default_log_query_result_pretty_prints() {
+        let calls = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+
+        struct StringOnly(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+
+        impl LoggerPlugin for StringOnly {
+            fn name(&self) -> String {
+                "string_only".to_string()
+            }
+
+            fn log_string(&self, message: &str) -> Result<(), String> {
+                self.0
+                    .lock()
+                    .map_err(|e| e.to_string())?
+                    .push(message.to_string());
+                Ok(())
+            }
+        }
+
+        let logger = StringOnly(calls.clone());
+        let result = logger.log_query_result(&serde_json::json!({"a": 1}));
+
+        assert!(result.is_ok());
+        assert_eq!(
+            calls.lock().map(|c| c.clone()).unwrap_or_default(),
+            vec!["{\n  \"a\": 1\n}".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_diff_results_dispatch_added_and_removed_separately() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct DiffLogger {
+            calls: Arc<Mutex<Vec<(String, Value)>>>,
+        }
+
+        impl DiffLogger {
+            fn push(&self, kind: &str, value: Value) -> Result<(), String> {
+                self.calls
+                    .lock()
+                    .map_err(|e| e.to_string())?
+                    .push((kind.to_string(), value));
+                Ok(())
+            }
+        }
+
+        impl LoggerPlugin for DiffLogger {
+            fn name(&self) -> String {
+                "diff_logger".to_string()
+            }
+
+            fn log_string(&self, message: &str) -> Result<(), String> {
+                self.push("string", Value::String(message.to_string()))
+            }
+
+            fn log_added(&self, rows: &[Value]) -> Result<(), String> {
+                self.push("added", Value::Array(rows.to_vec()))
+            }
+
+            fn log_removed(&self, rows: &[Value]) -> Result<(), String> {
+                self.push("removed", Value::Array(rows.to_vec()))
+            }
+        }
+
+        let logger = DiffLogger::default();
+        let wrapper = LoggerPluginWrapper::new(logger.clone());
+
+        let response = wrapper.handle_call(BTreeMap::from([(
+            "log".to_string(),
+            r#"{"name":"users","diffResults":{"added":[{"username":"alice"},{"username":"bob"}],"removed":[{"username":"mallory"}]}}"#
+                .to_string(),
+        )]));
+        assert_eq!(response.status.and_then(|s| s.code), Some(0));
+
+        // Nothing to report on an empty side
+        wrapper.handle_call(BTreeMap::from([(
+            "log".to_string(),
+            r#"{"name":"users","diffResults":{"added":[],"removed":[{"username":"bob"}]}}"#
+                .to_string(),
+        )]));
+
+        assert_eq!(
+            logger.calls.lock().map(|c| c.clone()).unwrap_or_default(),
+            vec![
+                (
+                    "added".to_string(),
+                    serde_json::json!([{"username": "alice"}, {"username": "bob"}])
+                ),
+                (
+                    "removed".to_string(),
+                    serde_json::json!([{"username": "mallory"}])
+                ),
+                (
+                    "removed".to_string(),
+                    serde_json::json!([{"username": "bob"}])
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_default_log_added_forwards_to_log_string() {
+        let logger = CapturingLogger::new("capture");
+
+        let result = logger.log_added(&[serde_json::json!({"username": "alice"})]);
+
+        assert!(result.is_ok());
+        assert_eq!(
+            logger.strings(),
+            vec!["{\n  \"added\": [\n    {\n      \"username\": \"alice\"\n    }\n  ]\n}"]
+        );
+    }
+
+    /// One valid entry and one without a message
+    const MIXED_STATUS_LOG: &str =
+        r#"[{"s":1,"f":"a.cpp","i":7,"m":"kept"},{"s":0,"f":"b.cpp","i":8}]"#;
+
+    #[test]
+    fn test_malformed_status_entry_is_dropped_and_counted() {
+        let logger = super::CapturingLogger::new("capture");
+        let wrapper = LoggerPluginWrapper::new(logger.clone());
+
+        let response = wrapper.handle_call(BTreeMap::from([
+            ("status".to_string(), "true".to_string()),
+            ("log".to_string(), MIXED_STATUS_LOG.to_string()),
+        ]));
+
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(0));
+        assert_eq!(
+            logger.statuses(),
+            vec![LogStatus::new(LogSeverity::Warning, "a.cpp", 7, "kept")]
+        );
+        assert_eq!(
+            wrapper.request_counts(),
+            LogRequestCounts {
+                status: 1,
+                dropped_status_entries: 1,
+                ..LogRequestCounts::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_strict_status_parsing_rejects_malformed_entry() {
+        let wrapper = LoggerPluginWrapper::new(TestLogger::new());
+
+        let strict = wrapper.parse_status_entries(MIXED_STATUS_LOG);
+        assert_eq!(
+            strict.map(|entries| entries.len()),
+            Err("Malformed status log entry 1: missing message".to_string())
+        );
+
+        let lenient = wrapper.parse_status_entries_lenient(MIXED_STATUS_LOG);
+        assert_eq!(
+            lenient.map(|(entries, dropped)| (entries.len(), dropped)),
+            Ok((1, 1))
+        );
+
+        let invalid_severity = wrapper.parse_status_entries(r#"[{"s":9,"m":"x"}]"#);
+        assert_eq!(
+            invalid_severity.map(|entries| entries.len()),
+            Err("Malformed status log entry 0: Invalid severity level: 9".to_string())
+        );
+    }
+
+    #[test]
+    fn test_strict_wrapper_does_not_log_partial_batches() {
+        let logger = super::CapturingLogger::new("capture");
+        let wrapper = LoggerPluginWrapper::new(logger.clone()).strict_status_logs(true);
+
+        wrapper.handle_call(BTreeMap::from([
+            ("status".to_string(), "true".to_string()),
+            ("log".to_string(), MIXED_STATUS_LOG.to_string()),
+        ]));
+
+        assert!(logger.statuses().is_empty());
+        assert_eq!(wrapper.request_counts().dropped_status_entries, 0);
+    }
+
+    #[test]
+    fn test_request_counts_by_type() {
+        let wrapper = LoggerPluginWrapper::new(TestLogger::new());
+        let counter = wrapper.request_counter();
+        let call = |pairs: &[(&str, &str)]| {
+            wrapper.handle_call(
+                pairs
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            )
+        };
+
+        call(&[("init", "test_logger")]);
+        call(&[
+            ("status", "true"),
+            ("log", r#"[{"s":0,"f":"a.cpp","i":1,"m":"one"}]"#),
+        ]);
+        call(&[
+            ("status", "true"),
+            ("log", r#"[{"s":1,"f":"b.cpp","i":2,"m":"two"}]"#),
+        ]);
+        call(&[("snapshot", r#"{"name":"uptime"}"#)]);
+        call(&[("unexpected", "")]);
+
+        let counts = wrapper.request_counts();
+        assert_eq!(
+            counts,
+            LogRequestCounts {
+                status: 2,
+                snapshot: 1,
+                init: 1,
+                unknown: 1,
+                ..LogRequestCounts::default()
+            }
+        );
+        assert_eq!(counter.counts(), counts);
+    }
+
     #[test]
     fn test_raw_string_request_returns_success() {
         let logger = TestLogger::new();
@@ -701,6 +1366,40 @@ mod tests {
         assert_eq!(status.and_then(|s| s.code), Some(0));
     }
 
+    #[test]
+    fn test_log_severity_into_log_level() {
+        assert_eq!(log::Level::from(LogSeverity::Info), log::Level::Info);
+        assert_eq!(log::Level::from(LogSeverity::Warning), log::Level::Warn);
+        assert_eq!(log::Level::from(LogSeverity::Error), log::Level::Error);
+    }
+
+    #[test]
+    fn test_log_level_into_log_severity() {
+        assert_eq!(LogSeverity::from(log::Level::Error), LogSeverity::Error);
+        assert_eq!(LogSeverity::from(log::Level::Warn), LogSeverity::Warning);
+        assert_eq!(LogSeverity::from(log::Level::Info), LogSeverity::Info);
+        // osquery has nothing below INFO
+        assert_eq!(LogSeverity::from(log::Level::Debug), LogSeverity::Info);
+        assert_eq!(LogSeverity::from(log::Level::Trace), LogSeverity::Info);
+
+        // The numeric conversion is unchanged
+        assert_eq!(LogSeverity::try_from(1), Ok(LogSeverity::Warning));
+    }
+
+    #[test]
+    fn test_logger_plugin_health_ok() {
+        let wrapper = LoggerPluginWrapper::new(TestLogger::new());
+        assert_eq!(wrapper.health().code, Some(0));
+    }
+
+    #[test]
+    fn test_logger_plugin_health_reports_failure() {
+        let wrapper = LoggerPluginWrapper::new(TestLogger::unhealthy("disk full"));
+        let status = wrapper.health();
+        assert_eq!(status.code, Some(1));
+        assert_eq!(status.message.as_deref(), Some("disk full"));
+    }
+
     #[test]
     fn test_logger_plugin_registry() {
         let logger = TestLogger::new();
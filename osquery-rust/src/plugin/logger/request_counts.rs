@@ -0,0 +1,94 @@
+//! Per-type counts of the requests a logger wrapper has received.
+
+use super::LogRequestType;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// How many requests of each kind osquery has sent a
+/// [`LoggerPluginWrapper`](super::LoggerPluginWrapper).
+///
+/// Useful for debugging an extension that osquery doesn't seem to log to,
+/// e.g. by exposing the counts in an internal table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogRequestCounts {
+    /// Batches of status log entries
+    pub status: u64,
+    /// Query results sent as JSON
+    pub query_result: u64,
+    /// Log lines that weren't JSON
+    pub raw_string: u64,
+    /// Snapshot query results
+    pub snapshot: u64,
+    /// Logger initialization requests
+    pub init: u64,
+    /// Health checks
+    pub health: u64,
+    /// Queries for the logger's supported features
+    pub features: u64,
+    /// Requests with none of the keys the wrapper understands
+    pub unknown: u64,
+    /// Malformed entries skipped from otherwise valid status log batches
+    pub dropped_status_entries: u64,
+}
+
+/// A live view of a wrapper's request counts, cheap to clone and share.
+///
+/// Get one with
+/// [`LoggerPluginWrapper::request_counter`](super::LoggerPluginWrapper::request_counter)
+/// before handing the wrapper to a [`Plugin`](crate::plugin::Plugin).
+#[derive(Debug, Clone, Default)]
+pub struct LogRequestCounter {
+    counts: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    status: AtomicU64,
+    query_result: AtomicU64,
+    raw_string: AtomicU64,
+    snapshot: AtomicU64,
+    init: AtomicU64,
+    health: AtomicU64,
+    features: AtomicU64,
+    unknown: AtomicU64,
+    dropped_status_entries: AtomicU64,
+}
+
+impl LogRequestCounter {
+    /// The counts so far.
+    pub fn counts(&self) -> LogRequestCounts {
+        let c = &self.counts;
+        LogRequestCounts {
+            status: c.status.load(Ordering::Relaxed),
+            query_result: c.query_result.load(Ordering::Relaxed),
+            raw_string: c.raw_string.load(Ordering::Relaxed),
+            snapshot: c.snapshot.load(Ordering::Relaxed),
+            init: c.init.load(Ordering::Relaxed),
+            health: c.health.load(Ordering::Relaxed),
+            features: c.features.load(Ordering::Relaxed),
+            unknown: c.unknown.load(Ordering::Relaxed),
+            dropped_status_entries: c.dropped_status_entries.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(super) fn record(&self, request: &LogRequestType) {
+        let c = &self.counts;
+        let counter = match request {
+            LogRequestType::StatusLog(_) => &c.status,
+            LogRequestType::QueryResult(_) => &c.query_result,
+            LogRequestType::RawString(_) => &c.raw_string,
+            LogRequestType::Snapshot(_) => &c.snapshot,
+            LogRequestType::Init(_) => &c.init,
+            LogRequestType::Health => &c.health,
+            LogRequestType::Features => &c.features,
+            LogRequestType::Unknown(_) => &c.unknown,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn record_dropped_status_entries(&self, dropped: u64) {
+        self.counts
+            .dropped_status_entries
+            .fetch_add(dropped, Ordering::Relaxed);
+    }
+}
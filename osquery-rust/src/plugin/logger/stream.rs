@@ -0,0 +1,231 @@
+//! A logger writing to stdout or stderr, for container deployments.
+
+use super::json_lines::{snapshot_entry, status_entry};
+use super::{LogStatus, LoggerPlugin};
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+/// A [`LoggerPlugin`] that writes one line per entry to stdout, stderr or
+/// any other stream, where container runtimes pick logs up.
+///
+/// By default status logs are written as `[WARNING] watcher.cpp:42 - message`
+/// and results and snapshots as compact JSON. With [`json`](Self::json) every
+/// line is a JSON object in the same shape as [`JsonLinesLogger`]'s.
+///
+/// Writes to a closed pipe (e.g. `ext | head`) are dropped rather than
+/// reported, so a reader going away doesn't make osquery log errors.
+///
+/// [`JsonLinesLogger`]: super::JsonLinesLogger
+///
+/// ```no_run
+/// use osquery_rust_ng::plugin::{Plugin, StreamLogger};
+///
+/// let plugin = Plugin::logger(StreamLogger::stdout("stdout").json(true));
+/// ```
+pub struct StreamLogger {
+    name: String,
+    json: bool,
+    stream: Mutex<Box<dyn Write + Send>>,
+}
+
+impl StreamLogger {
+    /// A logger registered as `name` writing to `stream`.
+    pub fn new(name: &str, stream: impl Write + Send + 'static) -> Self {
+        StreamLogger {
+            name: name.to_string(),
+            json: false,
+            stream: Mutex::new(Box::new(stream)),
+        }
+    }
+
+    /// A logger registered as `name` writing to stdout.
+    pub fn stdout(name: &str) -> Self {
+        Self::new(name, io::stdout())
+    }
+
+    /// A logger registered as `name` writing to stderr.
+    pub fn stderr(name: &str) -> Self {
+        Self::new(name, io::stderr())
+    }
+
+    /// Write every entry as a JSON object instead of text. Off by default.
+    pub fn json(mut self, json: bool) -> Self {
+        self.json = json;
+        self
+    }
+
+    fn write_line(&self, line: &str) -> Result<(), String> {
+        let mut stream = self
+            .stream
+            .lock()
+            .map_err(|e| format!("Failed to lock output stream: {e}"))?;
+        let written = writeln!(stream, "{line}").and_then(|()| stream.flush());
+
+        match written {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            written => written.map_err(|e| format!("Failed to write log line: {e}")),
+        }
+    }
+
+    /// Write `entry` as-is in JSON mode, or `text` otherwise.
+    fn write_entry(&self, entry: Value, text: impl FnOnce() -> String) -> Result<(), String> {
+        if self.json {
+            self.write_line(&entry.to_string())
+        } else {
+            self.write_line(&text())
+        }
+    }
+}
+
+impl LoggerPlugin for StreamLogger {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn log_string(&self, message: &str) -> Result<(), String> {
+        self.write_entry(json!({"type": "string", "message": message}), || {
+            message.to_string()
+        })
+    }
+
+    fn log_query_result(&self, value: &Value) -> Result<(), String> {
+        self.write_entry(json!({"type": "result", "result": value}), || {
+            value.to_string()
+        })
+    }
+
+    fn log_status(&self, status: &LogStatus) -> Result<(), String> {
+        self.write_entry(status_entry(status), || status.to_string())
+    }
+
+    fn log_snapshot(&self, snapshot: &str) -> Result<(), String> {
+        let entry = snapshot_entry(snapshot);
+        let text = entry
+            .get("snapshot")
+            .map(|snapshot| match snapshot {
+                Value::String(raw) => raw.clone(),
+                parsed => parsed.to_string(),
+            })
+            .unwrap_or_default();
+        self.write_entry(entry, || text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::LogSeverity;
+    use std::sync::Arc;
+
+    /// A stream tests can read back.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Captured {
+        fn lines(&self) -> Vec<String> {
+            self.0
+                .lock()
+                .map(|bytes| {
+                    String::from_utf8_lossy(&bytes)
+                        .lines()
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+    }
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0
+                .lock()
+                .map_err(|_| io::Error::other("poisoned"))?
+                .extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct ClosedPipe;
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    fn warning() -> LogStatus {
+        LogStatus::new(
+            LogSeverity::Warning,
+            "watcher.cpp",
+            42,
+            "Memory limits exceeded",
+        )
+    }
+
+    const SNAPSHOT: &str = "{\"name\": \"uptime\",\n \"snapshot\": [{\"days\": \"1\"}]}";
+
+    #[test]
+    fn test_status_and_snapshot_as_text_lines() {
+        let output = Captured::default();
+        let logger = StreamLogger::new("stdout", output.clone());
+
+        assert!(logger.log_status(&warning()).is_ok());
+        assert!(logger.log_snapshot(SNAPSHOT).is_ok());
+
+        assert_eq!(
+            output.lines(),
+            vec![
+                "[WARNING] watcher.cpp:42 - Memory limits exceeded",
+                r#"{"name":"uptime","snapshot":[{"days":"1"}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_status_and_snapshot_as_json_lines() {
+        let output = Captured::default();
+        let logger = StreamLogger::new("stdout", output.clone()).json(true);
+
+        assert!(logger.log_status(&warning()).is_ok());
+        assert!(logger.log_snapshot(SNAPSHOT).is_ok());
+
+        let lines: Vec<Value> = output
+            .lines()
+            .iter()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                json!({
+                    "type": "status",
+                    "severity": "WARNING",
+                    "filename": "watcher.cpp",
+                    "line": 42,
+                    "message": "Memory limits exceeded",
+                }),
+                json!({
+                    "type": "snapshot",
+                    "snapshot": {"name": "uptime", "snapshot": [{"days": "1"}]},
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_broken_pipe_is_not_an_error() {
+        let logger = StreamLogger::new("stdout", ClosedPipe);
+
+        assert_eq!(logger.log_status(&warning()), Ok(()));
+        assert_eq!(logger.log_string("dropped"), Ok(()));
+    }
+}
@@ -0,0 +1,208 @@
+//! Syslog facility and severity helpers for logger plugins that forward to
+//! syslog. Enabled with the `syslog-helpers` feature.
+
+use super::LogSeverity;
+use std::fmt;
+use std::str::FromStr;
+
+/// A standard syslog facility (RFC 5424, section 6.2.1).
+///
+/// Parses case-insensitively from the usual names (`"daemon"`, `"LOCAL0"`),
+/// which makes it a natural type for a `--facility` command-line flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyslogFacility {
+    Kern,
+    User,
+    Mail,
+    Daemon,
+    Auth,
+    Syslog,
+    Lpr,
+    News,
+    Uucp,
+    Cron,
+    AuthPriv,
+    Ftp,
+    Local0,
+    Local1,
+    Local2,
+    Local3,
+    Local4,
+    Local5,
+    Local6,
+    Local7,
+}
+
+impl SyslogFacility {
+    /// Every facility, in numeric order.
+    pub const ALL: [SyslogFacility; 20] = [
+        SyslogFacility::Kern,
+        SyslogFacility::User,
+        SyslogFacility::Mail,
+        SyslogFacility::Daemon,
+        SyslogFacility::Auth,
+        SyslogFacility::Syslog,
+        SyslogFacility::Lpr,
+        SyslogFacility::News,
+        SyslogFacility::Uucp,
+        SyslogFacility::Cron,
+        SyslogFacility::AuthPriv,
+        SyslogFacility::Ftp,
+        SyslogFacility::Local0,
+        SyslogFacility::Local1,
+        SyslogFacility::Local2,
+        SyslogFacility::Local3,
+        SyslogFacility::Local4,
+        SyslogFacility::Local5,
+        SyslogFacility::Local6,
+        SyslogFacility::Local7,
+    ];
+
+    /// The facility's conventional lowercase name, e.g. `"local0"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyslogFacility::Kern => "kern",
+            SyslogFacility::User => "user",
+            SyslogFacility::Mail => "mail",
+            SyslogFacility::Daemon => "daemon",
+            SyslogFacility::Auth => "auth",
+            SyslogFacility::Syslog => "syslog",
+            SyslogFacility::Lpr => "lpr",
+            SyslogFacility::News => "news",
+            SyslogFacility::Uucp => "uucp",
+            SyslogFacility::Cron => "cron",
+            SyslogFacility::AuthPriv => "authpriv",
+            SyslogFacility::Ftp => "ftp",
+            SyslogFacility::Local0 => "local0",
+            SyslogFacility::Local1 => "local1",
+            SyslogFacility::Local2 => "local2",
+            SyslogFacility::Local3 => "local3",
+            SyslogFacility::Local4 => "local4",
+            SyslogFacility::Local5 => "local5",
+            SyslogFacility::Local6 => "local6",
+            SyslogFacility::Local7 => "local7",
+        }
+    }
+
+    /// The facility's numeric code, as used in a message's PRI part.
+    pub fn code(&self) -> u8 {
+        match self {
+            SyslogFacility::Kern => 0,
+            SyslogFacility::User => 1,
+            SyslogFacility::Mail => 2,
+            SyslogFacility::Daemon => 3,
+            SyslogFacility::Auth => 4,
+            SyslogFacility::Syslog => 5,
+            SyslogFacility::Lpr => 6,
+            SyslogFacility::News => 7,
+            SyslogFacility::Uucp => 8,
+            SyslogFacility::Cron => 9,
+            SyslogFacility::AuthPriv => 10,
+            SyslogFacility::Ftp => 11,
+            SyslogFacility::Local0 => 16,
+            SyslogFacility::Local1 => 17,
+            SyslogFacility::Local2 => 18,
+            SyslogFacility::Local3 => 19,
+            SyslogFacility::Local4 => 20,
+            SyslogFacility::Local5 => 21,
+            SyslogFacility::Local6 => 22,
+            SyslogFacility::Local7 => 23,
+        }
+    }
+
+    /// The PRI value of a message with `severity` logged under this facility.
+    pub fn priority(&self, severity: LogSeverity) -> u8 {
+        self.code() * 8 + severity.syslog_severity()
+    }
+}
+
+impl fmt::Display for SyslogFacility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned when parsing an unknown [`SyslogFacility`] name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFacilityError(String);
+
+impl fmt::Display for ParseFacilityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown syslog facility: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseFacilityError {}
+
+impl FromStr for SyslogFacility {
+    type Err = ParseFacilityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SyslogFacility::ALL
+            .into_iter()
+            .find(|facility| facility.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ParseFacilityError(s.to_string()))
+    }
+}
+
+impl LogSeverity {
+    /// The syslog severity (RFC 5424 numeric level) matching this osquery
+    /// severity: informational (6), warning (4) or error (3).
+    pub fn syslog_severity(&self) -> u8 {
+        match self {
+            LogSeverity::Info => 6,
+            LogSeverity::Warning => 4,
+            LogSeverity::Error => 3,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_all_standard_facilities() {
+        let facilities = [
+            "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news", "uucp", "cron",
+            "authpriv", "ftp",
+        ];
+
+        for name in facilities {
+            let facility = name.parse::<SyslogFacility>();
+            assert_eq!(facility.map(|f| f.as_str()), Ok(name));
+        }
+    }
+
+    #[test]
+    fn test_parse_local0_through_7() {
+        for i in 0..=7u8 {
+            let facility = format!("local{i}").parse::<SyslogFacility>();
+            assert_eq!(facility.map(|f| f.code()), Ok(16 + i));
+        }
+    }
+
+    #[test]
+    fn test_parse_case_insensitive() {
+        assert_eq!("DAEMON".parse(), Ok(SyslogFacility::Daemon));
+        assert_eq!("Daemon".parse(), Ok(SyslogFacility::Daemon));
+        assert_eq!("LOCAL0".parse(), Ok(SyslogFacility::Local0));
+        assert_eq!("AuthPriv".parse(), Ok(SyslogFacility::AuthPriv));
+    }
+
+    #[test]
+    fn test_parse_unknown_facility() {
+        let result = "invalid_facility".parse::<SyslogFacility>();
+        assert_eq!(
+            result.map_err(|e| e.to_string()),
+            Err("Unknown syslog facility: invalid_facility".to_string())
+        );
+    }
+
+    #[test]
+    fn test_priority() {
+        assert_eq!(SyslogFacility::Kern.priority(LogSeverity::Error), 3);
+        assert_eq!(SyslogFacility::Local4.priority(LogSeverity::Info), 166);
+        assert_eq!(SyslogFacility::User.priority(LogSeverity::Warning), 12);
+    }
+}
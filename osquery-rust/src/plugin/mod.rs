@@ -1,8 +1,9 @@
 mod _enums;
 mod _traits;
 mod config;
+mod event;
 mod logger;
-mod table;
+pub(crate) mod table;
 
 // Re-exporting all public structures
 pub use _enums::plugin::Plugin;
@@ -10,13 +11,31 @@ pub use _enums::registry::Registry;
 
 pub use _traits::osquery_plugin::OsqueryPlugin;
 
+pub use table::builder::TableBuilder;
+pub use table::cancellation::CancellationToken;
 pub use table::column_def::ColumnDef;
 pub use table::column_def::ColumnOptions;
 pub use table::column_def::ColumnType;
-pub use table::query_constraint::QueryConstraints;
-pub use table::{DeleteResult, InsertResult, ReadOnlyTable, Table, TablePlugin, UpdateResult};
+pub use table::query_constraint::{
+    ConstraintList, Matcher, Operator, QueryConstraints, QueryConstraintsExt,
+};
+pub use table::row::{ColumnValue, Row};
+pub use table::static_table::StaticTable;
+pub use table::{
+    DeleteResult, InsertResult, QueryContext, ReadOnlyTable, RowIterator, Table, TableError,
+    TablePlugin, UpdateResult, DEFAULT_MAX_RESPONSE_BYTES, TABLE_ACTIONS,
+};
 
-pub use _enums::response::ExtensionResponseEnum;
+pub use _enums::response::{ExtensionResponseEnum, JsonRowError, StatusCode};
 
-pub use config::{ConfigPlugin, ConfigPluginWrapper};
-pub use logger::{LogSeverity, LogStatus, LoggerFeatures, LoggerPlugin, LoggerPluginWrapper};
+pub use config::{validate_config, ConfigPlugin, ConfigPluginWrapper, ConfigValidationError};
+pub use event::{EventHandle, EventSubscriberPlugin, EventSubscriberWrapper};
+#[cfg(any(test, feature = "test-util"))]
+pub use logger::CapturingLogger;
+pub use logger::{
+    BufferingLogger, FilteringLogger, JsonLinesLogger, LogFilter, LogRequestCounter,
+    LogRequestCounts, LogSeverity, LogStatus, LoggerFeatures, LoggerPlugin, LoggerPluginWrapper,
+    RegexFilter, StreamLogger,
+};
+#[cfg(feature = "syslog-helpers")]
+pub use logger::{ParseFacilityError, SyslogFacility};
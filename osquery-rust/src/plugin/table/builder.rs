@@ -0,0 +1,392 @@
+use crate::plugin::table::column_def::ColumnDef;
+use crate::plugin::table::query_constraint::{parse_constraints, QueryConstraints};
+use crate::plugin::table::row::Row;
+use crate::plugin::table::{
+    DeleteResult, InsertResult, QueryContext, ReadOnlyTable, RowIterator, Table, TableError,
+    TablePlugin, UpdateResult,
+};
+use crate::plugin::Plugin;
+use crate::{ExtensionPluginRequest, ExtensionPluginRequestExt, ExtensionPluginResponse};
+use crate::{ExtensionResponse, ExtensionStatus};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+type GenerateFn = dyn Fn(&QueryConstraints) -> Vec<BTreeMap<String, String>> + Send + Sync;
+type InsertFn = dyn Fn(bool, &Row) -> InsertResult + Send + Sync;
+type UpdateFn = dyn Fn(u64, &Row) -> UpdateResult + Send + Sync;
+type DeleteFn = dyn Fn(u64) -> DeleteResult + Send + Sync;
+
+/// Builds a table plugin from closures, without a struct or trait impl.
+///
+/// The table is read-only unless one of `insert`, `update` or `delete` is
+/// given; writes the table has no closure for are then rejected with an
+/// error. Closures that share state (e.g. the rows a writeable table keeps)
+/// hold it in an `Arc<Mutex<_>>`.
+///
+/// ```
+/// use osquery_rust_ng::plugin::{ColumnDef, ColumnOptions, ColumnType, TableBuilder};
+/// use std::collections::BTreeMap;
+///
+/// let plugin = TableBuilder::new("greetings")
+///     .column(ColumnDef::new("hello", ColumnType::Text, ColumnOptions::DEFAULT))
+///     .generate(|_constraints| {
+///         vec![BTreeMap::from([("hello".to_string(), "world".to_string())])]
+///     })
+///     .build();
+/// ```
+pub struct TableBuilder {
+    name: String,
+    columns: Vec<ColumnDef>,
+    generate: Option<Arc<GenerateFn>>,
+    insert: Option<Arc<InsertFn>>,
+    update: Option<Arc<UpdateFn>>,
+    delete: Option<Arc<DeleteFn>>,
+}
+
+impl TableBuilder {
+    pub fn new(name: &str) -> Self {
+        TableBuilder {
+            name: name.to_string(),
+            columns: Vec::new(),
+            generate: None,
+            insert: None,
+            update: None,
+            delete: None,
+        }
+    }
+
+    /// Add a column; columns are reported to osquery in the order added.
+    pub fn column(mut self, column: ColumnDef) -> Self {
+        self.columns.push(column);
+        self
+    }
+
+    /// Produce the rows for a query from its constraints. Without this the
+    /// table is always empty.
+    pub fn generate<F>(mut self, generate: F) -> Self
+    where
+        F: Fn(&QueryConstraints) -> Vec<BTreeMap<String, String>> + Send + Sync + 'static,
+    {
+        self.generate = Some(Arc::new(generate));
+        self
+    }
+
+    /// Handle `INSERT`s, see [`Table::insert_row`] for the arguments.
+    pub fn insert<F>(mut self, insert: F) -> Self
+    where
+        F: Fn(bool, &Row) -> InsertResult + Send + Sync + 'static,
+    {
+        self.insert = Some(Arc::new(insert));
+        self
+    }
+
+    /// Handle `UPDATE`s of the row with the given rowid.
+    pub fn update<F>(mut self, update: F) -> Self
+    where
+        F: Fn(u64, &Row) -> UpdateResult + Send + Sync + 'static,
+    {
+        self.update = Some(Arc::new(update));
+        self
+    }
+
+    /// Handle `DELETE`s of the row with the given rowid.
+    pub fn delete<F>(mut self, delete: F) -> Self
+    where
+        F: Fn(u64) -> DeleteResult + Send + Sync + 'static,
+    {
+        self.delete = Some(Arc::new(delete));
+        self
+    }
+
+    /// The table plugin, writeable if any write closure was given.
+    pub fn build(self) -> Plugin {
+        let writeable = self.insert.is_some() || self.update.is_some() || self.delete.is_some();
+        let table = ClosureTable {
+            name: self.name,
+            columns: self.columns,
+            generate: self.generate,
+            insert: self.insert,
+            update: self.update,
+            delete: self.delete,
+        };
+
+        if writeable {
+            Plugin::Table(TablePlugin::from_writeable_table(table))
+        } else {
+            Plugin::Table(TablePlugin::from_readonly_table(table))
+        }
+    }
+}
+
+/// The table a [`TableBuilder`] builds.
+struct ClosureTable {
+    name: String,
+    columns: Vec<ColumnDef>,
+    generate: Option<Arc<GenerateFn>>,
+    insert: Option<Arc<InsertFn>>,
+    update: Option<Arc<UpdateFn>>,
+    delete: Option<Arc<DeleteFn>>,
+}
+
+impl ClosureTable {
+    fn rows(&self, constraints: &QueryConstraints) -> ExtensionPluginResponse {
+        self.generate
+            .as_ref()
+            .map(|generate| generate(constraints))
+            .unwrap_or_default()
+    }
+
+    fn generate_response(&self, req: &ExtensionPluginRequest) -> ExtensionResponse {
+        let constraints = req
+            .context()
+            .as_ref()
+            .map(parse_constraints)
+            .unwrap_or_default();
+        ExtensionResponse::new(ExtensionStatus::new(0, None, None), self.rows(&constraints))
+    }
+
+    fn unsupported(&self, statement: &str) -> String {
+        format!("Table {} does not support {statement}", self.name)
+    }
+}
+
+impl ReadOnlyTable for ClosureTable {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn columns(&self) -> Vec<ColumnDef> {
+        self.columns.clone()
+    }
+
+    fn generate(&self, req: ExtensionPluginRequest) -> ExtensionResponse {
+        self.generate_response(&req)
+    }
+
+    fn generate_rows(&self, query: &QueryContext<'_>) -> Result<RowIterator<'_>, TableError> {
+        Ok(Box::new(self.rows(query.constraints()).into_iter()))
+    }
+
+    fn shutdown(&self) {}
+}
+
+impl Table for ClosureTable {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn columns(&self) -> Vec<ColumnDef> {
+        self.columns.clone()
+    }
+
+    fn generate(&self, req: ExtensionPluginRequest) -> ExtensionResponse {
+        self.generate_response(&req)
+    }
+
+    fn generate_rows(&self, query: &QueryContext<'_>) -> Result<RowIterator<'_>, TableError> {
+        Ok(Box::new(self.rows(query.constraints()).into_iter()))
+    }
+
+    fn update(&mut self, rowid: u64, row: &serde_json::Value) -> UpdateResult {
+        match Row::from_json(&self.columns, row) {
+            Ok(row) => self.update_row(rowid, &row),
+            Err(e) => UpdateResult::Err(e),
+        }
+    }
+
+    fn delete(&mut self, rowid: u64) -> DeleteResult {
+        match &self.delete {
+            Some(delete) => delete(rowid),
+            None => DeleteResult::Err(self.unsupported("DELETE")),
+        }
+    }
+
+    fn insert(&mut self, auto_rowid: bool, row: &serde_json::Value) -> InsertResult {
+        match Row::from_json(&self.columns, row) {
+            Ok(row) => self.insert_row(auto_rowid, &row),
+            Err(e) => InsertResult::Err(e),
+        }
+    }
+
+    fn insert_row(&mut self, auto_rowid: bool, row: &Row) -> InsertResult {
+        match &self.insert {
+            Some(insert) => insert(auto_rowid, row),
+            None => InsertResult::Err(self.unsupported("INSERT")),
+        }
+    }
+
+    fn update_row(&mut self, rowid: u64, row: &Row) -> UpdateResult {
+        match &self.update {
+            Some(update) => update(rowid, row),
+            None => UpdateResult::Err(self.unsupported("UPDATE")),
+        }
+    }
+
+    fn shutdown(&self) {}
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)] // Tests are allowed to panic on setup failures
+mod tests {
+    use super::*;
+    use crate::plugin::{
+        ColumnOptions, ColumnType, Operator, OsqueryPlugin, QueryConstraintsExt, Registry,
+    };
+    use std::sync::Mutex;
+
+    fn request(pairs: &[(&str, &str)]) -> ExtensionPluginRequest {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn row(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        request(pairs)
+    }
+
+    fn status_code(response: &ExtensionResponse) -> Option<i32> {
+        response.status.as_ref().and_then(|s| s.code)
+    }
+
+    #[test]
+    fn test_readonly_table_filters_on_constraints() {
+        let plugin = TableBuilder::new("colors")
+            .column(ColumnDef::new(
+                "name",
+                ColumnType::Text,
+                ColumnOptions::DEFAULT,
+            ))
+            .generate(|constraints| {
+                let wanted = constraints
+                    .constraints_for("name")
+                    .into_iter()
+                    .find(|(op, _)| *op == Operator::Equals)
+                    .map(|(_, expr)| expr);
+                ["red", "green"]
+                    .into_iter()
+                    .filter(|name| wanted.as_deref().is_none_or(|wanted| wanted == *name))
+                    .map(|name| row(&[("name", name)]))
+                    .collect()
+            })
+            .build();
+
+        assert_eq!(plugin.name(), "colors");
+        assert_eq!(plugin.registry(), Registry::Table);
+        assert!(matches!(&plugin, Plugin::Table(TablePlugin::Readonly(_))));
+
+        let response = plugin.handle_call(request(&[("action", "generate")]));
+        assert_eq!(
+            response.response,
+            Some(vec![row(&[("name", "red")]), row(&[("name", "green")])])
+        );
+
+        let context = r#"{"constraints":[{"name":"name","list":[{"op":2,"expr":"green"}],"affinity":"TEXT"}]}"#;
+        let response = plugin.handle_call(request(&[("action", "generate"), ("context", context)]));
+        assert_eq!(response.response, Some(vec![row(&[("name", "green")])]));
+
+        let response = plugin.handle_call(request(&[("action", "delete"), ("id", "1")]));
+        assert_eq!(
+            response.response,
+            Some(vec![row(&[("status", "readonly")])])
+        );
+    }
+
+    #[test]
+    fn test_writeable_table_through_plugin() {
+        let rows: Arc<Mutex<BTreeMap<u64, String>>> = Arc::default();
+
+        let generate_rows = rows.clone();
+        let insert_rows = rows.clone();
+        let update_rows = rows.clone();
+        let delete_rows = rows.clone();
+        let plugin = TableBuilder::new("notes")
+            .column(ColumnDef::new(
+                "text",
+                ColumnType::Text,
+                ColumnOptions::DEFAULT,
+            ))
+            .generate(move |_| {
+                let rows = generate_rows.lock().expect("rows lock poisoned");
+                rows.iter()
+                    .map(|(id, text)| row(&[("rowid", &id.to_string()), ("text", text)]))
+                    .collect()
+            })
+            .insert(move |_auto_rowid, row| {
+                let mut rows = insert_rows.lock().expect("rows lock poisoned");
+                let id = rows.keys().max().map_or(0, |id| id + 1);
+                rows.insert(id, row.get_text("text").unwrap_or_default().to_string());
+                InsertResult::Success(id)
+            })
+            .update(move |id, row| {
+                let mut rows = update_rows.lock().expect("rows lock poisoned");
+                match rows.get_mut(&id) {
+                    Some(text) => {
+                        *text = row.get_text("text").unwrap_or_default().to_string();
+                        UpdateResult::Success
+                    }
+                    None => UpdateResult::Err(format!("No row {id}")),
+                }
+            })
+            .delete(move |id| {
+                let mut rows = delete_rows.lock().expect("rows lock poisoned");
+                match rows.remove(&id) {
+                    Some(_) => DeleteResult::Success,
+                    None => DeleteResult::Err(format!("No row {id}")),
+                }
+            })
+            .build();
+
+        assert!(matches!(&plugin, Plugin::Table(TablePlugin::Writeable(_))));
+
+        let response = plugin.handle_call(request(&[
+            ("action", "insert"),
+            ("auto_rowid", "true"),
+            ("json_value_array", r#"["first"]"#),
+        ]));
+        assert_eq!(status_code(&response), Some(0));
+        plugin.handle_call(request(&[
+            ("action", "insert"),
+            ("auto_rowid", "true"),
+            ("json_value_array", r#"["second"]"#),
+        ]));
+
+        let response = plugin.handle_call(request(&[
+            ("action", "update"),
+            ("id", "0"),
+            ("json_value_array", r#"["edited"]"#),
+        ]));
+        assert_eq!(status_code(&response), Some(0));
+
+        let response = plugin.handle_call(request(&[("action", "delete"), ("id", "1")]));
+        assert_eq!(status_code(&response), Some(0));
+
+        let response = plugin.handle_call(request(&[("action", "generate")]));
+        assert_eq!(
+            response.response,
+            Some(vec![row(&[("rowid", "0"), ("text", "edited")])])
+        );
+    }
+
+    #[test]
+    fn test_missing_write_closures_are_rejected() {
+        let plugin = TableBuilder::new("append_only")
+            .column(ColumnDef::new(
+                "text",
+                ColumnType::Text,
+                ColumnOptions::DEFAULT,
+            ))
+            .insert(|_, _| InsertResult::Success(0))
+            .build();
+
+        let response = plugin.handle_call(request(&[("action", "delete"), ("id", "0")]));
+        assert_eq!(status_code(&response), Some(1));
+        let message = response
+            .response
+            .and_then(|rows| rows.first().and_then(|row| row.get("message")).cloned());
+        assert_eq!(
+            message.as_deref(),
+            Some("Plugin error Table append_only does not support DELETE")
+        );
+    }
+}
@@ -0,0 +1,65 @@
+//! Cooperative cancellation for long-running table generation.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Tells a running `generate` that the extension is shutting down.
+///
+/// The server hands every query a token tied to its shutdown flag, see
+/// [`QueryContext::cancellation`](super::QueryContext::cancellation).
+/// Tables that loop over a lot of data can check
+/// [`is_cancelled`](Self::is_cancelled) now and then and return early instead
+/// of keeping the extension alive after osquery asked it to stop. Clones share
+/// the same state.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// A token that is only cancelled by calling [`cancel`](Self::cancel),
+    /// e.g. to exercise a table in tests.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A token that is cancelled once `flag` is set.
+    pub(crate) fn from_flag(flag: Arc<AtomicBool>) -> Self {
+        CancellationToken { cancelled: flag }
+    }
+
+    /// Whether the work this token was handed to should stop.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Ask the work holding this token, or any clone of it, to stop.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_cancellation() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+
+        token.cancel();
+        assert!(clone.is_cancelled());
+    }
+
+    #[test]
+    fn test_token_follows_flag() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let token = CancellationToken::from_flag(flag.clone());
+        assert!(!token.is_cancelled());
+
+        flag.store(true, Ordering::Release);
+        assert!(token.is_cancelled());
+    }
+}
@@ -1,5 +1,5 @@
 use bitflags::bitflags;
-use strum_macros::Display;
+use strum_macros::{Display, EnumString};
 
 // ColumnDef defines a column used in a table plugin.
 // Prefer using the helper functions to create a ColumnDef.
@@ -8,9 +8,13 @@ pub struct ColumnDef {
     name: String,
     t: ColumnType,
     o: ColumnOptions,
+    description: String,
+    generated: Option<String>,
 }
 
-#[derive(Clone, Display, Debug)]
+/// The type of a column, displayed and parsed as osquery's type names
+/// (`"TEXT"`, `"BIGINT"`, ...). Parsing is case-sensitive.
+#[derive(Clone, Display, EnumString, Debug, PartialEq, Eq)]
 #[strum(serialize_all = "UPPERCASE")]
 pub enum ColumnType {
     // TEXT: containing strings
@@ -19,16 +23,24 @@ pub enum ColumnType {
     Integer,
     // BIGINT: containing large integers
     BigInt,
+    // UNSIGNED_BIGINT: containing large non-negative integers
+    #[strum(serialize = "UNSIGNED_BIGINT")]
+    UnsignedBigInt,
     // DOUBLE: containing floating point values
     Double,
+    // BLOB: containing binary data, hex-encoded on the wire (see ColumnValue::encode_blob)
+    Blob,
 }
 
 bitflags! {
     #[derive(Clone, Debug, PartialEq, Eq, Hash)]
     pub struct ColumnOptions: u32 {
         const DEFAULT = 0;
+        // Constraints on this column are used to look up rows
         const INDEX = 1;
+        // Queries must constrain this column; the table refuses to generate otherwise
         const REQUIRED = 2;
+        // Like INDEX, but constraining this column is optional
         const ADDITIONAL = 4;
         const OPTIMIZED = 8;
         const HIDDEN = 16;
@@ -42,9 +54,39 @@ impl ColumnDef {
             name: name.to_owned(),
             t,
             o,
+            description: String::new(),
+            generated: None,
         }
     }
 
+    // Attach a human-readable description. It is sent in the column's route
+    // under "description", so tools reading the routes (e.g. `Server::describe()`)
+    // can show it; osquery itself ignores the key.
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_owned();
+        self
+    }
+
+    // Mark the column as derived from other columns by `expression` (e.g.
+    // "size / 1024"). The expression is sent in the column's route under
+    // "generated"; osquery ignores the key, so the table still fills in the
+    // value when generating rows.
+    pub fn generated(mut self, expression: &str) -> Self {
+        self.generated = Some(expression.to_owned());
+        self
+    }
+
+    // Whether the column was declared with `generated()`; columns are not
+    // generated by default.
+    pub fn is_generated(&self) -> bool {
+        self.generated.is_some()
+    }
+
+    // The expression given to `generated()`, if any.
+    pub fn generated_expression(&self) -> Option<&str> {
+        self.generated.as_deref()
+    }
+
     pub(crate) fn name(&self) -> String {
         self.name.to_string()
     }
@@ -53,7 +95,67 @@ impl ColumnDef {
         self.t.to_string()
     }
 
+    pub(crate) fn column_type(&self) -> &ColumnType {
+        &self.t
+    }
+
+    pub(crate) fn is_required(&self) -> bool {
+        self.o.contains(ColumnOptions::REQUIRED)
+    }
+
     pub(crate) fn o(&self) -> String {
         self.o.bits().to_string()
     }
+
+    // The description given to `with_description()`; empty by default.
+    pub(crate) fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_column_type_round_trips() {
+        let types = [
+            ColumnType::Text,
+            ColumnType::Integer,
+            ColumnType::BigInt,
+            ColumnType::UnsignedBigInt,
+            ColumnType::Double,
+            ColumnType::Blob,
+        ];
+
+        for t in types {
+            assert_eq!(ColumnType::from_str(&t.to_string()), Ok(t));
+        }
+    }
+
+    #[test]
+    fn test_column_type_names() {
+        assert_eq!(ColumnType::BigInt.to_string(), "BIGINT");
+        assert_eq!(ColumnType::UnsignedBigInt.to_string(), "UNSIGNED_BIGINT");
+        assert_eq!("TEXT".parse::<ColumnType>(), Ok(ColumnType::Text));
+    }
+
+    #[test]
+    fn test_generated_column() {
+        let size = ColumnDef::new("size", ColumnType::BigInt, ColumnOptions::DEFAULT);
+        assert!(!size.is_generated());
+        assert_eq!(size.generated_expression(), None);
+
+        let size_kb = ColumnDef::new("size_kb", ColumnType::BigInt, ColumnOptions::DEFAULT)
+            .generated("size / 1024");
+        assert!(size_kb.is_generated());
+        assert_eq!(size_kb.generated_expression(), Some("size / 1024"));
+    }
+
+    #[test]
+    fn test_unknown_column_type() {
+        assert!("VARCHAR".parse::<ColumnType>().is_err());
+        assert!("text".parse::<ColumnType>().is_err());
+    }
 }
@@ -6,17 +6,36 @@ pub(crate) mod query_constraint;
 #[allow(unused_imports)]
 pub use query_constraint::QueryConstraints;
 
+pub(crate) mod row;
+use row::Row;
+
+pub(crate) mod builder;
+pub(crate) mod cancellation;
+use cancellation::CancellationToken;
+pub(crate) mod static_table;
+
 use crate::_osquery::{
     osquery, ExtensionPluginRequest, ExtensionPluginResponse, ExtensionResponse, ExtensionStatus,
 };
 use crate::plugin::ExtensionResponseEnum::SuccessWithId;
 use crate::plugin::_enums::response::ExtensionResponseEnum;
-use crate::plugin::{OsqueryPlugin, Registry};
+use crate::plugin::{OsqueryPlugin, Registry, StatusCode};
+use crate::{log_target, ExtensionPluginRequestExt, PluginRequest};
+use column_def::ColumnOptions;
 use enum_dispatch::enum_dispatch;
-use serde_json::Value;
 use std::collections::BTreeMap;
+use std::fmt;
 use std::sync::{Arc, Mutex};
 
+/// Default for [`ReadOnlyTable::max_response_bytes`], the same 100MB limit
+/// the client puts on responses from osquery.
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 100 * 1024 * 1024;
+
+/// The actions every table plugin answers. `columns` returns the table's
+/// routes (its declared columns, with index columns marked); other actions go
+/// to the table's `handle_action`.
+pub const TABLE_ACTIONS: [&str; 5] = ["columns", "generate", "insert", "update", "delete"];
+
 #[derive(Clone)]
 #[enum_dispatch(OsqueryPlugin)]
 pub enum TablePlugin {
@@ -53,31 +72,23 @@ impl OsqueryPlugin for TablePlugin {
     }
 
     fn routes(&self) -> ExtensionPluginResponse {
-        let mut resp = ExtensionPluginResponse::new();
-
-        let columns = match self {
+        let (columns, indexes) = match self {
             TablePlugin::Writeable(table) => {
                 let Ok(table) = table.lock() else {
-                    log::error!("Plugin was unavailable, could not lock table");
-                    return resp;
+                    log::error!(
+                        target: log_target::PLUGIN,
+                        "Plugin was unavailable, could not lock table"
+                    );
+                    return ExtensionPluginResponse::new();
                 };
 
-                table.columns()
+                (table.columns(), table.indexes())
             }
-            TablePlugin::Readonly(table) => table.columns(),
+            TablePlugin::Readonly(table) => (table.columns(), table.indexes()),
         };
 
-        for column in &columns {
-            let mut r: BTreeMap<String, String> = BTreeMap::new();
-
-            r.insert("id".to_string(), "column".to_string());
-            r.insert("name".to_string(), column.name());
-            r.insert("type".to_string(), column.t());
-            r.insert("op".to_string(), column.o());
-
-            resp.push(r);
-        }
-
+        let mut resp = column_routes(&columns);
+        mark_index_columns(&mut resp, &indexes);
         resp
     }
 
@@ -85,10 +96,28 @@ impl OsqueryPlugin for TablePlugin {
         ExtensionStatus::default()
     }
 
+    fn min_osquery_version(&self) -> Option<semver::Version> {
+        match self {
+            TablePlugin::Writeable(table) => table
+                .lock()
+                .ok()
+                .and_then(|table| table.min_osquery_version()),
+            TablePlugin::Readonly(table) => table.min_osquery_version(),
+        }
+    }
+
     fn handle_call(&self, request: crate::_osquery::ExtensionPluginRequest) -> ExtensionResponse {
-        let action = request.get("action").map(|s| s.as_str()).unwrap_or("");
+        self.handle_call_cancellable(request, &CancellationToken::new())
+    }
 
-        log::trace!("Action: {action}");
+    fn handle_call_cancellable(
+        &self,
+        request: crate::_osquery::ExtensionPluginRequest,
+        ctx: &CancellationToken,
+    ) -> ExtensionResponse {
+        let action = PluginRequest::new(&request).action();
+
+        log::trace!(target: log_target::PLUGIN, "Action: {action}");
 
         match action {
             "columns" => {
@@ -102,24 +131,24 @@ impl OsqueryPlugin for TablePlugin {
                     resp,
                 )
             }
-            "generate" => self.generate(request),
+            "generate" => self.generate(request, ctx),
             "update" => self.update(request),
             "delete" => self.delete(request),
             "insert" => self.insert(request),
-            _ => ExtensionResponseEnum::Failure(format!(
-                "Invalid table plugin action:{action:?} request:{request:?}"
-            ))
-            .into(),
+            _ => self.handle_custom_action(action, &request),
         }
     }
 
     fn shutdown(&self) {
-        log::trace!("Shutting down plugin: {}", self.name());
+        log::trace!(target: log_target::PLUGIN, "Shutting down plugin: {}", self.name());
 
         match self {
             TablePlugin::Writeable(table) => {
                 let Ok(table) = table.lock() else {
-                    log::error!("Plugin was unavailable, could not lock table");
+                    log::error!(
+                        target: log_target::PLUGIN,
+                        "Plugin was unavailable, could not lock table"
+                    );
                     return;
                 };
 
@@ -131,8 +160,54 @@ impl OsqueryPlugin for TablePlugin {
 }
 
 impl TablePlugin {
-    fn generate(&self, req: ExtensionPluginRequest) -> ExtensionResponse {
-        match self {
+    /// Pass an action that isn't built in to the table's `handle_action`.
+    fn handle_custom_action(
+        &self,
+        action: &str,
+        request: &ExtensionPluginRequest,
+    ) -> ExtensionResponse {
+        let handled = match self {
+            TablePlugin::Writeable(table) => match table.lock() {
+                Ok(table) => table.handle_action(action, request),
+                Err(_) => {
+                    return ExtensionResponseEnum::Failure(
+                        "Plugin was unavailable, could not lock table".to_string(),
+                    )
+                    .into()
+                }
+            },
+            TablePlugin::Readonly(table) => table.handle_action(action, request),
+        };
+
+        match handled {
+            Some(Ok(rows)) => ExtensionResponse::new(ExtensionStatus::new(0, None, None), rows),
+            Some(Err(e)) => {
+                ExtensionResponseEnum::FailureWithCode(e.code().code(), e.message().to_string())
+                    .into()
+            }
+            None => ExtensionResponseEnum::Failure(format!(
+                "Unknown table action '{action}', supported actions: {}",
+                TABLE_ACTIONS.join(", ")
+            ))
+            .into(),
+        }
+    }
+
+    fn generate(&self, req: ExtensionPluginRequest, ctx: &CancellationToken) -> ExtensionResponse {
+        let context = req.context();
+        let constraints = context
+            .as_ref()
+            .map(query_constraint::parse_constraints)
+            .unwrap_or_default();
+        let limit = context.as_ref().and_then(query_constraint::parse_limit);
+        let query = QueryContext {
+            request: &req,
+            constraints: &constraints,
+            limit,
+            cancellation: ctx,
+        };
+
+        let rows = match self {
             TablePlugin::Writeable(table) => {
                 let Ok(table) = table.lock() else {
                     return ExtensionResponseEnum::Failure(
@@ -141,9 +216,29 @@ impl TablePlugin {
                     .into();
                 };
 
-                table.generate(req)
+                let (name, columns) = (table.name(), table.columns());
+                let (max_rows, max_bytes) = (table.max_response_rows(), table.max_response_bytes());
+                check_required_columns(&name, &columns, &constraints)
+                    .and_then(|()| table.generate_rows(&query).map(Iterator::collect))
+                    .and_then(|rows| check_row_columns(&name, &columns, rows))
+                    .and_then(|rows| check_response_size(&name, rows, max_rows, max_bytes))
+            }
+            TablePlugin::Readonly(table) => {
+                let (name, columns) = (table.name(), table.columns());
+                let (max_rows, max_bytes) = (table.max_response_rows(), table.max_response_bytes());
+                check_required_columns(&name, &columns, &constraints)
+                    .and_then(|()| table.generate_rows(&query).map(Iterator::collect))
+                    .and_then(|rows| check_row_columns(&name, &columns, rows))
+                    .and_then(|rows| check_response_size(&name, rows, max_rows, max_bytes))
+            }
+        };
+
+        match rows {
+            Ok(rows) => ExtensionResponse::new(ExtensionStatus::new(0, None, None), rows),
+            Err(e) => {
+                ExtensionResponseEnum::FailureWithCode(e.code().code(), e.message().to_string())
+                    .into()
             }
-            TablePlugin::Readonly(table) => table.generate(req),
         }
     }
 
@@ -151,6 +246,7 @@ impl TablePlugin {
         let TablePlugin::Writeable(table) = self else {
             return ExtensionResponseEnum::Readonly().into();
         };
+        let constraints = request_constraints(&req);
 
         let Ok(mut table) = table.lock() else {
             return ExtensionResponseEnum::Failure(
@@ -159,7 +255,7 @@ impl TablePlugin {
             .into();
         };
 
-        let Some(id) = req.get("id") else {
+        let Some(id) = PluginRequest::new(&req).id() else {
             return ExtensionResponseEnum::Failure("Could not deserialize the id".to_string())
                 .into();
         };
@@ -168,7 +264,7 @@ impl TablePlugin {
             return ExtensionResponseEnum::Failure("Could not parse the id".to_string()).into();
         };
 
-        let Some(json_value_array) = req.get("json_value_array") else {
+        let Some(json_value_array) = PluginRequest::new(&req).json_value_array() else {
             return ExtensionResponseEnum::Failure(
                 "Could not deserialize the json_value_array".to_string(),
             )
@@ -176,14 +272,20 @@ impl TablePlugin {
         };
 
         // "json_value_array": "[1,\"lol\"]"
-        let Ok(row) = serde_json::from_str::<Value>(json_value_array) else {
+        let Ok(row) = json_value_array else {
             return ExtensionResponseEnum::Failure(
                 "Could not parse the json_value_array".to_string(),
             )
             .into();
         };
 
-        match table.update(id, &row) {
+        // Values that don't fit `columns()` go to the raw `update` as sent
+        let updated = match Row::from_json(&table.columns(), &row) {
+            Ok(row) => table.update_row_with_context(id, &row, &constraints),
+            Err(_) => table.update(id, &row),
+        };
+
+        match updated {
             UpdateResult::Success => ExtensionResponseEnum::Success().into(),
             UpdateResult::Constraint => ExtensionResponseEnum::Constraint().into(),
             UpdateResult::Err(err) => ExtensionResponseEnum::Failure(err).into(),
@@ -202,7 +304,7 @@ impl TablePlugin {
             .into();
         };
 
-        let Some(id) = req.get("id") else {
+        let Some(id) = PluginRequest::new(&req).id() else {
             return ExtensionResponseEnum::Failure("Could not deserialize the id".to_string())
                 .into();
         };
@@ -223,6 +325,7 @@ impl TablePlugin {
         let TablePlugin::Writeable(table) = self else {
             return ExtensionResponseEnum::Readonly().into();
         };
+        let constraints = request_constraints(&req);
 
         let Ok(mut table) = table.lock() else {
             return ExtensionResponseEnum::Failure(
@@ -231,9 +334,7 @@ impl TablePlugin {
             .into();
         };
 
-        let auto_rowid = req.get("auto_rowid").unwrap_or(&"false".to_string()) == "true";
-
-        let Some(json_value_array) = req.get("json_value_array") else {
+        let Some(json_value_array) = PluginRequest::new(&req).json_value_array() else {
             return ExtensionResponseEnum::Failure(
                 "Could not deserialize the json_value_array".to_string(),
             )
@@ -241,14 +342,32 @@ impl TablePlugin {
         };
 
         // "json_value_array": "[1,\"lol\"]"
-        let Ok(row) = serde_json::from_str::<Value>(json_value_array) else {
+        let Ok(row) = json_value_array else {
             return ExtensionResponseEnum::Failure(
                 "Could not parse the json_value_array".to_string(),
             )
             .into();
         };
 
-        match table.insert(auto_rowid, &row) {
+        // An INSERT that names a rowid comes with "auto_rowid": "false" and
+        // the rowid in "id"; without one the rowid is null and auto_rowid true
+        let req = PluginRequest::new(&req);
+        let id = req.id().and_then(|id| id.parse::<u64>().ok());
+
+        // Values that don't fit `columns()` go to the raw `insert` as sent
+        let inserted = match Row::from_json(&table.columns(), &row) {
+            Ok(typed) => {
+                let typed = match id {
+                    Some(id) => typed.with_rowid(id),
+                    None => typed,
+                };
+                let auto_rowid = req.auto_rowid().unwrap_or(typed.rowid().is_none());
+                table.insert_row_with_context(auto_rowid, &typed, &constraints)
+            }
+            Err(_) => table.insert(req.auto_rowid().unwrap_or(id.is_none()), &row),
+        };
+
+        match inserted {
             InsertResult::Success(rowid) => SuccessWithId(rowid).into(),
             InsertResult::Constraint => ExtensionResponseEnum::Constraint().into(),
             InsertResult::Err(err) => ExtensionResponseEnum::Failure(err).into(),
@@ -256,6 +375,219 @@ impl TablePlugin {
     }
 }
 
+/// The constraints osquery sent in the request's `context`, empty if it sent
+/// none.
+fn request_constraints(req: &ExtensionPluginRequest) -> QueryConstraints {
+    req.context()
+        .as_ref()
+        .map(query_constraint::parse_constraints)
+        .unwrap_or_default()
+}
+
+/// Fail the query if a `REQUIRED` column has no constraint, like osquery does
+/// for its own tables.
+fn check_required_columns(
+    table: &str,
+    columns: &[ColumnDef],
+    constraints: &QueryConstraints,
+) -> Result<(), TableError> {
+    let missing = columns.iter().find(|column| {
+        column.is_required()
+            && constraints
+                .get(&column.name())
+                .is_none_or(|list| list.is_empty())
+    });
+
+    match missing {
+        Some(column) => Err(TableError::new(&format!(
+            "Table {table} was queried without a required column in the WHERE clause: {}",
+            column.name()
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Fail the query if a row has a key that isn't one of `columns`, which
+/// osquery would otherwise drop without a word. `rowid` is always allowed.
+#[cfg(feature = "strict-columns")]
+fn check_row_columns(
+    table: &str,
+    columns: &[ColumnDef],
+    rows: ExtensionPluginResponse,
+) -> Result<ExtensionPluginResponse, TableError> {
+    let declared: std::collections::HashSet<String> = columns.iter().map(ColumnDef::name).collect();
+    let mut unknown: Vec<&str> = rows
+        .iter()
+        .flat_map(|row| row.keys())
+        .filter(|key| *key != "rowid" && !declared.contains(*key))
+        .map(String::as_str)
+        .collect();
+    if unknown.is_empty() {
+        return Ok(rows);
+    }
+
+    unknown.sort_unstable();
+    unknown.dedup();
+    let message = format!(
+        "Table {table} returned undeclared columns: {}",
+        unknown.join(", ")
+    );
+    log::warn!(target: log_target::PLUGIN, "{message}");
+    Err(TableError::new(&message))
+}
+
+/// Rows are passed through unchecked without the `strict-columns` feature.
+#[cfg(not(feature = "strict-columns"))]
+fn check_row_columns(
+    _table: &str,
+    _columns: &[ColumnDef],
+    rows: ExtensionPluginResponse,
+) -> Result<ExtensionPluginResponse, TableError> {
+    Ok(rows)
+}
+
+/// Fail the query if its rows are over the table's response limits, rather
+/// than sending osquery a frame it may not be able to take.
+fn check_response_size(
+    table: &str,
+    rows: ExtensionPluginResponse,
+    max_rows: Option<usize>,
+    max_bytes: Option<usize>,
+) -> Result<ExtensionPluginResponse, TableError> {
+    if let Some(max_rows) = max_rows.filter(|max| rows.len() > *max) {
+        return Err(TableError::new(&format!(
+            "Table {table} result exceeds configured limit: {} rows > {max_rows}",
+            rows.len()
+        )));
+    }
+
+    if let Some(max_bytes) = max_bytes {
+        let bytes = response_size(&rows);
+        if bytes > max_bytes {
+            return Err(TableError::new(&format!(
+                "Table {table} result exceeds configured limit: {bytes} bytes > {max_bytes}"
+            )));
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Approximate size of `rows` on the wire with the binary protocol: a list
+/// of maps of length-prefixed strings.
+fn response_size(rows: &ExtensionPluginResponse) -> usize {
+    const LIST_HEADER: usize = 5;
+    const MAP_HEADER: usize = 6;
+    const STRING_LENGTH: usize = 4;
+
+    rows.iter().fold(LIST_HEADER, |size, row| {
+        row.iter().fold(size + MAP_HEADER, |size, (column, value)| {
+            size + 2 * STRING_LENGTH + column.len() + value.len()
+        })
+    })
+}
+
+/// Describe `columns` the way osquery expects in a table plugin's routes.
+pub(crate) fn column_routes(columns: &[ColumnDef]) -> ExtensionPluginResponse {
+    let mut resp = ExtensionPluginResponse::new();
+
+    for column in columns {
+        let mut r: BTreeMap<String, String> = BTreeMap::new();
+
+        r.insert("id".to_string(), "column".to_string());
+        r.insert("name".to_string(), column.name());
+        r.insert("type".to_string(), column.t());
+        r.insert("op".to_string(), column.o());
+        if !column.description().is_empty() {
+            r.insert("description".to_string(), column.description().to_string());
+        }
+        if let Some(expression) = column.generated_expression() {
+            r.insert("generated".to_string(), expression.to_string());
+        }
+
+        resp.push(r);
+    }
+
+    resp
+}
+
+/// Mark the columns of the table's `indexes` `INDEX` in `routes`, so osquery
+/// passes their constraints on to the table.
+fn mark_index_columns(routes: &mut ExtensionPluginResponse, indexes: &[Vec<String>]) {
+    for index in indexes {
+        for route in routes.iter_mut() {
+            let in_index = route.get("id").is_some_and(|id| id == "column")
+                && route.get("name").is_some_and(|name| index.contains(name));
+            if !in_index {
+                continue;
+            }
+            let op = route
+                .get("op")
+                .and_then(|op| op.parse::<u32>().ok())
+                .unwrap_or(0);
+            route.insert(
+                "op".to_string(),
+                (op | ColumnOptions::INDEX.bits()).to_string(),
+            );
+        }
+    }
+}
+
+/// Lazily produced table rows, see [`Table::generate_rows`].
+pub type RowIterator<'a> = Box<dyn Iterator<Item = BTreeMap<String, String>> + 'a>;
+
+/// One query, as handed to [`Table::generate_rows`].
+pub struct QueryContext<'a> {
+    request: &'a ExtensionPluginRequest,
+    constraints: &'a QueryConstraints,
+    limit: Option<usize>,
+    cancellation: &'a CancellationToken,
+}
+
+impl QueryContext<'_> {
+    /// The request as osquery sent it.
+    pub fn request(&self) -> &ExtensionPluginRequest {
+        self.request
+    }
+
+    /// The constraints from the query's `WHERE` clause.
+    pub fn constraints(&self) -> &QueryConstraints {
+        self.constraints
+    }
+
+    /// How many rows the query wants, when the table may stop there.
+    ///
+    /// osquery doesn't send a limit, and the framework never cuts the rows
+    /// short itself; this is only set when there are no constraints or
+    /// `OFFSET` that osquery would still apply to the rows.
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// A token that is cancelled once the extension starts shutting down;
+    /// clone it into a lazy row iterator to check it there.
+    pub fn cancellation(&self) -> &CancellationToken {
+        self.cancellation
+    }
+
+    /// Whether the extension is shutting down and the query should stop.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
+
+/// Adapt a `generate` style response into a row stream.
+/// A non-zero status becomes an error carrying the status message.
+fn response_into_rows<'a>(resp: ExtensionResponse) -> Result<RowIterator<'a>, String> {
+    match resp.status.as_ref().and_then(|s| s.code) {
+        None | Some(0) => Ok(Box::new(resp.response.unwrap_or_default().into_iter())),
+        Some(code) => Err(resp
+            .status
+            .and_then(|s| s.message)
+            .unwrap_or_else(|| format!("Table generate failed with code {code}"))),
+    }
+}
+
 pub enum InsertResult {
     Success(u64),
     Constraint,
@@ -273,13 +605,199 @@ pub enum DeleteResult {
     Err(String),
 }
 
+/// A per-query error returned by `generate_rows`.
+///
+/// Only the query that hit it fails, with this message and status code; the
+/// extension stays registered and later queries are served as usual.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableError {
+    code: StatusCode,
+    message: String,
+}
+
+impl TableError {
+    /// The query failed (status code 1).
+    pub fn new(message: &str) -> Self {
+        TableError {
+            code: StatusCode::Failure,
+            message: message.to_string(),
+        }
+    }
+
+    /// The query failed but may succeed if retried, e.g. because a file the
+    /// table reads was briefly locked (status code 2).
+    pub fn transient(message: &str) -> Self {
+        TableError {
+            code: StatusCode::Transient,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn code(&self) -> StatusCode {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<String> for TableError {
+    fn from(message: String) -> Self {
+        TableError {
+            code: StatusCode::Failure,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for TableError {}
+
 pub trait Table: Send + Sync + 'static {
     fn name(&self) -> String;
     fn columns(&self) -> Vec<ColumnDef>;
     fn generate(&self, req: crate::ExtensionPluginRequest) -> crate::ExtensionResponse;
-    fn update(&mut self, rowid: u64, row: &serde_json::Value) -> UpdateResult;
+    /// Generate the rows for one query. This is what the framework calls.
+    ///
+    /// The default returns the rows from `generate`, failing the query if
+    /// its status isn't success. Override it to use what `query` carries:
+    ///
+    /// - its constraints, to only produce matching rows, and a `LIMIT` hint
+    ///   to stop early (see [`QueryContext::limit`]);
+    /// - a cancellation token, to stop early once the extension starts
+    ///   shutting down;
+    /// - an error return, to fail just this query with a [`TableError`] for
+    ///   transient problems that shouldn't take the extension down.
+    ///
+    /// Rows may be produced lazily. The framework drains the iterator into
+    /// the Thrift response, which saves the table's own buffers; the
+    /// complete response is still sent to osquery in a single Thrift reply.
+    fn generate_rows(&self, query: &QueryContext<'_>) -> Result<RowIterator<'_>, TableError> {
+        response_into_rows(self.generate(query.request().clone())).map_err(TableError::from)
+    }
+    /// Update a row given as the raw JSON array of column values, exactly as
+    /// osquery sent it.
+    ///
+    /// Implement this or `update_row`. The default rejects the values if
+    /// they don't fit `columns()`, which is how they get here when only
+    /// `update_row` is implemented, and otherwise reports that the table
+    /// doesn't support `UPDATE`.
+    fn update(&mut self, _rowid: u64, row: &serde_json::Value) -> UpdateResult {
+        match Row::from_json(&self.columns(), row) {
+            Err(e) => UpdateResult::Err(e),
+            Ok(_) => UpdateResult::Err(format!("Table {} does not support UPDATE", self.name())),
+        }
+    }
     fn delete(&mut self, rowid: u64) -> DeleteResult;
-    fn insert(&mut self, auto_rowid: bool, row: &serde_json::value::Value) -> InsertResult;
+    /// Insert a row given as the raw JSON array of column values, exactly as
+    /// osquery sent it.
+    ///
+    /// `auto_rowid` is true when the `INSERT` didn't specify a rowid (its
+    /// value is `null`): the table picks one and returns it in
+    /// [`InsertResult::Success`]. Otherwise the statement chose the rowid,
+    /// but osquery sends it in the request's `id`, which only reaches
+    /// `insert_row` (as [`Row::rowid`]); tables that honor a chosen rowid
+    /// should implement `insert_row` and return
+    /// [`InsertResult::Constraint`] if it is already taken.
+    ///
+    /// Implement this or `insert_row`. The default rejects the values if
+    /// they don't fit `columns()`, which is how they get here when only
+    /// `insert_row` is implemented, and otherwise reports that the table
+    /// doesn't support `INSERT`.
+    fn insert(&mut self, _auto_rowid: bool, row: &serde_json::value::Value) -> InsertResult {
+        match Row::from_json(&self.columns(), row) {
+            Err(e) => InsertResult::Err(e),
+            Ok(_) => InsertResult::Err(format!("Table {} does not support INSERT", self.name())),
+        }
+    }
+    /// Insert a row whose values have been matched up with `columns()`, to
+    /// read them by name (`row.get_text("name")`) instead of by position.
+    ///
+    /// The framework calls this when every value fits its column's type;
+    /// other rows go to `insert` unchanged. `auto_rowid` follows the same
+    /// contract as in `insert`; when it is false the chosen rowid is
+    /// [`Row::rowid`].
+    ///
+    /// The default passes the raw JSON array on to `insert`.
+    fn insert_row(&mut self, auto_rowid: bool, row: &Row) -> InsertResult {
+        self.insert(auto_rowid, row.as_json())
+    }
+    /// Update a row whose values have been matched up with `columns()`, like
+    /// `insert_row`; other rows go to `update` unchanged.
+    ///
+    /// The default passes the raw JSON array on to `update`.
+    fn update_row(&mut self, rowid: u64, row: &Row) -> UpdateResult {
+        self.update(rowid, row.as_json())
+    }
+    /// Insert a row, given the constraints osquery sent along with the
+    /// statement: which columns are constrained and to what values.
+    ///
+    /// This is what the framework calls. Override it to validate a write
+    /// against the constraints (e.g. an `INSERT` must match a `path = ...`
+    /// the table is keyed by). The default ignores them and calls
+    /// `insert_row`.
+    fn insert_row_with_context(
+        &mut self,
+        auto_rowid: bool,
+        row: &Row,
+        _constraints: &QueryConstraints,
+    ) -> InsertResult {
+        self.insert_row(auto_rowid, row)
+    }
+    /// Update a row, given the constraints osquery sent along with the
+    /// statement, like `insert_row_with_context`. The default ignores them
+    /// and calls `update_row`.
+    fn update_row_with_context(
+        &mut self,
+        rowid: u64,
+        row: &Row,
+        _constraints: &QueryConstraints,
+    ) -> UpdateResult {
+        self.update_row(rowid, row)
+    }
+    /// Groups of columns the table can look rows up by, e.g.
+    /// `vec![vec!["path".into(), "name".into()]]` for a composite index.
+    ///
+    /// osquery has no notion of composite indexes: each group's columns are
+    /// marked `INDEX`, so constraints on any of them are passed to the
+    /// table. The default declares none.
+    fn indexes(&self) -> Vec<Vec<String>> {
+        Vec::new()
+    }
+    /// Fail queries that would return more rows than this with a "result
+    /// exceeds configured limit" error instead of answering. The default,
+    /// `None`, allows any number of rows.
+    fn max_response_rows(&self) -> Option<usize> {
+        None
+    }
+    /// Fail queries whose response would be larger than this many bytes,
+    /// like `max_response_rows`. Defaults to [`DEFAULT_MAX_RESPONSE_BYTES`].
+    fn max_response_bytes(&self) -> Option<usize> {
+        Some(DEFAULT_MAX_RESPONSE_BYTES)
+    }
+    /// The oldest osquery this table works with, see
+    /// [`OsqueryPlugin::min_osquery_version`].
+    fn min_osquery_version(&self) -> Option<semver::Version> {
+        None
+    }
+    /// Answer an action other than the built-in [`TABLE_ACTIONS`], e.g. one
+    /// a newer osquery or a custom client sends.
+    ///
+    /// Return `None` for actions the table doesn't handle, which osquery is
+    /// told are unknown. The default handles none.
+    fn handle_action(
+        &self,
+        _action: &str,
+        _request: &crate::ExtensionPluginRequest,
+    ) -> Option<Result<crate::ExtensionPluginResponse, TableError>> {
+        None
+    }
     fn shutdown(&self);
 }
 
@@ -287,6 +805,43 @@ pub trait ReadOnlyTable: Send + Sync + 'static {
     fn name(&self) -> String;
     fn columns(&self) -> Vec<ColumnDef>;
     fn generate(&self, req: crate::ExtensionPluginRequest) -> crate::ExtensionResponse;
+    /// Generate the rows for one query, as in [`Table::generate_rows`].
+    fn generate_rows(&self, query: &QueryContext<'_>) -> Result<RowIterator<'_>, TableError> {
+        response_into_rows(self.generate(query.request().clone())).map_err(TableError::from)
+    }
+    /// Groups of columns the table can look rows up by, as in
+    /// [`Table::indexes`].
+    fn indexes(&self) -> Vec<Vec<String>> {
+        Vec::new()
+    }
+    /// Fail queries that would return more rows than this with a "result
+    /// exceeds configured limit" error instead of answering. The default,
+    /// `None`, allows any number of rows.
+    fn max_response_rows(&self) -> Option<usize> {
+        None
+    }
+    /// Fail queries whose response would be larger than this many bytes,
+    /// like `max_response_rows`. Defaults to [`DEFAULT_MAX_RESPONSE_BYTES`].
+    fn max_response_bytes(&self) -> Option<usize> {
+        Some(DEFAULT_MAX_RESPONSE_BYTES)
+    }
+    /// The oldest osquery this table works with, see
+    /// [`OsqueryPlugin::min_osquery_version`].
+    fn min_osquery_version(&self) -> Option<semver::Version> {
+        None
+    }
+    /// Answer an action other than the built-in [`TABLE_ACTIONS`], e.g. one
+    /// a newer osquery or a custom client sends.
+    ///
+    /// Return `None` for actions the table doesn't handle, which osquery is
+    /// told are unknown. The default handles none.
+    fn handle_action(
+        &self,
+        _action: &str,
+        _request: &crate::ExtensionPluginRequest,
+    ) -> Option<Result<crate::ExtensionPluginResponse, TableError>> {
+        None
+    }
     fn shutdown(&self);
 }
 
@@ -294,8 +849,8 @@ pub trait ReadOnlyTable: Send + Sync + 'static {
 mod tests {
     use super::*;
     use crate::_osquery::osquery;
-    use crate::plugin::OsqueryPlugin;
-    use column_def::ColumnOptions;
+    use crate::plugin::{Operator, OsqueryPlugin, QueryConstraintsExt};
+    use serde_json::Value;
 
     // ==================== Test Mock: ReadOnlyTable ====================
 
@@ -470,141 +1025,499 @@ mod tests {
     }
 
     #[test]
-    fn test_readonly_table_plugin_generate() {
-        let mut row = BTreeMap::new();
-        row.insert("id".to_string(), "1".to_string());
-        row.insert("value".to_string(), "test".to_string());
-        let table = TestReadOnlyTable::new("test_table").with_rows(vec![row]);
+    fn test_routes_include_column_description() {
+        let mut table = TestReadOnlyTable::new("described");
+        table.test_columns = vec![
+            ColumnDef::new("pid", ColumnType::Integer, ColumnOptions::DEFAULT)
+                .with_description("Process ID"),
+            ColumnDef::new("name", ColumnType::Text, ColumnOptions::DEFAULT),
+        ];
         let plugin = TablePlugin::from_readonly_table(table);
+        let routes = plugin.routes();
 
-        let mut req = BTreeMap::new();
-        req.insert("action".to_string(), "generate".to_string());
-        let response = plugin.handle_call(req);
-
-        let status = response.status.as_ref();
-        assert!(status.is_some(), "response should have status");
-        assert_eq!(status.and_then(|s| s.code), Some(0));
-        assert_eq!(response.response.as_ref().unwrap_or(&vec![]).len(), 1);
+        assert_eq!(
+            routes.first().and_then(|r| r.get("description")),
+            Some(&"Process ID".to_string())
+        );
+        // Columns without a description keep the original route shape
+        assert!(routes
+            .get(1)
+            .map(|r| !r.contains_key("description"))
+            .unwrap_or(false));
     }
 
     #[test]
-    fn test_readonly_table_routes_via_handle_call() {
-        let table = TestReadOnlyTable::new("test_table");
+    fn test_routes_mark_generated_columns() {
+        let mut table = TestReadOnlyTable::new("files");
+        table.test_columns = vec![
+            ColumnDef::new("size", ColumnType::BigInt, ColumnOptions::DEFAULT),
+            ColumnDef::new("size_kb", ColumnType::BigInt, ColumnOptions::DEFAULT)
+                .generated("size / 1024"),
+        ];
         let plugin = TablePlugin::from_readonly_table(table);
+        let routes = plugin.routes();
 
-        let mut req = BTreeMap::new();
-        req.insert("action".to_string(), "columns".to_string());
-        let response = plugin.handle_call(req);
-
-        let status = response.status.as_ref();
-        assert!(status.is_some(), "response should have status");
-        assert_eq!(status.and_then(|s| s.code), Some(0));
-        assert_eq!(response.response.as_ref().unwrap_or(&vec![]).len(), 2); // 2 columns
+        assert!(routes.first().is_some_and(|r| !r.contains_key("generated")));
+        assert_eq!(
+            routes.get(1).and_then(|r| r.get("generated")),
+            Some(&"size / 1024".to_string())
+        );
     }
 
     #[test]
-    fn test_readonly_table_registry() {
-        let table = TestReadOnlyTable::new("test_table");
-        let plugin = TablePlugin::from_readonly_table(table);
-        assert_eq!(plugin.registry(), Registry::Table);
-    }
-
-    // ==================== Writeable Table Tests ====================
+    fn test_routes_report_blob_column_type() {
+        let mut table = TestReadOnlyTable::new("blobs");
+        table.test_columns = vec![ColumnDef::new(
+            "data",
+            ColumnType::Blob,
+            ColumnOptions::DEFAULT,
+        )];
+        let routes = TablePlugin::from_readonly_table(table).routes();
 
-    #[test]
-    fn test_writeable_table_plugin_name() {
-        let table = TestWriteableTable::new("writeable_table");
-        let plugin = TablePlugin::from_writeable_table(table);
-        assert_eq!(plugin.name(), "writeable_table");
+        assert_eq!(
+            routes
+                .first()
+                .and_then(|r| r.get("type"))
+                .map(|t| t.as_str()),
+            Some("BLOB")
+        );
     }
 
     #[test]
-    fn test_writeable_table_insert() {
-        let table = TestWriteableTable::new("test_table");
-        let plugin = TablePlugin::from_writeable_table(table);
+    fn test_routes_report_unsigned_bigint_column_type() {
+        let mut table = TestReadOnlyTable::new("inodes");
+        table.test_columns = vec![ColumnDef::new(
+            "inode",
+            ColumnType::UnsignedBigInt,
+            ColumnOptions::DEFAULT,
+        )];
+        let routes = TablePlugin::from_readonly_table(table).routes();
 
-        let mut req = BTreeMap::new();
-        req.insert("action".to_string(), "insert".to_string());
-        req.insert("auto_rowid".to_string(), "true".to_string());
-        req.insert(
-            "json_value_array".to_string(),
-            "[null, \"test_value\"]".to_string(),
+        assert_eq!(
+            routes
+                .first()
+                .and_then(|r| r.get("type"))
+                .map(|t| t.as_str()),
+            Some("UNSIGNED_BIGINT")
         );
-        let response = plugin.handle_call(req);
+    }
 
-        let status = response.status.as_ref();
-        assert!(status.is_some(), "response should have status");
-        assert_eq!(status.and_then(|s| s.code), Some(0)); // Success
+    /// Generates `rows` numbered rows, limited to `max_rows`/`max_bytes`.
+    struct LimitedTable {
+        rows: usize,
+        max_rows: Option<usize>,
+        max_bytes: Option<usize>,
     }
 
-    #[test]
-    fn test_writeable_table_update() {
-        let table = TestWriteableTable::new("test_table").with_initial_row();
-        let plugin = TablePlugin::from_writeable_table(table);
+    impl ReadOnlyTable for LimitedTable {
+        fn name(&self) -> String {
+            "limited".to_string()
+        }
 
-        let mut req = BTreeMap::new();
-        req.insert("action".to_string(), "update".to_string());
-        req.insert("id".to_string(), "1".to_string());
-        req.insert(
-            "json_value_array".to_string(),
-            "[1, \"updated\"]".to_string(),
-        );
-        let response = plugin.handle_call(req);
+        fn columns(&self) -> Vec<ColumnDef> {
+            vec![ColumnDef::new(
+                "n",
+                ColumnType::Integer,
+                ColumnOptions::DEFAULT,
+            )]
+        }
 
-        let status = response.status.as_ref();
-        assert!(status.is_some(), "response should have status");
-        assert_eq!(status.and_then(|s| s.code), Some(0)); // Success
-    }
+        fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+            let rows: ExtensionPluginResponse = (0..self.rows)
+                .map(|n| BTreeMap::from([("n".to_string(), n.to_string())]))
+                .collect();
+            ExtensionResponse::new(ExtensionStatus::default(), rows)
+        }
 
-    #[test]
-    fn test_writeable_table_delete() {
-        let table = TestWriteableTable::new("test_table").with_initial_row();
-        let plugin = TablePlugin::from_writeable_table(table);
+        fn max_response_rows(&self) -> Option<usize> {
+            self.max_rows
+        }
 
-        let mut req = BTreeMap::new();
-        req.insert("action".to_string(), "delete".to_string());
-        req.insert("id".to_string(), "1".to_string());
-        let response = plugin.handle_call(req);
+        fn max_response_bytes(&self) -> Option<usize> {
+            self.max_bytes
+        }
 
-        let status = response.status.as_ref();
-        assert!(status.is_some(), "response should have status");
-        assert_eq!(status.and_then(|s| s.code), Some(0)); // Success
+        fn shutdown(&self) {}
     }
 
-    // ==================== Dispatch Tests ====================
+    fn generate_limited(table: LimitedTable, context: Option<&str>) -> ExtensionResponse {
+        let mut req = BTreeMap::from([("action".to_string(), "generate".to_string())]);
+        if let Some(context) = context {
+            req.insert("context".to_string(), context.to_string());
+        }
+        TablePlugin::from_readonly_table(table).handle_call(req)
+    }
 
-    #[test]
-    fn test_table_plugin_dispatch_readonly() {
-        let table = TestReadOnlyTable::new("readonly");
-        let plugin = TablePlugin::from_readonly_table(table);
-        assert!(matches!(plugin, TablePlugin::Readonly(_)));
-        assert_eq!(plugin.registry(), Registry::Table);
+    fn failure_message(response: &ExtensionResponse) -> Option<String> {
+        response
+            .response
+            .as_ref()
+            .and_then(|rows| rows.first())
+            .and_then(|row| row.get("message"))
+            .cloned()
     }
 
     #[test]
-    fn test_table_plugin_dispatch_writeable() {
-        let table = TestWriteableTable::new("writeable");
-        let plugin = TablePlugin::from_writeable_table(table);
-        assert!(matches!(plugin, TablePlugin::Writeable(_)));
-        assert_eq!(plugin.registry(), Registry::Table);
+    fn test_response_over_row_limit_fails() {
+        let response = generate_limited(
+            LimitedTable {
+                rows: 4,
+                max_rows: Some(3),
+                max_bytes: None,
+            },
+            None,
+        );
+
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(1));
+        assert_eq!(
+            failure_message(&response).as_deref(),
+            Some("Table limited result exceeds configured limit: 4 rows > 3")
+        );
     }
 
-    // ==================== Error Path Tests ====================
+    #[test]
+    fn test_response_within_limits_succeeds() {
+        let table = |rows| LimitedTable {
+            rows,
+            max_rows: Some(3),
+            max_bytes: None,
+        };
+
+        let response = generate_limited(table(3), None);
+        assert_eq!(response.status.and_then(|s| s.code), Some(0));
+        assert_eq!(response.response.map(|rows| rows.len()), Some(3));
+    }
 
     #[test]
-    fn test_readonly_table_insert_returns_readonly_error() {
-        let table = TestReadOnlyTable::new("readonly");
-        let plugin = TablePlugin::from_readonly_table(table);
+    fn test_response_over_byte_limit_fails() {
+        let response = generate_limited(
+            LimitedTable {
+                rows: 100,
+                max_rows: None,
+                max_bytes: Some(1024),
+            },
+            None,
+        );
+
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(1));
+        let message = failure_message(&response).unwrap_or_default();
+        assert!(
+            message.starts_with("Table limited result exceeds configured limit: "),
+            "{message}"
+        );
+        assert!(message.ends_with(" bytes > 1024"), "{message}");
+    }
+
+    #[test]
+    fn test_default_limits_allow_normal_tables() {
+        let table = TestReadOnlyTable::new("defaults");
+        assert_eq!(table.max_response_rows(), None);
+        assert_eq!(table.max_response_bytes(), Some(DEFAULT_MAX_RESPONSE_BYTES));
+
+        let rows = vec![BTreeMap::from([("n".to_string(), "1".to_string())])];
+        assert_eq!(response_size(&rows), 5 + 6 + 8 + 2);
+    }
+
+    #[test]
+    fn test_routes_mark_composite_index_columns() {
+        struct FilesTable;
+
+        impl ReadOnlyTable for FilesTable {
+            fn name(&self) -> String {
+                "files".to_string()
+            }
+
+            fn columns(&self) -> Vec<ColumnDef> {
+                vec![
+                    ColumnDef::new("directory", ColumnType::Text, ColumnOptions::DEFAULT),
+                    ColumnDef::new("filename", ColumnType::Text, ColumnOptions::HIDDEN),
+                    ColumnDef::new("size", ColumnType::BigInt, ColumnOptions::DEFAULT),
+                ]
+            }
+
+            fn indexes(&self) -> Vec<Vec<String>> {
+                vec![vec!["directory".to_string(), "filename".to_string()]]
+            }
+
+            fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+                ExtensionResponse::new(ExtensionStatus::default(), vec![])
+            }
+
+            fn shutdown(&self) {}
+        }
+
+        let routes = TablePlugin::from_readonly_table(FilesTable).routes();
+        let ops: Vec<Option<&str>> = routes
+            .iter()
+            .filter(|r| r.get("id").map(|id| id.as_str()) == Some("column"))
+            .map(|r| r.get("op").map(|op| op.as_str()))
+            .collect();
+
+        // INDEX is 1, HIDDEN 16
+        assert_eq!(ops, vec![Some("1"), Some("17"), Some("0")]);
+        assert_eq!(routes.len(), 3);
+    }
+
+    #[test]
+    fn test_readonly_table_plugin_generate() {
+        let mut row = BTreeMap::new();
+        row.insert("id".to_string(), "1".to_string());
+        row.insert("value".to_string(), "test".to_string());
+        let table = TestReadOnlyTable::new("test_table").with_rows(vec![row]);
+        let plugin = TablePlugin::from_readonly_table(table);
+
+        let mut req = BTreeMap::new();
+        req.insert("action".to_string(), "generate".to_string());
+        let response = plugin.handle_call(req);
+
+        let status = response.status.as_ref();
+        assert!(status.is_some(), "response should have status");
+        assert_eq!(status.and_then(|s| s.code), Some(0));
+        assert_eq!(response.response.as_ref().unwrap_or(&vec![]).len(), 1);
+    }
+
+    #[test]
+    fn test_generate_rows_error_fails_only_that_query() {
+        struct FlakyTable {
+            locked: std::sync::atomic::AtomicBool,
+        }
+
+        impl ReadOnlyTable for FlakyTable {
+            fn name(&self) -> String {
+                "flaky".to_string()
+            }
+
+            fn columns(&self) -> Vec<ColumnDef> {
+                vec![ColumnDef::new(
+                    "line",
+                    ColumnType::Text,
+                    ColumnOptions::DEFAULT,
+                )]
+            }
+
+            fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+                ExtensionResponse::new(ExtensionStatus::default(), vec![])
+            }
+
+            fn generate_rows(
+                &self,
+                _query: &QueryContext<'_>,
+            ) -> Result<RowIterator<'_>, TableError> {
+                // Only the first read finds the file locked
+                if self.locked.swap(false, std::sync::atomic::Ordering::SeqCst) {
+                    return Err(TableError::transient("/var/log/app.log is locked"));
+                }
+                Ok(Box::new(std::iter::once(BTreeMap::from([(
+                    "line".to_string(),
+                    "hello".to_string(),
+                )]))))
+            }
+
+            fn shutdown(&self) {}
+        }
+
+        let plugin = TablePlugin::from_readonly_table(FlakyTable {
+            locked: std::sync::atomic::AtomicBool::new(true),
+        });
+        let req = || BTreeMap::from([("action".to_string(), "generate".to_string())]);
+
+        let response = plugin.handle_call(req());
+        assert_eq!(
+            response.status.as_ref().and_then(|s| s.code),
+            Some(StatusCode::Transient.code())
+        );
+        assert_eq!(
+            response
+                .response
+                .as_ref()
+                .and_then(|rows| rows.first())
+                .and_then(|row| row.get("message"))
+                .map(|m| m.as_str()),
+            Some("/var/log/app.log is locked")
+        );
+
+        let response = plugin.handle_call(req());
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(0));
+        assert_eq!(response.response.map(|rows| rows.len()), Some(1));
+    }
+
+    #[test]
+    fn test_required_column_must_be_constrained() {
+        let mut table = TestReadOnlyTable::new("files").with_rows(vec![BTreeMap::from([(
+            "path".to_string(),
+            "/etc/hosts".to_string(),
+        )])]);
+        table.test_columns = vec![
+            ColumnDef::new("path", ColumnType::Text, ColumnOptions::REQUIRED),
+            ColumnDef::new("size", ColumnType::BigInt, ColumnOptions::DEFAULT),
+        ];
+        let plugin = TablePlugin::from_readonly_table(table);
+        let query = |context: &str| {
+            plugin.handle_call(BTreeMap::from([
+                ("action".to_string(), "generate".to_string()),
+                ("context".to_string(), context.to_string()),
+            ]))
+        };
+
+        // SELECT * FROM files WHERE size > 10
+        let response = query(
+            r#"{"constraints":[{"name":"size","affinity":"BIGINT","list":[{"op":4,"expr":"10"}]}]}"#,
+        );
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(1));
+        assert_eq!(
+            response
+                .response
+                .as_ref()
+                .and_then(|rows| rows.first())
+                .and_then(|row| row.get("message"))
+                .map(|m| m.as_str()),
+            Some("Table files was queried without a required column in the WHERE clause: path")
+        );
+
+        // SELECT * FROM files WHERE path = '/etc/hosts'
+        let response = query(
+            r#"{"constraints":[{"name":"path","affinity":"TEXT","list":[{"op":2,"expr":"/etc/hosts"}]}]}"#,
+        );
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(0));
+        assert_eq!(response.response.map(|rows| rows.len()), Some(1));
+    }
+
+    #[test]
+    fn test_table_error_from_string_is_failure() {
+        let error = TableError::from("boom".to_string());
+        assert_eq!(error.code(), StatusCode::Failure);
+        assert_eq!(error.to_string(), "boom");
+        assert_eq!(TableError::new("boom"), error);
+    }
+
+    #[test]
+    fn test_readonly_table_routes_via_handle_call() {
+        let table = TestReadOnlyTable::new("test_table");
+        let plugin = TablePlugin::from_readonly_table(table);
+
+        let mut req = BTreeMap::new();
+        req.insert("action".to_string(), "columns".to_string());
+        let response = plugin.handle_call(req);
+
+        let status = response.status.as_ref();
+        assert!(status.is_some(), "response should have status");
+        assert_eq!(status.and_then(|s| s.code), Some(0));
+        assert_eq!(response.response.as_ref().unwrap_or(&vec![]).len(), 2); // 2 columns
+    }
+
+    #[test]
+    fn test_readonly_table_registry() {
+        let table = TestReadOnlyTable::new("test_table");
+        let plugin = TablePlugin::from_readonly_table(table);
+        assert_eq!(plugin.registry(), Registry::Table);
+    }
+
+    // ==================== Writeable Table Tests ====================
+
+    #[test]
+    fn test_writeable_table_plugin_name() {
+        let table = TestWriteableTable::new("writeable_table");
+        let plugin = TablePlugin::from_writeable_table(table);
+        assert_eq!(plugin.name(), "writeable_table");
+    }
+
+    #[test]
+    fn test_writeable_table_insert() {
+        let table = TestWriteableTable::new("test_table");
+        let plugin = TablePlugin::from_writeable_table(table);
 
         let mut req = BTreeMap::new();
         req.insert("action".to_string(), "insert".to_string());
-        req.insert("json_value_array".to_string(), "[1, \"test\"]".to_string());
+        req.insert("auto_rowid".to_string(), "true".to_string());
+        req.insert(
+            "json_value_array".to_string(),
+            "[null, \"test_value\"]".to_string(),
+        );
         let response = plugin.handle_call(req);
 
-        // Readonly error returns code 1 (see ExtensionResponseEnum::Readonly)
         let status = response.status.as_ref();
         assert!(status.is_some(), "response should have status");
-        assert_eq!(status.and_then(|s| s.code), Some(1));
+        assert_eq!(status.and_then(|s| s.code), Some(0)); // Success
+    }
+
+    #[test]
+    fn test_writeable_table_update() {
+        let table = TestWriteableTable::new("test_table").with_initial_row();
+        let plugin = TablePlugin::from_writeable_table(table);
+
+        let mut req = BTreeMap::new();
+        req.insert("action".to_string(), "update".to_string());
+        req.insert("id".to_string(), "1".to_string());
+        req.insert(
+            "json_value_array".to_string(),
+            "[1, \"updated\"]".to_string(),
+        );
+        let response = plugin.handle_call(req);
+
+        let status = response.status.as_ref();
+        assert!(status.is_some(), "response should have status");
+        assert_eq!(status.and_then(|s| s.code), Some(0)); // Success
+    }
+
+    #[test]
+    fn test_writeable_table_delete() {
+        let table = TestWriteableTable::new("test_table").with_initial_row();
+        let plugin = TablePlugin::from_writeable_table(table);
+
+        let mut req = BTreeMap::new();
+        req.insert("action".to_string(), "delete".to_string());
+        req.insert("id".to_string(), "1".to_string());
+        let response = plugin.handle_call(req);
+
+        let status = response.status.as_ref();
+        assert!(status.is_some(), "response should have status");
+        assert_eq!(status.and_then(|s| s.code), Some(0)); // Success
+    }
+
+    // ==================== Dispatch Tests ====================
+
+    #[test]
+    fn test_table_plugin_dispatch_readonly() {
+        let table = TestReadOnlyTable::new("readonly");
+        let plugin = TablePlugin::from_readonly_table(table);
+        assert!(matches!(plugin, TablePlugin::Readonly(_)));
+        assert_eq!(plugin.registry(), Registry::Table);
+    }
+
+    #[test]
+    fn test_table_plugin_dispatch_writeable() {
+        let table = TestWriteableTable::new("writeable");
+        let plugin = TablePlugin::from_writeable_table(table);
+        assert!(matches!(plugin, TablePlugin::Writeable(_)));
+        assert_eq!(plugin.registry(), Registry::Table);
+    }
+
+    // ==================== Error Path Tests ====================
+
+    /// Assert `response` is the reply osquery maps to `SQLITE_READONLY`.
+    fn assert_readonly(response: &ExtensionResponse) {
+        let status = response.status.as_ref();
+        assert!(status.is_some(), "response should have status");
+        assert_eq!(status.and_then(|s| s.code), Some(0));
+        let row_status = response
+            .response
+            .as_ref()
+            .and_then(|rows| rows.first())
+            .and_then(|row| row.get("status"));
+        assert_eq!(row_status.map(String::as_str), Some("readonly"));
+    }
+
+    #[test]
+    fn test_readonly_table_insert_returns_readonly_error() {
+        let table = TestReadOnlyTable::new("readonly");
+        let plugin = TablePlugin::from_readonly_table(table);
+
+        let mut req = BTreeMap::new();
+        req.insert("action".to_string(), "insert".to_string());
+        req.insert("json_value_array".to_string(), "[1, \"test\"]".to_string());
+        let response = plugin.handle_call(req);
+
+        assert_readonly(&response);
     }
 
     #[test]
@@ -618,9 +1531,7 @@ mod tests {
         req.insert("json_value_array".to_string(), "[1, \"test\"]".to_string());
         let response = plugin.handle_call(req);
 
-        let status = response.status.as_ref();
-        assert!(status.is_some(), "response should have status");
-        assert_eq!(status.and_then(|s| s.code), Some(1)); // Readonly error
+        assert_readonly(&response);
     }
 
     #[test]
@@ -633,9 +1544,7 @@ mod tests {
         req.insert("id".to_string(), "1".to_string());
         let response = plugin.handle_call(req);
 
-        let status = response.status.as_ref();
-        assert!(status.is_some(), "response should have status");
-        assert_eq!(status.and_then(|s| s.code), Some(1)); // Readonly error
+        assert_readonly(&response);
     }
 
     #[test]
@@ -652,6 +1561,121 @@ mod tests {
         assert_eq!(status.and_then(|s| s.code), Some(1)); // Failure
     }
 
+    fn response_message(response: &ExtensionResponse) -> Option<&str> {
+        response
+            .response
+            .as_ref()
+            .and_then(|rows| rows.first())
+            .and_then(|row| row.get("message"))
+            .map(String::as_str)
+    }
+
+    #[test]
+    fn test_unknown_action_lists_supported_actions() {
+        let plugin = TablePlugin::from_readonly_table(TestReadOnlyTable::new("test"));
+
+        let response = plugin.handle_call(BTreeMap::from([(
+            "action".to_string(),
+            "describe".to_string(),
+        )]));
+
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(1));
+        assert_eq!(
+            response_message(&response),
+            Some("Unknown table action 'describe', supported actions: columns, generate, insert, update, delete")
+        );
+    }
+
+    #[test]
+    fn test_columns_action_returns_declared_columns() {
+        let plugin = TablePlugin::from_writeable_table(TestWriteableTable::new("test"));
+
+        let response = plugin.handle_call(BTreeMap::from([(
+            "action".to_string(),
+            "columns".to_string(),
+        )]));
+
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(0));
+        let columns: Vec<_> = response
+            .response
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|route| route.get("id").map(String::as_str) == Some("column"))
+            .filter_map(|route| Some((route.get("name")?.clone(), route.get("type")?.clone())))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                ("id".to_string(), "INTEGER".to_string()),
+                ("value".to_string(), "TEXT".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_action_is_passed_to_table() {
+        struct CountingTable;
+
+        impl ReadOnlyTable for CountingTable {
+            fn name(&self) -> String {
+                "counting".to_string()
+            }
+
+            fn columns(&self) -> Vec<ColumnDef> {
+                vec![ColumnDef::new(
+                    "n",
+                    ColumnType::Integer,
+                    ColumnOptions::DEFAULT,
+                )]
+            }
+
+            fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+                ExtensionResponse::new(ExtensionStatus::default(), vec![])
+            }
+
+            fn handle_action(
+                &self,
+                action: &str,
+                _request: &ExtensionPluginRequest,
+            ) -> Option<Result<ExtensionPluginResponse, TableError>> {
+                match action {
+                    "count" => Some(Ok(vec![BTreeMap::from([(
+                        "count".to_string(),
+                        "3".to_string(),
+                    )])])),
+                    "reset" => Some(Err(TableError::new("counter is read-only"))),
+                    _ => None,
+                }
+            }
+
+            fn shutdown(&self) {}
+        }
+
+        let plugin = TablePlugin::from_readonly_table(CountingTable);
+        let call = |action: &str| {
+            plugin.handle_call(BTreeMap::from([("action".to_string(), action.to_string())]))
+        };
+
+        let counted = call("count");
+        assert_eq!(counted.status.as_ref().and_then(|s| s.code), Some(0));
+        assert_eq!(
+            counted
+                .response
+                .as_ref()
+                .and_then(|rows| rows.first())
+                .and_then(|row| row.get("count"))
+                .map(String::as_str),
+            Some("3")
+        );
+
+        let reset = call("reset");
+        assert_eq!(reset.status.as_ref().and_then(|s| s.code), Some(1));
+        assert_eq!(response_message(&reset), Some("counter is read-only"));
+
+        assert!(response_message(&call("other"))
+            .is_some_and(|message| message.starts_with("Unknown table action 'other'")));
+    }
+
     #[test]
     fn test_update_with_invalid_id_returns_error() {
         let table = TestWriteableTable::new("test");
@@ -778,6 +1802,662 @@ mod tests {
         assert_eq!(response.response.as_ref().unwrap_or(&vec![]).len(), 0);
     }
 
+    // ==================== Limit Pushdown Tests ====================
+
+    fn rows(n: usize) -> Vec<BTreeMap<String, String>> {
+        (0..n)
+            .map(|i| {
+                let mut row = BTreeMap::new();
+                row.insert("id".to_string(), i.to_string());
+                row
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_generate_does_not_truncate_to_limit() {
+        let table = TestReadOnlyTable::new("limited").with_rows(rows(50));
+        let plugin = TablePlugin::from_readonly_table(table);
+
+        // osquery still has to apply the LIKE and OFFSET to what we return
+        let mut req = BTreeMap::new();
+        req.insert("action".to_string(), "generate".to_string());
+        req.insert(
+            "context".to_string(),
+            r#"{"constraints":[{"name":"value","affinity":"TEXT","list":[{"op":65,"expr":"a%"}]}],"limit":10,"offset":20}"#.to_string(),
+        );
+        let response = plugin.handle_call(req);
+
+        assert_eq!(response.response.as_ref().map(|r| r.len()), Some(50));
+    }
+
+    fn row_with_extra_column() -> Vec<BTreeMap<String, String>> {
+        vec![BTreeMap::from([
+            ("id".to_string(), "1".to_string()),
+            ("value".to_string(), "a".to_string()),
+            ("valeu".to_string(), "typo".to_string()),
+        ])]
+    }
+
+    #[cfg(feature = "strict-columns")]
+    #[test]
+    fn test_generate_rejects_undeclared_columns_in_strict_mode() {
+        let table = TestReadOnlyTable::new("strict").with_rows(row_with_extra_column());
+        let plugin = TablePlugin::from_readonly_table(table);
+
+        let req = BTreeMap::from([("action".to_string(), "generate".to_string())]);
+        let response = plugin.handle_call(req);
+
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(1));
+        assert_eq!(
+            response
+                .response
+                .as_ref()
+                .and_then(|rows| rows.first())
+                .and_then(|row| row.get("message"))
+                .map(String::as_str),
+            Some("Table strict returned undeclared columns: valeu")
+        );
+    }
+
+    #[cfg(not(feature = "strict-columns"))]
+    #[test]
+    fn test_generate_tolerates_undeclared_columns() {
+        let table = TestReadOnlyTable::new("lenient").with_rows(row_with_extra_column());
+        let plugin = TablePlugin::from_readonly_table(table);
+
+        let req = BTreeMap::from([("action".to_string(), "generate".to_string())]);
+        let response = plugin.handle_call(req);
+
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(0));
+        assert_eq!(response.response, Some(row_with_extra_column()));
+    }
+
+    #[test]
+    fn test_generate_without_limit_returns_all_rows() {
+        let table = TestReadOnlyTable::new("unlimited").with_rows(rows(50));
+        let plugin = TablePlugin::from_readonly_table(table);
+
+        let mut req = BTreeMap::new();
+        req.insert("action".to_string(), "generate".to_string());
+        req.insert("context".to_string(), r#"{"constraints":[]}"#.to_string());
+        let response = plugin.handle_call(req);
+
+        assert_eq!(response.response.as_ref().map(|r| r.len()), Some(50));
+    }
+
+    struct PagedTable {
+        seen_limit: Arc<Mutex<Option<Option<usize>>>>,
+    }
+
+    impl ReadOnlyTable for PagedTable {
+        fn name(&self) -> String {
+            "paged".to_string()
+        }
+
+        fn columns(&self) -> Vec<ColumnDef> {
+            vec![ColumnDef::new(
+                "id",
+                ColumnType::Integer,
+                ColumnOptions::DEFAULT,
+            )]
+        }
+
+        fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+            ExtensionResponse::new(ExtensionStatus::default(), rows(100))
+        }
+
+        fn generate_rows(&self, query: &QueryContext<'_>) -> Result<RowIterator<'_>, TableError> {
+            let limit = query.limit();
+            if let Ok(mut seen) = self.seen_limit.lock() {
+                *seen = Some(limit);
+            }
+            // Only unconstrained queries get a limit hint
+            assert!(limit.is_none() || query.constraints().is_empty());
+            Ok(Box::new(rows(limit.unwrap_or(100)).into_iter()))
+        }
+
+        fn shutdown(&self) {}
+    }
+
+    fn generate_limited_with(context: &str) -> (Option<usize>, Option<usize>) {
+        let seen_limit = Arc::new(Mutex::new(None));
+        let plugin = TablePlugin::from_readonly_table(PagedTable {
+            seen_limit: seen_limit.clone(),
+        });
+
+        let mut req = BTreeMap::new();
+        req.insert("action".to_string(), "generate".to_string());
+        req.insert("context".to_string(), context.to_string());
+        let response = plugin.handle_call(req);
+
+        let seen = seen_limit.lock().ok().and_then(|l| *l);
+        assert!(seen.is_some(), "generate_rows should be called");
+        (seen.flatten(), response.response.map(|r| r.len()))
+    }
+
+    #[test]
+    fn test_generate_rows_receives_parsed_limit() {
+        let (limit, rows) = generate_limited_with(r#"{"constraints":[],"limit":"3"}"#);
+
+        assert_eq!(limit, Some(3));
+        // The table chose to stop early
+        assert_eq!(rows, Some(3));
+    }
+
+    #[test]
+    fn test_generate_rows_gets_no_limit_with_constraints() {
+        let (limit, rows) = generate_limited_with(
+            r#"{"constraints":[{"name":"id","affinity":"INTEGER","list":[{"op":4,"expr":"5"}]}],"limit":"3"}"#,
+        );
+
+        assert_eq!(limit, None);
+        assert_eq!(rows, Some(100));
+    }
+
+    // ==================== Streaming Tests ====================
+
+    struct StreamingTable {
+        total: usize,
+        produced: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl ReadOnlyTable for StreamingTable {
+        fn name(&self) -> String {
+            "streaming".to_string()
+        }
+
+        fn columns(&self) -> Vec<ColumnDef> {
+            vec![ColumnDef::new(
+                "id",
+                ColumnType::Integer,
+                ColumnOptions::DEFAULT,
+            )]
+        }
+
+        fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+            ExtensionResponseEnum::Failure("use generate_rows".to_string()).into()
+        }
+
+        fn generate_rows(&self, query: &QueryContext<'_>) -> Result<RowIterator<'_>, TableError> {
+            let produced = self.produced.clone();
+            let total = query
+                .limit()
+                .map_or(self.total, |limit| limit.min(self.total));
+            Ok(Box::new((0..total).map(move |i| {
+                produced.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let mut row = BTreeMap::new();
+                row.insert("id".to_string(), i.to_string());
+                row
+            })))
+        }
+
+        fn shutdown(&self) {}
+    }
+
+    #[test]
+    fn test_generate_rows_drains_lazy_rows() {
+        let produced = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let plugin = TablePlugin::from_readonly_table(StreamingTable {
+            total: 100_000,
+            produced: produced.clone(),
+        });
+
+        let mut req = BTreeMap::new();
+        req.insert("action".to_string(), "generate".to_string());
+        let response = plugin.handle_call(req);
+
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(0));
+        assert_eq!(response.response.as_ref().map(|r| r.len()), Some(100_000));
+        assert_eq!(produced.load(std::sync::atomic::Ordering::SeqCst), 100_000);
+    }
+
+    #[test]
+    fn test_generate_rows_can_stop_at_limit_hint() {
+        let produced = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let plugin = TablePlugin::from_readonly_table(StreamingTable {
+            total: 100_000,
+            produced: produced.clone(),
+        });
+
+        let mut req = BTreeMap::new();
+        req.insert("action".to_string(), "generate".to_string());
+        req.insert("context".to_string(), r#"{"limit":5}"#.to_string());
+        let response = plugin.handle_call(req);
+
+        assert_eq!(response.response.as_ref().map(|r| r.len()), Some(5));
+        assert_eq!(produced.load(std::sync::atomic::Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn test_generate_failure_status_is_preserved_as_failure() {
+        struct FailingTable;
+
+        impl ReadOnlyTable for FailingTable {
+            fn name(&self) -> String {
+                "failing".to_string()
+            }
+
+            fn columns(&self) -> Vec<ColumnDef> {
+                vec![]
+            }
+
+            fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+                ExtensionResponse::new(
+                    ExtensionStatus::new(1, "backend down".to_string(), None),
+                    vec![],
+                )
+            }
+
+            fn shutdown(&self) {}
+        }
+
+        let plugin = TablePlugin::from_readonly_table(FailingTable);
+        let mut req = BTreeMap::new();
+        req.insert("action".to_string(), "generate".to_string());
+        let response = plugin.handle_call(req);
+
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(1));
+        assert_eq!(
+            response
+                .response
+                .as_ref()
+                .and_then(|r| r.first())
+                .and_then(|r| r.get("message"))
+                .map(|m| m.as_str()),
+            Some("backend down")
+        );
+    }
+
+    // ==================== Typed Row Tests ====================
+
+    struct PeopleTable {
+        people: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    impl Table for PeopleTable {
+        fn name(&self) -> String {
+            "people".to_string()
+        }
+
+        fn columns(&self) -> Vec<ColumnDef> {
+            vec![
+                ColumnDef::new("rowid", ColumnType::Integer, ColumnOptions::HIDDEN),
+                ColumnDef::new("name", ColumnType::Text, ColumnOptions::DEFAULT),
+                ColumnDef::new("lastname", ColumnType::Text, ColumnOptions::DEFAULT),
+            ]
+        }
+
+        fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+            ExtensionResponse::new(ExtensionStatus::default(), vec![])
+        }
+
+        fn delete(&mut self, _rowid: u64) -> DeleteResult {
+            DeleteResult::Err("unsupported".to_string())
+        }
+
+        fn insert_row(&mut self, _auto_rowid: bool, row: &Row) -> InsertResult {
+            let (Some(name), Some(lastname)) = (row.get_text("name"), row.get_text("lastname"))
+            else {
+                return InsertResult::Constraint;
+            };
+
+            let Ok(mut people) = self.people.lock() else {
+                return InsertResult::Err("poisoned".to_string());
+            };
+            people.push((name.to_string(), lastname.to_string()));
+            InsertResult::Success(people.len() as u64)
+        }
+
+        fn shutdown(&self) {}
+    }
+
+    /// Stores names by rowid, honoring osquery's rowid contract
+    #[derive(Default)]
+    struct RowidTable {
+        names: Arc<Mutex<BTreeMap<u64, String>>>,
+    }
+
+    impl Table for RowidTable {
+        fn name(&self) -> String {
+            "names".to_string()
+        }
+
+        fn columns(&self) -> Vec<ColumnDef> {
+            vec![
+                ColumnDef::new("rowid", ColumnType::Integer, ColumnOptions::HIDDEN),
+                ColumnDef::new("name", ColumnType::Text, ColumnOptions::DEFAULT),
+            ]
+        }
+
+        fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+            ExtensionResponse::new(ExtensionStatus::default(), vec![])
+        }
+
+        fn update(&mut self, _rowid: u64, _row: &Value) -> UpdateResult {
+            UpdateResult::Err("unsupported".to_string())
+        }
+
+        fn delete(&mut self, _rowid: u64) -> DeleteResult {
+            DeleteResult::Err("unsupported".to_string())
+        }
+
+        fn insert_row(&mut self, auto_rowid: bool, row: &Row) -> InsertResult {
+            let Ok(mut names) = self.names.lock() else {
+                return InsertResult::Err("poisoned".to_string());
+            };
+
+            let rowid = if auto_rowid {
+                names.keys().next_back().map_or(0, |id| id + 1)
+            } else {
+                match row.rowid() {
+                    Some(rowid) if !names.contains_key(&rowid) => rowid,
+                    _ => return InsertResult::Constraint,
+                }
+            };
+            names.insert(rowid, row.get_text("name").unwrap_or_default().to_string());
+            InsertResult::Success(rowid)
+        }
+
+        fn shutdown(&self) {}
+    }
+
+    fn rowid_insert(
+        auto_rowid: &str,
+        id: Option<&str>,
+        json_value_array: &str,
+    ) -> ExtensionResponse {
+        let plugin_table = RowidTable::default();
+        let names = plugin_table.names.clone();
+        if let Ok(mut names) = names.lock() {
+            names.insert(1, "existing".to_string());
+        }
+        let plugin = TablePlugin::from_writeable_table(plugin_table);
+
+        let mut req = BTreeMap::from([
+            ("action".to_string(), "insert".to_string()),
+            ("auto_rowid".to_string(), auto_rowid.to_string()),
+            ("json_value_array".to_string(), json_value_array.to_string()),
+        ]);
+        if let Some(id) = id {
+            req.insert("id".to_string(), id.to_string());
+        }
+        plugin.handle_call(req)
+    }
+
+    fn first_row_value(response: &ExtensionResponse, key: &str) -> Option<String> {
+        response
+            .response
+            .as_ref()
+            .and_then(|rows| rows.first())
+            .and_then(|row| row.get(key))
+            .cloned()
+    }
+
+    #[test]
+    fn test_insert_auto_rowid_lets_table_choose() {
+        // INSERT INTO names (name) VALUES ('a')
+        let response = rowid_insert("true", None, r#"[null, "a"]"#);
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(0));
+        assert_eq!(first_row_value(&response, "id").as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_insert_explicit_rowid_is_passed_to_table() {
+        // INSERT INTO names (rowid, name) VALUES (7, 'a')
+        let response = rowid_insert("false", Some("7"), r#"[7, "a"]"#);
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(0));
+        assert_eq!(first_row_value(&response, "id").as_deref(), Some("7"));
+
+        // The rowid osquery sends in "id" wins over the column value
+        let response = rowid_insert("false", Some("9"), r#"[null, "a"]"#);
+        assert_eq!(first_row_value(&response, "id").as_deref(), Some("9"));
+    }
+
+    #[test]
+    fn test_insert_duplicate_rowid_is_constraint_violation() {
+        // INSERT INTO names (rowid, name) VALUES (1, 'a') with rowid 1 taken
+        let response = rowid_insert("false", Some("1"), r#"[1, "a"]"#);
+        assert_eq!(
+            first_row_value(&response, "status").as_deref(),
+            Some("constraint")
+        );
+    }
+
+    fn insert_request(json_value_array: &str) -> ExtensionPluginRequest {
+        let mut req = BTreeMap::new();
+        req.insert("action".to_string(), "insert".to_string());
+        req.insert("auto_rowid".to_string(), "false".to_string());
+        req.insert("json_value_array".to_string(), json_value_array.to_string());
+        req
+    }
+
+    #[test]
+    fn test_insert_prefers_typed_insert_row() {
+        let people = Arc::new(Mutex::new(vec![]));
+        let plugin = TablePlugin::from_writeable_table(PeopleTable {
+            people: people.clone(),
+        });
+
+        let response = plugin.handle_call(insert_request(r#"[null, "a", "b"]"#));
+
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(0));
+        assert_eq!(
+            people.lock().map(|p| p.clone()).unwrap_or_default(),
+            vec![("a".to_string(), "b".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_insert_type_mismatch_is_reported() {
+        let people = Arc::new(Mutex::new(vec![]));
+        let plugin = TablePlugin::from_writeable_table(PeopleTable {
+            people: people.clone(),
+        });
+
+        let response = plugin.handle_call(insert_request(r#"["not a number", "a", "b"]"#));
+
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(1));
+        assert_eq!(
+            response
+                .response
+                .as_ref()
+                .and_then(|r| r.first())
+                .and_then(|r| r.get("message"))
+                .map(|m| m.as_str()),
+            Some("Column 'rowid' expects INTEGER, got \"not a number\"")
+        );
+        assert!(people.lock().map(|p| p.is_empty()).unwrap_or(false));
+    }
+
+    /// Records the raw values of every write
+    #[derive(Default)]
+    struct RawTable {
+        written: Arc<Mutex<Vec<Value>>>,
+    }
+
+    impl Table for RawTable {
+        fn name(&self) -> String {
+            "raw".to_string()
+        }
+
+        fn columns(&self) -> Vec<ColumnDef> {
+            vec![
+                ColumnDef::new("port", ColumnType::Integer, ColumnOptions::DEFAULT),
+                ColumnDef::new("name", ColumnType::Text, ColumnOptions::DEFAULT),
+            ]
+        }
+
+        fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+            ExtensionResponse::new(ExtensionStatus::default(), vec![])
+        }
+
+        fn update(&mut self, _rowid: u64, row: &Value) -> UpdateResult {
+            if let Ok(mut written) = self.written.lock() {
+                written.push(row.clone());
+            }
+            UpdateResult::Success
+        }
+
+        fn delete(&mut self, _rowid: u64) -> DeleteResult {
+            DeleteResult::Err("unsupported".to_string())
+        }
+
+        fn insert(&mut self, _auto_rowid: bool, row: &Value) -> InsertResult {
+            if let Ok(mut written) = self.written.lock() {
+                written.push(row.clone());
+            }
+            InsertResult::Success(1)
+        }
+
+        fn shutdown(&self) {}
+    }
+
+    #[test]
+    fn test_raw_writes_get_values_that_do_not_fit_columns() {
+        let table = RawTable::default();
+        let written = table.written.clone();
+        let plugin = TablePlugin::from_writeable_table(table);
+
+        // A port as a string and a missing column: fine for a raw table
+        let inserted = plugin.handle_call(insert_request(r#"["8080"]"#));
+        let updated = plugin.handle_call(BTreeMap::from([
+            ("action".to_string(), "update".to_string()),
+            ("id".to_string(), "1".to_string()),
+            (
+                "json_value_array".to_string(),
+                r#"["http", 80, "extra"]"#.to_string(),
+            ),
+        ]));
+
+        for response in [&inserted, &updated] {
+            assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(0));
+        }
+        assert_eq!(
+            written.lock().map(|w| w.clone()).unwrap_or_default(),
+            vec![
+                serde_json::json!(["8080"]),
+                serde_json::json!(["http", 80, "extra"])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_typed_only_table_rejects_values_that_do_not_fit() {
+        let plugin = TablePlugin::from_writeable_table(KeyedTable::default());
+
+        let response = plugin.handle_call(BTreeMap::from([
+            ("action".to_string(), "update".to_string()),
+            ("id".to_string(), "1".to_string()),
+            (
+                "json_value_array".to_string(),
+                r#"["/etc/hosts"]"#.to_string(),
+            ),
+        ]));
+
+        assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(1));
+        assert_eq!(
+            failure_message(&response).as_deref(),
+            Some("Expected 2 column values, got 1")
+        );
+    }
+
+    /// A write action and the `path` constraints it was made under
+    type KeyedWrite = (&'static str, Vec<(Operator, String)>);
+
+    /// Records the constraints each write was made under
+    #[derive(Default)]
+    struct KeyedTable {
+        seen: Arc<Mutex<Vec<KeyedWrite>>>,
+    }
+
+    impl KeyedTable {
+        fn record(&self, action: &'static str, constraints: &QueryConstraints) {
+            if let Ok(mut seen) = self.seen.lock() {
+                seen.push((action, constraints.constraints_for("path")));
+            }
+        }
+    }
+
+    impl Table for KeyedTable {
+        fn name(&self) -> String {
+            "keyed".to_string()
+        }
+
+        fn columns(&self) -> Vec<ColumnDef> {
+            vec![
+                ColumnDef::new("path", ColumnType::Text, ColumnOptions::INDEX),
+                ColumnDef::new("value", ColumnType::Text, ColumnOptions::DEFAULT),
+            ]
+        }
+
+        fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+            ExtensionResponse::new(ExtensionStatus::default(), vec![])
+        }
+
+        fn delete(&mut self, _rowid: u64) -> DeleteResult {
+            DeleteResult::Err("unsupported".to_string())
+        }
+
+        fn insert_row_with_context(
+            &mut self,
+            _auto_rowid: bool,
+            _row: &Row,
+            constraints: &QueryConstraints,
+        ) -> InsertResult {
+            self.record("insert", constraints);
+            InsertResult::Success(1)
+        }
+
+        fn update_row_with_context(
+            &mut self,
+            _rowid: u64,
+            _row: &Row,
+            constraints: &QueryConstraints,
+        ) -> UpdateResult {
+            self.record("update", constraints);
+            UpdateResult::Success
+        }
+
+        fn shutdown(&self) {}
+    }
+
+    #[test]
+    fn test_writes_pass_constraints_to_table() {
+        let table = KeyedTable::default();
+        let seen = table.seen.clone();
+        let plugin = TablePlugin::from_writeable_table(table);
+        let context = r#"{"constraints":[{"name":"path","affinity":"TEXT","list":[{"op":2,"expr":"/etc/hosts"}]}]}"#;
+
+        let mut insert = insert_request(r#"["/etc/hosts", "a"]"#);
+        insert.insert("context".to_string(), context.to_string());
+        let inserted = plugin.handle_call(insert);
+        let updated = plugin.handle_call(BTreeMap::from([
+            ("action".to_string(), "update".to_string()),
+            ("id".to_string(), "1".to_string()),
+            (
+                "json_value_array".to_string(),
+                r#"["/etc/hosts", "b"]"#.to_string(),
+            ),
+            ("context".to_string(), context.to_string()),
+        ]));
+        // Without a context the table sees no constraints
+        let unconstrained = plugin.handle_call(insert_request(r#"["/tmp/x", "c"]"#));
+
+        for response in [&inserted, &updated, &unconstrained] {
+            assert_eq!(response.status.as_ref().and_then(|s| s.code), Some(0));
+        }
+        let expected = vec![(Operator::Equals, "/etc/hosts".to_string())];
+        assert_eq!(
+            seen.lock().map(|s| s.clone()).unwrap_or_default(),
+            vec![
+                ("insert", expected.clone()),
+                ("update", expected),
+                ("insert", vec![]),
+            ]
+        );
+    }
+
     #[test]
     fn test_ping_returns_default_status() {
         let table = TestReadOnlyTable::new("test");
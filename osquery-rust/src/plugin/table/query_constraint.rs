@@ -1,4 +1,6 @@
 use crate::plugin::table::ColumnType;
+use serde_json::Value;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
 // QueryConstraints contains the constraints from the WHERE clause of the query,
@@ -49,6 +51,58 @@ impl ConstraintList {
     pub fn is_empty(&self) -> bool {
         self.constraints.is_empty()
     }
+
+    /// Iterate over the `(operator, expression)` pairs in this list
+    pub fn iter(&self) -> impl Iterator<Item = (Operator, &str)> {
+        self.constraints.iter().map(|c| (c.op, c.expr.as_str()))
+    }
+
+    /// Compile every constraint in this list into one [`Matcher`], which
+    /// matches values satisfying all of them. Fails on the first `REGEXP`
+    /// pattern that isn't valid.
+    pub fn compile(&self) -> Result<Matcher, String> {
+        let parts = self
+            .iter()
+            .map(|(op, expr)| op.compile(expr).map(|m| m.parts))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Matcher {
+            parts: parts.into_iter().flatten().collect(),
+        })
+    }
+}
+
+/// Typed access to the constraints on a single column of [`QueryConstraints`].
+///
+/// Lets a table push predicates down by matching on the operator:
+///
+/// ```
+/// use osquery_rust_ng::plugin::{Operator, QueryConstraints, QueryConstraintsExt};
+///
+/// fn wanted_paths(constraints: &QueryConstraints) -> Vec<String> {
+///     constraints
+///         .constraints_for("path")
+///         .into_iter()
+///         .filter(|(op, _)| *op == Operator::Equals)
+///         .map(|(_, expr)| expr)
+///         .collect()
+/// }
+/// ```
+pub trait QueryConstraintsExt {
+    /// The `(operator, expression)` pairs constraining `column`, empty if the
+    /// query has none.
+    fn constraints_for(&self, column: &str) -> Vec<(Operator, String)>;
+}
+
+impl QueryConstraintsExt for QueryConstraints {
+    fn constraints_for(&self, column: &str) -> Vec<(Operator, String)> {
+        self.get(column)
+            .map(|list| {
+                list.iter()
+                    .map(|(op, expr)| (op, expr.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 }
 
 // Constraint contains both an operator and an expression that are applied as
@@ -85,6 +139,243 @@ pub enum Operator {
     Regexp = 67,
 }
 
+impl Operator {
+    /// Map osquery's numeric constraint operator code to an operator.
+    pub fn from_osquery_code(code: u8) -> Option<Self> {
+        Self::try_from(i32::from(code)).ok()
+    }
+
+    /// Compile `<op> expr` into a [`Matcher`], so a table can filter its own
+    /// rows the way SQLite would:
+    ///
+    /// - `LIKE`: `%` matches any run of characters and `_` any one character,
+    ///   ignoring ASCII case.
+    /// - `GLOB`: `*`, `?` and `[...]` classes (`[^...]` negated), case-sensitive.
+    /// - `REGEXP`: `expr` is a [`regex`] pattern found anywhere in the value.
+    ///   An invalid pattern is an error.
+    /// - Comparisons compare numerically when both sides are numbers and as
+    ///   strings otherwise.
+    ///
+    /// `Unique` and `Match` can't be checked against a single value and
+    /// always match; osquery still applies every constraint to the rows a
+    /// table returns.
+    ///
+    /// ```
+    /// use osquery_rust_ng::plugin::Operator;
+    ///
+    /// let sshd = Operator::Like.compile("ssh%")?;
+    /// assert!(sshd.matches("sshd"));
+    /// assert!(Operator::Glob.compile("/etc/*.conf")?.matches("/etc/ssh.conf"));
+    /// assert!(Operator::Regexp.compile("^[0-9]+$")?.matches("1234"));
+    /// assert!(!Operator::GreaterThan.compile("10")?.matches("9"));
+    /// assert!(Operator::Regexp.compile("(unclosed").is_err());
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn compile(self, expr: &str) -> Result<Matcher, String> {
+        let part = match self {
+            Operator::Equals
+            | Operator::GreaterThan
+            | Operator::GreaterThanOrEquals
+            | Operator::LessThan
+            | Operator::LessThanOrEquals => Part::Compare(self, expr.to_string()),
+            Operator::Like => Part::Wildcard(like_tokens(expr), true),
+            Operator::Glob => Part::Wildcard(glob_tokens(expr), false),
+            Operator::Regexp => Part::Regex(
+                regex::Regex::new(expr)
+                    .map_err(|e| format!("Invalid REGEXP pattern {expr:?}: {e}"))?,
+            ),
+            Operator::Unique | Operator::Match => Part::Any,
+        };
+        Ok(Matcher { parts: vec![part] })
+    }
+}
+
+/// Constraints compiled by [`Operator::compile`] or
+/// [`ConstraintList::compile`], to check many values against.
+pub struct Matcher {
+    parts: Vec<Part>,
+}
+
+impl Matcher {
+    /// Whether `value` satisfies every compiled constraint.
+    pub fn matches(&self, value: &str) -> bool {
+        self.parts.iter().all(|part| part.matches(value))
+    }
+}
+
+/// One compiled constraint.
+enum Part {
+    Compare(Operator, String),
+    /// Tokens of a `LIKE` or `GLOB` pattern, and whether to ignore case
+    Wildcard(Vec<Token>, bool),
+    Regex(regex::Regex),
+    Any,
+}
+
+impl Part {
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            Part::Compare(op, expr) => {
+                let ordering = compare(value, expr);
+                match op {
+                    Operator::GreaterThan => ordering == Ordering::Greater,
+                    Operator::GreaterThanOrEquals => ordering != Ordering::Less,
+                    Operator::LessThan => ordering == Ordering::Less,
+                    Operator::LessThanOrEquals => ordering != Ordering::Greater,
+                    _ => ordering == Ordering::Equal,
+                }
+            }
+            Part::Wildcard(tokens, ignore_case) => wildcard_match(tokens, value, *ignore_case),
+            Part::Regex(re) => re.is_match(value),
+            Part::Any => true,
+        }
+    }
+}
+
+/// Order two column values, numerically if both parse as numbers.
+fn compare(value: &str, expr: &str) -> Ordering {
+    match (value.parse::<f64>(), expr.parse::<f64>()) {
+        (Ok(value), Ok(expr)) => value.partial_cmp(&expr).unwrap_or(Ordering::Equal),
+        _ => value.cmp(expr),
+    }
+}
+
+/// One element of a `LIKE` or `GLOB` pattern.
+enum Token {
+    /// Any run of characters, including none
+    Many,
+    /// Any single character
+    One,
+    /// A character class; matches characters in `ranges`, or outside them when `negated`
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    Literal(char),
+}
+
+impl Token {
+    /// Whether this token consumes `c`. `Many` is handled by the matcher.
+    fn matches(&self, c: char, ignore_case: bool) -> bool {
+        match self {
+            Token::Many => false,
+            Token::One => true,
+            Token::Class { negated, ranges } => {
+                ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&c)) != *negated
+            }
+            Token::Literal(l) if ignore_case => l.eq_ignore_ascii_case(&c),
+            Token::Literal(l) => *l == c,
+        }
+    }
+}
+
+fn like_tokens(pattern: &str) -> Vec<Token> {
+    pattern
+        .chars()
+        .map(|c| match c {
+            '%' => Token::Many,
+            '_' => Token::One,
+            c => Token::Literal(c),
+        })
+        .collect()
+}
+
+fn glob_tokens(pattern: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => tokens.push(Token::Many),
+            '?' => tokens.push(Token::One),
+            '[' => match glob_class(chars.clone()) {
+                Some((class, consumed)) => {
+                    tokens.push(class);
+                    chars.nth(consumed - 1);
+                }
+                // An unterminated class is a literal '['
+                None => tokens.push(Token::Literal('[')),
+            },
+            c => tokens.push(Token::Literal(c)),
+        }
+    }
+    tokens
+}
+
+/// Parse a glob character class from just after its `[`, returning it and
+/// the number of characters consumed including the closing `]`.
+fn glob_class(mut chars: impl Iterator<Item = char>) -> Option<(Token, usize)> {
+    let mut consumed = 0;
+    let mut next = || {
+        consumed += 1;
+        chars.next()
+    };
+
+    let mut c = next()?;
+    let negated = c == '^';
+    if negated {
+        c = next()?;
+    }
+
+    // A ']' right after the opening bracket is part of the class
+    let mut ranges = Vec::new();
+    let mut first = true;
+    while c != ']' || first {
+        first = false;
+        let lo = c;
+        c = next()?;
+        if c == '-' {
+            c = next()?;
+            if c == ']' {
+                ranges.push((lo, lo));
+                ranges.push(('-', '-'));
+                break;
+            }
+            ranges.push((lo, c));
+            c = next()?;
+        } else {
+            ranges.push((lo, lo));
+        }
+    }
+
+    Some((Token::Class { negated, ranges }, consumed))
+}
+
+/// Match the whole of `value` against `tokens`, backtracking to the last
+/// `Many` on a mismatch.
+fn wildcard_match(tokens: &[Token], value: &str, ignore_case: bool) -> bool {
+    let value: Vec<char> = value.chars().collect();
+    let (mut t, mut v) = (0, 0);
+    // Token index after the last `Many`, and where in `value` it resumes
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while let Some(&c) = value.get(v) {
+        match tokens.get(t) {
+            Some(Token::Many) => {
+                t += 1;
+                backtrack = Some((t, v));
+            }
+            Some(token) if token.matches(c, ignore_case) => {
+                t += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                Some((after_many, from)) => {
+                    t = after_many;
+                    v = from + 1;
+                    backtrack = Some((after_many, from + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    tokens
+        .get(t..)
+        .unwrap_or_default()
+        .iter()
+        .all(|token| matches!(token, Token::Many))
+}
+
 impl TryFrom<i32> for Operator {
     type Error = String;
 
@@ -105,10 +396,186 @@ impl TryFrom<i32> for Operator {
     }
 }
 
+/// Parse the constraints from osquery's query context JSON.
+///
+/// The context looks like:
+/// `{"constraints":[{"name":"col","affinity":"TEXT","list":[{"op":2,"expr":"x"}]}]}`.
+/// Malformed entries and unknown operators are skipped.
+pub(crate) fn parse_constraints(context: &Value) -> QueryConstraints {
+    let mut constraints = QueryConstraints::new();
+
+    let Some(entries) = context.get("constraints").and_then(|c| c.as_array()) else {
+        return constraints;
+    };
+
+    for entry in entries {
+        let Some(name) = entry.get("name").and_then(|n| n.as_str()) else {
+            continue;
+        };
+
+        let affinity = entry
+            .get("affinity")
+            .and_then(|a| a.as_str())
+            .and_then(|a| a.parse().ok())
+            .unwrap_or(ColumnType::Text);
+
+        let mut list = ConstraintList::new(affinity);
+        for constraint in entry
+            .get("list")
+            .and_then(|l| l.as_array())
+            .map(|l| l.as_slice())
+            .unwrap_or_default()
+        {
+            let op = constraint
+                .get("op")
+                .and_then(|op| op.as_i64())
+                .and_then(|op| i32::try_from(op).ok())
+                .and_then(|op| Operator::try_from(op).ok());
+            let Some(op) = op else {
+                continue;
+            };
+
+            let expr = match constraint.get("expr") {
+                Some(Value::String(s)) => s.clone(),
+                Some(Value::Null) | None => String::new(),
+                Some(other) => other.to_string(),
+            };
+            list.add_constraint(op, expr);
+        }
+
+        constraints.insert(name.to_string(), list);
+    }
+
+    constraints
+}
+
+/// Build the `constraints` part of an osquery query context, the inverse of
+/// [`parse_constraints`].
+pub(crate) fn constraints_to_context(constraints: &QueryConstraints) -> Value {
+    let entries: Vec<Value> = constraints
+        .iter()
+        .map(|(name, list)| {
+            let ops: Vec<Value> = list
+                .constraints
+                .iter()
+                .map(|c| serde_json::json!({"op": c.op as i32, "expr": c.expr}))
+                .collect();
+            serde_json::json!({
+                "name": name,
+                "affinity": list.affinity.to_string(),
+                "list": ops,
+            })
+        })
+        .collect();
+
+    serde_json::json!({ "constraints": entries })
+}
+
+/// Parse a `LIMIT` passed down in the query context, if any. osquery itself
+/// doesn't send one; other callers may.
+///
+/// `None` when the context also has constraints or an `OFFSET`: osquery
+/// applies those to the rows after the table returns them, so a table
+/// stopping after `LIMIT` rows would leave the query short.
+pub(crate) fn parse_limit(context: &Value) -> Option<usize> {
+    let limit = context_integer(context, "limit")?;
+    let offset = context_integer(context, "offset").unwrap_or(0);
+    let constrained = parse_constraints(context)
+        .values()
+        .any(|list| !list.is_empty());
+    if offset != 0 || constrained {
+        return None;
+    }
+
+    // SQLite uses a negative LIMIT to mean "no limit"
+    usize::try_from(limit).ok()
+}
+
+/// An integer in the query context, sent as a number or a string.
+fn context_integer(context: &Value, key: &str) -> Option<i64> {
+    match context.get(key)? {
+        Value::String(s) => s.parse::<i64>().ok(),
+        other => other.as_i64(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn matches(op: Operator, expr: &str, value: &str) -> bool {
+        op.compile(expr).is_ok_and(|m| m.matches(value))
+    }
+
+    #[test]
+    fn test_like_matches() {
+        assert!(matches(Operator::Like, "a%", "a"));
+        assert!(matches(Operator::Like, "a%", "apple"));
+        assert!(matches(Operator::Like, "a%", "Apple"));
+        assert!(!matches(Operator::Like, "a%", "banana"));
+        assert!(matches(Operator::Like, "%an%", "banana"));
+        assert!(matches(Operator::Like, "b_n%", "banana"));
+        assert!(!matches(Operator::Like, "b_n", "banana"));
+        assert!(matches(Operator::Like, "%", ""));
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(matches(Operator::Glob, "a*", "apple"));
+        assert!(!matches(Operator::Glob, "a*", "Apple"));
+        assert!(matches(Operator::Glob, "/usr/*/bin", "/usr/local/bin"));
+        assert!(matches(Operator::Glob, "file?.txt", "file1.txt"));
+        assert!(!matches(Operator::Glob, "file?.txt", "file10.txt"));
+        assert!(matches(Operator::Glob, "[a-c]at", "bat"));
+        assert!(!matches(Operator::Glob, "[a-c]at", "rat"));
+        assert!(matches(Operator::Glob, "[^a-c]at", "rat"));
+        assert!(matches(Operator::Glob, "[]]", "]"));
+        assert!(matches(Operator::Glob, "[a-]", "-"));
+        assert!(matches(Operator::Glob, "[abc", "[abc"));
+        assert!(matches(Operator::Glob, "*%_*", "100%_done"));
+    }
+
+    #[test]
+    fn test_regexp_matches() {
+        assert!(matches(Operator::Regexp, "^ssh(d)?$", "sshd"));
+        assert!(matches(Operator::Regexp, "[0-9]{3}", "port 443 open"));
+        assert!(!matches(Operator::Regexp, "^[0-9]+$", "443/tcp"));
+        assert!(Operator::Regexp
+            .compile("(unclosed")
+            .is_err_and(|e| e.contains("(unclosed")));
+    }
+
+    #[test]
+    fn test_comparison_matches() {
+        assert!(matches(Operator::Equals, "42", "42"));
+        assert!(matches(Operator::Equals, "42", "42.0"));
+        assert!(matches(Operator::GreaterThan, "9", "10"));
+        assert!(matches(Operator::LessThanOrEquals, "b", "a"));
+        assert!(!matches(Operator::GreaterThanOrEquals, "b", "a"));
+        assert!(matches(Operator::Unique, "anything", "else"));
+    }
+
+    #[test]
+    fn test_constraint_list_matches_all_constraints() {
+        let mut list = ConstraintList::new(ColumnType::Text);
+        list.add_constraint(Operator::Like, "a%".to_string());
+        list.add_constraint(Operator::Glob, "*e".to_string());
+
+        let matching = list.compile().map(|matcher| {
+            ["apple", "avocado", "grape", "axe"]
+                .into_iter()
+                .filter(|value| matcher.matches(value))
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(matching, Ok(vec!["apple", "axe"]));
+        assert!(ConstraintList::new(ColumnType::Text)
+            .compile()
+            .is_ok_and(|m| m.matches("anything")));
+
+        list.add_constraint(Operator::Regexp, "[".to_string());
+        assert!(list.compile().is_err());
+    }
+
     #[test]
     fn test_constraint_list_creation() {
         let list = ConstraintList::new(ColumnType::Text);
@@ -174,6 +641,44 @@ mod tests {
         assert!(Operator::try_from(-1).is_err());
     }
 
+    #[test]
+    fn test_operator_from_osquery_code() {
+        assert_eq!(Operator::from_osquery_code(1), Some(Operator::Unique));
+        assert_eq!(Operator::from_osquery_code(2), Some(Operator::Equals));
+        assert_eq!(
+            Operator::from_osquery_code(32),
+            Some(Operator::GreaterThanOrEquals)
+        );
+        assert_eq!(Operator::from_osquery_code(67), Some(Operator::Regexp));
+        assert_eq!(Operator::from_osquery_code(0), None);
+        assert_eq!(Operator::from_osquery_code(255), None);
+    }
+
+    #[test]
+    fn test_constraints_for_column() {
+        let context = serde_json::json!({"constraints": [
+            {"name": "size", "affinity": "BIGINT", "list": [
+                {"op": 4, "expr": "10"},
+                {"op": 16, "expr": "99"}
+            ]},
+            {"name": "path", "affinity": "TEXT", "list": [{"op": 65, "expr": "/etc/%"}]}
+        ]});
+        let constraints = parse_constraints(&context);
+
+        assert_eq!(
+            constraints.constraints_for("size"),
+            vec![
+                (Operator::GreaterThan, "10".to_string()),
+                (Operator::LessThan, "99".to_string()),
+            ]
+        );
+        assert_eq!(
+            constraints.constraints_for("path"),
+            vec![(Operator::Like, "/etc/%".to_string())]
+        );
+        assert!(constraints.constraints_for("mode").is_empty());
+    }
+
     #[test]
     fn test_query_constraints_map() {
         let mut constraints: QueryConstraints = HashMap::new();
@@ -213,4 +718,90 @@ mod tests {
         assert!(matches!(bigint_list.affinity(), ColumnType::BigInt));
         assert!(matches!(double_list.affinity(), ColumnType::Double));
     }
+
+    #[test]
+    fn test_parse_constraints_from_context() {
+        let context: Value = serde_json::from_str(
+            r#"{"constraints":[
+                {"name":"path","affinity":"TEXT","list":[{"op":2,"expr":"/etc/hosts"}]},
+                {"name":"size","affinity":"BIGINT","list":[{"op":4,"expr":"10"},{"op":16,"expr":"99"}]}
+            ]}"#,
+        )
+        .unwrap_or_default();
+
+        let constraints = parse_constraints(&context);
+        assert_eq!(constraints.len(), 2);
+        assert_eq!(constraints.get("path").map(|l| l.len()), Some(1));
+        assert_eq!(constraints.get("size").map(|l| l.len()), Some(2));
+        assert!(matches!(
+            constraints.get("size").map(|l| l.affinity()),
+            Some(ColumnType::BigInt)
+        ));
+    }
+
+    #[test]
+    fn test_parse_constraints_skips_unknown_operators() {
+        let context: Value = serde_json::from_str(
+            r#"{"constraints":[{"name":"path","affinity":"TEXT","list":[{"op":3,"expr":"x"}]}]}"#,
+        )
+        .unwrap_or_default();
+
+        let constraints = parse_constraints(&context);
+        assert_eq!(constraints.get("path").map(|l| l.len()), Some(0));
+    }
+
+    #[test]
+    fn test_parse_constraints_missing_key() {
+        let constraints = parse_constraints(&serde_json::json!({}));
+        assert!(constraints.is_empty());
+    }
+
+    #[test]
+    fn test_constraints_to_context_round_trips() {
+        let mut list = ConstraintList::new(ColumnType::BigInt);
+        list.add_constraint(Operator::GreaterThan, "10".to_string());
+        let mut constraints = QueryConstraints::new();
+        constraints.insert("size".to_string(), list);
+
+        let parsed = parse_constraints(&constraints_to_context(&constraints));
+        assert_eq!(parsed.get("size").map(|l| l.len()), Some(1));
+        assert!(matches!(
+            parsed.get("size").map(|l| l.affinity()),
+            Some(ColumnType::BigInt)
+        ));
+    }
+
+    #[test]
+    fn test_parse_limit() {
+        assert_eq!(parse_limit(&serde_json::json!({"limit": 10})), Some(10));
+        assert_eq!(parse_limit(&serde_json::json!({"limit": "25"})), Some(25));
+        assert_eq!(parse_limit(&serde_json::json!({"limit": -1})), None);
+        assert_eq!(parse_limit(&serde_json::json!({})), None);
+    }
+
+    #[test]
+    fn test_parse_limit_ignored_with_offset_or_constraints() {
+        assert_eq!(
+            parse_limit(&serde_json::json!({"limit": 5, "offset": 10})),
+            None
+        );
+        assert_eq!(
+            parse_limit(&serde_json::json!({"limit": 5, "offset": "0"})),
+            Some(5)
+        );
+        assert_eq!(
+            parse_limit(&serde_json::json!({
+                "limit": 5,
+                "constraints": [{"name": "x", "affinity": "TEXT", "list": [{"op": 65, "expr": "a%"}]}],
+            })),
+            None
+        );
+        assert_eq!(
+            parse_limit(&serde_json::json!({
+                "limit": 5,
+                "constraints": [{"name": "x", "affinity": "TEXT", "list": []}],
+            })),
+            Some(5)
+        );
+    }
 }
@@ -0,0 +1,452 @@
+use crate::plugin::table::column_def::{ColumnDef, ColumnType};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A single typed value in a row written by osquery.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnValue {
+    Null,
+    Text(String),
+    Integer(i64),
+    BigInt(i64),
+    UnsignedBigInt(u64),
+    Double(f64),
+    Blob(Vec<u8>),
+}
+
+impl ColumnValue {
+    /// Encode bytes for a `BLOB` column as lowercase hex.
+    ///
+    /// Column values travel between osquery and extensions as UTF-8 strings,
+    /// so arbitrary bytes can't be sent as-is. osquery-rust hex-encodes blob
+    /// columns instead; use this when filling a `BLOB` column in `generate()`,
+    /// and [`ColumnValue::decode_blob`] to read one back.
+    pub fn encode_blob(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// Decode a hex-encoded `BLOB` column value, the inverse of
+    /// [`ColumnValue::encode_blob`]. Upper- and lowercase digits are accepted.
+    pub fn decode_blob(hex: &str) -> Result<Vec<u8>, String> {
+        if !hex.len().is_multiple_of(2) {
+            return Err(format!("Blob hex has odd length {}", hex.len()));
+        }
+
+        hex.as_bytes()
+            .chunks(2)
+            .map(|pair| {
+                std::str::from_utf8(pair)
+                    .ok()
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(|| format!("Invalid blob hex: {hex}"))
+            })
+            .collect()
+    }
+
+    /// Format a value for an `INTEGER` or `BIGINT` column.
+    pub fn format_int(value: i64) -> String {
+        value.to_string()
+    }
+
+    /// Format a value for a `DOUBLE` column.
+    ///
+    /// Always plain decimal notation with the shortest digits that round-trip
+    /// (`1e20` becomes `"100000000000000000000"`, `0.1` stays `"0.1"`), and
+    /// `-0.0` is written as `"0"`. NaN and infinities have no SQL literal and
+    /// become an empty string, which osquery reads as NULL.
+    pub fn format_double(value: f64) -> String {
+        if !value.is_finite() {
+            return String::new();
+        }
+        if value == 0.0 {
+            return "0".to_string();
+        }
+        value.to_string()
+    }
+
+    /// Build a row for `generate()` from typed values, formatting each one
+    /// canonically for its type (see the `Display` impl).
+    ///
+    /// ```
+    /// use osquery_rust_ng::plugin::ColumnValue;
+    ///
+    /// let row = ColumnValue::typed_row([
+    ///     ("pid", ColumnValue::Integer(42)),
+    ///     ("cpu", ColumnValue::Double(1e20)),
+    /// ]);
+    /// assert_eq!(row["cpu"], "100000000000000000000");
+    /// ```
+    pub fn typed_row<K: Into<String>>(
+        values: impl IntoIterator<Item = (K, ColumnValue)>,
+    ) -> BTreeMap<String, String> {
+        values
+            .into_iter()
+            .map(|(column, value)| (column.into(), value.to_string()))
+            .collect()
+    }
+
+    /// Convert a JSON value from osquery's `json_value_array` into the column's type.
+    ///
+    /// Follows SQLite's type affinity: numbers are accepted for `TEXT` columns
+    /// and numeric strings for numeric columns. Anything else is an error.
+    fn from_json(column: &ColumnDef, value: &Value) -> Result<Self, String> {
+        let mismatch = || {
+            format!(
+                "Column '{}' expects {}, got {value}",
+                column.name(),
+                column.t()
+            )
+        };
+
+        if value.is_null() {
+            return Ok(ColumnValue::Null);
+        }
+
+        match column.column_type() {
+            ColumnType::Text => match value {
+                Value::String(s) => Ok(ColumnValue::Text(s.clone())),
+                Value::Number(n) => Ok(ColumnValue::Text(n.to_string())),
+                _ => Err(mismatch()),
+            },
+            ColumnType::Integer => parse_i64(value)
+                .map(ColumnValue::Integer)
+                .ok_or_else(mismatch),
+            ColumnType::BigInt => parse_i64(value)
+                .map(ColumnValue::BigInt)
+                .ok_or_else(mismatch),
+            ColumnType::UnsignedBigInt => {
+                let unsigned = match value {
+                    Value::String(s) => s.parse::<u64>().ok(),
+                    other => other.as_u64(),
+                };
+                unsigned
+                    .map(ColumnValue::UnsignedBigInt)
+                    .ok_or_else(mismatch)
+            }
+            ColumnType::Double => {
+                let double = match value {
+                    Value::String(s) => s.parse::<f64>().ok(),
+                    other => other.as_f64(),
+                };
+                double.map(ColumnValue::Double).ok_or_else(mismatch)
+            }
+            ColumnType::Blob => value
+                .as_str()
+                .and_then(|hex| Self::decode_blob(hex).ok())
+                .map(ColumnValue::Blob)
+                .ok_or_else(mismatch),
+        }
+    }
+}
+
+/// Formats the value the way osquery expects it in a row: integers and
+/// doubles with [`ColumnValue::format_int`] and [`ColumnValue::format_double`],
+/// blobs hex-encoded, and NULL as an empty string.
+impl fmt::Display for ColumnValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnValue::Null => Ok(()),
+            ColumnValue::Text(text) => f.write_str(text),
+            ColumnValue::Integer(value) | ColumnValue::BigInt(value) => {
+                f.write_str(&Self::format_int(*value))
+            }
+            ColumnValue::UnsignedBigInt(value) => write!(f, "{value}"),
+            ColumnValue::Double(value) => f.write_str(&Self::format_double(*value)),
+            ColumnValue::Blob(bytes) => f.write_str(&Self::encode_blob(bytes)),
+        }
+    }
+}
+
+fn parse_i64(value: &Value) -> Option<i64> {
+    match value {
+        Value::String(s) => s.parse::<i64>().ok(),
+        other => other.as_i64(),
+    }
+}
+
+/// A row written to a [`Table`](crate::plugin::Table), keyed by column name.
+///
+/// Built by zipping osquery's `json_value_array` with the table's `columns()`,
+/// so values can be read by name instead of by position.
+#[derive(Clone, Debug)]
+pub struct Row {
+    values: BTreeMap<String, ColumnValue>,
+    raw: Value,
+    rowid: Option<u64>,
+}
+
+impl Row {
+    pub(crate) fn from_json(columns: &[ColumnDef], raw: &Value) -> Result<Self, String> {
+        let Some(array) = raw.as_array() else {
+            return Err(format!("Expected a JSON array of column values, got {raw}"));
+        };
+
+        if array.len() != columns.len() {
+            return Err(format!(
+                "Expected {} column values, got {}",
+                columns.len(),
+                array.len()
+            ));
+        }
+
+        let values = columns
+            .iter()
+            .zip(array)
+            .map(|(column, value)| Ok((column.name(), ColumnValue::from_json(column, value)?)))
+            .collect::<Result<BTreeMap<_, _>, String>>()?;
+
+        let rowid = match values.get("rowid") {
+            Some(ColumnValue::Integer(id) | ColumnValue::BigInt(id)) => u64::try_from(*id).ok(),
+            Some(ColumnValue::UnsignedBigInt(id)) => Some(*id),
+            _ => None,
+        };
+
+        Ok(Row {
+            values,
+            raw: raw.clone(),
+            rowid,
+        })
+    }
+
+    /// Use the rowid osquery sent next to the values instead of the `rowid` column.
+    pub(crate) fn with_rowid(mut self, rowid: u64) -> Self {
+        self.rowid = Some(rowid);
+        self
+    }
+
+    /// The rowid the statement chose for this row, or `None` when the table
+    /// should pick one (an `INSERT` without a rowid, see
+    /// [`Table::insert_row`](crate::plugin::Table::insert_row)).
+    ///
+    /// Taken from the rowid osquery sends with the request or, failing that,
+    /// from a column named `rowid`.
+    pub fn rowid(&self) -> Option<u64> {
+        self.rowid
+    }
+
+    /// The value of `column`, if the table has such a column.
+    pub fn get(&self, column: &str) -> Option<&ColumnValue> {
+        self.values.get(column)
+    }
+
+    /// The value of a `TEXT` column, or `None` if it is missing or `NULL`.
+    pub fn get_text(&self, column: &str) -> Option<&str> {
+        match self.get(column)? {
+            ColumnValue::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The value of an `INTEGER` or `BIGINT` column, or `None` if it is missing or `NULL`.
+    pub fn get_integer(&self, column: &str) -> Option<i64> {
+        match self.get(column)? {
+            ColumnValue::Integer(i) | ColumnValue::BigInt(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// The value of an `UNSIGNED_BIGINT` column, or `None` if it is missing or `NULL`.
+    pub fn get_unsigned(&self, column: &str) -> Option<u64> {
+        match self.get(column)? {
+            ColumnValue::UnsignedBigInt(u) => Some(*u),
+            _ => None,
+        }
+    }
+
+    /// The value of a `DOUBLE` column, or `None` if it is missing or `NULL`.
+    pub fn get_double(&self, column: &str) -> Option<f64> {
+        match self.get(column)? {
+            ColumnValue::Double(d) => Some(*d),
+            _ => None,
+        }
+    }
+
+    /// The decoded bytes of a `BLOB` column, or `None` if it is missing or `NULL`.
+    pub fn get_blob(&self, column: &str) -> Option<&[u8]> {
+        match self.get(column)? {
+            ColumnValue::Blob(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Whether `column` was written as `NULL`, e.g. an omitted `rowid`.
+    pub fn is_null(&self, column: &str) -> bool {
+        matches!(self.get(column), Some(ColumnValue::Null))
+    }
+
+    /// The `json_value_array` exactly as osquery sent it.
+    pub fn as_json(&self) -> &Value {
+        &self.raw
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::panic)]
+mod tests {
+    use super::*;
+    use crate::plugin::ColumnOptions;
+    use serde_json::json;
+
+    fn columns() -> Vec<ColumnDef> {
+        vec![
+            ColumnDef::new("rowid", ColumnType::Integer, ColumnOptions::HIDDEN),
+            ColumnDef::new("name", ColumnType::Text, ColumnOptions::DEFAULT),
+            ColumnDef::new("lastname", ColumnType::Text, ColumnOptions::DEFAULT),
+        ]
+    }
+
+    #[test]
+    fn test_row_from_insert_values() {
+        // INSERT INTO t VALUES (null, 'a', 'b')
+        let row = Row::from_json(&columns(), &json!([null, "a", "b"]));
+        let Ok(row) = row else {
+            panic!("row should convert: {row:?}");
+        };
+
+        assert!(row.is_null("rowid"));
+        assert_eq!(row.get_text("name"), Some("a"));
+        assert_eq!(row.get_text("lastname"), Some("b"));
+        assert_eq!(row.get_integer("rowid"), None);
+        assert_eq!(row.as_json(), &json!([null, "a", "b"]));
+    }
+
+    #[test]
+    fn test_format_double() {
+        assert_eq!(ColumnValue::format_double(1e20), "100000000000000000000");
+        assert_eq!(ColumnValue::format_double(1.5e-7), "0.00000015");
+        assert_eq!(ColumnValue::format_double(-0.0), "0");
+        assert_eq!(ColumnValue::format_double(0.1), "0.1");
+        assert_eq!(ColumnValue::format_double(1.0), "1");
+        assert_eq!(ColumnValue::format_double(-2.5), "-2.5");
+        assert_eq!(ColumnValue::format_double(f64::NAN), "");
+        assert_eq!(ColumnValue::format_double(f64::INFINITY), "");
+    }
+
+    #[test]
+    fn test_format_int() {
+        assert_eq!(ColumnValue::format_int(i64::MIN), "-9223372036854775808");
+        assert_eq!(ColumnValue::format_int(i64::MAX), "9223372036854775807");
+        assert_eq!(ColumnValue::format_int(0), "0");
+    }
+
+    #[test]
+    fn test_typed_row_formats_each_type() {
+        let row = ColumnValue::typed_row([
+            ("name", ColumnValue::Text("init".to_string())),
+            ("pid", ColumnValue::Integer(1)),
+            ("start", ColumnValue::BigInt(i64::MIN)),
+            ("size", ColumnValue::UnsignedBigInt(u64::MAX)),
+            ("cpu", ColumnValue::Double(-0.0)),
+            ("hash", ColumnValue::Blob(vec![0xde, 0xad])),
+            ("parent", ColumnValue::Null),
+        ]);
+
+        let expected: BTreeMap<String, String> = [
+            ("name", "init"),
+            ("pid", "1"),
+            ("start", "-9223372036854775808"),
+            ("size", "18446744073709551615"),
+            ("cpu", "0"),
+            ("hash", "dead"),
+            ("parent", ""),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        assert_eq!(row, expected);
+    }
+
+    #[test]
+    fn test_row_numeric_affinity() {
+        let columns = vec![
+            ColumnDef::new("count", ColumnType::BigInt, ColumnOptions::DEFAULT),
+            ColumnDef::new("ratio", ColumnType::Double, ColumnOptions::DEFAULT),
+            ColumnDef::new("label", ColumnType::Text, ColumnOptions::DEFAULT),
+        ];
+        let row = Row::from_json(&columns, &json!(["42", 0.5, 7]));
+        let Ok(row) = row else {
+            panic!("row should convert: {row:?}");
+        };
+
+        assert_eq!(row.get("count"), Some(&ColumnValue::BigInt(42)));
+        assert_eq!(row.get_double("ratio"), Some(0.5));
+        assert_eq!(row.get_text("label"), Some("7"));
+    }
+
+    #[test]
+    fn test_row_unsigned_bigint_column() {
+        let columns = vec![ColumnDef::new(
+            "inode",
+            ColumnType::UnsignedBigInt,
+            ColumnOptions::DEFAULT,
+        )];
+
+        let row = Row::from_json(&columns, &json!(["18446744073709551615"]));
+        let Ok(row) = row else {
+            panic!("row should convert: {row:?}");
+        };
+        assert_eq!(row.get_unsigned("inode"), Some(u64::MAX));
+
+        assert!(Row::from_json(&columns, &json!([-1])).is_err());
+    }
+
+    #[test]
+    fn test_blob_encoding_round_trips() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let hex = ColumnValue::encode_blob(&bytes);
+        assert_eq!(hex.len(), 512);
+        assert!(hex.starts_with("000102"));
+        assert!(hex.ends_with("fdfeff"));
+        assert_eq!(ColumnValue::decode_blob(&hex), Ok(bytes));
+
+        assert_eq!(ColumnValue::encode_blob(&[]), "");
+        assert_eq!(
+            ColumnValue::decode_blob("DEADbeef"),
+            Ok(vec![0xde, 0xad, 0xbe, 0xef])
+        );
+        assert!(ColumnValue::decode_blob("abc").is_err());
+        assert!(ColumnValue::decode_blob("zz").is_err());
+        assert!(ColumnValue::decode_blob("é0").is_err());
+    }
+
+    #[test]
+    fn test_row_blob_column() {
+        let columns = vec![ColumnDef::new(
+            "data",
+            ColumnType::Blob,
+            ColumnOptions::DEFAULT,
+        )];
+        let row = Row::from_json(
+            &columns,
+            &json!([ColumnValue::encode_blob(b"\x00\xffosquery")]),
+        );
+        let Ok(row) = row else {
+            panic!("row should convert: {row:?}");
+        };
+        assert_eq!(row.get_blob("data"), Some(&b"\x00\xffosquery"[..]));
+
+        assert_eq!(
+            Row::from_json(&columns, &json!(["not hex"])).err(),
+            Some("Column 'data' expects BLOB, got \"not hex\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_row_type_mismatch_is_reported() {
+        let result = Row::from_json(&columns(), &json!(["abc", "a", "b"]));
+        assert_eq!(
+            result.err(),
+            Some("Column 'rowid' expects INTEGER, got \"abc\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_row_wrong_shape_is_reported() {
+        assert!(Row::from_json(&columns(), &json!({"name": "a"})).is_err());
+        assert_eq!(
+            Row::from_json(&columns(), &json!([null, "a"])).err(),
+            Some("Expected 3 column values, got 2".to_string())
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use crate::plugin::table::column_def::ColumnDef;
+use crate::plugin::table::ReadOnlyTable;
+use crate::{ExtensionPluginRequest, ExtensionResponse, ExtensionStatus};
+use std::collections::BTreeMap;
+
+type RowGenerator = dyn Fn() -> Vec<BTreeMap<String, String>> + Send + Sync;
+
+/// A read-only table defined by its name, columns and a row generator closure.
+///
+/// Saves writing a struct and `ReadOnlyTable` impl for tables that simply
+/// return a list of rows:
+///
+/// ```
+/// use osquery_rust_ng::plugin::{ColumnDef, ColumnOptions, ColumnType, Plugin};
+/// use std::collections::BTreeMap;
+///
+/// let plugin = Plugin::static_table(
+///     "greetings",
+///     vec![ColumnDef::new("hello", ColumnType::Text, ColumnOptions::DEFAULT)],
+///     || vec![BTreeMap::from([("hello".to_string(), "world".to_string())])],
+/// );
+/// ```
+pub struct StaticTable {
+    name: String,
+    columns: Vec<ColumnDef>,
+    generator: Box<RowGenerator>,
+}
+
+impl StaticTable {
+    pub fn new<F>(name: &str, columns: Vec<ColumnDef>, generator: F) -> Self
+    where
+        F: Fn() -> Vec<BTreeMap<String, String>> + Send + Sync + 'static,
+    {
+        StaticTable {
+            name: name.to_string(),
+            columns,
+            generator: Box::new(generator),
+        }
+    }
+}
+
+impl ReadOnlyTable for StaticTable {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn columns(&self) -> Vec<ColumnDef> {
+        self.columns.clone()
+    }
+
+    fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+        ExtensionResponse::new(ExtensionStatus::new(0, None, None), (self.generator)())
+    }
+
+    fn shutdown(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::{ColumnOptions, ColumnType, OsqueryPlugin, Plugin};
+
+    #[test]
+    fn test_static_table_through_wrapper() {
+        let plugin = Plugin::static_table(
+            "pets",
+            vec![
+                ColumnDef::new("name", ColumnType::Text, ColumnOptions::DEFAULT),
+                ColumnDef::new("age", ColumnType::Integer, ColumnOptions::DEFAULT),
+            ],
+            || {
+                vec![BTreeMap::from([
+                    ("name".to_string(), "rex".to_string()),
+                    ("age".to_string(), "3".to_string()),
+                ])]
+            },
+        );
+
+        assert_eq!(plugin.name(), "pets");
+        assert_eq!(plugin.routes().len(), 2);
+
+        let request = BTreeMap::from([("action".to_string(), "generate".to_string())]);
+        let response = plugin.handle_call(request);
+
+        assert_eq!(response.status.and_then(|s| s.code), Some(0));
+        assert_eq!(
+            response.response,
+            Some(vec![BTreeMap::from([
+                ("age".to_string(), "3".to_string()),
+                ("name".to_string(), "rex".to_string()),
+            ])])
+        );
+    }
+}
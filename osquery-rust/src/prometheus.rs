@@ -0,0 +1,359 @@
+//! A [`MetricsSink`] serving plugin call metrics for Prometheus to scrape.
+//!
+//! Enabled with the `prometheus` feature. [`PrometheusMetrics`] keeps a call
+//! counter and a latency histogram per plugin and serves them in the text
+//! exposition format at `GET /metrics`:
+//!
+//! - `osquery_extension_plugin_calls_total{registry, plugin, status}`, where
+//!   `status` is `ok` or `error`
+//! - `osquery_extension_plugin_call_duration_seconds{registry, plugin}`
+//!
+//! Like the `debug-http` endpoint this is a plain HTTP/1.1 server handling one
+//! request per connection.
+
+use crate::log_target;
+use crate::metrics::MetricsSink;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How often the accept loop checks for shutdown.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Upper bounds of the latency histogram buckets, in seconds. These are the
+/// Prometheus client libraries' defaults.
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Measurements for one `(registry, plugin)` pair.
+#[derive(Default)]
+struct PluginStats {
+    ok: u64,
+    errors: u64,
+    /// Calls that took at most `BUCKETS[i]`, per bucket (not cumulative)
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+}
+
+type Stats = Arc<Mutex<BTreeMap<(String, String), PluginStats>>>;
+
+/// A [`MetricsSink`] aggregating call counts and latencies per plugin and
+/// serving them on `/metrics` for Prometheus.
+///
+/// The endpoint runs on its own thread from [`serve`](Self::serve) until the
+/// sink is dropped, which happens when the [`Server`](crate::Server) it was
+/// given to is.
+///
+/// ```no_run
+/// use osquery_rust_ng::plugin::Plugin;
+/// use osquery_rust_ng::{PrometheusMetrics, Server};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut server: Server<Plugin> = Server::new(None, "/var/osquery/osquery.em")?;
+/// server.set_metrics_sink(PrometheusMetrics::serve("127.0.0.1:9100".parse()?)?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct PrometheusMetrics {
+    stats: Stats,
+    local_addr: SocketAddr,
+    shutdown_flag: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl PrometheusMetrics {
+    /// Bind `addr` and start serving `/metrics` on it.
+    ///
+    /// Use port 0 to let the OS pick a free port, then ask
+    /// [`local_addr`](Self::local_addr) which one it chose.
+    pub fn serve(addr: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+
+        let stats = Stats::default();
+        let shutdown_flag = Arc::new(AtomicBool::new(false));
+        let thread = thread::Builder::new()
+            .name("osquery-prometheus".to_string())
+            .spawn({
+                let stats = stats.clone();
+                let shutdown_flag = shutdown_flag.clone();
+                move || accept_loop(listener, &stats, &shutdown_flag)
+            })?;
+
+        Ok(PrometheusMetrics {
+            stats,
+            local_addr,
+            shutdown_flag,
+            thread: Some(thread),
+        })
+    }
+
+    /// The address the endpoint is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// The current metrics in the Prometheus text format, as `/metrics`
+    /// serves them.
+    pub fn render(&self) -> String {
+        render(&self.stats)
+    }
+}
+
+impl MetricsSink for PrometheusMetrics {
+    fn on_call(&self, registry: &str, item: &str, duration: Duration, ok: bool) {
+        let Ok(mut stats) = self.stats.lock() else {
+            return;
+        };
+        let plugin = stats
+            .entry((registry.to_string(), item.to_string()))
+            .or_default();
+
+        if ok {
+            plugin.ok += 1;
+        } else {
+            plugin.errors += 1;
+        }
+        let seconds = duration.as_secs_f64();
+        plugin.sum += seconds;
+        if let Some(bucket) = BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .and_then(|index| plugin.buckets.get_mut(index))
+        {
+            *bucket += 1;
+        }
+    }
+}
+
+impl Drop for PrometheusMetrics {
+    fn drop(&mut self) {
+        self.shutdown_flag.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                log::warn!(target: log_target::SERVER, "Prometheus endpoint thread panicked");
+            }
+        }
+    }
+}
+
+/// Serve scrapes on `listener` until `shutdown_flag` is set.
+fn accept_loop(listener: TcpListener, stats: &Stats, shutdown_flag: &AtomicBool) {
+    while !shutdown_flag.load(Ordering::Acquire) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if let Err(e) = handle_connection(stream, stats) {
+                    log::debug!(target: log_target::SERVER, "Prometheus scrape failed: {e}");
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                log::warn!(target: log_target::SERVER, "Prometheus listener failed: {e}");
+                break;
+            }
+        }
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, stats: &Stats) -> io::Result<()> {
+    // Accepted sockets inherit non-blocking mode on some platforms
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Scrapes have no body; skip the headers
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", render(stats)),
+        _ => (
+            "404 Not Found",
+            "Not found; metrics are at /metrics\n".to_string(),
+        ),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+fn render(stats: &Stats) -> String {
+    let Ok(stats) = stats.lock() else {
+        return String::new();
+    };
+    let mut out = String::new();
+
+    out.push_str("# HELP osquery_extension_plugin_calls_total Plugin calls handled, by outcome.\n");
+    out.push_str("# TYPE osquery_extension_plugin_calls_total counter\n");
+    for ((registry, plugin), s) in stats.iter() {
+        let labels = labels(registry, plugin);
+        for (status, count) in [("ok", s.ok), ("error", s.errors)] {
+            let _ = writeln!(
+                out,
+                "osquery_extension_plugin_calls_total{{{labels},status=\"{status}\"}} {count}"
+            );
+        }
+    }
+
+    out.push_str(
+        "# HELP osquery_extension_plugin_call_duration_seconds Time plugins took to handle calls.\n",
+    );
+    out.push_str("# TYPE osquery_extension_plugin_call_duration_seconds histogram\n");
+    for ((registry, plugin), s) in stats.iter() {
+        let labels = labels(registry, plugin);
+        let mut cumulative = 0;
+        for (bound, count) in BUCKETS.iter().zip(s.buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "osquery_extension_plugin_call_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {cumulative}"
+            );
+        }
+        let total = s.ok + s.errors;
+        let _ = writeln!(
+            out,
+            "osquery_extension_plugin_call_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {total}"
+        );
+        let _ = writeln!(
+            out,
+            "osquery_extension_plugin_call_duration_seconds_sum{{{labels}}} {}",
+            s.sum
+        );
+        let _ = writeln!(
+            out,
+            "osquery_extension_plugin_call_duration_seconds_count{{{labels}}} {total}"
+        );
+    }
+
+    out
+}
+
+fn labels(registry: &str, plugin: &str) -> String {
+    format!(
+        "registry=\"{}\",plugin=\"{}\"",
+        escape(registry),
+        escape(plugin)
+    )
+}
+
+/// Escape a label value as the text format requires.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::_osquery::osquery::ExtensionSyncHandler;
+    use crate::plugin::{ColumnDef, ColumnOptions, ColumnType, Plugin, TableBuilder};
+    use crate::server::Handler;
+    use std::io::Read;
+
+    fn scrape(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).expect("Failed to connect");
+        stream
+            .write_all(format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").as_bytes())
+            .expect("Failed to send request");
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .expect("Failed to read response");
+        response
+    }
+
+    #[test]
+    fn test_scrape_reports_plugin_calls() {
+        let metrics = PrometheusMetrics::serve(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .expect("endpoint should bind");
+        let addr = metrics.local_addr();
+
+        let plugins = vec![TableBuilder::new("users")
+            .column(ColumnDef::new(
+                "username",
+                ColumnType::Text,
+                ColumnOptions::DEFAULT,
+            ))
+            .build()];
+        let handler: Handler<Plugin> = Handler::new(&plugins, Arc::new(AtomicBool::new(false)))
+            .expect("handler should build")
+            .with_metrics(Arc::new(metrics));
+
+        for action in ["generate", "generate", "columns", "bogus"] {
+            handler
+                .handle_call(
+                    "table".to_string(),
+                    "users".to_string(),
+                    BTreeMap::from([("action".to_string(), action.to_string())]),
+                )
+                .expect("call should be dispatched");
+        }
+
+        let response = scrape(addr, "/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        for line in [
+            r#"osquery_extension_plugin_calls_total{registry="table",plugin="users",status="ok"} 3"#,
+            r#"osquery_extension_plugin_calls_total{registry="table",plugin="users",status="error"} 1"#,
+            r#"osquery_extension_plugin_call_duration_seconds_bucket{registry="table",plugin="users",le="+Inf"} 4"#,
+            r#"osquery_extension_plugin_call_duration_seconds_count{registry="table",plugin="users"} 4"#,
+        ] {
+            assert!(
+                response.lines().any(|l| l == line),
+                "missing {line}:\n{response}"
+            );
+        }
+
+        assert!(scrape(addr, "/").starts_with("HTTP/1.1 404 Not Found"));
+    }
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let metrics = PrometheusMetrics::serve(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .expect("endpoint should bind");
+        metrics.on_call("config", "fs\"cfg", Duration::from_millis(20), true);
+        metrics.on_call("config", "fs\"cfg", Duration::from_secs(30), false);
+
+        let rendered = metrics.render();
+        let bucket = |le: &str| {
+            format!(
+                r#"osquery_extension_plugin_call_duration_seconds_bucket{{registry="config",plugin="fs\"cfg",le="{le}"}}"#
+            )
+        };
+        assert!(
+            rendered.contains(&format!("{} 0\n", bucket("0.01"))),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains(&format!("{} 1\n", bucket("0.025"))),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains(&format!("{} 1\n", bucket("10"))),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains(&format!("{} 2\n", bucket("+Inf"))),
+            "{rendered}"
+        );
+    }
+}
@@ -0,0 +1,476 @@
+//! Thrift protocol selection, and a wrapper that refuses oversized reads.
+//!
+//! The stock binary and compact protocols allocate whatever length a peer
+//! announces before reading the payload, so a single bogus length prefix (for
+//! example `0xFFFFFFFF`) can make the extension attempt a multi-gigabyte
+//! allocation. [`BoundedInputProtocol`] checks every length and container size
+//! against the bytes left in the message first and fails with a `SizeLimit`
+//! protocol error instead. Container sizes are counted in the fewest bytes
+//! their elements could be encoded in, so a list can't announce more elements
+//! than the rest of the message could hold.
+
+use thrift::protocol::{
+    TBinaryInputProtocol, TBinaryInputProtocolFactory, TBinaryOutputProtocolFactory,
+    TCompactInputProtocol, TCompactInputProtocolFactory, TCompactOutputProtocolFactory,
+    TFieldIdentifier, TInputProtocol, TInputProtocolFactory, TListIdentifier, TMapIdentifier,
+    TMessageIdentifier, TMessageType, TOutputProtocolFactory, TSetIdentifier, TStructIdentifier,
+    TType,
+};
+use thrift::transport::TReadTransport;
+use thrift::{ProtocolError, ProtocolErrorKind};
+
+use std::io::Read;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// Thrift protocol spoken over the extension socket.
+///
+/// osquery speaks the binary protocol; `Compact` is for builds configured to
+/// use the compact one. The server's listener
+/// ([`Server::set_protocol`](crate::Server::set_protocol)) and its client
+/// ([`ClientOptions::set_protocol`](crate::ClientOptions::set_protocol)) must
+/// agree with osquery.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Protocol {
+    #[default]
+    Binary,
+    Compact,
+}
+
+impl Protocol {
+    pub(crate) fn input_factory(self) -> Box<dyn TInputProtocolFactory + Send> {
+        match self {
+            Protocol::Binary => Box::new(TBinaryInputProtocolFactory::new()),
+            Protocol::Compact => Box::new(TCompactInputProtocolFactory::new()),
+        }
+    }
+
+    pub(crate) fn output_factory(self) -> Box<dyn TOutputProtocolFactory + Send> {
+        match self {
+            Protocol::Binary => Box::new(TBinaryOutputProtocolFactory::new()),
+            Protocol::Compact => Box::new(TCompactOutputProtocolFactory::new()),
+        }
+    }
+}
+
+/// Default limit, matching the largest response osquery will send.
+pub(crate) const DEFAULT_MAX_FRAME_SIZE: usize = 100 * 1024 * 1024;
+
+const VERSION_MASK: u32 = 0xffff_0000;
+const VERSION_1: u32 = 0x8001_0000;
+
+const COMPACT_PROTOCOL_ID: u8 = 0x82;
+const COMPACT_VERSION: u8 = 0x01;
+const COMPACT_VERSION_MASK: u8 = 0x1f;
+
+/// Transport that counts the bytes read through it.
+struct CountingTransport<T> {
+    inner: T,
+    read: usize,
+}
+
+/// Handle on a [`CountingTransport`] shared by the wrapped protocol and
+/// [`BoundedInputProtocol`], which reads lengths and payloads itself.
+struct SharedTransport<T>(Arc<Mutex<CountingTransport<T>>>);
+
+impl<T> SharedTransport<T> {
+    fn lock(&self) -> MutexGuard<'_, CountingTransport<T>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T> Clone for SharedTransport<T> {
+    fn clone(&self) -> Self {
+        SharedTransport(Arc::clone(&self.0))
+    }
+}
+
+impl<T: Read> Read for SharedTransport<T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut transport = self.lock();
+        let n = transport.inner.read(buf)?;
+        transport.read = transport.read.saturating_add(n);
+        Ok(n)
+    }
+}
+
+/// Read an unsigned varint, as the compact protocol encodes lengths and
+/// sequence numbers.
+fn read_varint(transport: &mut impl Read) -> thrift::Result<u32> {
+    let mut value = 0u32;
+    for shift in (0..32).step_by(7) {
+        let mut buf = [0u8; 1];
+        transport.read_exact(&mut buf)?;
+        let [byte] = buf;
+        value |= u32::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(thrift::Error::Protocol(ProtocolError::new(
+        ProtocolErrorKind::InvalidData,
+        "varint longer than 32 bits",
+    )))
+}
+
+/// The wrapped protocol, reading from the shared transport.
+enum Decoder<T: TReadTransport> {
+    Binary(TBinaryInputProtocol<SharedTransport<T>>),
+    Compact(TCompactInputProtocol<SharedTransport<T>>),
+}
+
+/// Strict input protocol that limits each message to a maximum size.
+pub(crate) struct BoundedInputProtocol<T: TReadTransport> {
+    inner: Decoder<T>,
+    transport: SharedTransport<T>,
+    max_frame_size: usize,
+}
+
+impl<T: TReadTransport> BoundedInputProtocol<T> {
+    pub(crate) fn new(transport: T, protocol: Protocol, max_frame_size: usize) -> Self {
+        let transport = SharedTransport(Arc::new(Mutex::new(CountingTransport {
+            inner: transport,
+            read: 0,
+        })));
+        let inner = match protocol {
+            Protocol::Binary => Decoder::Binary(TBinaryInputProtocol::new(transport.clone(), true)),
+            Protocol::Compact => Decoder::Compact(TCompactInputProtocol::new(transport.clone())),
+        };
+        BoundedInputProtocol {
+            inner,
+            transport,
+            max_frame_size,
+        }
+    }
+
+    fn inner(&mut self) -> &mut dyn TInputProtocol {
+        match &mut self.inner {
+            Decoder::Binary(inner) => inner,
+            Decoder::Compact(inner) => inner,
+        }
+    }
+
+    /// Fewest bytes a value of type `ttype` takes in the wrapped protocol.
+    fn min_encoded_size(&self, ttype: TType) -> usize {
+        if let Decoder::Compact(_) = self.inner {
+            // everything but doubles can be a single byte varint or header
+            return if ttype == TType::Double { 8 } else { 1 };
+        }
+        match ttype {
+            TType::I16 => 2,
+            // strings are a 4 byte length; containers add their element types
+            TType::I32 | TType::String | TType::Utf7 | TType::Utf8 | TType::Utf16 => 4,
+            TType::List | TType::Set => 5,
+            TType::Map => 6,
+            TType::I64 | TType::Double => 8,
+            // a struct is at least its stop byte
+            _ => 1,
+        }
+    }
+
+    fn read_binary_message_begin(&mut self) -> thrift::Result<TMessageIdentifier> {
+        let header = self.inner().read_i32()? as u32;
+        if header & VERSION_MASK != VERSION_1 {
+            return Err(thrift::Error::Protocol(ProtocolError::new(
+                ProtocolErrorKind::BadVersion,
+                format!("received bad version: {header:#010x}"),
+            )));
+        }
+
+        let message_type = TMessageType::try_from((header & 0xff) as u8)?;
+        let name = self.read_string()?;
+        let sequence_number = self.read_i32()?;
+        Ok(TMessageIdentifier::new(name, message_type, sequence_number))
+    }
+
+    fn read_compact_message_begin(&mut self) -> thrift::Result<TMessageIdentifier> {
+        let protocol_id = self.inner().read_byte()?;
+        if protocol_id != COMPACT_PROTOCOL_ID {
+            return Err(thrift::Error::Protocol(ProtocolError::new(
+                ProtocolErrorKind::BadVersion,
+                format!("invalid compact protocol header: {protocol_id:#04x}"),
+            )));
+        }
+        let type_and_version = self.inner().read_byte()?;
+        if type_and_version & COMPACT_VERSION_MASK != COMPACT_VERSION {
+            return Err(thrift::Error::Protocol(ProtocolError::new(
+                ProtocolErrorKind::BadVersion,
+                format!("received bad compact version: {type_and_version:#04x}"),
+            )));
+        }
+
+        // Read here rather than by the wrapped protocol, whose name read is
+        // unbounded; its field ids are back at 0 once the last message's
+        // structs have been read, as resetting them for a new message would do
+        let message_type = TMessageType::try_from(type_and_version >> 5)?;
+        // the sequence number is written as an unsigned varint
+        let sequence_number = read_varint(&mut self.transport)? as i32;
+        let name = self.read_string()?;
+        Ok(TMessageIdentifier::new(name, message_type, sequence_number))
+    }
+
+    /// Validate a length or element count announced by the peer: `size`
+    /// items of at least `item_size` bytes each must fit in what is left of
+    /// the message.
+    fn check_size(&self, size: i32, item_size: usize) -> thrift::Result<usize> {
+        let remaining = self
+            .max_frame_size
+            .saturating_sub(self.transport.lock().read);
+        match usize::try_from(size) {
+            Ok(size) if size.saturating_mul(item_size) <= remaining => Ok(size),
+            _ => Err(thrift::Error::Protocol(ProtocolError::new(
+                ProtocolErrorKind::SizeLimit,
+                format!(
+                    "response frame exceeds max size: {} items of {item_size}+ bytes with {remaining} of {} bytes left",
+                    size as u32, self.max_frame_size
+                ),
+            ))),
+        }
+    }
+}
+
+impl<T: TReadTransport> TInputProtocol for BoundedInputProtocol<T> {
+    fn read_message_begin(&mut self) -> thrift::Result<TMessageIdentifier> {
+        self.transport.lock().read = 0;
+        match self.inner {
+            Decoder::Binary(_) => self.read_binary_message_begin(),
+            Decoder::Compact(_) => self.read_compact_message_begin(),
+        }
+    }
+
+    fn read_message_end(&mut self) -> thrift::Result<()> {
+        self.inner().read_message_end()
+    }
+
+    fn read_struct_begin(&mut self) -> thrift::Result<Option<TStructIdentifier>> {
+        self.inner().read_struct_begin()
+    }
+
+    fn read_struct_end(&mut self) -> thrift::Result<()> {
+        self.inner().read_struct_end()
+    }
+
+    fn read_field_begin(&mut self) -> thrift::Result<TFieldIdentifier> {
+        self.inner().read_field_begin()
+    }
+
+    fn read_field_end(&mut self) -> thrift::Result<()> {
+        self.inner().read_field_end()
+    }
+
+    fn read_bool(&mut self) -> thrift::Result<bool> {
+        self.inner().read_bool()
+    }
+
+    fn read_bytes(&mut self) -> thrift::Result<Vec<u8>> {
+        let size = match self.inner {
+            Decoder::Binary(_) => self.inner().read_i32()?,
+            // an unsigned varint; lengths past i32::MAX turn negative and are rejected
+            Decoder::Compact(_) => read_varint(&mut self.transport)? as i32,
+        };
+        let size = self.check_size(size, 1)?;
+
+        let mut buf = vec![0u8; size];
+        self.transport.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn read_i8(&mut self) -> thrift::Result<i8> {
+        self.inner().read_i8()
+    }
+
+    fn read_i16(&mut self) -> thrift::Result<i16> {
+        self.inner().read_i16()
+    }
+
+    fn read_i32(&mut self) -> thrift::Result<i32> {
+        self.inner().read_i32()
+    }
+
+    fn read_i64(&mut self) -> thrift::Result<i64> {
+        self.inner().read_i64()
+    }
+
+    fn read_double(&mut self) -> thrift::Result<f64> {
+        self.inner().read_double()
+    }
+
+    fn read_string(&mut self) -> thrift::Result<String> {
+        let bytes = self.read_bytes()?;
+        String::from_utf8(bytes).map_err(From::from)
+    }
+
+    fn read_list_begin(&mut self) -> thrift::Result<TListIdentifier> {
+        let ident = self.inner().read_list_begin()?;
+        self.check_size(ident.size, self.min_encoded_size(ident.element_type))?;
+        Ok(ident)
+    }
+
+    fn read_list_end(&mut self) -> thrift::Result<()> {
+        self.inner().read_list_end()
+    }
+
+    fn read_set_begin(&mut self) -> thrift::Result<TSetIdentifier> {
+        let ident = self.inner().read_set_begin()?;
+        self.check_size(ident.size, self.min_encoded_size(ident.element_type))?;
+        Ok(ident)
+    }
+
+    fn read_set_end(&mut self) -> thrift::Result<()> {
+        self.inner().read_set_end()
+    }
+
+    fn read_map_begin(&mut self) -> thrift::Result<TMapIdentifier> {
+        let ident = self.inner().read_map_begin()?;
+        let entry_size = ident
+            .key_type
+            .map_or(1, |ttype| self.min_encoded_size(ttype))
+            + ident
+                .value_type
+                .map_or(1, |ttype| self.min_encoded_size(ttype));
+        self.check_size(ident.size, entry_size)?;
+        Ok(ident)
+    }
+
+    fn read_map_end(&mut self) -> thrift::Result<()> {
+        self.inner().read_map_end()
+    }
+
+    fn read_byte(&mut self) -> thrift::Result<u8> {
+        self.inner().read_byte()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn protocol(bytes: Vec<u8>, max_frame_size: usize) -> BoundedInputProtocol<Cursor<Vec<u8>>> {
+        BoundedInputProtocol::new(Cursor::new(bytes), Protocol::Binary, max_frame_size)
+    }
+
+    fn compact(bytes: Vec<u8>, max_frame_size: usize) -> BoundedInputProtocol<Cursor<Vec<u8>>> {
+        BoundedInputProtocol::new(Cursor::new(bytes), Protocol::Compact, max_frame_size)
+    }
+
+    fn is_size_error(result: thrift::Result<impl Sized>) -> bool {
+        matches!(
+            result,
+            Err(thrift::Error::Protocol(ProtocolError {
+                kind: ProtocolErrorKind::SizeLimit,
+                ..
+            }))
+        )
+    }
+
+    #[test]
+    fn test_reads_string_within_limit() {
+        let mut prot = protocol(vec![0, 0, 0, 2, b'o', b'k'], 16);
+        assert_eq!(prot.read_string().ok().as_deref(), Some("ok"));
+    }
+
+    #[test]
+    fn test_rejects_negative_length() {
+        let mut prot = protocol(vec![0xff, 0xff, 0xff, 0xff], 16);
+        assert!(is_size_error(prot.read_bytes()));
+    }
+
+    #[test]
+    fn test_rejects_length_over_limit() {
+        let mut prot = protocol(vec![0, 0, 0, 17], 16);
+        assert!(is_size_error(prot.read_string()));
+    }
+
+    #[test]
+    fn test_rejects_oversized_list() {
+        // element type STRING (11), 0x7fffffff elements
+        let mut prot = protocol(vec![11, 0x7f, 0xff, 0xff, 0xff], 16);
+        assert!(is_size_error(prot.read_list_begin()));
+    }
+
+    #[test]
+    fn test_rejects_list_larger_than_rest_of_frame() {
+        // element type I64 (10), 3 elements: 24 bytes, with 11 of 16 left
+        let mut prot = protocol(vec![10, 0, 0, 0, 3], 16);
+        assert!(is_size_error(prot.read_list_begin()));
+
+        // element type BYTE (3), 3 elements fit
+        let mut prot = protocol(vec![3, 0, 0, 0, 3], 16);
+        assert!(prot.read_list_begin().is_ok_and(|ident| ident.size == 3));
+    }
+
+    #[test]
+    fn test_rejects_map_larger_than_rest_of_frame() {
+        // STRING -> STRING (11, 11), 2 entries: 16 bytes, with 10 of 16 left
+        let mut prot = protocol(vec![11, 11, 0, 0, 0, 2], 16);
+        assert!(is_size_error(prot.read_map_begin()));
+    }
+
+    #[test]
+    fn test_limit_counts_bytes_already_read() {
+        // two 6 byte strings fit the limit one at a time but not together
+        let mut bytes = vec![0, 0, 0, 6];
+        bytes.extend_from_slice(b"abcdef");
+        bytes.extend_from_slice(&[0, 0, 0, 6]);
+        bytes.extend_from_slice(b"ghijkl");
+        let mut prot = protocol(bytes, 16);
+
+        assert_eq!(prot.read_string().ok().as_deref(), Some("abcdef"));
+        assert!(is_size_error(prot.read_string()));
+    }
+
+    #[test]
+    fn test_reads_message_header() {
+        let mut bytes = vec![0x80, 0x01, 0x00, 0x02, 0, 0, 0, 4];
+        bytes.extend_from_slice(b"ping");
+        bytes.extend_from_slice(&[0, 0, 0, 1]);
+        let mut prot = protocol(bytes, 16);
+
+        let ident = prot.read_message_begin();
+        assert!(ident.is_ok_and(|ident| ident.name == "ping"
+            && ident.message_type == TMessageType::Reply
+            && ident.sequence_number == 1));
+    }
+
+    #[test]
+    fn test_compact_reads_string_within_limit() {
+        let mut prot = compact(vec![2, b'o', b'k'], 16);
+        assert_eq!(prot.read_string().ok().as_deref(), Some("ok"));
+    }
+
+    #[test]
+    fn test_compact_rejects_length_over_limit() {
+        let mut prot = compact(vec![17], 16);
+        assert!(is_size_error(prot.read_string()));
+
+        // 0xFFFFFFFF as a varint
+        let mut prot = compact(vec![0xff, 0xff, 0xff, 0xff, 0x0f], 16);
+        assert!(is_size_error(prot.read_bytes()));
+    }
+
+    #[test]
+    fn test_compact_rejects_oversized_list() {
+        // element count follows the header, element type BINARY (8), 0x7fffffff elements
+        let mut prot = compact(vec![0xf8, 0xff, 0xff, 0xff, 0xff, 0x07], 16);
+        assert!(is_size_error(prot.read_list_begin()));
+    }
+
+    #[test]
+    fn test_compact_reads_message_header() {
+        // protocol id, reply (2) in the top bits of the version, sequence number 1
+        let mut bytes = vec![0x82, 0x41, 0x01, 4];
+        bytes.extend_from_slice(b"ping");
+        let mut prot = compact(bytes, 16);
+
+        let ident = prot.read_message_begin();
+        assert!(ident.is_ok_and(|ident| ident.name == "ping"
+            && ident.message_type == TMessageType::Reply
+            && ident.sequence_number == 1));
+    }
+
+    #[test]
+    fn test_compact_rejects_oversized_message_name() {
+        let mut prot = compact(vec![0x82, 0x41, 0x01, 0xff, 0xff, 0xff, 0xff, 0x0f], 16);
+        assert!(is_size_error(prot.read_message_begin()));
+    }
+}
@@ -0,0 +1,232 @@
+//! Helpers for reading osquery's plugin request map.
+
+use crate::ExtensionPluginRequest;
+use serde_json::Value;
+
+/// Convenience accessors on [`ExtensionPluginRequest`].
+///
+/// osquery sends table queries with a `context` key holding a JSON document
+/// that describes the query: the constraints from the `WHERE` clause, the
+/// columns actually referenced (`colsUsed`), and so on.
+pub trait ExtensionPluginRequestExt {
+    /// The parsed query context, if the request carries a valid one.
+    fn context(&self) -> Option<Value>;
+
+    /// Names of the columns the query uses.
+    ///
+    /// Tables may skip computing columns that are not listed here. Empty when
+    /// osquery did not send `colsUsed`.
+    fn columns_used(&self) -> Vec<String>;
+}
+
+/// Typed access to the fields osquery sends in a plugin request.
+///
+/// Keeps the protocol's key names in one place instead of spreading
+/// `request.get("...")` lookups across plugins.
+#[derive(Debug, Clone, Copy)]
+pub struct PluginRequest<'a>(&'a ExtensionPluginRequest);
+
+impl<'a> PluginRequest<'a> {
+    pub fn new(request: &'a ExtensionPluginRequest) -> Self {
+        PluginRequest(request)
+    }
+
+    /// What osquery asks the plugin to do, e.g. `"generate"` or `"genConfig"`.
+    ///
+    /// Empty when the request has no action.
+    pub fn action(&self) -> &'a str {
+        self.0.get("action").map(String::as_str).unwrap_or("")
+    }
+
+    /// The rowid an `UPDATE` or `DELETE` targets, or the rowid an `INSERT`
+    /// names explicitly.
+    pub fn id(&self) -> Option<&'a str> {
+        self.0.get("id").map(String::as_str)
+    }
+
+    /// Whether an `INSERT` left the rowid to the table.
+    pub fn auto_rowid(&self) -> Option<bool> {
+        self.0
+            .get("auto_rowid")
+            .map(|auto_rowid| auto_rowid == "true")
+    }
+
+    /// The values of an `INSERT` or `UPDATE`, one per column in order.
+    ///
+    /// `None` when the request carries no values, `Some(Err(_))` when they are
+    /// not valid JSON.
+    pub fn json_value_array(&self) -> Option<Result<Value, serde_json::Error>> {
+        self.0
+            .get("json_value_array")
+            .map(|values| serde_json::from_str(values))
+    }
+
+    /// The pack name of a config plugin's `genPack` request.
+    pub fn name(&self) -> Option<&'a str> {
+        self.0.get("name").map(String::as_str)
+    }
+
+    /// The pack value (usually a path or URL) of a `genPack` request.
+    pub fn value(&self) -> Option<&'a str> {
+        self.0.get("value").map(String::as_str)
+    }
+
+    /// The pack names of a bulk `genPack` request, sent as a JSON array.
+    ///
+    /// `None` for single-pack requests, `Some(Err(_))` when the names are not
+    /// a JSON array of strings.
+    pub fn names(&self) -> Option<Result<Vec<String>, serde_json::Error>> {
+        self.0.get("names").map(|names| serde_json::from_str(names))
+    }
+}
+
+impl<'a> From<&'a ExtensionPluginRequest> for PluginRequest<'a> {
+    fn from(request: &'a ExtensionPluginRequest) -> Self {
+        PluginRequest::new(request)
+    }
+}
+
+impl ExtensionPluginRequestExt for ExtensionPluginRequest {
+    fn context(&self) -> Option<Value> {
+        self.get("context")
+            .and_then(|context| serde_json::from_str(context).ok())
+    }
+
+    fn columns_used(&self) -> Vec<String> {
+        self.context()
+            .as_ref()
+            .and_then(|context| context.get("colsUsed"))
+            .and_then(|cols| cols.as_array())
+            .map(|cols| {
+                cols.iter()
+                    .filter_map(|col| col.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)] // Tests are allowed to panic on setup failures
+mod tests {
+    use super::*;
+
+    fn request_with_context(context: &str) -> ExtensionPluginRequest {
+        let mut req = ExtensionPluginRequest::new();
+        req.insert("action".to_string(), "generate".to_string());
+        req.insert("context".to_string(), context.to_string());
+        req
+    }
+
+    #[test]
+    fn test_columns_used_from_realistic_context() {
+        let req = request_with_context(
+            r#"{"colsUsed":["path","size"],"colsUsedBitset":5,"constraints":[{"name":"path","list":[{"op":2,"expr":"/etc/hosts"}],"affinity":"TEXT"},{"name":"size","list":[],"affinity":"BIGINT"}]}"#,
+        );
+
+        assert_eq!(req.columns_used(), vec!["path", "size"]);
+
+        let context = req.context();
+        assert_eq!(
+            context
+                .as_ref()
+                .and_then(|c| c.get("colsUsedBitset"))
+                .and_then(|b| b.as_i64()),
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn test_missing_or_invalid_context() {
+        let req = ExtensionPluginRequest::new();
+        assert!(req.context().is_none());
+        assert!(req.columns_used().is_empty());
+
+        let req = request_with_context("not json");
+        assert!(req.context().is_none());
+        assert!(req.columns_used().is_empty());
+    }
+
+    fn request(fields: &[(&str, &str)]) -> ExtensionPluginRequest {
+        fields
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_plugin_request_action() {
+        let req = request(&[("action", "generate")]);
+        assert_eq!(PluginRequest::new(&req).action(), "generate");
+
+        let req = request(&[]);
+        assert_eq!(PluginRequest::new(&req).action(), "");
+    }
+
+    #[test]
+    fn test_plugin_request_id_and_auto_rowid() {
+        let req = request(&[("id", "42"), ("auto_rowid", "false")]);
+        let req = PluginRequest::from(&req);
+        assert_eq!(req.id(), Some("42"));
+        assert_eq!(req.auto_rowid(), Some(false));
+
+        let req = request(&[("auto_rowid", "true")]);
+        let req = PluginRequest::from(&req);
+        assert_eq!(req.id(), None);
+        assert_eq!(req.auto_rowid(), Some(true));
+
+        let req = request(&[]);
+        assert_eq!(PluginRequest::new(&req).auto_rowid(), None);
+    }
+
+    #[test]
+    fn test_plugin_request_json_value_array() {
+        let req = request(&[("json_value_array", r#"[1,"lol"]"#)]);
+        let values = PluginRequest::new(&req)
+            .json_value_array()
+            .expect("values should be present")
+            .expect("values should parse");
+        assert_eq!(values, serde_json::json!([1, "lol"]));
+
+        let req = request(&[("json_value_array", "[1,")]);
+        assert!(matches!(
+            PluginRequest::new(&req).json_value_array(),
+            Some(Err(_))
+        ));
+
+        let req = request(&[]);
+        assert!(PluginRequest::new(&req).json_value_array().is_none());
+    }
+
+    #[test]
+    fn test_plugin_request_name_and_value() {
+        let req = request(&[
+            ("name", "incident"),
+            ("value", "/etc/osquery/packs/ir.conf"),
+        ]);
+        let req = PluginRequest::new(&req);
+        assert_eq!(req.name(), Some("incident"));
+        assert_eq!(req.value(), Some("/etc/osquery/packs/ir.conf"));
+
+        let req = request(&[]);
+        let req = PluginRequest::new(&req);
+        assert_eq!(req.name(), None);
+        assert_eq!(req.value(), None);
+    }
+
+    #[test]
+    fn test_plugin_request_names() {
+        let req = request(&[("names", r#"["incident","base"]"#)]);
+        let names = PluginRequest::new(&req)
+            .names()
+            .expect("names should be present")
+            .expect("names should parse");
+        assert_eq!(names, vec!["incident", "base"]);
+
+        let req = request(&[("names", "incident")]);
+        assert!(matches!(PluginRequest::new(&req).names(), Some(Err(_))));
+
+        let req = request(&[("name", "incident")]);
+        assert!(PluginRequest::new(&req).names().is_none());
+    }
+}
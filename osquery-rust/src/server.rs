@@ -1,20 +1,180 @@
 use clap::crate_name;
-use std::collections::HashMap;
-use std::io::Error;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use strum::VariantNames;
-use thrift::protocol::*;
-use thrift::transport::*;
+use strum_macros::Display;
 
 use crate::_osquery as osquery;
 use crate::client::{OsqueryClient, ThriftClient};
-use crate::plugin::{OsqueryPlugin, Registry};
+use crate::clock::{Clock, SystemClock};
+use crate::error::ServerError;
+use crate::internal_table::{InternalTable, ServerStats};
+use crate::local_query;
+use crate::log_target;
+use crate::metrics::{MetricsSink, NoopMetricsSink};
+use crate::plugin::{
+    CancellationToken, ColumnDef, ColumnOptions, ColumnType, ExtensionResponseEnum, OsqueryPlugin,
+    Plugin, Registry,
+};
+use crate::protocol::Protocol;
+use crate::transport::{self, BufferSizes};
 use crate::util::OptionToThriftResult;
 
+/// Plugins keyed by registry name, then plugin name
+type PluginMap<P> = HashMap<String, HashMap<String, P>>;
+/// Builds a plugin from the extension name and the server's clock
+type PluginFactory<P> = Box<dyn Fn(&str, &Arc<dyn Clock>) -> P + Send>;
+
 const DEFAULT_PING_INTERVAL: Duration = Duration::from_millis(500);
+/// Threads serving osquery's calls unless overridden with `set_worker_threads()`
+const DEFAULT_WORKER_THREADS: usize = 10;
+/// Version reported for the extension unless overridden with `set_version()`:
+/// unknown, as only the extension's own crate knows its version
+const DEFAULT_VERSION: &str = "";
+/// osquery SDK version the Thrift interface is built and tested against
+const DEFAULT_SDK_VERSION: &str = "5.20.0";
+/// Oldest osquery SDK version the extension is expected to work with
+const DEFAULT_MIN_SDK_VERSION: &str = "5.0.0";
+
+/// Environment variable checked first by [`Server::discover_socket`]
+const SOCKET_ENV_VAR: &str = "OSQUERY_SOCKET";
+/// How long to wait for the listener to bind its socket before giving up on
+/// applying `socket_mode`
+const SOCKET_MODE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// What [`Server::poll_once`] found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PollOutcome {
+    /// osquery answered the ping; poll again after the ping interval.
+    Continue,
+    /// Shutdown was requested; call [`Server::finish`].
+    Shutdown,
+}
+
+/// Why the server stopped, as reported by [`Server::last_shutdown_reason`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownReason {
+    /// `stop()` was called on the server or a [`ServerStopHandle`], or the
+    /// host called [`Server::finish`] without another cause.
+    Stopped,
+    /// osquery sent the shutdown RPC.
+    OsqueryRequested,
+    /// A ping to osquery failed, so osquery has most likely gone away.
+    PingFailed,
+    /// SIGINT or SIGTERM (a console control event on Windows) arrived while
+    /// running under `run_with_signal_handling()`.
+    Signal,
+}
+
+/// Set in a recorded [`ShutdownReason`] when a stop was requested after it.
+const STOP_REQUESTED: u8 = 0x80;
+
+impl ShutdownReason {
+    fn to_u8(self) -> u8 {
+        match self {
+            ShutdownReason::Stopped => 1,
+            ShutdownReason::OsqueryRequested => 2,
+            ShutdownReason::PingFailed => 3,
+            ShutdownReason::Signal => 4,
+        }
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        match value & !STOP_REQUESTED {
+            1 => Some(ShutdownReason::Stopped),
+            2 => Some(ShutdownReason::OsqueryRequested),
+            3 => Some(ShutdownReason::PingFailed),
+            4 => Some(ShutdownReason::Signal),
+            _ => None,
+        }
+    }
+
+    /// Store `self` in `cell` unless an earlier cause is already there.
+    fn record(self, cell: &AtomicU8) {
+        let _ = cell.compare_exchange(0, self.to_u8(), Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    /// Like `record()`, but mark an earlier cause as followed by a request
+    /// to stop, so a stop arriving while `run_with_restart()` is about to
+    /// register again isn't lost.
+    fn record_request(self, cell: &AtomicU8) {
+        let _ = cell.fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+            Some(match current {
+                0 => self.to_u8(),
+                earlier => earlier | STOP_REQUESTED,
+            })
+        });
+    }
+}
+
+/// How [`Server::run_with_restart`] retries after losing osquery.
+///
+/// The wait before the first restart is `initial_backoff`, doubling for each
+/// further attempt up to `max_backoff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// Give up after this many restart attempts in total
+    pub max_restarts: u32,
+    /// Wait before the first restart attempt
+    pub initial_backoff: Duration,
+    /// Longest wait between attempts
+    pub max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy {
+            max_restarts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// The wait before restart attempt `attempt`, counting from 1.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(1).min(31);
+        self.initial_backoff
+            .saturating_mul(1 << doublings)
+            .min(self.max_backoff)
+    }
+}
+
+/// A plugin left out when registering with osquery, see
+/// [`Server::allow_partial_registration`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginFailure {
+    /// The plugin's registry, e.g. `"table"`
+    pub registry: String,
+    /// The plugin's name
+    pub name: String,
+    /// Why it couldn't be registered
+    pub reason: String,
+}
+
+/// The type of an option the extension contributes to osquery, displayed
+/// as osquery's flag type names (`"bool"`, `"int32"`, ...).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum OptionType {
+    Bool,
+    Int32,
+    Int64,
+    UInt64,
+    Double,
+    String,
+}
+
+/// An option the extension contributes to osquery, see [`Server::add_option`].
+struct ExtensionOption {
+    option_type: OptionType,
+    default: String,
+    description: String,
+}
 
 /// Handle that allows stopping the server from another thread.
 ///
@@ -43,6 +203,8 @@ const DEFAULT_PING_INTERVAL: Duration = Duration::from_millis(500);
 #[derive(Clone)]
 pub struct ServerStopHandle {
     shutdown_flag: Arc<AtomicBool>,
+    shutdown_reason: Arc<AtomicU8>,
+    registered_uuid: Arc<Mutex<Option<u16>>>,
 }
 
 impl ServerStopHandle {
@@ -51,6 +213,7 @@ impl ServerStopHandle {
     /// This method is idempotent - multiple calls are safe.
     /// The server will exit its run loop on the next iteration.
     pub fn stop(&self) {
+        ShutdownReason::Stopped.record_request(&self.shutdown_reason);
         self.shutdown_flag.store(true, Ordering::Release);
     }
 
@@ -61,6 +224,13 @@ impl ServerStopHandle {
     pub fn is_running(&self) -> bool {
         !self.shutdown_flag.load(Ordering::Acquire)
     }
+
+    /// Get the UUID osquery assigned to the extension.
+    ///
+    /// Returns `None` until the server has registered with osquery.
+    pub fn uuid(&self) -> Option<u16> {
+        self.registered_uuid.lock().ok().and_then(|uuid| *uuid)
+    }
 }
 
 pub struct Server<P: OsqueryPlugin + Clone + Send + Sync + 'static, C: OsqueryClient = ThriftClient>
@@ -69,15 +239,231 @@ pub struct Server<P: OsqueryPlugin + Clone + Send + Sync + 'static, C: OsqueryCl
     socket_path: String,
     client: C,
     plugins: Vec<P>,
+    /// Routes of `plugins` as sent to osquery, described once by `start()`
+    registry: osquery::ExtensionRegistry,
+    /// Plugins the listener dispatches to, shared with it once started
+    dispatch: Option<Arc<PluginMap<P>>>,
     ping_interval: Duration,
+    worker_threads: usize,
+    /// Thrift protocol the listener speaks
+    protocol: Protocol,
+    /// Capacity of the listener's read and write buffers
+    buffer_sizes: BufferSizes,
+    /// Permission bits applied to the listener socket once it is created
+    socket_mode: Option<u32>,
+    /// Plugin calls running longer than this are answered with an error
+    call_timeout: Option<Duration>,
     uuid: Option<osquery::ExtensionRouteUUID>,
+    /// UUID shared with stop handles so other threads can read it after registration
+    registered_uuid: Arc<Mutex<Option<u16>>>,
     // Used to ensure tests wait until the server is actually started
     started: bool,
+    /// Set once deregistration and socket cleanup have run, so `Drop` doesn't
+    /// repeat them after `run()`
+    cleaned_up: AtomicBool,
     shutdown_flag: Arc<AtomicBool>,
+    /// First cause of shutdown, see [`ShutdownReason`]; 0 until one is recorded
+    shutdown_reason: Arc<AtomicU8>,
+    /// Set by the signal handlers alongside `shutdown_flag`, which can't
+    /// record a reason themselves
+    signal_received: Arc<AtomicBool>,
     /// Handle to the listener thread for graceful shutdown
     listener_thread: Option<thread::JoinHandle<()>>,
     /// Path to the listener socket for wake-up connection on shutdown
     listen_path: Option<String>,
+    /// Server-level callback run once during shutdown, before deregistration
+    shutdown_hook: Option<Box<dyn FnOnce() + Send>>,
+    /// Callback run inside osquery's shutdown RPC, before it is acknowledged
+    shutdown_request_hook: Option<Arc<dyn Fn() + Send + Sync>>,
+    /// How long shutdown cleanup may take before it moves on without the
+    /// steps still running
+    shutdown_deadline: Option<Duration>,
+    /// Options contributed to osquery, by name, see `add_option()`
+    options: BTreeMap<String, ExtensionOption>,
+    /// Register the valid plugins when some fail instead of refusing to start
+    allow_partial_registration: bool,
+    /// Plugins left out by `start()` with partial registration allowed
+    registration_failures: Vec<PluginFailure>,
+    /// User callback run after every successful ping
+    tick_hook: Option<Box<dyn FnMut() + Send>>,
+    /// Time of the last call from osquery, in milliseconds since the Unix epoch
+    last_call: Arc<AtomicU64>,
+    /// Where uptime and call times are read from
+    clock: Arc<dyn Clock>,
+    /// How long osquery may go without calling a plugin before we report it
+    idle_timeout: Option<Duration>,
+    /// User callback run when the extension becomes idle
+    idle_hook: Option<Box<dyn FnMut() + Send>>,
+    /// Whether the current idle period has been reported already
+    idle_reported: bool,
+    /// Receives the latency and outcome of every plugin call
+    metrics: Arc<dyn MetricsSink>,
+    /// Uptime and ping counters reported by the internal table
+    stats: Arc<ServerStats>,
+    /// Builds the `<name>_internal` table from the extension name, if enabled
+    internal_table: Option<PluginFactory<P>>,
+    /// Builds the `<name>_build_info` table from the extension name, if set
+    build_info_table: Option<PluginFactory<P>>,
+    /// Address of the development HTTP endpoint, if enabled
+    #[cfg(feature = "debug-http")]
+    debug_http_addr: Option<String>,
+    #[cfg(feature = "debug-http")]
+    debug_http_thread: Option<thread::JoinHandle<()>>,
+    /// Versions reported to osquery, visible in the `osquery_extensions` table
+    version: String,
+    sdk_version: String,
+    min_sdk_version: String,
+}
+
+impl Server<Plugin, ThriftClient> {
+    /// Find osquery's extension socket without being told where it is.
+    ///
+    /// Checks, in order, the path in the `OSQUERY_SOCKET` environment
+    /// variable, then where osqueryd and osqueryi put it by default:
+    /// `/var/osquery/osquery.em`, `/tmp/osquery.em` and
+    /// `$HOME/.osquery/shell.em` (`\\.\pipe\osquery.em` on Windows). Returns
+    /// the first that exists, or `None`.
+    ///
+    /// ```no_run
+    /// use osquery_rust_ng::plugin::Plugin;
+    /// use osquery_rust_ng::Server;
+    ///
+    /// let socket = Server::discover_socket().ok_or("osquery socket not found")?;
+    /// let server: Server<Plugin> = Server::new(None, &socket)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn discover_socket() -> Option<String> {
+        let env_path = std::env::var(SOCKET_ENV_VAR).ok();
+        first_existing(env_path.into_iter().chain(default_socket_paths()))
+    }
+}
+
+/// Where osquery creates its extension socket unless told otherwise.
+fn default_socket_paths() -> Vec<String> {
+    if cfg!(windows) {
+        return vec![r"\\.\pipe\osquery.em".to_string()];
+    }
+
+    let mut paths = vec![
+        "/var/osquery/osquery.em".to_string(),
+        "/tmp/osquery.em".to_string(),
+    ];
+    if let Ok(home) = std::env::var("HOME") {
+        paths.push(format!("{home}/.osquery/shell.em"));
+    }
+    paths
+}
+
+/// The first of `paths` that exists.
+fn first_existing(paths: impl IntoIterator<Item = String>) -> Option<String> {
+    paths
+        .into_iter()
+        .find(|path| std::path::Path::new(path).exists())
+}
+
+/// Setters only available when the server hosts the crate's own [`Plugin`].
+impl<C: OsqueryClient> Server<Plugin, C> {
+    /// Also register a read-only `<extension name>_internal` table reporting
+    /// the server's own `uptime_seconds`, `ping_count` and `last_call_time`
+    /// (Unix time, 0 before the first call), so operators can check the
+    /// extension's health with SQL.
+    ///
+    /// The table is added when the server starts and doesn't count as a
+    /// plugin for [`ServerError::NoPlugins`]. Off by default.
+    pub fn set_internal_table(&mut self, enabled: bool) -> &mut Self {
+        self.internal_table = enabled.then(|| {
+            let stats = self.stats.clone();
+            let last_call = self.last_call.clone();
+            Box::new(move |name: &str, clock: &Arc<dyn Clock>| {
+                Plugin::readonly_table(InternalTable::new(
+                    name,
+                    stats.clone(),
+                    last_call.clone(),
+                    clock.clone(),
+                ))
+            }) as PluginFactory<Plugin>
+        });
+        self
+    }
+
+    /// Also register a read-only `<extension name>_build_info` table with a
+    /// `key`/`value` row per entry of `info`, such as the git SHA and build
+    /// time, so fleet tooling can see which build is deployed:
+    /// `SELECT value FROM my_ext_build_info WHERE key = 'git_sha'`.
+    ///
+    /// osquery's registration only carries the extension's name and version,
+    /// so anything else has to be published as a table. Like the internal
+    /// table it is added when the server starts and doesn't count as a
+    /// plugin for [`ServerError::NoPlugins`]. Calling this again replaces the
+    /// info.
+    pub fn set_build_info<K, V>(&mut self, info: impl IntoIterator<Item = (K, V)>) -> &mut Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let rows: Vec<BTreeMap<String, String>> = info
+            .into_iter()
+            .map(|(key, value)| {
+                BTreeMap::from([
+                    ("key".to_string(), key.into()),
+                    ("value".to_string(), value.into()),
+                ])
+            })
+            .collect();
+        self.build_info_table = Some(Box::new(move |name: &str, _clock: &Arc<dyn Clock>| {
+            let rows = rows.clone();
+            Plugin::static_table(
+                &format!("{name}_build_info"),
+                vec![
+                    ColumnDef::new("key", ColumnType::Text, ColumnOptions::DEFAULT)
+                        .with_description("Name of the build property"),
+                    ColumnDef::new("value", ColumnType::Text, ColumnOptions::DEFAULT)
+                        .with_description("Value of the build property"),
+                ],
+                move || rows.clone(),
+            )
+        }));
+        self
+    }
+}
+
+/// Handler run on a console control event: requests shutdown of the server
+/// owning `shutdown_flag`, noting in `signal_received` that a signal did it.
+#[cfg(windows)]
+fn console_handler(
+    shutdown_flag: Arc<AtomicBool>,
+    signal_received: Arc<AtomicBool>,
+) -> impl FnMut() + Send + 'static {
+    move || {
+        signal_received.store(true, Ordering::Release);
+        shutdown_flag.store(true, Ordering::Release);
+    }
+}
+
+/// Deregisters a server that was started but never shut down (e.g. dropped
+/// without calling `run()`), so osquery doesn't keep routing to it and the
+/// socket file doesn't linger. Its listener and debug HTTP threads are
+/// stopped first, as `shutdown_and_cleanup()` does. Best-effort: errors are
+/// only logged.
+impl<P: OsqueryPlugin + Clone + Send + Sync + 'static, C: OsqueryClient> Drop for Server<P, C> {
+    fn drop(&mut self) {
+        if !self.started || self.cleaned_up.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let Some(uuid) = self.uuid else {
+            return;
+        };
+
+        log::debug!(target: log_target::SERVER, "Server dropped while registered, deregistering");
+        self.request_shutdown(ShutdownReason::Stopped);
+        self.wake_listener();
+        self.join_listener_thread();
+        self.join_debug_http();
+        if let Err(e) = self.client.deregister_extension(uuid) {
+            log::warn!(target: log_target::SERVER, "Failed to deregister from osquery: {e}");
+        }
+        self.cleanup_socket();
+    }
 }
 
 /// Implementation for `Server` using the default `ThriftClient`.
@@ -85,13 +471,18 @@ impl<P: OsqueryPlugin + Clone + Send + 'static> Server<P, ThriftClient> {
     /// Create a new server that connects to osquery at the given socket path.
     ///
     /// # Arguments
-    /// * `name` - Optional extension name (defaults to crate name)
+    /// * `name` - Optional extension name (defaults to crate name). This is the
+    ///   name of the extension itself, not of its plugins; each plugin is
+    ///   registered under its own `name()`.
     /// * `socket_path` - Path to osquery's extension socket
     ///
     /// # Errors
-    /// Returns an error if the connection to osquery fails.
-    pub fn new(name: Option<&str>, socket_path: &str) -> Result<Self, Error> {
+    /// Returns [`ServerError::Connect`] if the connection to osquery fails. Its
+    /// kind is `InvalidInput` when `socket_path` is too long for a Unix socket
+    /// once the extension's `.<uuid>` suffix is added.
+    pub fn new(name: Option<&str>, socket_path: &str) -> Result<Self, ServerError> {
         let name = name.unwrap_or(crate_name!());
+        transport::check_listen_path_len(socket_path)?;
         let client = ThriftClient::new(socket_path, Default::default())?;
 
         Ok(Server {
@@ -99,24 +490,79 @@ impl<P: OsqueryPlugin + Clone + Send + 'static> Server<P, ThriftClient> {
             socket_path: socket_path.to_string(),
             client,
             plugins: Vec::new(),
+            registry: osquery::ExtensionRegistry::new(),
+            dispatch: None,
             ping_interval: DEFAULT_PING_INTERVAL,
+            worker_threads: DEFAULT_WORKER_THREADS,
+            protocol: Protocol::Binary,
+            buffer_sizes: BufferSizes::default(),
+            socket_mode: None,
+            call_timeout: None,
             uuid: None,
+            registered_uuid: Arc::new(Mutex::new(None)),
             started: false,
+            cleaned_up: AtomicBool::new(false),
             shutdown_flag: Arc::new(AtomicBool::new(false)),
+            shutdown_reason: Arc::new(AtomicU8::new(0)),
+            signal_received: Arc::new(AtomicBool::new(false)),
             listener_thread: None,
             listen_path: None,
+            shutdown_hook: None,
+            shutdown_request_hook: None,
+            shutdown_deadline: None,
+            options: BTreeMap::new(),
+            allow_partial_registration: false,
+            registration_failures: Vec::new(),
+            tick_hook: None,
+            last_call: Arc::new(AtomicU64::new(0)),
+            clock: Arc::new(SystemClock),
+            idle_timeout: None,
+            idle_hook: None,
+            idle_reported: false,
+            metrics: Arc::new(NoopMetricsSink),
+            stats: Arc::new(ServerStats::default()),
+            internal_table: None,
+            build_info_table: None,
+            #[cfg(feature = "debug-http")]
+            debug_http_addr: None,
+            #[cfg(feature = "debug-http")]
+            debug_http_thread: None,
+            version: DEFAULT_VERSION.to_string(),
+            sdk_version: DEFAULT_SDK_VERSION.to_string(),
+            min_sdk_version: DEFAULT_MIN_SDK_VERSION.to_string(),
         })
     }
 }
 
+/// Implementation for `Server` with a type-erased client.
+impl<P: OsqueryPlugin + Clone + Send + 'static> Server<P, Box<dyn OsqueryClient>> {
+    /// Create a server with a boxed client.
+    ///
+    /// Like `with_client()`, but the server's type is `Server<P, Box<dyn
+    /// OsqueryClient>>` whatever client is passed in, so a custom client (e.g.
+    /// a logging decorator over [`ThriftClient`]) doesn't have to be named in
+    /// every type annotation.
+    pub fn with_boxed_client(
+        name: Option<&str>,
+        socket_path: &str,
+        client: Box<dyn OsqueryClient>,
+    ) -> Self {
+        Self::with_client(name, socket_path, client)
+    }
+}
+
 /// Implementation for `Server` with any client type (generic over `C: OsqueryClient`).
 impl<P: OsqueryPlugin + Clone + Send + 'static, C: OsqueryClient> Server<P, C> {
     /// Create a server with a pre-constructed client.
     ///
-    /// This constructor is useful for testing, allowing injection of mock clients.
+    /// Use this to inject mock clients in tests, or a custom client such as a
+    /// decorator over [`ThriftClient`]. The client type becomes part of the
+    /// server's type; see `with_boxed_client()` to avoid that.
     ///
     /// # Arguments
-    /// * `name` - Optional extension name (defaults to crate name)
+    /// * `name` - Optional extension name (defaults to crate name). This is the
+    ///   name of the extension itself, not of its plugins; each plugin is
+    ///   registered under its own `name()`.
     /// * `socket_path` - Path to osquery's extension socket (used for listener socket naming)
     /// * `client` - Pre-constructed client implementing `OsqueryClient`
     pub fn with_client(name: Option<&str>, socket_path: &str, client: C) -> Self {
@@ -126,12 +572,46 @@ impl<P: OsqueryPlugin + Clone + Send + 'static, C: OsqueryClient> Server<P, C> {
             socket_path: socket_path.to_string(),
             client,
             plugins: Vec::new(),
+            registry: osquery::ExtensionRegistry::new(),
+            dispatch: None,
             ping_interval: DEFAULT_PING_INTERVAL,
+            worker_threads: DEFAULT_WORKER_THREADS,
+            protocol: Protocol::Binary,
+            buffer_sizes: BufferSizes::default(),
+            socket_mode: None,
+            call_timeout: None,
             uuid: None,
+            registered_uuid: Arc::new(Mutex::new(None)),
             started: false,
+            cleaned_up: AtomicBool::new(false),
             shutdown_flag: Arc::new(AtomicBool::new(false)),
+            shutdown_reason: Arc::new(AtomicU8::new(0)),
+            signal_received: Arc::new(AtomicBool::new(false)),
             listener_thread: None,
             listen_path: None,
+            shutdown_hook: None,
+            shutdown_request_hook: None,
+            shutdown_deadline: None,
+            options: BTreeMap::new(),
+            allow_partial_registration: false,
+            registration_failures: Vec::new(),
+            tick_hook: None,
+            last_call: Arc::new(AtomicU64::new(0)),
+            clock: Arc::new(SystemClock),
+            idle_timeout: None,
+            idle_hook: None,
+            idle_reported: false,
+            metrics: Arc::new(NoopMetricsSink),
+            stats: Arc::new(ServerStats::default()),
+            internal_table: None,
+            build_info_table: None,
+            #[cfg(feature = "debug-http")]
+            debug_http_addr: None,
+            #[cfg(feature = "debug-http")]
+            debug_http_thread: None,
+            version: DEFAULT_VERSION.to_string(),
+            sdk_version: DEFAULT_SDK_VERSION.to_string(),
+            min_sdk_version: DEFAULT_MIN_SDK_VERSION.to_string(),
         }
     }
 
@@ -139,8 +619,312 @@ impl<P: OsqueryPlugin + Clone + Send + 'static, C: OsqueryClient> Server<P, C> {
     /// Registers a plugin, something which implements the OsqueryPlugin trait.
     /// Consumes the plugin.
     ///
-    pub fn register_plugin(&mut self, plugin: P) -> &Self {
+    /// Fails if another plugin with the same name is already registered in
+    /// the same registry (e.g. two tables named "users"), or with
+    /// [`ServerError::AlreadyStarted`] once the server has started: osquery
+    /// only learns about plugins when the extension registers.
+    ///
+    pub fn register_plugin(&mut self, plugin: P) -> Result<&mut Self, ServerError> {
+        if self.started {
+            return Err(ServerError::AlreadyStarted);
+        }
+
+        let registry = plugin.registry();
+        let name = plugin.name();
+        if self
+            .plugins
+            .iter()
+            .any(|p| p.registry() == registry && p.name() == name)
+        {
+            return Err(ServerError::DuplicatePlugin {
+                registry: registry.to_string(),
+                name,
+            });
+        }
+
         self.plugins.push(plugin);
+        Ok(self)
+    }
+
+    ///
+    /// Same as `register_plugin()`, which reports failures itself now.
+    ///
+    #[deprecated(note = "use `register_plugin()`, which returns the same errors")]
+    pub fn try_register_plugin(&mut self, plugin: P) -> Result<&mut Self, ServerError> {
+        self.register_plugin(plugin)
+    }
+
+    ///
+    /// Registers several plugins at once, in iteration order.
+    /// Consumes the plugins, stopping at the first that fails to register.
+    ///
+    pub fn register_plugins<I: IntoIterator<Item = P>>(
+        &mut self,
+        plugins: I,
+    ) -> Result<&mut Self, ServerError> {
+        for plugin in plugins {
+            self.register_plugin(plugin)?;
+        }
+        Ok(self)
+    }
+
+    /// Register a callback to run when the server shuts down.
+    ///
+    /// The callback runs once, after the listener thread has stopped but before
+    /// the extension deregisters from osquery and before plugins are notified.
+    /// Use it to flush state shared between plugins (metrics, connection pools).
+    /// Registering a new callback replaces the previous one.
+    pub fn on_shutdown<F: FnOnce() + Send + 'static>(&mut self, f: F) -> &mut Self {
+        self.shutdown_hook = Some(Box::new(f));
+        self
+    }
+
+    /// Register a callback run when osquery asks the extension to shut down.
+    ///
+    /// Unlike `on_shutdown()`, this runs synchronously inside osquery's
+    /// shutdown RPC, so osquery only gets its reply (and tears down the
+    /// socket) once the callback returns. Keep it to critical, fast work such
+    /// as flushing buffered logs; osquery won't wait long. A panicking callback
+    /// is logged and does not stop the shutdown. Not called when shutdown is
+    /// triggered any other way. Registering a new callback replaces the
+    /// previous one.
+    pub fn on_shutdown_request<F: Fn() + Send + Sync + 'static>(&mut self, f: F) -> &mut Self {
+        self.shutdown_request_hook = Some(Arc::new(f));
+        self
+    }
+
+    /// Bound how long cleanup may take once the server stops: the
+    /// `on_shutdown()` callback, deregistering from osquery and the plugins'
+    /// `flush()` and `shutdown()` must finish within `deadline` in total.
+    ///
+    /// osquery kills extensions that don't exit soon after it asks them to,
+    /// so a plugin hanging in `shutdown()` would otherwise get the extension
+    /// killed mid-cleanup. Steps still running when the deadline passes are
+    /// logged and left behind on their own thread, and deregistration is
+    /// skipped once it has passed. Joining the listener is always capped at
+    /// 100ms. Without a deadline (the default) shutdown waits for every step.
+    pub fn set_shutdown_deadline(&mut self, deadline: Duration) -> &mut Self {
+        self.shutdown_deadline = Some(deadline);
+        self
+    }
+
+    /// Register a callback run on every iteration of the ping loop.
+    ///
+    /// The callback runs on the server thread after each successful ping, so it
+    /// fires roughly once per ping interval (see `set_ping_interval()`). Use it
+    /// for periodic maintenance such as cache eviction or metric flushes. A
+    /// panicking callback is logged and does not stop the server.
+    /// Registering a new callback replaces the previous one.
+    pub fn on_tick<F: FnMut() + Send + 'static>(&mut self, f: F) -> &mut Self {
+        self.tick_hook = Some(Box::new(f));
+        self
+    }
+
+    /// Warn when osquery hasn't called any plugin for `timeout`.
+    ///
+    /// Unlike the ping, which checks that osquery is alive, this flags an
+    /// extension osquery has stopped using (e.g. a table no scheduled query
+    /// reads any more). Checked once per ping interval; each idle period is
+    /// reported once, and the next call from osquery resets it.
+    pub fn set_idle_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Register a callback run when the extension becomes idle (see
+    /// `set_idle_timeout()`), in addition to the warning that is logged.
+    ///
+    /// The callback runs on the server thread; a panicking callback is logged
+    /// and does not stop the server. Registering a new callback replaces the
+    /// previous one.
+    pub fn on_idle<F: FnMut() + Send + 'static>(&mut self, f: F) -> &mut Self {
+        self.idle_hook = Some(Box::new(f));
+        self
+    }
+
+    /// Read the current time from `clock` instead of the system clock, for
+    /// uptime, idle tracking and the time of the last call.
+    ///
+    /// Meant for tests that need time to move deterministically; call it
+    /// before `start()`.
+    pub fn set_clock<K: Clock + 'static>(&mut self, clock: K) -> &mut Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Report the latency and outcome of every plugin call to `sink`.
+    ///
+    /// By default measurements are discarded. Setting a new sink replaces the
+    /// previous one.
+    pub fn set_metrics_sink<M: MetricsSink + 'static>(&mut self, sink: M) -> &mut Self {
+        self.metrics = Arc::new(sink);
+        self
+    }
+
+    /// Serve plugins over HTTP at `addr` (e.g. `"127.0.0.1:8080"`) while the
+    /// server runs, for poking at tables with `curl` during development.
+    ///
+    /// The endpoint is unauthenticated, so `addr` must be a loopback address;
+    /// see the `debug_http` module docs for the routes. `start()` fails with
+    /// [`ServerError::Listen`] if `addr` can't be bound or isn't loopback.
+    #[cfg(feature = "debug-http")]
+    pub fn set_debug_http(&mut self, addr: &str) -> &mut Self {
+        self.debug_http_addr = Some(addr.to_string());
+        self
+    }
+
+    /// Set how often the server pings osquery (and runs the `on_tick()` callback).
+    pub fn set_ping_interval(&mut self, interval: Duration) -> &mut Self {
+        self.ping_interval = interval;
+        self
+    }
+
+    /// Set how many threads serve osquery's calls (default 10).
+    ///
+    /// osquery mostly calls an extension serially, one query at a time, so a
+    /// couple of threads is usually enough and saves memory in small
+    /// extensions. Raise it if several osquery connections (e.g. concurrent
+    /// distributed queries) call slow tables at once. Values below 1 are
+    /// treated as 1. Takes effect when the server starts.
+    pub fn set_worker_threads(&mut self, threads: usize) -> &mut Self {
+        self.worker_threads = threads.max(1);
+        self
+    }
+
+    /// Let `start()` register the plugins that can be registered when others
+    /// can't (a panic while describing their routes), instead of failing.
+    /// The plugins left out are listed by `registration_failures()`.
+    ///
+    /// Off by default, so a broken plugin stops the extension from starting.
+    pub fn allow_partial_registration(&mut self, allow: bool) -> &mut Self {
+        self.allow_partial_registration = allow;
+        self
+    }
+
+    /// The plugins `start()` left out with partial registration allowed.
+    pub fn registration_failures(&self) -> &[PluginFailure] {
+        &self.registration_failures
+    }
+
+    /// Contribute an option (a flag) named `name` of type `option_type` to
+    /// osquery, with the value `default`.
+    ///
+    /// Adding a name again replaces the earlier option.
+    pub fn add_option(
+        &mut self,
+        name: &str,
+        option_type: OptionType,
+        default: &str,
+        description: &str,
+    ) -> &mut Self {
+        self.options.insert(
+            name.to_string(),
+            ExtensionOption {
+                option_type,
+                default: default.to_string(),
+                description: description.to_string(),
+            },
+        );
+        self
+    }
+
+    /// The options added with `add_option()`, as osquery's `options` RPC
+    /// returns them.
+    ///
+    /// osquery reads an extension option's description from the `value`
+    /// field of its `InternalOptionInfo`.
+    pub fn options(&self) -> osquery::InternalOptionList {
+        self.options
+            .iter()
+            .map(|(name, option)| {
+                let info = osquery::InternalOptionInfo::new(
+                    option.description.clone(),
+                    option.default.clone(),
+                    option.option_type.to_string(),
+                );
+                (name.clone(), info)
+            })
+            .collect()
+    }
+
+    /// Answer osquery with an error status when a plugin call (e.g. a slow
+    /// table's `generate()`) takes longer than `timeout`.
+    ///
+    /// Each call then runs on its own thread so the worker serving osquery
+    /// can give up waiting. Rust threads can't be cancelled: a timed-out call
+    /// keeps running in the background and its result is discarded, so
+    /// plugins doing unbounded work should still check their own deadlines.
+    /// Off by default.
+    pub fn set_call_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.call_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the Thrift protocol the extension's listener speaks.
+    ///
+    /// osquery uses the binary protocol (the default); only change this for
+    /// an osquery built to use the compact one. The client must match: pass
+    /// `with_client()` a client made by [`ThriftClient::with_options`] with
+    /// [`ClientOptions::set_protocol`](crate::ClientOptions::set_protocol),
+    /// since `new()` connects with the binary protocol.
+    pub fn set_protocol(&mut self, protocol: Protocol) -> &mut Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Set the sizes of the buffers between the listener's connections and
+    /// the Thrift protocol, see [`BufferSizes`].
+    ///
+    /// Raising them can help extensions that return big result sets. For the
+    /// client side, pass `with_client()` a client made by
+    /// [`ThriftClient::with_options`] with
+    /// [`ClientOptions::set_buffer_sizes`](crate::ClientOptions::set_buffer_sizes).
+    pub fn set_buffer_sizes(&mut self, buffer_sizes: BufferSizes) -> &mut Self {
+        self.buffer_sizes = buffer_sizes;
+        self
+    }
+
+    /// Restrict who can connect to the extension's socket by setting its
+    /// permission bits, e.g. `0o600` for the osquery user only.
+    ///
+    /// The socket is bound in a directory only this process can enter, given
+    /// `mode` there and only then moved to the path osquery connects to, so
+    /// nobody else can connect in between. `start()` fails with
+    /// [`ServerError::Listen`] if the mode can't be applied. Ignored on
+    /// Windows, where osquery uses named pipes.
+    pub fn set_socket_mode(&mut self, mode: u32) -> &mut Self {
+        self.socket_mode = Some(mode);
+        self
+    }
+
+    /// Set the name osquery lists this extension under.
+    ///
+    /// This names the extension process as a whole (the `osquery_extensions`
+    /// table); each plugin is still registered under its own `name()`.
+    pub fn set_extension_name(&mut self, name: &str) -> &mut Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Set the extension version reported to osquery.
+    ///
+    /// Defaults to an empty (unknown) version. Pass
+    /// `env!("CARGO_PKG_VERSION")` from the extension's crate to report its
+    /// own version.
+    pub fn set_version(&mut self, version: &str) -> &mut Self {
+        self.version = version.to_string();
+        self
+    }
+
+    /// Set the osquery SDK version the extension was built against.
+    pub fn set_sdk_version(&mut self, sdk_version: &str) -> &mut Self {
+        self.sdk_version = sdk_version.to_string();
+        self
+    }
+
+    /// Set the oldest osquery SDK version the extension supports.
+    pub fn set_min_sdk_version(&mut self, min_sdk_version: &str) -> &mut Self {
+        self.min_sdk_version = min_sdk_version.to_string();
         self
     }
 
@@ -154,31 +938,109 @@ impl<P: OsqueryPlugin + Clone + Send + 'static, C: OsqueryClient> Server<P, C> {
     /// - `stop()` being called from another thread
     ///
     /// For signal handling (SIGTERM/SIGINT), use `run_with_signal_handling()` instead.
-    pub fn run(&mut self) -> thrift::Result<()> {
+    ///
+    /// # Errors
+    /// Returns [`ServerError::NoPlugins`] if no plugins were registered,
+    /// [`ServerError::AlreadyStarted`] if the server was started before, or
+    /// [`ServerError::Thrift`] if registering with osquery fails.
+    pub fn run(&mut self) -> Result<(), ServerError> {
         self.start()?;
         self.run_loop();
-        self.shutdown_and_cleanup();
+        self.finish();
         Ok(())
     }
 
-    /// Run the server with signal handling enabled (Unix only).
+    /// Like `run()`, but when osquery goes away (a failed ping) wait and
+    /// register again instead of returning, following `policy`.
+    ///
+    /// Plugins keep their state across restarts and are only shut down once
+    /// the server finally stops. A stop requested with `stop()` or by osquery's
+    /// shutdown RPC ends the server as usual, as does using up
+    /// `policy.max_restarts`; then the last error from registering again is
+    /// returned, if any.
+    ///
+    /// # Errors
+    /// Same as `run()` for the first start.
+    pub fn run_with_restart(&mut self, policy: RestartPolicy) -> Result<(), ServerError> {
+        self.start()?;
+        let mut attempts = 0;
+        let mut result = Ok(());
+
+        'run: loop {
+            self.run_loop();
+            if self.last_shutdown_reason() != Some(ShutdownReason::PingFailed) {
+                break;
+            }
+            self.stop_serving();
+            if self.should_shutdown() {
+                break;
+            }
+
+            loop {
+                if attempts >= policy.max_restarts {
+                    log::error!(
+                        target: log_target::SERVER,
+                        "Giving up after {attempts} restart attempts"
+                    );
+                    break 'run;
+                }
+                attempts += 1;
+                let backoff = policy.backoff(attempts);
+                log::warn!(
+                    target: log_target::SERVER,
+                    "Lost osquery, restarting in {backoff:?} (attempt {attempts} of {})",
+                    policy.max_restarts
+                );
+                thread::sleep(backoff);
+                if self.should_shutdown() {
+                    break 'run;
+                }
+
+                result = self.restart();
+                match &result {
+                    Ok(()) => break,
+                    Err(e) => log::warn!(target: log_target::SERVER, "Restart failed: {e}"),
+                }
+            }
+        }
+
+        self.finish();
+        result
+    }
+
+    /// Run the server with signal handling enabled.
     ///
-    /// This method registers handlers for SIGTERM and SIGINT that will trigger
-    /// graceful shutdown. Use this instead of `run()` if you want the server to
-    /// respond to OS signals (e.g., systemd sending SIGTERM, or Ctrl+C sending SIGINT).
+    /// On Unix this registers handlers for SIGTERM and SIGINT; on Windows it
+    /// installs a console control handler for CTRL_C and CTRL_BREAK. Either
+    /// triggers graceful shutdown. Use this instead of `run()` if you want the
+    /// server to respond to OS signals (e.g., systemd sending SIGTERM, or
+    /// Ctrl+C in a terminal).
     ///
     /// The loop exits when shutdown is triggered by any of:
-    /// - SIGTERM or SIGINT signal received
+    /// - SIGTERM or SIGINT signal received (Unix)
+    /// - CTRL_C or CTRL_BREAK console event received (Windows)
     /// - osquery calling the shutdown RPC
     /// - Connection to osquery being lost
     /// - `stop()` being called from another thread
     ///
     /// # Platform Support
     ///
-    /// This method is only available on Unix platforms. For Windows, use `run()`
-    /// and implement your own signal handling.
+    /// On Windows only console events are handled: a service stopped through
+    /// the Service Control Manager never sees them and should call `stop()`
+    /// from its service control handler instead. Only one console handler can
+    /// be installed per process, so a second call logs a warning and relies on
+    /// the other shutdown mechanisms.
+    pub fn run_with_signal_handling(&mut self) -> Result<(), ServerError> {
+        self.register_signal_handlers();
+
+        self.start()?;
+        self.run_loop();
+        self.finish();
+        Ok(())
+    }
+
     #[cfg(unix)]
-    pub fn run_with_signal_handling(&mut self) -> thrift::Result<()> {
+    fn register_signal_handlers(&self) {
         use signal_hook::consts::{SIGINT, SIGTERM};
         use signal_hook::flag;
 
@@ -186,48 +1048,115 @@ impl<P: OsqueryPlugin + Clone + Send + 'static, C: OsqueryClient> Server<P, C> {
         // signal_hook::flag::register atomically sets the bool when signal received.
         // Errors are rare (e.g., invalid signal number) and non-fatal - signals
         // just won't trigger shutdown, but other shutdown mechanisms still work.
-        if let Err(e) = flag::register(SIGINT, self.shutdown_flag.clone()) {
-            log::warn!("Failed to register SIGINT handler: {e}");
-        }
-        if let Err(e) = flag::register(SIGTERM, self.shutdown_flag.clone()) {
-            log::warn!("Failed to register SIGTERM handler: {e}");
+        // `signal_received` is set first so the reason is known once the
+        // shutdown flag is seen.
+        for (signal, name) in [(SIGINT, "SIGINT"), (SIGTERM, "SIGTERM")] {
+            let registered = flag::register(signal, self.signal_received.clone())
+                .and_then(|_| flag::register(signal, self.shutdown_flag.clone()));
+            if let Err(e) = registered {
+                log::warn!(target: log_target::SERVER, "Failed to register {name} handler: {e}");
+            }
         }
+    }
 
-        self.start()?;
-        self.run_loop();
-        self.shutdown_and_cleanup();
-        Ok(())
+    #[cfg(windows)]
+    fn register_signal_handlers(&self) {
+        // ctrlc handles CTRL_C_EVENT and CTRL_BREAK_EVENT on Windows. It fails
+        // if a handler is already installed; as on Unix this is non-fatal.
+        if let Err(e) = ctrlc::set_handler(console_handler(
+            self.shutdown_flag.clone(),
+            self.signal_received.clone(),
+        )) {
+            log::warn!(
+                target: log_target::SERVER,
+                "Failed to register console control handler: {e}"
+            );
+        }
     }
 
     /// The main ping loop. Exits when should_shutdown() returns true.
     fn run_loop(&mut self) {
-        while !self.should_shutdown() {
-            if let Err(e) = self.client.ping() {
-                log::warn!("Ping failed, initiating shutdown: {e}");
-                self.request_shutdown();
-                break;
-            }
+        while let Ok(PollOutcome::Continue) = self.poll_once() {
             thread::sleep(self.ping_interval);
         }
     }
 
+    /// Do one iteration of `run()`'s loop: check for shutdown, ping osquery,
+    /// then run the tick and idle hooks.
+    ///
+    /// For hosts with their own event loop: call `start()`, then `poll_once()`
+    /// about every ping interval until it returns [`PollOutcome::Shutdown`] or
+    /// an error, then `finish()`. A failed ping means osquery went away, so it
+    /// also requests shutdown.
+    pub fn poll_once(&mut self) -> thrift::Result<PollOutcome> {
+        if self.should_shutdown() {
+            self.note_signal();
+            return Ok(PollOutcome::Shutdown);
+        }
+        if let Err(e) = self.client.ping() {
+            log::warn!(target: log_target::SERVER, "Ping failed, initiating shutdown: {e}");
+            self.request_shutdown(ShutdownReason::PingFailed);
+            return Err(e);
+        }
+        self.stats.record_ping();
+        self.run_tick_hook();
+        self.check_idle();
+        Ok(PollOutcome::Continue)
+    }
+
+    /// Shut down a server driven with `start()` and `poll_once()`: stop the
+    /// listener, deregister from osquery, flush and shut down the plugins and
+    /// remove the socket.
+    ///
+    /// Requests shutdown first if nothing else has. Does nothing if the server
+    /// never started or has already finished; `run()` calls this itself.
+    pub fn finish(&mut self) {
+        if !self.started {
+            return;
+        }
+        self.note_signal();
+        self.request_shutdown(ShutdownReason::Stopped);
+        self.shutdown_and_cleanup();
+    }
+
     /// Common shutdown logic: wake listener, join thread, deregister, notify plugins, cleanup socket.
     fn shutdown_and_cleanup(&mut self) {
-        log::info!("Shutting down");
+        if self.cleaned_up.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        log::info!(target: log_target::SERVER, "Shutting down");
+        let deadline = self.shutdown_deadline.map(|limit| Instant::now() + limit);
 
         self.join_listener_thread();
+        self.join_debug_http();
+        self.run_shutdown_hook(deadline);
 
         // Deregister from osquery (best-effort, allows faster cleanup than timeout)
         if let Some(uuid) = self.uuid {
-            if let Err(e) = self.client.deregister_extension(uuid) {
-                log::warn!("Failed to deregister from osquery: {e}");
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                log::warn!(
+                    target: log_target::SERVER,
+                    "Shutdown deadline passed, not deregistering from osquery"
+                );
+            } else if let Err(e) = self.client.deregister_extension(uuid) {
+                log::warn!(target: log_target::SERVER, "Failed to deregister from osquery: {e}");
             }
         }
 
-        self.notify_plugins_shutdown();
+        self.notify_plugins_shutdown(deadline);
         self.cleanup_socket();
     }
 
+    /// Wait for the debug HTTP endpoint, which exits once shutdown is requested.
+    fn join_debug_http(&mut self) {
+        #[cfg(feature = "debug-http")]
+        if let Some(thread) = self.debug_http_thread.take() {
+            if let Err(e) = thread.join() {
+                log::warn!(target: log_target::SERVER, "Debug HTTP thread panicked: {e:?}");
+            }
+        }
+    }
+
     /// Attempt to join the listener thread with a timeout.
     ///
     /// The thrift listener has an infinite loop that we cannot control, so we use
@@ -246,12 +1175,13 @@ impl<P: OsqueryPlugin + Clone + Send + 'static, C: OsqueryClient> Server<P, C> {
             return;
         };
 
-        log::debug!("Waiting for listener thread to exit");
+        log::debug!(target: log_target::SERVER, "Waiting for listener thread to exit");
         let start = Instant::now();
 
         while !thread.is_finished() {
             if start.elapsed() > JOIN_TIMEOUT {
                 log::warn!(
+                    target: log_target::SERVER,
                     "Listener thread did not exit within {:?}, orphaning (will terminate on process exit)",
                     JOIN_TIMEOUT
                 );
@@ -263,82 +1193,367 @@ impl<P: OsqueryPlugin + Clone + Send + 'static, C: OsqueryClient> Server<P, C> {
 
         // Thread finished, now we can join without blocking
         if let Err(e) = thread.join() {
-            log::warn!("Listener thread panicked: {e:?}");
+            log::warn!(target: log_target::SERVER, "Listener thread panicked: {e:?}");
         }
     }
 
-    fn start(&mut self) -> thrift::Result<()> {
-        let stat = self.client.register_extension(
-            osquery::InternalExtensionInfo {
-                name: Some(self.name.clone()),
-                version: Some("1.0".to_string()),
-                sdk_version: Some("Unknown".to_string()),
-                min_sdk_version: Some("Unknown".to_string()),
-            },
-            self.generate_registry()?,
-        )?;
+    /// Register with osquery and start serving plugin calls on a background
+    /// thread, without blocking.
+    ///
+    /// `run()` does this and then pings osquery until shutdown; call it
+    /// directly to drive the server from your own loop with `poll_once()`
+    /// and `finish()`.
+    ///
+    /// # Errors
+    /// Same as `run()`.
+    pub fn start(&mut self) -> Result<(), ServerError> {
+        if self.started {
+            return Err(ServerError::AlreadyStarted);
+        }
+        self.drop_unsupported_plugins();
+        self.registry = self.generate_registry()?;
+        if self.plugins.is_empty() {
+            return Err(ServerError::NoPlugins);
+        }
+        if let Some(internal_table) = &self.internal_table {
+            let table = internal_table(&self.name, &self.clock);
+            self.add_builtin_table(table)?;
+        }
+        if let Some(build_info_table) = &self.build_info_table {
+            let table = build_info_table(&self.name, &self.clock);
+            self.add_builtin_table(table)?;
+        }
 
-        //if stat.code != Some(0) {
-        log::info!(
-            "Status {} registering extension {} ({}): {}",
-            stat.code.unwrap_or(0),
-            self.name,
+        self.register_and_serve()?;
+        self.started = true;
+
+        Ok(())
+    }
+
+    /// Register the plugins with osquery and start serving them.
+    fn register_and_serve(&mut self) -> Result<(), ServerError> {
+        self.register_extension(self.registry.clone())?;
+
+        // Idle time and uptime count from registration
+        let now = self.clock.now();
+        self.last_call.store(unix_millis(now), Ordering::Release);
+        self.stats.record_start(now);
+        self.dispatch = Some(Arc::new(plugin_map(&self.plugins)?));
+        if let Err(e) = self.spawn_listener() {
+            // osquery already knows our UUID; don't leave it routing to a
+            // socket nobody serves
+            self.stop_serving();
+            return Err(e);
+        }
+        #[cfg(feature = "debug-http")]
+        if let Err(e) = self.start_debug_http() {
+            self.stop_serving();
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Stop serving after osquery went away, leaving the plugins and hooks
+    /// for `restart()`: stop the listener, deregister and remove the socket.
+    fn stop_serving(&mut self) {
+        self.join_listener_thread();
+        self.join_debug_http();
+        if let Some(uuid) = self.uuid {
+            if let Err(e) = self.client.deregister_extension(uuid) {
+                log::debug!(target: log_target::SERVER, "Failed to deregister from osquery: {e}");
+            }
+        }
+        self.cleanup_socket();
+
+        self.uuid = None;
+        if let Ok(mut registered_uuid) = self.registered_uuid.lock() {
+            *registered_uuid = None;
+        }
+        self.listen_path = None;
+        self.dispatch = None;
+
+        // Only forget a failed ping: a stop requested meanwhile must still
+        // end `run_with_restart()`
+        self.shutdown_flag.store(false, Ordering::Release);
+        let ping_failed = ShutdownReason::PingFailed.to_u8();
+        let requested = match self.shutdown_reason.compare_exchange(
+            ping_failed,
+            0,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => false,
+            Err(reason) => reason != 0,
+        };
+        if requested || self.signal_received.load(Ordering::Acquire) {
+            self.shutdown_flag.store(true, Ordering::Release);
+        }
+    }
+
+    /// Reconnect to osquery and register again after `stop_serving()`.
+    fn restart(&mut self) -> Result<(), ServerError> {
+        self.client.reconnect()?;
+        self.register_and_serve()
+    }
+
+    /// Register one of the crate's own tables next to the user's plugins,
+    /// adding its routes to those `start()` described.
+    fn add_builtin_table(&mut self, table: P) -> Result<(), ServerError> {
+        let registry = table.registry().to_string();
+        let name = table.name();
+        let routes = table.routes();
+        self.register_plugin(table)?;
+        self.registry
+            .entry(registry)
+            .or_default()
+            .insert(name, routes);
+        Ok(())
+    }
+
+    /// Leave out plugins that need a newer osquery than the one we talk to.
+    ///
+    /// osquery is only asked for its version when some plugin declares a
+    /// minimum. If the version can't be determined every plugin is kept, since
+    /// refusing to start would be worse than a plugin misbehaving.
+    fn drop_unsupported_plugins(&mut self) {
+        if self
+            .plugins
+            .iter()
+            .all(|plugin| plugin.min_osquery_version().is_none())
+        {
+            return;
+        }
+
+        let Some(running) = self.osquery_version() else {
+            return;
+        };
+
+        self.plugins.retain(|plugin| match plugin.min_osquery_version() {
+            Some(required) if running < required => {
+                log::warn!(
+                    target: log_target::SERVER,
+                    "Not registering {} plugin {}: it requires osquery {required}, running {running}",
+                    plugin.registry(),
+                    plugin.name()
+                );
+                false
+            }
+            _ => true,
+        });
+    }
+
+    /// The version of the osquery we are connected to, from `osquery_info`.
+    fn osquery_version(&mut self) -> Option<semver::Version> {
+        let version = self
+            .client
+            .query("SELECT version FROM osquery_info".to_string())
+            .map_err(|e| e.to_string())
+            .and_then(|response| {
+                response
+                    .response
+                    .and_then(|rows| rows.into_iter().next())
+                    .and_then(|mut row| row.remove("version"))
+                    .ok_or_else(|| "osquery_info returned no version".to_string())
+            });
+
+        match version.map(|v| (parse_osquery_version(&v), v)) {
+            Ok((Some(version), _)) => Some(version),
+            Ok((None, raw)) => {
+                log::warn!(
+                    target: log_target::SERVER,
+                    "Could not parse osquery version '{raw}', registering all plugins"
+                );
+                None
+            }
+            Err(e) => {
+                log::warn!(
+                    target: log_target::SERVER,
+                    "Could not determine osquery version ({e}), registering all plugins"
+                );
+                None
+            }
+        }
+    }
+
+    /// Register with osquery, recording the UUID it assigns us.
+    fn register_extension(
+        &mut self,
+        registry: osquery::ExtensionRegistry,
+    ) -> Result<(), ServerError> {
+        let stat = self.client.register_extension(
+            osquery::InternalExtensionInfo {
+                name: Some(self.name.clone()),
+                version: Some(self.version.clone()),
+                sdk_version: Some(self.sdk_version.clone()),
+                min_sdk_version: Some(self.min_sdk_version.clone()),
+            },
+            registry,
+        )?;
+
+        //if stat.code != Some(0) {
+        log::info!(
+            target: log_target::SERVER,
+            "Status {} registering extension {} ({}): {}",
+            stat.code.unwrap_or(0),
+            self.name,
             stat.uuid.unwrap_or(0),
             stat.message.unwrap_or_else(|| "No message".to_string())
         );
         //}
 
         self.uuid = stat.uuid;
-        let listen_path = format!("{}.{}", self.socket_path, self.uuid.unwrap_or(0));
-
-        let processor = osquery::ExtensionManagerSyncProcessor::new(Handler::new(
-            &self.plugins,
-            self.shutdown_flag.clone(),
-        )?);
-        let i_tr_fact: Box<dyn TReadTransportFactory + Send> =
-            Box::new(TBufferedReadTransportFactory::new());
-        let i_pr_fact: Box<dyn TInputProtocolFactory + Send> =
-            Box::new(TBinaryInputProtocolFactory::new());
-        let o_tr_fact: Box<dyn TWriteTransportFactory + Send> =
-            Box::new(TBufferedWriteTransportFactory::new());
-        let o_pr_fact: Box<dyn TOutputProtocolFactory + Send> =
-            Box::new(TBinaryOutputProtocolFactory::new());
-
-        let mut server =
-            thrift::server::TServer::new(i_tr_fact, i_pr_fact, o_tr_fact, o_pr_fact, processor, 10);
+        if let Ok(mut registered_uuid) = self.registered_uuid.lock() {
+            *registered_uuid = self.uuid();
+        }
+        Ok(())
+    }
 
+    /// Serve the dispatch table on the socket osquery expects for our UUID.
+    fn spawn_listener(&mut self) -> Result<(), ServerError> {
+        let Some(dispatch) = self.dispatch.clone() else {
+            return Ok(());
+        };
+        let listen_path = format!("{}.{}", self.socket_path, self.uuid.unwrap_or(0));
+        let workers = self.worker_threads;
+        let protocol = self.protocol;
+        let buffers = self.buffer_sizes;
+
+        let processor = osquery::ExtensionManagerSyncProcessor::new(
+            Handler::with_dispatch(dispatch, self.shutdown_flag.clone())
+                .with_shutdown_reason(self.shutdown_reason.clone())
+                .with_last_call(self.last_call.clone())
+                .with_clock(self.clock.clone())
+                .with_metrics(self.metrics.clone())
+                .with_call_timeout(self.call_timeout)
+                .with_shutdown_request_hook(self.shutdown_request_hook.clone())
+                .with_options(self.options()),
+        );
+        for (name, option) in &self.options {
+            log::debug!(
+                target: log_target::SERVER,
+                "Providing option {name}={}: {}",
+                option.default,
+                option.description
+            );
+        }
+        // With a socket mode, bind where nobody else can connect until the
+        // mode is set
+        let bind_path = match self.socket_mode {
+            Some(_) => transport::stage(&listen_path).map_err(ServerError::Listen)?,
+            None => listen_path.clone(),
+        };
         // Store the listen path for wake-up connection on shutdown
-        self.listen_path = Some(listen_path.clone());
+        self.listen_path = Some(bind_path.clone());
 
         // Spawn the listener in a background thread so we can check shutdown flag
-        // in run_loop(). The thrift listen_uds() blocks forever, so without this
+        // in run_loop(). The thrift listener blocks forever, so without this
         // the server cannot gracefully shutdown.
         let listener_thread = thread::spawn(move || {
-            if let Err(e) = server.listen_uds(listen_path) {
+            if let Err(e) = transport::listen(processor, &bind_path, workers, protocol, buffers) {
                 // Log but don't panic - listener exiting is expected on shutdown
-                log::debug!("Listener thread exited: {e}");
+                log::debug!(target: log_target::SERVER, "Listener thread exited: {e}");
             }
         });
 
         self.listener_thread = Some(listener_thread);
-        self.started = true;
 
+        if let (Some(mode), Some(bind_path)) = (self.socket_mode, &self.listen_path) {
+            transport::publish(bind_path, &listen_path, mode, SOCKET_MODE_TIMEOUT)
+                .map_err(ServerError::Listen)?;
+            self.listen_path = Some(listen_path);
+        }
+        Ok(())
+    }
+
+    /// Spawn the debug HTTP endpoint, if one was configured.
+    #[cfg(feature = "debug-http")]
+    fn start_debug_http(&mut self) -> Result<(), ServerError> {
+        let (Some(addr), Some(dispatch)) = (self.debug_http_addr.as_deref(), self.dispatch.clone())
+        else {
+            return Ok(());
+        };
+
+        let listener = std::net::TcpListener::bind(addr).map_err(ServerError::Listen)?;
+        let local_addr = listener.local_addr().map_err(ServerError::Listen)?;
+        if !local_addr.ip().is_loopback() {
+            return Err(ServerError::Listen(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("debug HTTP endpoint must listen on loopback, not {local_addr}"),
+            )));
+        }
+        listener
+            .set_nonblocking(true)
+            .map_err(ServerError::Listen)?;
+        log::info!(target: log_target::SERVER, "Serving debug HTTP endpoint on {addr}");
+
+        let handler = Handler::with_dispatch(dispatch, self.shutdown_flag.clone());
+        let shutdown_flag = self.shutdown_flag.clone();
+        self.debug_http_thread = Some(thread::spawn(move || {
+            crate::debug_http::serve(listener, handler, shutdown_flag)
+        }));
         Ok(())
     }
 
-    fn generate_registry(&self) -> thrift::Result<osquery::ExtensionRegistry> {
+    /// Describe the routes of every plugin for osquery. Plugins that panic
+    /// while doing so are left out and reported with partial registration
+    /// allowed; otherwise the first one is an error.
+    fn generate_registry(&mut self) -> Result<osquery::ExtensionRegistry, ServerError> {
         let mut registry = osquery::ExtensionRegistry::new();
 
+        // Report every registry, even empty ones, like osquery's own SDKs do;
+        // osquery ignores empty route tables but this keeps the broadcast
+        // shape independent of which plugins happen to be registered
         for var in Registry::VARIANTS {
             registry.insert((*var).to_string(), osquery::ExtensionRouteTable::new());
         }
 
-        for plugin in self.plugins.iter() {
+        let mut failed = Vec::new();
+        for (index, plugin) in self.plugins.iter().enumerate() {
+            let name = plugin.name();
+            let routes = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| plugin.routes()));
+            let Ok(routes) = routes else {
+                let registry = plugin.registry().to_string();
+                let reason = "panicked while describing its routes".to_string();
+                if !self.allow_partial_registration {
+                    return Err(ServerError::InvalidPlugin {
+                        registry,
+                        name,
+                        reason,
+                    });
+                }
+                failed.push((
+                    index,
+                    PluginFailure {
+                        registry,
+                        name,
+                        reason,
+                    },
+                ));
+                continue;
+            };
             registry
-                .get_mut(plugin.registry().to_string().as_str())
-                .ok_or_thrift_err(|| format!("Failed to register plugin {}", plugin.name()))?
-                .insert(plugin.name(), plugin.routes());
+                .entry(plugin.registry().to_string())
+                .or_default()
+                .insert(name, routes);
+        }
+
+        let mut index = 0;
+        self.plugins.retain(|_| {
+            let keep = !failed.iter().any(|(failed, _)| *failed == index);
+            index += 1;
+            keep
+        });
+        for (_, failure) in &failed {
+            log::warn!(
+                target: log_target::SERVER,
+                "Not registering {} plugin {}: {}",
+                failure.registry,
+                failure.name,
+                failure.reason
+            );
         }
+        self.registration_failures = failed.into_iter().map(|(_, failure)| failure).collect();
         Ok(registry)
     }
 
@@ -347,11 +1562,21 @@ impl<P: OsqueryPlugin + Clone + Send + 'static, C: OsqueryClient> Server<P, C> {
         self.shutdown_flag.load(Ordering::Acquire)
     }
 
-    /// Request shutdown by setting the shutdown flag.
-    fn request_shutdown(&self) {
+    /// Request shutdown by setting the shutdown flag, recording `reason`
+    /// unless an earlier cause already was.
+    fn request_shutdown(&self, reason: ShutdownReason) {
+        reason.record(&self.shutdown_reason);
         self.shutdown_flag.store(true, Ordering::Release);
     }
 
+    /// Record a signal as the shutdown reason if one has arrived, since the
+    /// signal handlers can only set flags.
+    fn note_signal(&self) {
+        if self.signal_received.load(Ordering::Acquire) {
+            ShutdownReason::Signal.record_request(&self.shutdown_reason);
+        }
+    }
+
     /// Wake the blocking listener thread by making a dummy connection.
     ///
     /// # Why This Workaround Exists
@@ -375,44 +1600,141 @@ impl<P: OsqueryPlugin + Clone + Send + 'static, C: OsqueryClient> Server<P, C> {
     /// 2. We connect to our own socket, which unblocks `accept()`
     /// 3. The listener thread receives the connection, checks shutdown flag, and exits
     /// 4. The connection is immediately dropped (never read from)
+    ///
+    /// On Windows the same trick is applied by opening a client end of our named pipe.
     fn wake_listener(&self) {
         if let Some(ref path) = self.listen_path {
-            let _ = std::os::unix::net::UnixStream::connect(path);
+            transport::wake(path);
         }
     }
 
-    /// Notify all registered plugins that shutdown is occurring.
-    /// Uses catch_unwind to ensure all plugins are notified even if one panics.
-    fn notify_plugins_shutdown(&self) {
-        log::debug!("Notifying {} plugins of shutdown", self.plugins.len());
-        for plugin in &self.plugins {
-            let plugin_name = plugin.name();
-            if let Err(e) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                plugin.shutdown();
-            })) {
-                log::error!("Plugin '{plugin_name}' panicked during shutdown: {e:?}");
+    /// Run the user's tick callback, if any; a panic is logged, not propagated.
+    fn run_tick_hook(&mut self) {
+        let Some(hook) = self.tick_hook.as_mut() else {
+            return;
+        };
+
+        if let Err(e) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(hook)) {
+            log::error!(target: log_target::SERVER, "Tick hook panicked: {e:?}");
+        }
+    }
+
+    /// Report the extension as idle once osquery hasn't called it for longer
+    /// than the idle timeout.
+    fn check_idle(&mut self) {
+        let Some(timeout) = self.idle_timeout else {
+            return;
+        };
+
+        let last_call = self.last_call.load(Ordering::Acquire);
+        let now = unix_millis(self.clock.now());
+        let idle = Duration::from_millis(now.saturating_sub(last_call));
+        if idle < timeout {
+            self.idle_reported = false;
+            return;
+        }
+        if self.idle_reported {
+            return;
+        }
+        self.idle_reported = true;
+
+        log::warn!(
+            target: log_target::SERVER,
+            "Extension {} has not been called by osquery for {}s",
+            self.name,
+            idle.as_secs()
+        );
+        if let Some(hook) = self.idle_hook.as_mut() {
+            if let Err(e) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(hook)) {
+                log::error!(target: log_target::SERVER, "Idle hook panicked: {e:?}");
             }
         }
     }
 
+    /// Run the server-level shutdown callback, if any.
+    /// The callback is taken so it can never run twice; a panic is logged, not propagated.
+    fn run_shutdown_hook(&mut self, deadline: Option<Instant>) {
+        let Some(hook) = self.shutdown_hook.take() else {
+            return;
+        };
+
+        run_until(deadline, "Shutdown hook", move || {
+            if let Err(e) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(hook)) {
+                log::error!(target: log_target::SERVER, "Shutdown hook panicked: {e:?}");
+            }
+        });
+    }
+
+    /// Notify all registered plugins that shutdown is occurring, giving up
+    /// waiting for them at `deadline`.
+    fn notify_plugins_shutdown(&self, deadline: Option<Instant>) {
+        log::debug!(
+            target: log_target::SERVER,
+            "Notifying {} plugins of shutdown", self.plugins.len()
+        );
+        let plugins = self.plugins.clone();
+        run_until(deadline, "Plugin shutdown", move || {
+            notify_plugins(&plugins)
+        });
+    }
+
     /// Clean up the socket file created during start().
     /// Logs errors (except NotFound, which is expected if socket was already cleaned up).
     fn cleanup_socket(&self) {
         let Some(uuid) = self.uuid else {
-            log::debug!("No socket to clean up (uuid not set)");
+            log::debug!(target: log_target::SERVER, "No socket to clean up (uuid not set)");
             return;
         };
 
         let socket_path = format!("{}.{}", self.socket_path, uuid);
-        log::debug!("Cleaning up socket: {socket_path}");
+        log::debug!(target: log_target::SERVER, "Cleaning up socket: {socket_path}");
 
-        if let Err(e) = std::fs::remove_file(&socket_path) {
+        if let Err(e) = transport::cleanup(&socket_path) {
             if e.kind() != std::io::ErrorKind::NotFound {
-                log::warn!("Failed to remove socket file {socket_path}: {e}");
+                log::warn!(
+                    target: log_target::SERVER,
+                    "Failed to remove socket file {socket_path}: {e}"
+                );
             }
         }
     }
 
+    /// Describe the routes each registered plugin will report to osquery.
+    ///
+    /// Maps each registry (`"table"`, `"config"`, `"logger"`) to its plugins'
+    /// names and routes, in registration order; for tables the routes are the
+    /// column definitions. Doesn't talk to osquery, so it can back a
+    /// `--describe` flag that dumps the extension's schema, e.g. with
+    /// `serde_json::to_string_pretty(&server.describe())`.
+    pub fn describe(&self) -> BTreeMap<String, Vec<(String, osquery::ExtensionPluginResponse)>> {
+        let mut description: BTreeMap<_, Vec<_>> = Registry::VARIANTS
+            .iter()
+            .map(|var| ((*var).to_string(), Vec::new()))
+            .collect();
+
+        for plugin in &self.plugins {
+            description
+                .entry(plugin.registry().to_string())
+                .or_default()
+                .push((plugin.name(), plugin.routes()));
+        }
+        description
+    }
+
+    /// Run a simple `SELECT` against one of the registered tables, in process.
+    ///
+    /// Meant for self-tests that want to see a table the way osquery would
+    /// without a running daemon. Only `SELECT <*|columns> FROM <table>` with
+    /// an optional `WHERE` of `AND`ed `<column> <op> <literal>` terms is
+    /// understood; the terms reach the table as [`QueryConstraints`] and
+    /// filter the rows it returns. Unsupported SQL, unknown tables and unknown
+    /// columns come back as a failure status.
+    ///
+    /// [`QueryConstraints`]: crate::plugin::QueryConstraints
+    pub fn local_query(&self, sql: &str) -> osquery::ExtensionResponse {
+        local_query::run(&self.plugins, sql)
+    }
+
     /// Get a handle that can be used to stop the server from another thread.
     ///
     /// The returned handle can be cloned and shared across threads. Calling
@@ -421,16 +1743,39 @@ impl<P: OsqueryPlugin + Clone + Send + 'static, C: OsqueryClient> Server<P, C> {
     pub fn get_stop_handle(&self) -> ServerStopHandle {
         ServerStopHandle {
             shutdown_flag: self.shutdown_flag.clone(),
+            shutdown_reason: self.shutdown_reason.clone(),
+            registered_uuid: self.registered_uuid.clone(),
         }
     }
 
+    /// Get the UUID osquery assigned to this extension during registration.
+    ///
+    /// Returns `None` until `run()` has registered the extension. The UUID matches
+    /// the `uuid` column of osquery's `osquery_extensions` table.
+    pub fn uuid(&self) -> Option<u16> {
+        self.uuid.and_then(|uuid| u16::try_from(uuid).ok())
+    }
+
     /// Request the server to stop.
     ///
     /// This is a convenience method equivalent to calling `stop()` on a
     /// `ServerStopHandle`. The server will exit its `run()` loop on the next
-    /// iteration.
+    /// iteration, and `poll_once()` returns [`PollOutcome::Shutdown`].
     pub fn stop(&self) {
-        self.request_shutdown();
+        ShutdownReason::Stopped.record_request(&self.shutdown_reason);
+        self.shutdown_flag.store(true, Ordering::Release);
+    }
+
+    /// Why the server stopped, or `None` while it is still running.
+    ///
+    /// The first cause wins: a `stop()` after osquery's shutdown RPC still
+    /// reports [`ShutdownReason::OsqueryRequested`]. Check this after `run()`
+    /// returns to tell a requested stop from losing osquery.
+    pub fn last_shutdown_reason(&self) -> Option<ShutdownReason> {
+        if self.should_shutdown() {
+            self.note_signal();
+        }
+        ShutdownReason::from_u8(self.shutdown_reason.load(Ordering::Acquire))
     }
 
     /// Check if the server is still running.
@@ -443,34 +1788,267 @@ impl<P: OsqueryPlugin + Clone + Send + 'static, C: OsqueryClient> Server<P, C> {
     }
 }
 
-struct Handler<P: OsqueryPlugin + Clone> {
-    registry: HashMap<String, HashMap<String, P>>,
+/// Parse an osquery version such as `5.12.1` or a development build's
+/// `5.12.1-10-gabcdef`, ignoring everything after the release number.
+fn parse_osquery_version(version: &str) -> Option<semver::Version> {
+    let release = version.trim().split(['-', '+']).next()?;
+    let mut parts = release.split('.').map(str::parse::<u64>);
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    Some(semver::Version::new(major, minor, patch))
+}
+
+/// Flush, then shut down, every plugin in `plugins`.
+/// Uses catch_unwind to ensure all plugins are notified even if one panics.
+fn notify_plugins<P: OsqueryPlugin>(plugins: &[P]) {
+    // Flush everything first so plugins shut down early (e.g. a table a
+    // logger depends on) can't lose another plugin's buffered data
+    for plugin in plugins {
+        let plugin_name = plugin.name();
+        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| plugin.flush())) {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => log::warn!(
+                target: log_target::SERVER,
+                "Plugin '{plugin_name}' failed to flush during shutdown: {e}"
+            ),
+            Err(e) => log::error!(
+                target: log_target::SERVER,
+                "Plugin '{plugin_name}' panicked during flush: {e:?}"
+            ),
+        }
+    }
+
+    for plugin in plugins {
+        let plugin_name = plugin.name();
+        if let Err(e) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            plugin.shutdown();
+        })) {
+            log::error!(
+                target: log_target::SERVER,
+                "Plugin '{plugin_name}' panicked during shutdown: {e:?}"
+            );
+        }
+    }
+}
+
+/// Run `f` on its own thread and wait for it until `deadline`, logging and
+/// leaving it running if it hasn't finished by then. Without a deadline `f`
+/// runs on the calling thread.
+fn run_until<F: FnOnce() + Send + 'static>(deadline: Option<Instant>, step: &str, f: F) {
+    let Some(deadline) = deadline else {
+        f();
+        return;
+    };
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    thread::spawn(move || {
+        f();
+        let _ = tx.send(());
+    });
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if let Err(std::sync::mpsc::RecvTimeoutError::Timeout) = rx.recv_timeout(remaining) {
+        log::warn!(
+            target: log_target::SERVER,
+            "{step} did not finish before the shutdown deadline, proceeding without it"
+        );
+    }
+}
+
+/// Milliseconds from the Unix epoch to `time`, for timestamps shared across
+/// threads.
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+pub(crate) struct Handler<P: OsqueryPlugin + Clone> {
+    registry: Arc<PluginMap<P>>,
     shutdown_flag: Arc<AtomicBool>,
+    /// Where `handle_shutdown()` records [`ShutdownReason::OsqueryRequested`]
+    shutdown_reason: Arc<AtomicU8>,
+    /// Updated with the time of every call from osquery, in Unix milliseconds
+    last_call: Arc<AtomicU64>,
+    clock: Arc<dyn Clock>,
+    metrics: Arc<dyn MetricsSink>,
+    /// Run before acknowledging osquery's shutdown RPC
+    shutdown_request_hook: Option<Arc<dyn Fn() + Send + Sync>>,
+    /// Give up on plugin calls that run longer than this
+    call_timeout: Option<Duration>,
+    /// Returned by `handle_options()`
+    options: Arc<osquery::InternalOptionList>,
 }
 
 impl<P: OsqueryPlugin + Clone> Handler<P> {
-    fn new(plugins: &[P], shutdown_flag: Arc<AtomicBool>) -> thrift::Result<Self> {
-        let mut reg: HashMap<String, HashMap<String, P>> = HashMap::new();
-        for var in Registry::VARIANTS {
-            reg.insert((*var).to_string(), HashMap::new());
+    pub(crate) fn new(plugins: &[P], shutdown_flag: Arc<AtomicBool>) -> thrift::Result<Self> {
+        Ok(Self::with_dispatch(
+            Arc::new(plugin_map(plugins)?),
+            shutdown_flag,
+        ))
+    }
+
+    /// Dispatch to a plugin table shared with the server.
+    pub(crate) fn with_dispatch(
+        registry: Arc<PluginMap<P>>,
+        shutdown_flag: Arc<AtomicBool>,
+    ) -> Self {
+        Handler {
+            registry,
+            shutdown_flag,
+            shutdown_reason: Arc::new(AtomicU8::new(0)),
+            last_call: Arc::new(AtomicU64::new(0)),
+            clock: Arc::new(SystemClock),
+            metrics: Arc::new(NoopMetricsSink),
+            shutdown_request_hook: None,
+            call_timeout: None,
+            options: Arc::new(osquery::InternalOptionList::new()),
         }
+    }
+
+    /// Record the time of each call in `last_call`, shared with the server.
+    pub(crate) fn with_last_call(mut self, last_call: Arc<AtomicU64>) -> Self {
+        self.last_call = last_call;
+        self
+    }
+
+    /// Read the time of each call from `clock`.
+    pub(crate) fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Record why shutdown was requested in `reason`, shared with the server.
+    pub(crate) fn with_shutdown_reason(mut self, reason: Arc<AtomicU8>) -> Self {
+        self.shutdown_reason = reason;
+        self
+    }
+
+    /// Report every plugin call to `metrics`.
+    pub(crate) fn with_metrics(mut self, metrics: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Answer with an error once a plugin call runs longer than `timeout`.
+    pub(crate) fn with_call_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.call_timeout = timeout;
+        self
+    }
+
+    /// Answer osquery's `options` RPC with `options`.
+    pub(crate) fn with_options(mut self, options: osquery::InternalOptionList) -> Self {
+        self.options = Arc::new(options);
+        self
+    }
+
+    /// Run `hook` in `handle_shutdown()` before replying to osquery.
+    pub(crate) fn with_shutdown_request_hook(
+        mut self,
+        hook: Option<Arc<dyn Fn() + Send + Sync>>,
+    ) -> Self {
+        self.shutdown_request_hook = hook;
+        self
+    }
+
+    /// Names of the plugins in `registry`, sorted.
+    #[cfg(feature = "debug-http")]
+    pub(crate) fn plugin_names(&self, registry: &str) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .registry
+            .get(registry_key(registry).as_str())
+            .map(|plugins| plugins.keys().cloned().collect())
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+}
+
+impl<P: OsqueryPlugin + Clone + 'static> Handler<P> {
+    /// Call `plugin`, giving up after `call_timeout` if one is set.
+    fn call_plugin(
+        &self,
+        plugin: &P,
+        item: &str,
+        request: osquery::ExtensionPluginRequest,
+    ) -> osquery::ExtensionResponse {
+        let ctx = CancellationToken::from_flag(self.shutdown_flag.clone());
+        let Some(timeout) = self.call_timeout else {
+            return plugin.handle_call_cancellable(request, &ctx);
+        };
 
-        for plugin in plugins.iter() {
-            reg.get_mut(plugin.registry().to_string().as_str())
-                .ok_or_thrift_err(|| format!("Failed to register plugin {}", plugin.name()))?
-                .insert(plugin.name(), plugin.clone());
+        let plugin = plugin.clone();
+        let (tx, rx) = std::sync::mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(plugin.handle_call_cancellable(request, &ctx));
+        });
+
+        match rx.recv_timeout(timeout) {
+            Ok(response) => response,
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                log::warn!(
+                    target: log_target::SERVER,
+                    "Call to {item} timed out after {timeout:?}"
+                );
+                ExtensionResponseEnum::Failure(format!("Call timed out after {timeout:?}")).into()
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                ExtensionResponseEnum::Failure(format!("Call to {item} panicked")).into()
+            }
         }
+    }
+}
 
-        Ok(Handler {
-            registry: reg,
-            shutdown_flag,
-        })
+/// The key a registry name is stored and looked up under in a [`PluginMap`],
+/// so `"Table"` or `" table"` from osquery still finds the table plugins.
+fn registry_key(name: &str) -> String {
+    name.trim().to_ascii_lowercase()
+}
+
+/// Index `plugins` by registry and name for dispatch.
+fn plugin_map<P: OsqueryPlugin + Clone>(plugins: &[P]) -> thrift::Result<PluginMap<P>> {
+    let mut reg: PluginMap<P> = HashMap::new();
+    for var in Registry::VARIANTS {
+        reg.insert(registry_key(var), HashMap::new());
     }
+
+    for plugin in plugins.iter() {
+        reg.get_mut(registry_key(&plugin.registry().to_string()).as_str())
+            .ok_or_thrift_err(|| format!("Failed to register plugin {}", plugin.name()))?
+            .insert(plugin.name(), plugin.clone());
+    }
+    Ok(reg)
 }
 
-impl<P: OsqueryPlugin + Clone> osquery::ExtensionSyncHandler for Handler<P> {
+impl<P: OsqueryPlugin + Clone + 'static> osquery::ExtensionSyncHandler for Handler<P> {
     fn handle_ping(&self) -> thrift::Result<osquery::ExtensionStatus> {
-        Ok(osquery::ExtensionStatus::default())
+        let unhealthy: Vec<String> = self
+            .registry
+            .values()
+            .flat_map(|plugins| plugins.values())
+            .filter_map(|plugin| {
+                let status = plugin.health();
+                match status.code {
+                    None | Some(0) => None,
+                    Some(code) => Some(format!(
+                        "{} ({}): {}",
+                        plugin.name(),
+                        code,
+                        status.message.unwrap_or_else(|| "unhealthy".to_string())
+                    )),
+                }
+            })
+            .collect();
+
+        if unhealthy.is_empty() {
+            return Ok(osquery::ExtensionStatus::default());
+        }
+
+        Ok(osquery::ExtensionStatus {
+            code: Some(1),
+            message: Some(format!("Unhealthy plugins: {}", unhealthy.join("; "))),
+            uuid: None,
+        })
     }
 
     fn handle_call(
@@ -479,17 +2057,21 @@ impl<P: OsqueryPlugin + Clone> osquery::ExtensionSyncHandler for Handler<P> {
         item: String,
         request: osquery::ExtensionPluginRequest,
     ) -> thrift::Result<osquery::ExtensionResponse> {
-        log::trace!("Registry: {registry}");
-        log::trace!("Item: {item}");
-        log::trace!("Request: {request:?}");
+        log::trace!(target: log_target::SERVER, "Registry: {registry}");
+        log::trace!(target: log_target::SERVER, "Item: {item}");
+        log::trace!(target: log_target::SERVER, "Request: {request:?}");
+        self.last_call
+            .store(unix_millis(self.clock.now()), Ordering::Release);
 
         let plugin = self
             .registry
-            .get(registry.as_str())
+            .get(registry_key(&registry).as_str())
             .ok_or_thrift_err(|| {
+                let mut available: Vec<&str> = self.registry.keys().map(String::as_str).collect();
+                available.sort_unstable();
                 format!(
-                    "Failed to get registry:{} from registries",
-                    registry.as_str()
+                    "Unknown registry '{registry}', available registries: {}",
+                    available.join(", ")
                 )
             })?
             .get(item.as_str())
@@ -501,25 +2083,41 @@ impl<P: OsqueryPlugin + Clone> osquery::ExtensionSyncHandler for Handler<P> {
                 )
             })?;
 
-        Ok(plugin.handle_call(request))
+        let started = Instant::now();
+        let response = self.call_plugin(plugin, &item, request);
+        let ok = response
+            .status
+            .as_ref()
+            .and_then(|status| status.code)
+            .is_none_or(|code| code == 0);
+        self.metrics
+            .on_call(&registry, &item, started.elapsed(), ok);
+
+        Ok(response)
     }
 
     fn handle_shutdown(&self) -> thrift::Result<()> {
-        log::debug!("Shutdown RPC received from osquery");
+        log::debug!(target: log_target::SERVER, "Shutdown RPC received from osquery");
+        ShutdownReason::OsqueryRequested.record_request(&self.shutdown_reason);
         self.shutdown_flag.store(true, Ordering::Release);
+
+        if let Some(hook) = &self.shutdown_request_hook {
+            if let Err(e) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| hook())) {
+                log::error!(target: log_target::SERVER, "Shutdown request hook panicked: {e:?}");
+            }
+        }
         Ok(())
     }
 }
 
-impl<P: OsqueryPlugin + Clone> osquery::ExtensionManagerSyncHandler for Handler<P> {
+impl<P: OsqueryPlugin + Clone + 'static> osquery::ExtensionManagerSyncHandler for Handler<P> {
     fn handle_extensions(&self) -> thrift::Result<osquery::InternalExtensionList> {
         // Extension management not supported - return empty list
         Ok(osquery::InternalExtensionList::new())
     }
 
     fn handle_options(&self) -> thrift::Result<osquery::InternalOptionList> {
-        // Extension options not supported - return empty list
-        Ok(osquery::InternalOptionList::new())
+        Ok(self.options.as_ref().clone())
     }
 
     fn handle_register_extension(
@@ -576,7 +2174,7 @@ impl<P: OsqueryPlugin + Clone> osquery::ExtensionManagerSyncHandler for Handler<
 #[allow(clippy::expect_used, clippy::panic)] // Tests are allowed to panic on setup failures
 mod tests {
     use super::*;
-    use crate::client::MockOsqueryClient;
+    use crate::client::{ClientOptions, MockOsqueryClient};
     use crate::plugin::Plugin;
     use crate::plugin::{ColumnDef, ColumnOptions, ColumnType, ReadOnlyTable, TablePlugin};
 
@@ -603,6 +2201,24 @@ mod tests {
         fn shutdown(&self) {}
     }
 
+    struct DupeTable;
+
+    impl ReadOnlyTable for DupeTable {
+        fn name(&self) -> String {
+            "dupe".to_string()
+        }
+
+        fn columns(&self) -> Vec<ColumnDef> {
+            vec![]
+        }
+
+        fn generate(&self, _request: crate::ExtensionPluginRequest) -> crate::ExtensionResponse {
+            crate::ExtensionResponse::new(osquery::ExtensionStatus::default(), vec![])
+        }
+
+        fn shutdown(&self) {}
+    }
+
     #[test]
     fn test_server_with_mock_client_creation() {
         let mock_client = MockOsqueryClient::new();
@@ -631,7 +2247,9 @@ mod tests {
             Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
 
         let plugin = Plugin::Table(TablePlugin::from_readonly_table(TestTable));
-        server.register_plugin(plugin);
+        server
+            .register_plugin(plugin)
+            .expect("plugin should register");
 
         assert_eq!(server.plugins.len(), 1);
     }
@@ -642,52 +2260,108 @@ mod tests {
         let mut server: Server<Plugin, MockOsqueryClient> =
             Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
 
-        server.register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)));
-        server.register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)));
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(DupeTable)))
+            .expect("plugin should register");
 
         assert_eq!(server.plugins.len(), 2);
     }
 
     #[test]
-    fn test_server_stop_handle_with_mock_client() {
+    fn test_server_register_plugins_in_one_call() {
         let mock_client = MockOsqueryClient::new();
-        let server: Server<Plugin, MockOsqueryClient> =
+        let mut server: Server<Plugin, MockOsqueryClient> =
             Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
 
-        assert!(server.is_running());
-
-        let handle = server.get_stop_handle();
-        assert!(handle.is_running());
-
-        handle.stop();
+        server
+            .register_plugins(vec![
+                Plugin::Table(TablePlugin::from_readonly_table(TestTable)),
+                Plugin::Table(TablePlugin::from_readonly_table(DupeTable)),
+                Plugin::static_table("third", Vec::new(), Vec::new),
+            ])
+            .expect("plugin should register");
 
-        assert!(!server.is_running());
-        assert!(!handle.is_running());
+        assert_eq!(server.plugins.len(), 3);
     }
 
     #[test]
-    fn test_server_stop_method_with_mock_client() {
+    fn test_server_register_plugin_chaining() {
         let mock_client = MockOsqueryClient::new();
-        let server: Server<Plugin, MockOsqueryClient> =
+        let mut server: Server<Plugin, MockOsqueryClient> =
             Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
 
-        assert!(server.is_running());
-        server.stop();
-        assert!(!server.is_running());
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register")
+            .register_plugins([Plugin::Table(TablePlugin::from_readonly_table(DupeTable))])
+            .expect("plugin should register");
+
+        assert_eq!(server.plugins.len(), 2);
     }
 
     #[test]
-    fn test_generate_registry_with_mock_client() {
+    fn test_register_plugin_rejects_duplicate_name() {
         let mock_client = MockOsqueryClient::new();
         let mut server: Server<Plugin, MockOsqueryClient> =
             Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
 
-        server.register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)));
+        let first = server.register_plugin(Plugin::readonly_table(DupeTable));
+        assert!(first.is_ok());
 
-        let registry = server.generate_registry();
-        assert!(registry.is_ok());
+        let second = server.register_plugin(Plugin::readonly_table(DupeTable));
+        assert!(matches!(
+            second,
+            Err(ServerError::DuplicatePlugin { ref registry, ref name })
+                if registry == "table" && name == "dupe"
+        ));
+        assert_eq!(server.plugins.len(), 1);
+    }
 
-        let registry = registry.ok();
+    #[test]
+    fn test_server_stop_handle_with_mock_client() {
+        let mock_client = MockOsqueryClient::new();
+        let server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+
+        assert!(server.is_running());
+
+        let handle = server.get_stop_handle();
+        assert!(handle.is_running());
+
+        handle.stop();
+
+        assert!(!server.is_running());
+        assert!(!handle.is_running());
+    }
+
+    #[test]
+    fn test_server_stop_method_with_mock_client() {
+        let mock_client = MockOsqueryClient::new();
+        let server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+
+        assert!(server.is_running());
+        server.stop();
+        assert!(!server.is_running());
+    }
+
+    #[test]
+    fn test_generate_registry_with_mock_client() {
+        let mock_client = MockOsqueryClient::new();
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+
+        let registry = server.generate_registry();
+        assert!(registry.is_ok());
+
+        let registry = registry.ok();
         assert!(registry.is_some());
 
         let registry = registry.unwrap_or_default();
@@ -695,6 +2369,32 @@ mod tests {
         assert!(registry.contains_key("table"));
     }
 
+    #[test]
+    fn test_generate_registry_includes_empty_registries() {
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", MockOsqueryClient::new());
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+
+        let registry = server
+            .generate_registry()
+            .expect("registry should generate");
+
+        let mut keys: Vec<&str> = registry.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["config", "logger", "table"]);
+        assert!(registry
+            .get("config")
+            .is_some_and(|routes| routes.is_empty()));
+        assert!(registry
+            .get("logger")
+            .is_some_and(|routes| routes.is_empty()));
+        assert!(registry
+            .get("table")
+            .is_some_and(|routes| routes.contains_key("test_table")));
+    }
+
     // ========================================================================
     // cleanup_socket() tests
     // ========================================================================
@@ -762,14 +2462,20 @@ mod tests {
 
     /// Test config plugin that tracks whether shutdown was called
     struct ShutdownTrackingConfigPlugin {
+        name: &'static str,
         shutdown_called: Arc<AtomicBool>,
     }
 
     impl ShutdownTrackingConfigPlugin {
         fn new() -> (Self, Arc<AtomicBool>) {
+            Self::named("shutdown_tracker")
+        }
+
+        fn named(name: &'static str) -> (Self, Arc<AtomicBool>) {
             let flag = Arc::new(AtomicBool::new(false));
             (
                 Self {
+                    name,
                     shutdown_called: Arc::clone(&flag),
                 },
                 flag,
@@ -779,7 +2485,7 @@ mod tests {
 
     impl ConfigPlugin for ShutdownTrackingConfigPlugin {
         fn name(&self) -> String {
-            "shutdown_tracker".to_string()
+            self.name.to_string()
         }
 
         fn gen_config(&self) -> Result<HashMap<String, String>, String> {
@@ -802,11 +2508,13 @@ mod tests {
             Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
 
         let (plugin, shutdown_flag) = ShutdownTrackingConfigPlugin::new();
-        server.register_plugin(Plugin::config(plugin));
+        server
+            .register_plugin(Plugin::config(plugin))
+            .expect("plugin should register");
 
         assert!(!shutdown_flag.load(Ordering::SeqCst));
 
-        server.notify_plugins_shutdown();
+        server.notify_plugins_shutdown(None);
 
         assert!(shutdown_flag.load(Ordering::SeqCst));
     }
@@ -817,19 +2525,25 @@ mod tests {
         let mut server: Server<Plugin, MockOsqueryClient> =
             Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
 
-        let (plugin1, shutdown_flag1) = ShutdownTrackingConfigPlugin::new();
-        let (plugin2, shutdown_flag2) = ShutdownTrackingConfigPlugin::new();
-        let (plugin3, shutdown_flag3) = ShutdownTrackingConfigPlugin::new();
+        let (plugin1, shutdown_flag1) = ShutdownTrackingConfigPlugin::named("tracker1");
+        let (plugin2, shutdown_flag2) = ShutdownTrackingConfigPlugin::named("tracker2");
+        let (plugin3, shutdown_flag3) = ShutdownTrackingConfigPlugin::named("tracker3");
 
-        server.register_plugin(Plugin::config(plugin1));
-        server.register_plugin(Plugin::config(plugin2));
-        server.register_plugin(Plugin::config(plugin3));
+        server
+            .register_plugin(Plugin::config(plugin1))
+            .expect("plugin should register");
+        server
+            .register_plugin(Plugin::config(plugin2))
+            .expect("plugin should register");
+        server
+            .register_plugin(Plugin::config(plugin3))
+            .expect("plugin should register");
 
         assert!(!shutdown_flag1.load(Ordering::SeqCst));
         assert!(!shutdown_flag2.load(Ordering::SeqCst));
         assert!(!shutdown_flag3.load(Ordering::SeqCst));
 
-        server.notify_plugins_shutdown();
+        server.notify_plugins_shutdown(None);
 
         // All plugins should have been notified
         assert!(shutdown_flag1.load(Ordering::SeqCst));
@@ -837,6 +2551,81 @@ mod tests {
         assert!(shutdown_flag3.load(Ordering::SeqCst));
     }
 
+    #[test]
+    fn test_loggers_flush_before_any_plugin_shuts_down() {
+        use crate::plugin::LoggerPlugin;
+
+        /// Records flush and shutdown calls into a log shared by all plugins
+        struct OrderedLogger {
+            name: &'static str,
+            fail_flush: bool,
+            events: Arc<Mutex<Vec<String>>>,
+        }
+
+        impl OrderedLogger {
+            fn record(&self, event: &str) {
+                if let Ok(mut events) = self.events.lock() {
+                    events.push(format!("{} {event}", self.name));
+                }
+            }
+        }
+
+        impl LoggerPlugin for OrderedLogger {
+            fn name(&self) -> String {
+                self.name.to_string()
+            }
+
+            fn log_string(&self, _message: &str) -> Result<(), String> {
+                Ok(())
+            }
+
+            fn flush_pending(&self) -> Result<(), String> {
+                self.record("flush");
+                if self.fail_flush {
+                    Err("collector unreachable".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+
+            fn shutdown(&self) {
+                self.record("shutdown");
+            }
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", MockOsqueryClient::new());
+        server
+            .register_plugin(Plugin::logger(OrderedLogger {
+                name: "first",
+                fail_flush: true,
+                events: events.clone(),
+            }))
+            .expect("plugin should register");
+        server
+            .register_plugin(Plugin::logger(OrderedLogger {
+                name: "second",
+                fail_flush: false,
+                events: events.clone(),
+            }))
+            .expect("plugin should register");
+
+        server.shutdown_and_cleanup();
+
+        // A failed flush is logged and doesn't stop the shutdown
+        let events = events.lock().expect("events lock poisoned");
+        assert_eq!(
+            *events,
+            vec![
+                "first flush",
+                "second flush",
+                "first shutdown",
+                "second shutdown"
+            ]
+        );
+    }
+
     #[test]
     fn test_notify_plugins_shutdown_empty_plugins() {
         let mock_client = MockOsqueryClient::new();
@@ -846,93 +2635,732 @@ mod tests {
         assert!(server.plugins.is_empty());
 
         // Should not panic with no plugins
-        server.notify_plugins_shutdown();
+        server.notify_plugins_shutdown(None);
+    }
+
+    #[test]
+    fn test_shutdown_deadline_bounds_slow_plugin_shutdown() {
+        use tempfile::tempdir;
+
+        struct HangingTable(Arc<AtomicBool>);
+
+        impl ReadOnlyTable for HangingTable {
+            fn name(&self) -> String {
+                "hanging".to_string()
+            }
+
+            fn columns(&self) -> Vec<ColumnDef> {
+                vec![ColumnDef::new(
+                    "id",
+                    ColumnType::Integer,
+                    ColumnOptions::DEFAULT,
+                )]
+            }
+
+            fn generate(
+                &self,
+                _request: crate::ExtensionPluginRequest,
+            ) -> crate::ExtensionResponse {
+                crate::ExtensionResponse::new(osquery::ExtensionStatus::default(), vec![])
+            }
+
+            fn shutdown(&self) {
+                thread::sleep(Duration::from_secs(5));
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let mut server = ticking_server(&socket_base.to_string_lossy());
+        let finished = Arc::new(AtomicBool::new(false));
+        server
+            .register_plugin(Plugin::readonly_table(HangingTable(finished.clone())))
+            .expect("plugin should register");
+        server.set_shutdown_deadline(Duration::from_millis(200));
+        server.start().expect("start should succeed");
+
+        let started = Instant::now();
+        server.stop();
+        server.shutdown_and_cleanup();
+
+        assert!(
+            started.elapsed() < Duration::from_secs(2),
+            "shutdown took {:?}",
+            started.elapsed()
+        );
+        // The plugin was left behind, still shutting down
+        assert!(!finished.load(Ordering::SeqCst));
     }
 
     // ========================================================================
-    // join_listener_thread() tests
+    // on_shutdown() tests
     // ========================================================================
 
     #[test]
-    fn test_join_listener_thread_no_thread() {
-        let mock_client = MockOsqueryClient::new();
+    fn test_on_shutdown_hook_runs_during_run() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client.expect_register_extension().returning(|_, _| {
+            Ok(osquery::ExtensionStatus {
+                code: Some(0),
+                message: Some("OK".to_string()),
+                uuid: Some(7),
+            })
+        });
+        // A failing ping ends the run loop immediately
+        mock_client.expect_ping().returning(|| {
+            Err(thrift::Error::from(std::io::Error::other(
+                "connection lost",
+            )))
+        });
+        mock_client
+            .expect_deregister_extension()
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
         let mut server: Server<Plugin, MockOsqueryClient> =
-            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+            Server::with_client(Some("test"), &socket_base_str, mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
 
-        // listener_thread is None by default
-        assert!(server.listener_thread.is_none());
+        let hook_called = Arc::new(AtomicBool::new(false));
+        let hook_flag = Arc::clone(&hook_called);
+        server.on_shutdown(move || hook_flag.store(true, Ordering::SeqCst));
 
-        // Should return immediately without panic
-        server.join_listener_thread();
+        server.run().expect("run should succeed");
+
+        assert!(hook_called.load(Ordering::SeqCst));
+        assert!(server.shutdown_hook.is_none());
+    }
+
+    /// Build a server with a mock client whose pings always succeed
+    fn ticking_server(socket_base_str: &str) -> Server<Plugin, MockOsqueryClient> {
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client
+            .expect_register_extension()
+            .returning(|_, _| Ok(osquery::ExtensionStatus::new(0, None, 8)));
+        mock_client
+            .expect_ping()
+            .returning(|| Ok(osquery::ExtensionStatus::default()));
+        mock_client
+            .expect_deregister_extension()
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), socket_base_str, mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        server.set_ping_interval(Duration::from_millis(1));
+        server
     }
 
     #[test]
-    fn test_join_listener_thread_finished_thread() {
-        let mock_client = MockOsqueryClient::new();
+    fn test_shutdown_request_hook_runs_before_reply() {
+        use osquery::ExtensionSyncHandler;
+
+        let shutdown_flag = Arc::new(AtomicBool::new(false));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorder = seen.clone();
+        let flag = shutdown_flag.clone();
+        let hook: Arc<dyn Fn() + Send + Sync> = Arc::new(move || {
+            if let Ok(mut seen) = recorder.lock() {
+                seen.push(flag.load(Ordering::Acquire));
+            }
+        });
+
+        let plugins = vec![Plugin::Table(TablePlugin::from_readonly_table(TestTable))];
+        let handler = Handler::new(&plugins, shutdown_flag.clone())
+            .expect("handler should build")
+            .with_shutdown_request_hook(Some(hook));
+
+        handler.handle_shutdown().expect("shutdown should succeed");
+
+        // The hook ran exactly once, before handle_shutdown returned, and saw
+        // the shutdown already requested
+        assert_eq!(*seen.lock().expect("seen lock poisoned"), vec![true]);
+        assert!(shutdown_flag.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn test_shutdown_request_hook_panic_is_caught() {
+        use osquery::ExtensionSyncHandler;
+
+        let hook: Arc<dyn Fn() + Send + Sync> = Arc::new(|| panic!("flush failure"));
+        let handler = Handler::<Plugin>::new(&[], Arc::new(AtomicBool::new(false)))
+            .expect("handler should build")
+            .with_shutdown_request_hook(Some(hook));
+
+        assert!(handler.handle_shutdown().is_ok());
+    }
+
+    #[test]
+    fn test_handle_call_normalizes_registry_name() {
+        use osquery::ExtensionSyncHandler;
+
+        let plugins = vec![Plugin::Table(TablePlugin::from_readonly_table(TestTable))];
+        let handler =
+            Handler::new(&plugins, Arc::new(AtomicBool::new(false))).expect("handler should build");
+        let generate = || BTreeMap::from([("action".to_string(), "generate".to_string())]);
+
+        let response = handler
+            .handle_call(" Table".to_string(), "test_table".to_string(), generate())
+            .expect("mixed-case registry should dispatch");
+        assert_eq!(response.status.and_then(|s| s.code), Some(0));
+
+        let err =
+            match handler.handle_call("tables".to_string(), "test_table".to_string(), generate()) {
+                Err(thrift::Error::Application(e)) => e.message,
+                other => format!("{other:?}"),
+            };
+        assert!(
+            err.contains("Unknown registry 'tables', available registries: config, logger, table"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn test_idle_hook_fires_once_until_next_call() {
+        use osquery::ExtensionSyncHandler;
+        use std::sync::atomic::AtomicUsize;
+
         let mut server: Server<Plugin, MockOsqueryClient> =
-            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+            Server::with_client(Some("test"), "/tmp/test.sock", MockOsqueryClient::new());
+        let plugins = vec![Plugin::Table(TablePlugin::from_readonly_table(TestTable))];
+        let handler = Handler::new(&plugins, Arc::new(AtomicBool::new(false)))
+            .expect("handler should build")
+            .with_last_call(server.last_call.clone());
+
+        let idle_count = Arc::new(AtomicUsize::new(0));
+        let idle = Arc::clone(&idle_count);
+        server
+            .set_idle_timeout(Duration::from_secs(60))
+            .on_idle(move || {
+                idle.fetch_add(1, Ordering::SeqCst);
+            });
+
+        let generate = || BTreeMap::from([("action".to_string(), "generate".to_string())]);
+        let call = || {
+            handler
+                .handle_call("table".to_string(), "test_table".to_string(), generate())
+                .expect("call should succeed");
+        };
 
-        // Create a thread that finishes immediately
-        let thread = thread::spawn(|| {
-            // Thread exits immediately
+        // A call from osquery keeps the extension active
+        call();
+        server.check_idle();
+        assert_eq!(idle_count.load(Ordering::SeqCst), 0);
+
+        // Two minutes without calls crosses the threshold, reported once
+        let two_minutes_ago = unix_millis(SystemTime::now()) - 120_000;
+        server.last_call.store(two_minutes_ago, Ordering::SeqCst);
+        server.check_idle();
+        server.check_idle();
+        assert_eq!(idle_count.load(Ordering::SeqCst), 1);
+
+        // The next call resets the timer, so a later idle period is reported again
+        call();
+        server.check_idle();
+        assert_eq!(idle_count.load(Ordering::SeqCst), 1);
+        server.last_call.store(two_minutes_ago, Ordering::SeqCst);
+        server.check_idle();
+        assert_eq!(idle_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_metrics_sink_records_plugin_calls() {
+        use osquery::ExtensionSyncHandler;
+
+        type Call = (String, String, Duration, bool);
+
+        #[derive(Clone, Default)]
+        struct RecordingSink(Arc<Mutex<Vec<Call>>>);
+
+        impl MetricsSink for RecordingSink {
+            fn on_call(&self, registry: &str, item: &str, duration: Duration, ok: bool) {
+                if let Ok(mut calls) = self.0.lock() {
+                    calls.push((registry.to_string(), item.to_string(), duration, ok));
+                }
+            }
+        }
+
+        let sink = RecordingSink::default();
+        let plugins = vec![Plugin::Table(TablePlugin::from_readonly_table(TestTable))];
+        let handler = Handler::new(&plugins, Arc::new(AtomicBool::new(false)))
+            .expect("handler should build")
+            .with_metrics(Arc::new(sink.clone()));
+
+        let call = |action: &str| {
+            handler
+                .handle_call(
+                    "table".to_string(),
+                    "test_table".to_string(),
+                    BTreeMap::from([("action".to_string(), action.to_string())]),
+                )
+                .expect("call should succeed");
+        };
+        call("generate");
+        call("bogus");
+
+        let calls = sink.0.lock().expect("sink lock").clone();
+        let summary: Vec<_> = calls
+            .iter()
+            .map(|(registry, item, _, ok)| (registry.as_str(), item.as_str(), *ok))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("table", "test_table", true),
+                ("table", "test_table", false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_call_timeout_answers_slow_tables_promptly() {
+        use osquery::ExtensionSyncHandler;
+
+        struct SlowTable;
+
+        impl ReadOnlyTable for SlowTable {
+            fn name(&self) -> String {
+                "slow".to_string()
+            }
+
+            fn columns(&self) -> Vec<ColumnDef> {
+                vec![]
+            }
+
+            fn generate(
+                &self,
+                _request: crate::ExtensionPluginRequest,
+            ) -> crate::ExtensionResponse {
+                thread::sleep(Duration::from_secs(2));
+                crate::ExtensionResponse::new(osquery::ExtensionStatus::default(), vec![])
+            }
+
+            fn shutdown(&self) {}
+        }
+
+        let plugins = vec![
+            Plugin::Table(TablePlugin::from_readonly_table(SlowTable)),
+            Plugin::Table(TablePlugin::from_readonly_table(TestTable)),
+        ];
+        let handler = Handler::new(&plugins, Arc::new(AtomicBool::new(false)))
+            .expect("handler should build")
+            .with_call_timeout(Some(Duration::from_millis(100)));
+        let generate = || BTreeMap::from([("action".to_string(), "generate".to_string())]);
+
+        let started = Instant::now();
+        let response = handler
+            .handle_call("table".to_string(), "slow".to_string(), generate())
+            .expect("call should return a response");
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(response.status.and_then(|s| s.code), Some(1));
+        let message = response
+            .response
+            .and_then(|rows| rows.first().and_then(|row| row.get("message")).cloned());
+        assert_eq!(message.as_deref(), Some("Call timed out after 100ms"));
+
+        // Fast tables are unaffected
+        let response = handler
+            .handle_call("table".to_string(), "test_table".to_string(), generate())
+            .expect("call should return a response");
+        assert_eq!(response.status.and_then(|s| s.code), Some(0));
+    }
+
+    #[test]
+    fn test_generate_stops_once_shutdown_is_requested() {
+        use crate::plugin::{QueryContext, RowIterator, TableError};
+        use osquery::ExtensionSyncHandler;
+
+        /// Works until its token is cancelled, then reports how far it got.
+        struct EndlessTable;
+
+        impl ReadOnlyTable for EndlessTable {
+            fn name(&self) -> String {
+                "endless".to_string()
+            }
+
+            fn columns(&self) -> Vec<ColumnDef> {
+                vec![ColumnDef::new(
+                    "status",
+                    ColumnType::Text,
+                    ColumnOptions::DEFAULT,
+                )]
+            }
+
+            fn generate(
+                &self,
+                _request: crate::ExtensionPluginRequest,
+            ) -> crate::ExtensionResponse {
+                crate::ExtensionResponse::new(osquery::ExtensionStatus::default(), vec![])
+            }
+
+            fn generate_rows(
+                &self,
+                query: &QueryContext<'_>,
+            ) -> Result<RowIterator<'_>, TableError> {
+                while !query.is_cancelled() {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                Ok(Box::new(std::iter::once(BTreeMap::from([(
+                    "status".to_string(),
+                    "cancelled".to_string(),
+                )]))))
+            }
+
+            fn shutdown(&self) {}
+        }
+
+        let shutdown_flag = Arc::new(AtomicBool::new(false));
+        let plugins = vec![Plugin::Table(TablePlugin::from_readonly_table(
+            EndlessTable,
+        ))];
+        let handler = Handler::new(&plugins, shutdown_flag.clone()).expect("handler should build");
+
+        let query = thread::spawn(move || {
+            handler.handle_call(
+                "table".to_string(),
+                "endless".to_string(),
+                BTreeMap::from([("action".to_string(), "generate".to_string())]),
+            )
         });
+        thread::sleep(Duration::from_millis(50));
+        assert!(!query.is_finished());
+
+        shutdown_flag.store(true, Ordering::Release);
+        let started = Instant::now();
+        let response = query
+            .join()
+            .expect("query thread should not panic")
+            .expect("call should return a response");
+        assert!(started.elapsed() < Duration::from_secs(1));
+        let status = response
+            .response
+            .and_then(|rows| rows.first().and_then(|row| row.get("status")).cloned());
+        assert_eq!(status.as_deref(), Some("cancelled"));
+    }
 
-        // Wait a bit for thread to finish
-        thread::sleep(Duration::from_millis(10));
+    #[test]
+    fn test_internal_table_reports_server_stats() {
+        use tempfile::tempdir;
 
-        server.listener_thread = Some(thread);
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let mut server = ticking_server(&socket_base.to_string_lossy());
+        server.set_internal_table(true);
 
-        // Should join successfully
-        server.join_listener_thread();
+        let handle = server.get_stop_handle();
+        let stopper = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            handle.stop();
+        });
+        server.run().expect("run should succeed");
+        stopper.join().expect("stopper thread should not panic");
 
-        // Thread should have been taken
-        assert!(server.listener_thread.is_none());
+        assert!(server
+            .registry
+            .get("table")
+            .is_some_and(|tables| tables.contains_key("test_internal")));
+
+        let table = server
+            .plugins
+            .iter()
+            .find(|plugin| plugin.name() == "test_internal")
+            .expect("internal table should be registered");
+        let response = table.handle_call(BTreeMap::from([(
+            "action".to_string(),
+            "generate".to_string(),
+        )]));
+        let row = response
+            .response
+            .and_then(|rows| rows.into_iter().next())
+            .expect("internal table should return a row");
+        let column = |name: &str| -> u64 {
+            row.get(name)
+                .and_then(|value| value.parse().ok())
+                .expect("column should be a number")
+        };
+        assert!(column("uptime_seconds") < 60);
+        assert!(column("ping_count") > 0);
+        assert!(column("last_call_time") > 0);
     }
 
-    // ========================================================================
-    // wake_listener() tests
-    // ========================================================================
+    #[test]
+    fn test_internal_table_uptime_follows_clock() {
+        use crate::clock::MockClock;
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let started = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = MockClock::new(started);
+        let mut server = ticking_server(&socket_base.to_string_lossy());
+        server.set_clock(clock.clone()).set_internal_table(true);
+        server.start().expect("start should succeed");
+
+        let internal_row = |server: &Server<Plugin, MockOsqueryClient>| {
+            server
+                .plugins
+                .iter()
+                .find(|plugin| plugin.name() == "test_internal")
+                .and_then(|table| {
+                    table
+                        .handle_call(BTreeMap::from([(
+                            "action".to_string(),
+                            "generate".to_string(),
+                        )]))
+                        .response
+                })
+                .and_then(|rows| rows.into_iter().next())
+                .expect("internal table should return a row")
+        };
+
+        let row = internal_row(&server);
+        assert_eq!(row.get("uptime_seconds").map(String::as_str), Some("0"));
+        assert_eq!(
+            row.get("last_call_time").map(String::as_str),
+            Some("1700000000")
+        );
+
+        clock.advance(Duration::from_secs(90));
+        let row = internal_row(&server);
+        assert_eq!(row.get("uptime_seconds").map(String::as_str), Some("90"));
+
+        server.stop();
+        server.shutdown_and_cleanup();
+    }
 
     #[test]
-    fn test_wake_listener_no_path() {
-        let mock_client = MockOsqueryClient::new();
-        let server: Server<Plugin, MockOsqueryClient> =
-            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+    fn test_build_info_table_reports_configured_metadata() {
+        use tempfile::tempdir;
 
-        // listen_path is None by default
-        assert!(server.listen_path.is_none());
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let mut server = ticking_server(&socket_base.to_string_lossy());
+        server.set_build_info([
+            ("git_sha", "4f2a9c1"),
+            ("build_time", "2026-10-16T12:00:00Z"),
+        ]);
+        server.start().expect("start should succeed");
+
+        assert!(server
+            .registry
+            .get("table")
+            .is_some_and(|tables| tables.contains_key("test_build_info")));
+
+        let rows = server
+            .plugins
+            .iter()
+            .find(|plugin| plugin.name() == "test_build_info")
+            .and_then(|table| {
+                table
+                    .handle_call(BTreeMap::from([(
+                        "action".to_string(),
+                        "generate".to_string(),
+                    )]))
+                    .response
+            })
+            .expect("build info table should return rows");
+        let info: Vec<(&str, &str)> = rows
+            .iter()
+            .filter_map(|row| Some((row.get("key")?.as_str(), row.get("value")?.as_str())))
+            .collect();
+        assert_eq!(
+            info,
+            vec![
+                ("git_sha", "4f2a9c1"),
+                ("build_time", "2026-10-16T12:00:00Z")
+            ]
+        );
 
-        // Should not panic with no path
-        server.wake_listener();
+        server.stop();
+        server.shutdown_and_cleanup();
     }
 
     #[test]
-    fn test_wake_listener_with_path() {
-        use std::os::unix::net::UnixListener;
+    fn test_internal_table_is_off_by_default() {
+        let mut server = ticking_server("/tmp/test.sock");
+        assert!(server.internal_table.is_none());
+
+        server.set_internal_table(true).set_internal_table(false);
+        assert!(server.internal_table.is_none());
+    }
+
+    #[test]
+    fn test_on_tick_runs_each_loop_iteration() {
+        use std::sync::atomic::AtomicUsize;
         use tempfile::tempdir;
 
         let temp_dir = tempdir().expect("Failed to create temp dir");
-        let socket_path = temp_dir.path().join("test.sock");
-        let socket_path_str = socket_path.to_string_lossy().to_string();
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let mut server = ticking_server(&socket_base.to_string_lossy());
 
-        // Create a Unix listener on the socket
-        let listener = UnixListener::bind(&socket_path).expect("Failed to bind listener");
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let tick_count = Arc::clone(&ticks);
+        let handle = server.get_stop_handle();
+        server.on_tick(move || {
+            if tick_count.fetch_add(1, Ordering::SeqCst) + 1 == 5 {
+                handle.stop();
+            }
+        });
 
-        // Set non-blocking so accept doesn't hang
-        listener
-            .set_nonblocking(true)
-            .expect("Failed to set non-blocking");
+        server.run().expect("run should succeed");
+
+        assert_eq!(ticks.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn test_on_tick_panic_does_not_stop_loop() {
+        use std::sync::atomic::AtomicUsize;
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let mut server = ticking_server(&socket_base.to_string_lossy());
+
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let tick_count = Arc::clone(&ticks);
+        let handle = server.get_stop_handle();
+        server.on_tick(move || {
+            let tick = tick_count.fetch_add(1, Ordering::SeqCst) + 1;
+            if tick == 3 {
+                handle.stop();
+            }
+            if tick == 1 {
+                panic!("tick failed");
+            }
+        });
+
+        server.run().expect("run should succeed");
+
+        assert_eq!(ticks.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_shutdown_hook_runs_only_once() {
+        use std::sync::atomic::AtomicUsize;
 
         let mock_client = MockOsqueryClient::new();
         let mut server: Server<Plugin, MockOsqueryClient> =
             Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
 
-        server.listen_path = Some(socket_path_str);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        server.on_shutdown(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
 
-        // Call wake_listener
-        server.wake_listener();
+        server.run_shutdown_hook(None);
+        server.run_shutdown_hook(None);
 
-        // Verify connection was received (or would have been if blocking)
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_shutdown_hook_panic_is_caught() {
+        let mock_client = MockOsqueryClient::new();
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+
+        server.on_shutdown(|| panic!("hook failure"));
+
+        // Should not propagate the panic
+        server.run_shutdown_hook(None);
+    }
+
+    // ========================================================================
+    // join_listener_thread() tests
+    // ========================================================================
+
+    #[test]
+    fn test_join_listener_thread_no_thread() {
+        let mock_client = MockOsqueryClient::new();
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+
+        // listener_thread is None by default
+        assert!(server.listener_thread.is_none());
+
+        // Should return immediately without panic
+        server.join_listener_thread();
+    }
+
+    #[test]
+    fn test_join_listener_thread_finished_thread() {
+        let mock_client = MockOsqueryClient::new();
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+
+        // Create a thread that finishes immediately
+        let thread = thread::spawn(|| {
+            // Thread exits immediately
+        });
+
+        // Wait a bit for thread to finish
+        thread::sleep(Duration::from_millis(10));
+
+        server.listener_thread = Some(thread);
+
+        // Should join successfully
+        server.join_listener_thread();
+
+        // Thread should have been taken
+        assert!(server.listener_thread.is_none());
+    }
+
+    // ========================================================================
+    // wake_listener() tests
+    // ========================================================================
+
+    #[test]
+    fn test_wake_listener_no_path() {
+        let mock_client = MockOsqueryClient::new();
+        let server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+
+        // listen_path is None by default
+        assert!(server.listen_path.is_none());
+
+        // Should not panic with no path
+        server.wake_listener();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wake_listener_with_path() {
+        use std::os::unix::net::UnixListener;
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_path = temp_dir.path().join("test.sock");
+        let socket_path_str = socket_path.to_string_lossy().to_string();
+
+        // Create a Unix listener on the socket
+        let listener = UnixListener::bind(&socket_path).expect("Failed to bind listener");
+
+        // Set non-blocking so accept doesn't hang
+        listener
+            .set_nonblocking(true)
+            .expect("Failed to set non-blocking");
+
+        let mock_client = MockOsqueryClient::new();
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+
+        server.listen_path = Some(socket_path_str);
+
+        // Call wake_listener
+        server.wake_listener();
+
+        // Verify connection was received (or would have been if blocking)
         // The connection attempt is best-effort, so we just verify no panic
         // and that accept would have received something if blocking
         match listener.accept() {
@@ -949,6 +3377,1751 @@ mod tests {
         }
     }
 
+    // ========================================================================
+    // ServerError tests
+    // ========================================================================
+
+    #[cfg(unix)]
+    #[test]
+    fn test_new_refused_connection_is_connect_error() {
+        use std::os::unix::net::UnixListener;
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_path = temp_dir.path().join("stale.sock");
+
+        // Leave a socket file behind with nobody listening on it
+        drop(UnixListener::bind(&socket_path).expect("Failed to bind listener"));
+
+        let result = Server::<Plugin>::new(None, &socket_path.to_string_lossy());
+        assert!(matches!(
+            result,
+            Err(ServerError::Connect(ref e)) if e.kind() == std::io::ErrorKind::ConnectionRefused
+        ));
+    }
+
+    #[test]
+    fn test_new_rejects_overlong_socket_path() {
+        let socket_path = format!("/tmp/{}/osquery.em", "ci-runner-workspace".repeat(10));
+
+        let Err(ServerError::Connect(e)) = Server::<Plugin>::new(None, &socket_path) else {
+            panic!("an overlong socket path should be rejected");
+        };
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(
+            e.to_string()
+                .starts_with("socket path too long for platform, max "),
+            "{e}"
+        );
+    }
+
+    #[test]
+    fn test_new_missing_socket_is_connect_error() {
+        let result = Server::<Plugin>::new(None, "/nonexistent/path/osquery.sock");
+        assert!(matches!(
+            result,
+            Err(ServerError::Connect(ref e)) if e.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_console_handler_stops_run_loop() {
+        let mock_client = MockOsqueryClient::new();
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+        assert!(!server.should_shutdown());
+
+        // Simulate the console delivering CTRL_C to the installed handler
+        let mut handler =
+            console_handler(server.shutdown_flag.clone(), server.signal_received.clone());
+        handler();
+
+        assert!(server.should_shutdown());
+        assert_eq!(server.last_shutdown_reason(), Some(ShutdownReason::Signal));
+        // run_loop() checks the flag before pinging, so it returns at once
+        server.run_loop();
+    }
+
+    #[test]
+    fn test_run_without_plugins_fails() {
+        let mock_client = MockOsqueryClient::new();
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+
+        assert!(matches!(server.run(), Err(ServerError::NoPlugins)));
+    }
+
+    #[test]
+    fn test_start_twice_fails() {
+        let mock_client = MockOsqueryClient::new();
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        server.started = true;
+
+        assert!(matches!(server.start(), Err(ServerError::AlreadyStarted)));
+    }
+
+    #[test]
+    fn test_uuid_none_before_start() {
+        let mock_client = MockOsqueryClient::new();
+        let server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+
+        assert_eq!(server.uuid(), None);
+        assert_eq!(server.get_stop_handle().uuid(), None);
+    }
+
+    #[test]
+    fn test_uuid_available_after_start() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client.expect_register_extension().returning(|_, _| {
+            Ok(osquery::ExtensionStatus {
+                code: Some(0),
+                message: Some("OK".to_string()),
+                uuid: Some(42),
+            })
+        });
+        mock_client
+            .expect_deregister_extension()
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), &socket_base_str, mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        let handle = server.get_stop_handle();
+
+        server.start().expect("start should succeed");
+
+        assert_eq!(server.uuid(), Some(42));
+        assert_eq!(handle.uuid(), Some(42));
+
+        server.stop();
+        server.shutdown_and_cleanup();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_boxed_decorator_client() {
+        use std::sync::atomic::AtomicUsize;
+        use tempfile::tempdir;
+
+        /// Counts pings on their way to the wrapped client
+        struct CountingClient {
+            inner: ThriftClient,
+            pings: Arc<AtomicUsize>,
+        }
+
+        impl OsqueryClient for CountingClient {
+            fn register_extension(
+                &mut self,
+                info: osquery::InternalExtensionInfo,
+                registry: osquery::ExtensionRegistry,
+            ) -> thrift::Result<osquery::ExtensionStatus> {
+                self.inner.register_extension(info, registry)
+            }
+
+            fn deregister_extension(
+                &mut self,
+                uuid: osquery::ExtensionRouteUUID,
+            ) -> thrift::Result<osquery::ExtensionStatus> {
+                self.inner.deregister_extension(uuid)
+            }
+
+            fn ping(&mut self) -> thrift::Result<osquery::ExtensionStatus> {
+                self.pings.fetch_add(1, Ordering::SeqCst);
+                OsqueryClient::ping(&mut self.inner)
+            }
+
+            fn query(&mut self, sql: String) -> thrift::Result<crate::ExtensionResponse> {
+                self.inner.query(sql)
+            }
+
+            fn get_query_columns(
+                &mut self,
+                sql: String,
+            ) -> thrift::Result<crate::ExtensionResponse> {
+                self.inner.get_query_columns(sql)
+            }
+        }
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_path = temp_dir.path().join("osquery.em");
+        let socket_path = socket_path.to_string_lossy().to_string();
+
+        // Stand in for osquery with an extension manager that has no plugins
+        let manager = Handler::<Plugin>::new(&[], Arc::new(AtomicBool::new(false)))
+            .expect("handler should build");
+        let manager_path = socket_path.clone();
+        thread::spawn(move || {
+            let processor = osquery::ExtensionManagerSyncProcessor::new(manager);
+            let _ = transport::listen(
+                processor,
+                &manager_path,
+                1,
+                Protocol::Binary,
+                BufferSizes::default(),
+            );
+        });
+
+        let start = Instant::now();
+        let inner = loop {
+            match ThriftClient::new(&socket_path, Duration::from_secs(1)) {
+                Ok(client) => break client,
+                Err(e) if start.elapsed() > Duration::from_secs(5) => {
+                    panic!("fake osquery never came up: {e}")
+                }
+                Err(_) => thread::sleep(Duration::from_millis(10)),
+            }
+        };
+
+        let pings = Arc::new(AtomicUsize::new(0));
+        let client = CountingClient {
+            inner,
+            pings: pings.clone(),
+        };
+
+        let mut server =
+            Server::<Plugin, _>::with_boxed_client(Some("test"), &socket_path, Box::new(client));
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        server.set_ping_interval(Duration::from_millis(1));
+        let handle = server.get_stop_handle();
+        let ticks = pings.clone();
+        server.on_tick(move || {
+            if ticks.load(Ordering::SeqCst) >= 3 {
+                handle.stop();
+            }
+        });
+
+        server.run().expect("server should run");
+        assert_eq!(pings.load(Ordering::SeqCst), 3);
+    }
+
+    /// Records the target and message of every log record
+    struct CapturingLogger(Mutex<Vec<(String, String)>>);
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            if let Ok(mut records) = self.0.lock() {
+                records.push((record.target().to_string(), record.args().to_string()));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static CAPTURED_LOGS: CapturingLogger = CapturingLogger(Mutex::new(Vec::new()));
+
+    /// Install `CAPTURED_LOGS` as the process-wide logger.
+    fn capture_logs() {
+        // A logger can only be installed once per process
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+        INSTALL.call_once(|| {
+            if log::set_logger(&CAPTURED_LOGS).is_ok() {
+                log::set_max_level(log::LevelFilter::Trace);
+            }
+        });
+    }
+
+    #[test]
+    fn test_plugins_needing_newer_osquery_are_not_registered() {
+        use tempfile::tempdir;
+
+        /// Needs a newer osquery than the mock reports
+        struct NewTable;
+
+        impl ReadOnlyTable for NewTable {
+            fn name(&self) -> String {
+                "new_table".to_string()
+            }
+
+            fn columns(&self) -> Vec<ColumnDef> {
+                vec![]
+            }
+
+            fn generate(
+                &self,
+                _request: crate::ExtensionPluginRequest,
+            ) -> crate::ExtensionResponse {
+                crate::ExtensionResponse::new(osquery::ExtensionStatus::default(), vec![])
+            }
+
+            fn min_osquery_version(&self) -> Option<semver::Version> {
+                Some(semver::Version::new(5, 10, 0))
+            }
+
+            fn shutdown(&self) {}
+        }
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+
+        let registered = Arc::new(Mutex::new(None));
+        let seen = registered.clone();
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client
+            .expect_query()
+            .withf(|sql| sql == "SELECT version FROM osquery_info")
+            .returning(|_| {
+                Ok(crate::ExtensionResponse::new(
+                    osquery::ExtensionStatus::default(),
+                    vec![BTreeMap::from([(
+                        "version".to_string(),
+                        "5.4.0".to_string(),
+                    )])],
+                ))
+            });
+        mock_client
+            .expect_register_extension()
+            .returning(move |_, registry| {
+                if let Ok(mut seen) = seen.lock() {
+                    *seen = Some(registry);
+                }
+                Ok(osquery::ExtensionStatus::new(0, None, 1))
+            });
+        mock_client
+            .expect_deregister_extension()
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), &socket_base_str, mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register")
+            .register_plugin(Plugin::readonly_table(NewTable))
+            .expect("plugin should register");
+        server.start().expect("start should succeed");
+        server.stop();
+        server.shutdown_and_cleanup();
+
+        let registry = registered
+            .lock()
+            .expect("registry lock poisoned")
+            .take()
+            .expect("extension should be registered");
+        let tables: Vec<&String> = registry
+            .get("table")
+            .map(|routes| routes.keys().collect())
+            .unwrap_or_default();
+        assert_eq!(tables, vec!["test_table"]);
+    }
+
+    #[test]
+    fn test_parse_osquery_version() {
+        assert_eq!(
+            parse_osquery_version("5.12.1"),
+            Some(semver::Version::new(5, 12, 1))
+        );
+        assert_eq!(
+            parse_osquery_version("5.12.1-10-gabcdef"),
+            Some(semver::Version::new(5, 12, 1))
+        );
+        assert_eq!(
+            parse_osquery_version("4.9"),
+            Some(semver::Version::new(4, 9, 0))
+        );
+        assert_eq!(parse_osquery_version("unknown"), None);
+    }
+
+    #[test]
+    fn test_lifecycle_logs_use_server_target() {
+        use tempfile::tempdir;
+
+        capture_logs();
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client
+            .expect_register_extension()
+            .returning(|_, _| Ok(osquery::ExtensionStatus::new(0, None, 1)));
+        mock_client
+            .expect_deregister_extension()
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("log_target_test"), &socket_base_str, mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        server.start().expect("start should succeed");
+        server.stop();
+        server.shutdown_and_cleanup();
+
+        let records = CAPTURED_LOGS.0.lock().expect("log lock poisoned");
+        let registered = records
+            .iter()
+            .find(|(_, message)| message.contains("registering extension log_target_test"));
+        assert_eq!(
+            registered.map(|(target, _)| target.as_str()),
+            Some(log_target::SERVER)
+        );
+        assert!(records
+            .iter()
+            .any(|(target, message)| target == log_target::SERVER && message == "Shutting down"));
+        assert!(records
+            .iter()
+            .all(|(target, _)| !target.starts_with("osquery_rust_ng")));
+    }
+
+    #[test]
+    fn test_cleanup_socket_tolerates_concurrent_removal() {
+        use std::fs::File;
+        use tempfile::tempdir;
+
+        capture_logs();
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("racy.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), &socket_base_str, MockOsqueryClient::new());
+        server.uuid = Some(9);
+        let socket_path = format!("{socket_base_str}.9");
+
+        // Another process (e.g. osquery restarting) removes the socket while
+        // we clean up; whichever removal loses must be a quiet no-op
+        for _ in 0..50 {
+            File::create(&socket_path).expect("Failed to create test socket file");
+            let racer_path = socket_path.clone();
+            let racer = thread::spawn(move || {
+                let _ = std::fs::remove_file(racer_path);
+            });
+            server.cleanup_socket();
+            racer.join().expect("racing thread panicked");
+            assert!(!std::path::Path::new(&socket_path).exists());
+        }
+
+        let records = CAPTURED_LOGS.0.lock().expect("log lock poisoned");
+        assert!(!records.iter().any(|(_, message)| message
+            .starts_with(&format!("Failed to remove socket file {socket_path}"))));
+    }
+
+    #[test]
+    fn test_drop_started_server_deregisters_once() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client
+            .expect_register_extension()
+            .returning(|_, _| Ok(osquery::ExtensionStatus::new(0, None, 7)));
+        mock_client
+            .expect_deregister_extension()
+            .withf(|uuid| *uuid == 7)
+            .times(1)
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), &socket_base_str, mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        let handle = server.get_stop_handle();
+        server.start().expect("start should succeed");
+
+        // The mock verifies `times(1)` when it is dropped along with the server
+        drop(server);
+        assert!(!handle.is_running());
+        assert!(!temp_dir.path().join("osquery.sock.7").exists());
+    }
+
+    /// Connect to the extension's socket at `path` once its listener is up.
+    fn connect_to_listener(path: &str, protocol: Protocol) -> ThriftClient {
+        let start = Instant::now();
+        loop {
+            match ThriftClient::with_options(
+                path,
+                Duration::from_secs(1),
+                ClientOptions::default().set_protocol(protocol),
+            ) {
+                Ok(client) => break client,
+                Err(e) if start.elapsed() > Duration::from_secs(5) => {
+                    panic!("listener never came up: {e}")
+                }
+                Err(_) => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+    }
+
+    /// A started server listening at `<socket_base>.<uuid>`.
+    fn listening_server(
+        socket_base: &str,
+        uuid: u16,
+        configure: impl FnOnce(&mut Server<Plugin, MockOsqueryClient>),
+    ) -> Server<Plugin, MockOsqueryClient> {
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client
+            .expect_register_extension()
+            .returning(move |_, _| Ok(osquery::ExtensionStatus::new(0, None, i64::from(uuid))));
+        mock_client
+            .expect_deregister_extension()
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), socket_base, mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        configure(&mut server);
+        server.start().expect("start should succeed");
+        server
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_worker_threads_limit_concurrent_connections() {
+        use std::sync::mpsc;
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+        let mut server = listening_server(&socket_base_str, 3, |server| {
+            server.set_worker_threads(1);
+        });
+
+        // The only worker stays busy with the first connection while it is open
+        let listen_path = format!("{socket_base_str}.3");
+        let mut first = connect_to_listener(&listen_path, Protocol::Binary);
+        assert!(OsqueryClient::ping(&mut first).is_ok());
+
+        let (tx, rx) = mpsc::channel();
+        let second_path = listen_path.clone();
+        thread::spawn(move || {
+            let mut second = connect_to_listener(&second_path, Protocol::Binary);
+            let _ = tx.send(OsqueryClient::ping(&mut second).is_ok());
+        });
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+
+        drop(first);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(true));
+
+        server.stop();
+        server.shutdown_and_cleanup();
+    }
+
+    #[test]
+    fn test_listener_speaks_configured_protocol() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+        let mut server = listening_server(&socket_base_str, 4, |server| {
+            assert_eq!(server.protocol, Protocol::Binary);
+            server.set_protocol(Protocol::Compact);
+        });
+
+        let listen_path = format!("{socket_base_str}.4");
+        let mut compact = connect_to_listener(&listen_path, Protocol::Compact);
+        assert!(OsqueryClient::ping(&mut compact).is_ok());
+        let mut binary = connect_to_listener(&listen_path, Protocol::Binary);
+        assert!(OsqueryClient::ping(&mut binary).is_err());
+
+        server.stop();
+        server.shutdown_and_cleanup();
+    }
+
+    #[test]
+    fn test_listener_serves_with_configured_buffer_sizes() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+        let mut server = listening_server(&socket_base_str, 5, |server| {
+            assert_eq!(server.buffer_sizes, BufferSizes::default());
+            server.set_buffer_sizes(BufferSizes { read: 1, write: 1 });
+        });
+
+        let listen_path = format!("{socket_base_str}.5");
+        let mut client = connect_to_listener(&listen_path, Protocol::Binary);
+        let response = OsqueryClient::call(
+            &mut client,
+            "table".to_string(),
+            "test_table".to_string(),
+            BTreeMap::from([("action".to_string(), "columns".to_string())]),
+        )
+        .expect("call should succeed");
+        assert_eq!(response.status.and_then(|s| s.code), Some(0));
+        assert_eq!(response.response.map(|rows| rows.len()), Some(1));
+
+        server.stop();
+        server.shutdown_and_cleanup();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socket_mode_applied_to_listener_socket() {
+        use std::os::unix::fs::PermissionsExt;
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+
+        let mut server = ticking_server(&socket_base_str);
+        server.set_socket_mode(0o600);
+        server.start().expect("start should succeed");
+
+        let mode = std::fs::metadata(format!("{socket_base_str}.8"))
+            .expect("listener socket should exist")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(std::os::unix::net::UnixStream::connect(format!("{socket_base_str}.8")).is_ok());
+        assert!(!std::path::Path::new(&format!("{socket_base_str}.8.d")).exists());
+
+        server.stop();
+        server.shutdown_and_cleanup();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_start_fails_and_deregisters_when_socket_mode_cannot_be_applied() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+        // A file where the private bind directory has to go
+        std::fs::write(format!("{socket_base_str}.8.d"), "").expect("Failed to create file");
+
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client
+            .expect_register_extension()
+            .returning(|_, _| Ok(osquery::ExtensionStatus::new(0, None, 8)));
+        mock_client
+            .expect_deregister_extension()
+            .times(1)
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), &socket_base_str, mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        server.set_socket_mode(0o600);
+
+        assert!(matches!(server.start(), Err(ServerError::Listen(_))));
+        assert!(!server.started);
+        assert!(!std::path::Path::new(&format!("{socket_base_str}.8")).exists());
+    }
+
+    #[test]
+    fn test_compact_client_talks_to_compact_listener() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_path = temp_dir.path().join("osquery.em");
+        let socket_path = socket_path.to_string_lossy().to_string();
+
+        let plugins = vec![Plugin::Table(TablePlugin::from_readonly_table(TestTable))];
+        let manager =
+            Handler::new(&plugins, Arc::new(AtomicBool::new(false))).expect("handler should build");
+        let manager_path = socket_path.clone();
+        thread::spawn(move || {
+            let processor = osquery::ExtensionManagerSyncProcessor::new(manager);
+            let _ = transport::listen(
+                processor,
+                &manager_path,
+                1,
+                Protocol::Compact,
+                BufferSizes::default(),
+            );
+        });
+
+        let start = Instant::now();
+        let mut client = loop {
+            match ThriftClient::with_options(
+                &socket_path,
+                Duration::from_secs(1),
+                ClientOptions::default().set_protocol(Protocol::Compact),
+            ) {
+                Ok(client) => break client,
+                Err(e) if start.elapsed() > Duration::from_secs(5) => {
+                    panic!("compact listener never came up: {e}")
+                }
+                Err(_) => thread::sleep(Duration::from_millis(10)),
+            }
+        };
+
+        let status = OsqueryClient::ping(&mut client).expect("ping should succeed");
+        assert_eq!(status.code.unwrap_or(0), 0);
+        let response = OsqueryClient::call(
+            &mut client,
+            "table".to_string(),
+            "test_table".to_string(),
+            BTreeMap::from([("action".to_string(), "generate".to_string())]),
+        )
+        .expect("call should succeed");
+        assert_eq!(response.status.and_then(|s| s.code), Some(0));
+    }
+
+    #[test]
+    fn test_start_poll_stop_finish() {
+        use std::sync::atomic::AtomicUsize;
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let mut server = ticking_server(&socket_base.to_string_lossy());
+
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let tick_count = Arc::clone(&ticks);
+        server.on_tick(move || {
+            tick_count.fetch_add(1, Ordering::SeqCst);
+        });
+        let (plugin, plugin_shut_down) = ShutdownTrackingConfigPlugin::new();
+        server
+            .register_plugin(Plugin::config(plugin))
+            .expect("plugin should register");
+
+        server.start().expect("start should succeed");
+        for _ in 0..3 {
+            assert_eq!(
+                server.poll_once().expect("poll should succeed"),
+                PollOutcome::Continue
+            );
+        }
+        assert_eq!(ticks.load(Ordering::SeqCst), 3);
+
+        server.stop();
+        assert_eq!(
+            server.poll_once().expect("poll should succeed"),
+            PollOutcome::Shutdown
+        );
+        assert_eq!(ticks.load(Ordering::SeqCst), 3);
+        assert!(!plugin_shut_down.load(Ordering::SeqCst));
+
+        server.finish();
+        assert!(plugin_shut_down.load(Ordering::SeqCst));
+        assert!(server.cleaned_up.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_poll_once_failed_ping_requests_shutdown() {
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client.expect_ping().returning(|| {
+            Err(thrift::Error::Transport(thrift::TransportError::new(
+                thrift::TransportErrorKind::EndOfFile,
+                "osquery went away",
+            )))
+        });
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+
+        assert!(server.poll_once().is_err());
+        assert!(!server.is_running());
+        assert_eq!(
+            server.poll_once().expect("poll should succeed"),
+            PollOutcome::Shutdown
+        );
+    }
+
+    #[test]
+    fn test_shutdown_reason_ping_failed_wins_over_later_stop() {
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client.expect_ping().returning(|| {
+            Err(thrift::Error::from(std::io::Error::other(
+                "connection lost",
+            )))
+        });
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+        assert_eq!(server.last_shutdown_reason(), None);
+
+        assert!(server.poll_once().is_err());
+        server.stop();
+
+        assert_eq!(
+            server.last_shutdown_reason(),
+            Some(ShutdownReason::PingFailed)
+        );
+    }
+
+    #[test]
+    fn test_shutdown_reason_stop_handle_ends_run() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let mut server = ticking_server(&socket_base.to_string_lossy());
+
+        let handle = server.get_stop_handle();
+        server.on_tick(move || handle.stop());
+        server.run().expect("run should succeed");
+
+        assert_eq!(server.last_shutdown_reason(), Some(ShutdownReason::Stopped));
+    }
+
+    /// A mock osquery that goes away once: the first ping fails, later ones
+    /// succeed. Counts registrations in the returned counter, which also
+    /// hands out the UUIDs.
+    fn flaky_osquery() -> (MockOsqueryClient, Arc<std::sync::atomic::AtomicU32>) {
+        use std::sync::atomic::AtomicU32;
+
+        let mut mock_client = MockOsqueryClient::new();
+        let registrations = Arc::new(AtomicU32::new(0));
+        let counter = registrations.clone();
+        mock_client
+            .expect_register_extension()
+            .times(2)
+            .returning(move |_, _| {
+                let uuid = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                Ok(osquery::ExtensionStatus::new(0, None, i64::from(uuid)))
+            });
+        // osquery goes away once, then answers every ping
+        let pings = Arc::new(AtomicU32::new(0));
+        mock_client.expect_ping().returning(move || {
+            if pings.fetch_add(1, Ordering::SeqCst) == 0 {
+                Err(thrift::Error::from(std::io::Error::other(
+                    "connection lost",
+                )))
+            } else {
+                Ok(osquery::ExtensionStatus::default())
+            }
+        });
+        mock_client.expect_reconnect().times(1).returning(|| Ok(()));
+        mock_client
+            .expect_deregister_extension()
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+        (mock_client, registrations)
+    }
+
+    /// Run `server` with restarts until its first tick after registering again.
+    fn run_until_restarted<C: OsqueryClient>(server: &mut Server<Plugin, C>) {
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        server.set_ping_interval(Duration::from_millis(1));
+        let handle = server.get_stop_handle();
+        server.on_tick(move || handle.stop());
+
+        let policy = RestartPolicy {
+            max_restarts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+        };
+        server.run_with_restart(policy).expect("run should succeed");
+    }
+
+    #[test]
+    fn test_run_with_restart_reregisters_after_lost_connection() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let (mock_client, registrations) = flaky_osquery();
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), &socket_base.to_string_lossy(), mock_client);
+        run_until_restarted(&mut server);
+
+        assert_eq!(registrations.load(Ordering::SeqCst), 2);
+        assert_eq!(server.uuid(), Some(2));
+        assert_eq!(server.last_shutdown_reason(), Some(ShutdownReason::Stopped));
+    }
+
+    #[test]
+    fn test_run_with_restart_honors_stop_during_restart() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let stop_handle: Arc<Mutex<Option<ServerStopHandle>>> = Arc::new(Mutex::new(None));
+
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client
+            .expect_register_extension()
+            .times(1)
+            .returning(|_, _| Ok(osquery::ExtensionStatus::new(0, None, 1)));
+        mock_client.expect_ping().returning(|| {
+            Err(thrift::Error::from(std::io::Error::other(
+                "connection lost",
+            )))
+        });
+        // The stop arrives after the failed ping, while the server tears
+        // down the old registration to restart
+        let handle = stop_handle.clone();
+        mock_client
+            .expect_deregister_extension()
+            .returning(move |_| {
+                if let Some(handle) = handle.lock().ok().and_then(|handle| handle.clone()) {
+                    handle.stop();
+                }
+                Ok(osquery::ExtensionStatus::default())
+            });
+        mock_client.expect_reconnect().times(0);
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), &socket_base.to_string_lossy(), mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        server.set_ping_interval(Duration::from_millis(1));
+        if let Ok(mut slot) = stop_handle.lock() {
+            *slot = Some(server.get_stop_handle());
+        }
+
+        let policy = RestartPolicy {
+            max_restarts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+        };
+        server.run_with_restart(policy).expect("run should succeed");
+
+        // The mock checks that the server never reconnected to register again
+        assert!(!server.is_running());
+        assert_eq!(
+            server.last_shutdown_reason(),
+            Some(ShutdownReason::PingFailed)
+        );
+    }
+
+    #[test]
+    fn test_run_with_restart_reconnects_boxed_client() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        // The mock expects exactly one reconnect, checked when it is dropped
+        let (mock_client, registrations) = flaky_osquery();
+
+        let mut server = Server::<Plugin, _>::with_boxed_client(
+            Some("test"),
+            &socket_base.to_string_lossy(),
+            Box::new(mock_client),
+        );
+        run_until_restarted(&mut server);
+
+        assert_eq!(registrations.load(Ordering::SeqCst), 2);
+        drop(server);
+    }
+
+    /// A table whose `columns()` panics, so it can't describe its routes
+    struct BrokenTable;
+
+    impl ReadOnlyTable for BrokenTable {
+        fn name(&self) -> String {
+            "broken".to_string()
+        }
+
+        fn columns(&self) -> Vec<ColumnDef> {
+            panic!("columns unavailable")
+        }
+
+        fn generate(&self, _request: crate::ExtensionPluginRequest) -> crate::ExtensionResponse {
+            crate::ExtensionResponse::new(osquery::ExtensionStatus::default(), vec![])
+        }
+
+        fn shutdown(&self) {}
+    }
+
+    #[test]
+    fn test_partial_registration_skips_failing_plugin() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+
+        let registered = Arc::new(Mutex::new(Vec::new()));
+        let seen = registered.clone();
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client
+            .expect_register_extension()
+            .returning(move |_, registry| {
+                if let (Ok(mut seen), Some(tables)) = (seen.lock(), registry.get("table")) {
+                    seen.extend(tables.keys().cloned());
+                }
+                Ok(osquery::ExtensionStatus::new(0, None, 3))
+            });
+        mock_client
+            .expect_deregister_extension()
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), &socket_base.to_string_lossy(), mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register")
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(BrokenTable)))
+            .expect("plugin should register")
+            .allow_partial_registration(true);
+
+        server.start().expect("start should succeed");
+        server.finish();
+
+        assert_eq!(
+            registered.lock().map(|r| r.clone()).unwrap_or_default(),
+            vec!["test_table"]
+        );
+        assert_eq!(
+            server.registration_failures(),
+            [PluginFailure {
+                registry: "table".to_string(),
+                name: "broken".to_string(),
+                reason: "panicked while describing its routes".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_failing_plugin_stops_start_by_default() {
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", MockOsqueryClient::new());
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register")
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(BrokenTable)))
+            .expect("plugin should register");
+
+        assert!(matches!(
+            server.start(),
+            Err(ServerError::InvalidPlugin { ref name, .. }) if name == "broken"
+        ));
+        assert!(server.registration_failures().is_empty());
+    }
+
+    #[test]
+    fn test_restart_policy_backoff_doubles_up_to_max() {
+        let policy = RestartPolicy {
+            max_restarts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_handle_options_returns_added_options() {
+        use osquery::ExtensionManagerSyncHandler;
+
+        let mock_client = MockOsqueryClient::new();
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+        server
+            .add_option(
+                "my_ext_endpoint",
+                OptionType::String,
+                "https://example.com",
+                "Where to send data",
+            )
+            .add_option(
+                "my_ext_retries",
+                OptionType::UInt64,
+                "3",
+                "How often to retry",
+            );
+        let plugins = vec![Plugin::Table(TablePlugin::from_readonly_table(TestTable))];
+        let handler =
+            Handler::new(&plugins, server.shutdown_flag.clone()).expect("handler should build");
+        assert!(handler
+            .handle_options()
+            .expect("options should succeed")
+            .is_empty());
+
+        let options = handler
+            .with_options(server.options())
+            .handle_options()
+            .expect("options should succeed");
+
+        assert_eq!(
+            options.keys().collect::<Vec<_>>(),
+            vec!["my_ext_endpoint", "my_ext_retries"]
+        );
+        let retries = options.get("my_ext_retries");
+        assert_eq!(retries.and_then(|o| o.default_value.as_deref()), Some("3"));
+        assert_eq!(
+            retries.and_then(|o| o.value.as_deref()),
+            Some("How often to retry")
+        );
+        assert_eq!(retries.and_then(|o| o.type_.as_deref()), Some("uint64"));
+        let endpoint = options.get("my_ext_endpoint");
+        assert_eq!(endpoint.and_then(|o| o.type_.as_deref()), Some("string"));
+    }
+
+    #[test]
+    fn test_shutdown_reason_osquery_rpc() {
+        use osquery::ExtensionSyncHandler;
+
+        let mock_client = MockOsqueryClient::new();
+        let server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+        let plugins = vec![Plugin::Table(TablePlugin::from_readonly_table(TestTable))];
+        let handler = Handler::new(&plugins, server.shutdown_flag.clone())
+            .expect("handler should build")
+            .with_shutdown_reason(server.shutdown_reason.clone());
+
+        handler.handle_shutdown().expect("shutdown should succeed");
+        server.stop();
+
+        assert!(!server.is_running());
+        assert_eq!(
+            server.last_shutdown_reason(),
+            Some(ShutdownReason::OsqueryRequested)
+        );
+    }
+
+    #[test]
+    fn test_discover_socket_prefers_env_path() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let env_socket = temp_dir.path().join("env.em");
+        let well_known = temp_dir.path().join("osquery.em");
+        std::fs::File::create(&env_socket).expect("failed to create env socket");
+        std::fs::File::create(&well_known).expect("failed to create well-known socket");
+        let env_socket = env_socket.to_string_lossy().to_string();
+        let well_known = well_known.to_string_lossy().to_string();
+
+        assert_eq!(
+            first_existing([env_socket.clone(), well_known.clone()]),
+            Some(env_socket)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discover_socket_finds_existing_well_known_path() {
+        use std::os::unix::net::UnixListener;
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let missing_env = temp_dir.path().join("missing.em");
+        let missing = temp_dir.path().join("var-osquery.em");
+        let present = temp_dir.path().join("tmp-osquery.em");
+        let _listener = UnixListener::bind(&present).expect("failed to bind test socket");
+        let paths =
+            [missing_env, missing, present.clone()].map(|path| path.to_string_lossy().to_string());
+
+        assert_eq!(
+            first_existing(paths),
+            Some(present.to_string_lossy().to_string())
+        );
+        assert_eq!(first_existing(Vec::new()), None);
+    }
+
+    #[test]
+    fn test_default_socket_paths_cover_osqueryd_and_shell() {
+        let paths = default_socket_paths();
+        if cfg!(windows) {
+            assert_eq!(paths, vec![r"\\.\pipe\osquery.em"]);
+        } else {
+            assert_eq!(
+                paths.first().map(String::as_str),
+                Some("/var/osquery/osquery.em")
+            );
+            assert!(paths.iter().any(|path| path == "/tmp/osquery.em"));
+        }
+    }
+
+    #[test]
+    fn test_worker_threads_default_and_minimum() {
+        let mock_client = MockOsqueryClient::new();
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", mock_client);
+        assert_eq!(server.worker_threads, DEFAULT_WORKER_THREADS);
+
+        server.set_worker_threads(0);
+        assert_eq!(server.worker_threads, 1);
+    }
+
+    #[test]
+    fn test_drop_after_shutdown_does_not_deregister_again() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client
+            .expect_register_extension()
+            .returning(|_, _| Ok(osquery::ExtensionStatus::new(0, None, 7)));
+        mock_client
+            .expect_deregister_extension()
+            .times(1)
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), &socket_base_str, mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        server.start().expect("start should succeed");
+        server.stop();
+        server.shutdown_and_cleanup();
+
+        drop(server);
+    }
+
+    #[test]
+    fn test_register_plugin_after_start_is_rejected() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+
+        // Registered once, never re-registered
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client
+            .expect_register_extension()
+            .times(1)
+            .returning(|_, _| Ok(osquery::ExtensionStatus::new(0, None, 1)));
+        mock_client
+            .expect_deregister_extension()
+            .times(1)
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), &socket_base_str, mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        server.start().expect("start should succeed");
+
+        let result =
+            server.register_plugin(Plugin::Table(TablePlugin::from_readonly_table(DupeTable)));
+        assert!(matches!(result, Err(ServerError::AlreadyStarted)));
+        assert_eq!(server.plugins.len(), 1);
+        assert_eq!(server.uuid(), Some(1));
+
+        server.stop();
+        server.shutdown_and_cleanup();
+    }
+
+    #[cfg(feature = "debug-http")]
+    #[test]
+    fn test_debug_http_generates_rows() {
+        use std::io::{Read, Write};
+        use std::net::{TcpListener, TcpStream};
+        use tempfile::tempdir;
+
+        struct UsersTable;
+
+        impl ReadOnlyTable for UsersTable {
+            fn name(&self) -> String {
+                "users".to_string()
+            }
+
+            fn columns(&self) -> Vec<ColumnDef> {
+                vec![ColumnDef::new(
+                    "username",
+                    ColumnType::Text,
+                    ColumnOptions::DEFAULT,
+                )]
+            }
+
+            fn generate(
+                &self,
+                _request: crate::ExtensionPluginRequest,
+            ) -> crate::ExtensionResponse {
+                let row = BTreeMap::from([("username".to_string(), "root".to_string())]);
+                crate::ExtensionResponse::new(osquery::ExtensionStatus::default(), vec![row])
+            }
+
+            fn shutdown(&self) {}
+        }
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client
+            .expect_register_extension()
+            .returning(|_, _| Ok(osquery::ExtensionStatus::default()));
+        mock_client
+            .expect_deregister_extension()
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
+        // Reserve a free port, then hand it to the server
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("Failed to find a free port");
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), &socket_base_str, mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(UsersTable)))
+            .expect("plugin should register");
+        server.set_debug_http(&addr.to_string());
+        server.start().expect("start should succeed");
+
+        let request = |request: &str| -> String {
+            let mut stream = TcpStream::connect(addr).expect("Failed to connect");
+            stream
+                .write_all(request.as_bytes())
+                .expect("Failed to send request");
+            let mut response = String::new();
+            stream
+                .read_to_string(&mut response)
+                .expect("Failed to read response");
+            response
+        };
+
+        let response =
+            request("POST /tables/users/generate HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.ends_with(r#"[{"username":"root"}]"#), "{response}");
+
+        let response = request("GET /tables HTTP/1.1\r\n\r\n");
+        assert!(response.contains(r#""users":[{"#), "{response}");
+
+        let response = request("POST /tables/missing/generate HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 404"), "{response}");
+
+        server.stop();
+        server.shutdown_and_cleanup();
+        assert!(TcpStream::connect(addr).is_err());
+    }
+
+    #[cfg(feature = "debug-http")]
+    #[test]
+    fn test_drop_started_server_stops_debug_http() {
+        use std::net::{TcpListener, TcpStream};
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client
+            .expect_register_extension()
+            .returning(|_, _| Ok(osquery::ExtensionStatus::new(0, None, 7)));
+        mock_client
+            .expect_deregister_extension()
+            .times(1)
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("Failed to find a free port");
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), &socket_base_str, mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        server.set_debug_http(&addr.to_string());
+        server.start().expect("start should succeed");
+        assert!(TcpStream::connect(addr).is_ok());
+
+        // Dropping joins the debug HTTP thread, closing its port before returning
+        drop(server);
+        assert!(TcpStream::connect(addr).is_err());
+    }
+
+    #[cfg(feature = "debug-http")]
+    #[test]
+    fn test_debug_http_start_fails_unless_bound_on_loopback() {
+        use std::net::TcpListener;
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+
+        // Keep a port busy so binding it fails
+        let taken = TcpListener::bind("127.0.0.1:0").expect("Failed to bind a port");
+        let taken_addr = taken.local_addr().expect("Failed to get bound address");
+
+        for addr in ["0.0.0.0:0".to_string(), taken_addr.to_string()] {
+            let mut mock_client = MockOsqueryClient::new();
+            mock_client
+                .expect_register_extension()
+                .times(1)
+                .returning(|_, _| Ok(osquery::ExtensionStatus::new(0, None, 8)));
+            mock_client
+                .expect_deregister_extension()
+                .times(1)
+                .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
+            let mut server: Server<Plugin, MockOsqueryClient> =
+                Server::with_client(Some("test"), &socket_base_str, mock_client);
+            server
+                .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+                .expect("plugin should register");
+            server.set_debug_http(&addr);
+
+            assert!(
+                matches!(server.start(), Err(ServerError::Listen(_))),
+                "{addr} should be refused"
+            );
+            assert!(!server.started);
+        }
+    }
+
+    /// Start a server with a mock client that records the extension info sent to osquery
+    fn registered_info(
+        configure: impl FnOnce(&mut Server<Plugin, MockOsqueryClient>),
+    ) -> Option<osquery::InternalExtensionInfo> {
+        registration(configure).map(|(info, _)| info)
+    }
+
+    /// Start a server with a mock client that records the extension info and
+    /// plugin registry sent to osquery
+    fn registration(
+        configure: impl FnOnce(&mut Server<Plugin, MockOsqueryClient>),
+    ) -> Option<(osquery::InternalExtensionInfo, osquery::ExtensionRegistry)> {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let socket_base = temp_dir.path().join("osquery.sock");
+        let socket_base_str = socket_base.to_string_lossy().to_string();
+
+        let captured = Arc::new(Mutex::new(None));
+        let captured_clone = captured.clone();
+
+        let mut mock_client = MockOsqueryClient::new();
+        mock_client
+            .expect_register_extension()
+            .returning(move |info, registry| {
+                if let Ok(mut captured) = captured_clone.lock() {
+                    *captured = Some((info, registry));
+                }
+                Ok(osquery::ExtensionStatus::new(0, None, 1))
+            });
+        mock_client
+            .expect_deregister_extension()
+            .returning(|_| Ok(osquery::ExtensionStatus::default()));
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), &socket_base_str, mock_client);
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+        configure(&mut server);
+
+        server.start().expect("start should succeed");
+        server.stop();
+        server.shutdown_and_cleanup();
+
+        let registration = captured.lock().ok().and_then(|mut r| r.take());
+        registration
+    }
+
+    #[test]
+    fn test_register_sends_default_versions() {
+        let info = registered_info(|_| {}).expect("extension should be registered");
+
+        assert_eq!(info.name.as_deref(), Some("test"));
+        assert_eq!(info.version.as_deref(), Some(""));
+        assert_eq!(info.sdk_version.as_deref(), Some(DEFAULT_SDK_VERSION));
+        assert_eq!(
+            info.min_sdk_version.as_deref(),
+            Some(DEFAULT_MIN_SDK_VERSION)
+        );
+    }
+
+    #[test]
+    fn test_register_sends_configured_versions() {
+        let info = registered_info(|server| {
+            server
+                .set_version("3.1.4")
+                .set_sdk_version("5.12.1")
+                .set_min_sdk_version("5.8.0");
+        })
+        .expect("extension should be registered");
+
+        assert_eq!(info.version.as_deref(), Some("3.1.4"));
+        assert_eq!(info.sdk_version.as_deref(), Some("5.12.1"));
+        assert_eq!(info.min_sdk_version.as_deref(), Some("5.8.0"));
+    }
+
+    #[test]
+    fn test_extension_name_is_independent_of_plugin_names() {
+        struct FileLogger;
+
+        impl LoggerPlugin for FileLogger {
+            fn name(&self) -> String {
+                "file_logger".to_string()
+            }
+
+            fn log_string(&self, _message: &str) -> Result<(), String> {
+                Ok(())
+            }
+        }
+
+        let (info, registry) = registration(|server| {
+            server
+                .set_extension_name("mysuite")
+                .register_plugin(Plugin::logger(FileLogger))
+                .expect("plugin should register");
+        })
+        .expect("extension should be registered");
+
+        assert_eq!(info.name.as_deref(), Some("mysuite"));
+        let loggers = registry.get("logger").expect("logger registry");
+        assert!(loggers.contains_key("file_logger"));
+        assert!(!loggers.contains_key("mysuite"));
+        let tables = registry.get("table").expect("table registry");
+        assert!(tables.contains_key("test_table"));
+    }
+
+    #[test]
+    fn test_describe_lists_plugin_routes() {
+        struct QuietLogger;
+
+        impl LoggerPlugin for QuietLogger {
+            fn name(&self) -> String {
+                "quiet_logger".to_string()
+            }
+
+            fn log_string(&self, _message: &str) -> Result<(), String> {
+                Ok(())
+            }
+        }
+
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", MockOsqueryClient::new());
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register")
+            .register_plugin(Plugin::logger(QuietLogger))
+            .expect("plugin should register");
+
+        let description = server.describe();
+
+        let tables = description.get("table").expect("table registry");
+        let Some((name, columns)) = tables.first() else {
+            panic!("table should be described");
+        };
+        assert_eq!(name, "test_table");
+        assert_eq!(
+            columns
+                .first()
+                .and_then(|c| c.get("name"))
+                .map(String::as_str),
+            Some("col")
+        );
+
+        let loggers = description.get("logger").expect("logger registry");
+        assert_eq!(loggers, &vec![("quiet_logger".to_string(), vec![])]);
+        assert_eq!(description.get("config"), Some(&vec![]));
+    }
+
+    #[test]
+    fn test_local_query_dispatches_to_table_with_constraints() {
+        use crate::plugin::{Operator, QueryContext, RowIterator, TableError};
+
+        /// Returns every user, recording the `uid` constraints it was given
+        struct Users {
+            seen: Arc<Mutex<Vec<(Operator, String)>>>,
+        }
+
+        impl ReadOnlyTable for Users {
+            fn name(&self) -> String {
+                "users".to_string()
+            }
+
+            fn columns(&self) -> Vec<ColumnDef> {
+                vec![
+                    ColumnDef::new("uid", ColumnType::BigInt, ColumnOptions::INDEX),
+                    ColumnDef::new("username", ColumnType::Text, ColumnOptions::DEFAULT),
+                ]
+            }
+
+            fn generate(
+                &self,
+                _request: crate::ExtensionPluginRequest,
+            ) -> crate::ExtensionResponse {
+                let rows: osquery::ExtensionPluginResponse = [("0", "root"), ("501", "alice")]
+                    .into_iter()
+                    .map(|(uid, username)| {
+                        BTreeMap::from([
+                            ("uid".to_string(), uid.to_string()),
+                            ("username".to_string(), username.to_string()),
+                        ])
+                    })
+                    .collect();
+                crate::ExtensionResponse::new(osquery::ExtensionStatus::default(), rows)
+            }
+
+            fn generate_rows(
+                &self,
+                query: &QueryContext<'_>,
+            ) -> Result<RowIterator<'_>, TableError> {
+                if let (Ok(mut seen), Some(list)) =
+                    (self.seen.lock(), query.constraints().get("uid"))
+                {
+                    seen.extend(list.iter().map(|(op, expr)| (op, expr.to_string())));
+                }
+                let rows = self.generate(query.request().clone()).response;
+                Ok(Box::new(rows.unwrap_or_default().into_iter()))
+            }
+
+            fn shutdown(&self) {}
+        }
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", MockOsqueryClient::new());
+        server
+            .register_plugin(Plugin::readonly_table(Users { seen: seen.clone() }))
+            .expect("plugin should register");
+
+        let response = server.local_query("SELECT username FROM users WHERE uid = 501");
+
+        assert_eq!(response.status.and_then(|s| s.code), Some(0));
+        assert_eq!(
+            response.response,
+            Some(vec![BTreeMap::from([(
+                "username".to_string(),
+                "alice".to_string()
+            )])])
+        );
+        assert_eq!(
+            *seen.lock().expect("seen lock poisoned"),
+            vec![(Operator::Equals, "501".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_local_query_reports_unknown_tables_and_columns() {
+        let mut server: Server<Plugin, MockOsqueryClient> =
+            Server::with_client(Some("test"), "/tmp/test.sock", MockOsqueryClient::new());
+        server
+            .register_plugin(Plugin::Table(TablePlugin::from_readonly_table(TestTable)))
+            .expect("plugin should register");
+
+        let messages: Vec<Option<String>> = [
+            "SELECT * FROM missing",
+            "SELECT nope FROM test_table",
+            "SELECT * FROM test_table WHERE nope = 'x'",
+        ]
+        .into_iter()
+        .map(|sql| {
+            server
+                .local_query(sql)
+                .response
+                .and_then(|rows| rows.first().and_then(|row| row.get("message").cloned()))
+        })
+        .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                Some("no such table: missing".to_string()),
+                Some("no such column: nope".to_string()),
+                Some("no such column: nope".to_string()),
+            ]
+        );
+        assert!(server
+            .local_query("SELECT * FROM test_table WHERE col REGEXP '('")
+            .response
+            .and_then(|rows| rows.first().and_then(|row| row.get("message").cloned()))
+            .is_some_and(|message| message.starts_with("Invalid REGEXP pattern \"(\"")));
+        assert_eq!(
+            server
+                .local_query("SELECT col FROM test_table")
+                .status
+                .and_then(|s| s.code),
+            Some(0)
+        );
+    }
+
+    // ========================================================================
+    // Handler::handle_ping() tests
+    // ========================================================================
+
+    use crate::plugin::{LogStatus, LoggerPlugin};
+    use osquery::ExtensionSyncHandler;
+
+    struct HealthLogger {
+        healthy: bool,
+    }
+
+    impl LoggerPlugin for HealthLogger {
+        fn name(&self) -> String {
+            if self.healthy {
+                "healthy_logger".to_string()
+            } else {
+                "broken_logger".to_string()
+            }
+        }
+
+        fn log_string(&self, _message: &str) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn log_status(&self, _status: &LogStatus) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn health(&self) -> Result<(), String> {
+            if self.healthy {
+                Ok(())
+            } else {
+                Err("log sink unreachable".to_string())
+            }
+        }
+    }
+
+    #[test]
+    fn test_handle_ping_all_healthy() {
+        let plugins = vec![
+            Plugin::Table(TablePlugin::from_readonly_table(TestTable)),
+            Plugin::logger(HealthLogger { healthy: true }),
+        ];
+        let handler =
+            Handler::new(&plugins, Arc::new(AtomicBool::new(false))).expect("handler should build");
+
+        let status = handler.handle_ping().expect("ping should succeed");
+        assert!(status.code.is_none() || status.code == Some(0));
+    }
+
+    #[test]
+    fn test_handle_ping_reports_unhealthy_plugin() {
+        let plugins = vec![
+            Plugin::logger(HealthLogger { healthy: true }),
+            Plugin::logger(HealthLogger { healthy: false }),
+        ];
+        let handler =
+            Handler::new(&plugins, Arc::new(AtomicBool::new(false))).expect("handler should build");
+
+        let status = handler.handle_ping().expect("ping should succeed");
+        assert_eq!(status.code, Some(1));
+
+        let message = status.message.unwrap_or_default();
+        assert!(message.contains("broken_logger"));
+        assert!(message.contains("log sink unreachable"));
+        assert!(!message.contains("healthy_logger"));
+    }
+
     #[test]
     fn test_mock_client_query() {
         use crate::ExtensionResponse;
@@ -0,0 +1,266 @@
+//! Run plugins in-process, without osquery.
+//!
+//! [`StandaloneHarness`] dispatches requests through the same path osquery's
+//! Thrift calls take, so extension logic can be exercised from unit tests or a
+//! `--standalone` CLI mode without a running osqueryd.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use crate::_osquery::ExtensionSyncHandler;
+use crate::error::ServerError;
+use crate::plugin::table::query_constraint;
+use crate::plugin::{OsqueryPlugin, QueryConstraints, Registry};
+use crate::server::Handler;
+use crate::{ExtensionPluginRequest, ExtensionResponse};
+
+/// Invoke registered plugins directly, as osquery would.
+///
+/// ```
+/// use osquery_rust_ng::plugin::{ColumnDef, Plugin, QueryConstraints, ReadOnlyTable};
+/// use osquery_rust_ng::{ExtensionPluginRequest, ExtensionResponse, ExtensionStatus};
+/// use osquery_rust_ng::StandaloneHarness;
+///
+/// struct Empty;
+///
+/// impl ReadOnlyTable for Empty {
+///     fn name(&self) -> String { "empty".to_string() }
+///     fn columns(&self) -> Vec<ColumnDef> { vec![] }
+///     fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+///         ExtensionResponse::new(ExtensionStatus::default(), vec![])
+///     }
+///     fn shutdown(&self) {}
+/// }
+///
+/// let harness = StandaloneHarness::new([Plugin::readonly_table(Empty)])?;
+/// let response = harness.query_table("empty", &QueryConstraints::new())?;
+/// assert_eq!(response.response.map(|rows| rows.len()), Some(0));
+/// # Ok::<(), osquery_rust_ng::ServerError>(())
+/// ```
+pub struct StandaloneHarness<P: OsqueryPlugin + Clone> {
+    handler: Handler<P>,
+}
+
+impl<P: OsqueryPlugin + Clone + 'static> StandaloneHarness<P> {
+    /// Build a harness around `plugins`. No connection to osquery is made.
+    pub fn new<I: IntoIterator<Item = P>>(plugins: I) -> Result<Self, ServerError> {
+        let plugins: Vec<P> = plugins.into_iter().collect();
+        if plugins.is_empty() {
+            return Err(ServerError::NoPlugins);
+        }
+
+        Ok(StandaloneHarness {
+            handler: Handler::new(&plugins, Arc::new(AtomicBool::new(false)))?,
+        })
+    }
+
+    /// Send a raw request to the plugin `name` in `registry`.
+    pub fn call(
+        &self,
+        registry: Registry,
+        name: &str,
+        request: ExtensionPluginRequest,
+    ) -> Result<ExtensionResponse, ServerError> {
+        Ok(self
+            .handler
+            .handle_call(registry.to_string(), name.to_string(), request)?)
+    }
+
+    /// Generate the rows of table `name`, passing `constraints` in the query context.
+    pub fn query_table(
+        &self,
+        name: &str,
+        constraints: &QueryConstraints,
+    ) -> Result<ExtensionResponse, ServerError> {
+        let context = query_constraint::constraints_to_context(constraints);
+        let request = BTreeMap::from([
+            ("action".to_string(), "generate".to_string()),
+            ("context".to_string(), context.to_string()),
+        ]);
+        self.call(Registry::Table, name, request)
+    }
+
+    /// Ask config plugin `name` for its configuration.
+    pub fn gen_config(&self, name: &str) -> Result<ExtensionResponse, ServerError> {
+        let request = BTreeMap::from([("action".to_string(), "genConfig".to_string())]);
+        self.call(Registry::Config, name, request)
+    }
+
+    /// Send `message` to logger plugin `name` as a plain log line.
+    pub fn log(&self, name: &str, message: &str) -> Result<ExtensionResponse, ServerError> {
+        let request = BTreeMap::from([("log".to_string(), message.to_string())]);
+        self.call(Registry::Logger, name, request)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::plugin::{
+        ColumnDef, ColumnOptions, ColumnType, ConfigPlugin, ConstraintList, LoggerPlugin, Operator,
+        Plugin, QueryContext, ReadOnlyTable, RowIterator, TableError,
+    };
+    use crate::ExtensionStatus;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Returns one row per value of an `id = ?` constraint, or a default row
+    struct EchoTable;
+
+    impl ReadOnlyTable for EchoTable {
+        fn name(&self) -> String {
+            "echo".to_string()
+        }
+
+        fn columns(&self) -> Vec<ColumnDef> {
+            vec![
+                ColumnDef::new("id", ColumnType::Integer, ColumnOptions::DEFAULT),
+                ColumnDef::new("id_constraints", ColumnType::Integer, ColumnOptions::HIDDEN),
+            ]
+        }
+
+        fn generate(&self, _req: ExtensionPluginRequest) -> ExtensionResponse {
+            ExtensionResponse::new(
+                ExtensionStatus::default(),
+                vec![BTreeMap::from([("id".to_string(), "0".to_string())])],
+            )
+        }
+
+        fn generate_rows(&self, query: &QueryContext<'_>) -> Result<RowIterator<'_>, TableError> {
+            let rows = match query.constraints().get("id") {
+                Some(list) => vec![BTreeMap::from([(
+                    "id_constraints".to_string(),
+                    list.len().to_string(),
+                )])],
+                None => self
+                    .generate(query.request().clone())
+                    .response
+                    .unwrap_or_default(),
+            };
+            Ok(Box::new(rows.into_iter()))
+        }
+
+        fn shutdown(&self) {}
+    }
+
+    struct StaticConfig;
+
+    impl ConfigPlugin for StaticConfig {
+        fn name(&self) -> String {
+            "static".to_string()
+        }
+
+        fn gen_config(&self) -> Result<HashMap<String, String>, String> {
+            Ok(HashMap::from([("main".to_string(), "{}".to_string())]))
+        }
+    }
+
+    struct MemoryLogger {
+        lines: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl LoggerPlugin for MemoryLogger {
+        fn name(&self) -> String {
+            "memory".to_string()
+        }
+
+        fn log_string(&self, message: &str) -> Result<(), String> {
+            self.lines
+                .lock()
+                .map_err(|e| e.to_string())?
+                .push(message.to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_query_table_returns_rows() {
+        let harness =
+            StandaloneHarness::new([Plugin::readonly_table(EchoTable)]).expect("valid plugins");
+
+        let response = harness
+            .query_table("echo", &QueryConstraints::new())
+            .expect("table exists");
+
+        assert_eq!(response.status.and_then(|s| s.code), Some(0));
+        assert_eq!(
+            response.response,
+            Some(vec![BTreeMap::from([("id".to_string(), "0".to_string())])])
+        );
+    }
+
+    #[test]
+    fn test_query_table_passes_constraints() {
+        let harness =
+            StandaloneHarness::new([Plugin::readonly_table(EchoTable)]).expect("valid plugins");
+
+        let mut list = ConstraintList::new(ColumnType::Integer);
+        list.add_constraint(Operator::Equals, "1".to_string());
+        list.add_constraint(Operator::Equals, "2".to_string());
+        let constraints = QueryConstraints::from([("id".to_string(), list)]);
+
+        let response = harness
+            .query_table("echo", &constraints)
+            .expect("table exists");
+
+        assert_eq!(
+            response.response,
+            Some(vec![BTreeMap::from([(
+                "id_constraints".to_string(),
+                "2".to_string()
+            )])])
+        );
+    }
+
+    #[test]
+    fn test_gen_config_and_log() {
+        let lines = Arc::new(Mutex::new(vec![]));
+        let harness = StandaloneHarness::new([
+            Plugin::config(StaticConfig),
+            Plugin::logger(MemoryLogger {
+                lines: lines.clone(),
+            }),
+        ])
+        .expect("valid plugins");
+
+        let config = harness.gen_config("static").expect("config exists");
+        assert_eq!(
+            config
+                .response
+                .as_ref()
+                .and_then(|rows| rows.first())
+                .and_then(|row| row.get("main"))
+                .map(|s| s.as_str()),
+            Some("{}")
+        );
+
+        let logged = harness.log("memory", "hello").expect("logger exists");
+        assert_eq!(logged.status.and_then(|s| s.code), Some(0));
+        assert_eq!(
+            lines.lock().map(|l| l.clone()).unwrap_or_default(),
+            vec!["hello".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_unknown_plugin_is_an_error() {
+        let harness =
+            StandaloneHarness::new([Plugin::readonly_table(EchoTable)]).expect("valid plugins");
+
+        assert!(matches!(
+            harness.query_table("missing", &QueryConstraints::new()),
+            Err(ServerError::Thrift(_))
+        ));
+    }
+
+    #[test]
+    fn test_no_plugins_is_an_error() {
+        let plugins: Vec<Plugin> = vec![];
+        assert!(matches!(
+            StandaloneHarness::new(plugins),
+            Err(ServerError::NoPlugins)
+        ));
+    }
+}
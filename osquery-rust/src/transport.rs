@@ -0,0 +1,514 @@
+//! Platform-specific transport between the extension and osquery.
+//!
+//! osquery talks Thrift over Unix domain sockets on Unix platforms and over
+//! named pipes (e.g. `\\.\pipe\osquery.em`) on Windows. This module hides the
+//! difference so `ThriftClient` and `Server` can stay platform-agnostic.
+
+use std::io::Error;
+use std::time::Duration;
+use thrift::transport::{TBufferedReadTransport, TBufferedWriteTransport};
+
+#[cfg(unix)]
+pub(crate) use self::unix::*;
+#[cfg(windows)]
+pub(crate) use self::windows::*;
+
+/// Thrift's own buffer size, used unless overridden.
+const DEFAULT_BUFFER_SIZE: usize = 4096;
+
+/// Capacity in bytes of the buffers between a connection and its Thrift protocol.
+///
+/// Bigger buffers mean fewer reads and writes on the socket when large
+/// result sets go back and forth. Set on the listener with
+/// [`Server::set_buffer_sizes`](crate::Server::set_buffer_sizes) and on the
+/// client with [`ClientOptions::set_buffer_sizes`](crate::ClientOptions::set_buffer_sizes).
+/// Both default to 4KB; sizes below one byte are treated as one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BufferSizes {
+    pub read: usize,
+    pub write: usize,
+}
+
+impl Default for BufferSizes {
+    fn default() -> Self {
+        BufferSizes {
+            read: DEFAULT_BUFFER_SIZE,
+            write: DEFAULT_BUFFER_SIZE,
+        }
+    }
+}
+
+/// Wrap the read and write halves of a connection in buffers of `sizes`.
+pub(crate) fn buffered<R: std::io::Read, W: std::io::Write>(
+    read: R,
+    write: W,
+    sizes: BufferSizes,
+) -> (TBufferedReadTransport<R>, TBufferedWriteTransport<W>) {
+    (
+        read_buffer(sizes.read, read),
+        write_buffer(sizes.write, write),
+    )
+}
+
+/// Buffer reads from `read` in a buffer of `capacity` bytes, or one byte if zero.
+fn read_buffer<R: std::io::Read>(capacity: usize, read: R) -> TBufferedReadTransport<R> {
+    TBufferedReadTransport::with_capacity(capacity.max(1), read)
+}
+
+/// Buffer writes to `write` in a buffer of `capacity` bytes, or one byte if zero.
+fn write_buffer<W: std::io::Write>(capacity: usize, write: W) -> TBufferedWriteTransport<W> {
+    TBufferedWriteTransport::with_capacity(capacity.max(1), write)
+}
+
+/// Room the extension's own socket needs beyond osquery's: `.` and a `u16` UUID.
+const LISTEN_SUFFIX_LEN: usize = ".65535".len();
+
+/// Fail with `InvalidInput` if `path` is too long to be a socket on this platform.
+pub(crate) fn check_path_len(path: &str) -> Result<(), Error> {
+    check_len(path, MAX_PATH_LEN, "")
+}
+
+/// Like [`check_path_len`], for osquery's socket `path` with room to spare
+/// for the `.<uuid>` suffix of the socket the extension listens on.
+pub(crate) fn check_listen_path_len(path: &str) -> Result<(), Error> {
+    check_len(
+        path,
+        MAX_PATH_LEN.saturating_sub(LISTEN_SUFFIX_LEN),
+        " to leave room for the extension's .<uuid> suffix",
+    )
+}
+
+fn check_len(path: &str, max: usize, why: &str) -> Result<(), Error> {
+    if path.len() <= max {
+        return Ok(());
+    }
+    Err(Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("socket path too long for platform, max {max} bytes{why}"),
+    ))
+}
+
+/// Connect to osquery and return a read half and a write half of the
+/// connection, whose reads and writes fail after `timeout` (never if zero).
+pub(crate) fn connect(path: &str, timeout: Duration) -> Result<(Stream, Stream), Error> {
+    let tx = open(path)?;
+    set_timeout(&tx, timeout)?;
+    let rx = tx.try_clone()?;
+    Ok((tx, rx))
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::BufferSizes;
+    use crate::protocol::Protocol;
+    use std::fs::{DirBuilder, Permissions};
+    use std::io::{Error, ErrorKind, Read, Write};
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::UnixStream;
+    use std::path::Path;
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use thrift::server::TProcessor;
+    use thrift::transport::*;
+
+    /// Connection type used to talk to osquery.
+    pub(crate) type Stream = UnixStream;
+
+    /// Longest path that fits `sockaddr_un.sun_path` with its NUL terminator.
+    /// Longer paths fail with a vague error (or get truncated by other tools).
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) const MAX_PATH_LEN: usize = 107;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub(crate) const MAX_PATH_LEN: usize = 103;
+
+    pub(crate) fn open(path: &str) -> Result<Stream, Error> {
+        super::check_path_len(path)?;
+        UnixStream::connect(path)
+    }
+
+    /// Make reads and writes on `stream` fail after `timeout`, or never if it
+    /// is zero. Clones share the socket and so the timeouts.
+    pub(crate) fn set_timeout(stream: &Stream, timeout: Duration) -> Result<(), Error> {
+        let timeout = Some(timeout).filter(|timeout| !timeout.is_zero());
+        stream.set_read_timeout(timeout)?;
+        stream.set_write_timeout(timeout)
+    }
+
+    /// Serve `processor` on a Unix domain socket at `path` with a pool of
+    /// `workers` threads, blocking forever.
+    pub(crate) fn listen<PRC>(
+        processor: PRC,
+        path: &str,
+        workers: usize,
+        protocol: Protocol,
+        buffers: BufferSizes,
+    ) -> thrift::Result<()>
+    where
+        PRC: TProcessor + Send + Sync + 'static,
+    {
+        super::check_path_len(path)?;
+
+        let i_tr_fact: Box<dyn TReadTransportFactory + Send> =
+            Box::new(SizedReadTransportFactory(buffers.read));
+        let i_pr_fact = protocol.input_factory();
+        let o_tr_fact: Box<dyn TWriteTransportFactory + Send> =
+            Box::new(SizedWriteTransportFactory(buffers.write));
+        let o_pr_fact = protocol.output_factory();
+
+        let mut server = thrift::server::TServer::new(
+            i_tr_fact, i_pr_fact, o_tr_fact, o_pr_fact, processor, workers,
+        );
+
+        server.listen_uds(path)
+    }
+
+    /// Like `TBufferedReadTransportFactory`, with a configurable capacity.
+    struct SizedReadTransportFactory(usize);
+
+    impl TReadTransportFactory for SizedReadTransportFactory {
+        fn create(&self, channel: Box<dyn Read + Send>) -> Box<dyn TReadTransport + Send> {
+            Box::new(super::read_buffer(self.0, channel))
+        }
+    }
+
+    /// Like `TBufferedWriteTransportFactory`, with a configurable capacity.
+    struct SizedWriteTransportFactory(usize);
+
+    impl TWriteTransportFactory for SizedWriteTransportFactory {
+        fn create(&self, channel: Box<dyn Write + Send>) -> Box<dyn TWriteTransport + Send> {
+            Box::new(super::write_buffer(self.0, channel))
+        }
+    }
+
+    /// Make a throwaway connection to unblock a listener waiting in `accept()`.
+    pub(crate) fn wake(path: &str) {
+        let _ = UnixStream::connect(path);
+    }
+
+    /// Remove the socket file left behind by `listen()`.
+    pub(crate) fn cleanup(path: &str) -> Result<(), Error> {
+        std::fs::remove_file(path)
+    }
+
+    /// Make a directory only we can enter to bind the socket for `path` in,
+    /// returning the path to bind. Nobody else can connect to the socket
+    /// there until `publish()` has set its mode and moved it to `path`.
+    pub(crate) fn stage(path: &str) -> Result<String, Error> {
+        let dir = format!("{path}.d");
+        // Left behind by a crashed run; `create()` refuses to reuse it
+        if Path::new(&dir).is_dir() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        DirBuilder::new().mode(0o700).create(&dir)?;
+        Ok(format!("{dir}/s"))
+    }
+
+    /// Wait up to `timeout` for the listener to bind `staged`, set its mode
+    /// and move it to `path`. The staging directory is removed either way.
+    pub(crate) fn publish(
+        staged: &str,
+        path: &str,
+        mode: u32,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        let published = wait_for(staged, timeout)
+            .and_then(|()| std::fs::set_permissions(staged, Permissions::from_mode(mode)))
+            .and_then(|()| std::fs::rename(staged, path));
+
+        if let Some(dir) = Path::new(staged).parent() {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+        published
+    }
+
+    /// Wait up to `timeout` for `listen()` to create the socket at `path`;
+    /// it binds on the listener thread.
+    fn wait_for(path: &str, timeout: Duration) -> Result<(), Error> {
+        let deadline = Instant::now() + timeout;
+        while !Path::new(path).exists() {
+            if Instant::now() >= deadline {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("listener socket {path} was not created"),
+                ));
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::BufferSizes;
+    use crate::log_target;
+    use crate::protocol::Protocol;
+    use named_pipe::{PipeOptions, PipeServer};
+    use std::cell::RefCell;
+    use std::fs::{File, OpenOptions};
+    use std::io::{Error, Read, Write};
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use thrift::protocol::{
+        TBinaryInputProtocol, TBinaryOutputProtocol, TCompactInputProtocol, TCompactOutputProtocol,
+        TInputProtocol, TOutputProtocol,
+    };
+    use thrift::server::TProcessor;
+    use thrift::TransportErrorKind;
+
+    /// Connection type used to talk to osquery.
+    ///
+    /// A client end of a named pipe can be opened like a regular file, which
+    /// also gives us `try_clone()` for splitting into read and write halves.
+    pub(crate) type Stream = File;
+
+    /// Pipe names, including the `\\.\pipe\` prefix, are limited to 256 characters.
+    pub(crate) const MAX_PATH_LEN: usize = 256;
+
+    pub(crate) fn open(path: &str) -> Result<Stream, Error> {
+        super::check_path_len(path)?;
+        OpenOptions::new().read(true).write(true).open(path)
+    }
+
+    /// Pipes opened as files have no read or write timeouts, so `timeout` is
+    /// ignored.
+    pub(crate) fn set_timeout(_stream: &Stream, _timeout: Duration) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Serve `processor` on the named pipe `path`, blocking forever.
+    ///
+    /// Mirrors `TServer::listen_uds()`: every accepted client is handled on its
+    /// own thread while a fresh pipe instance waits for the next client. There
+    /// is no pool, so `workers` only matters on Unix.
+    pub(crate) fn listen<PRC>(
+        processor: PRC,
+        path: &str,
+        _workers: usize,
+        protocol: Protocol,
+        buffers: BufferSizes,
+    ) -> thrift::Result<()>
+    where
+        PRC: TProcessor + Send + Sync + 'static,
+    {
+        let processor = Arc::new(processor);
+        let mut first = true;
+
+        loop {
+            let pipe = PipeOptions::new(path).first(first).single()?.wait()?;
+            first = false;
+
+            let processor = processor.clone();
+            thread::spawn(move || handle_connection(processor, pipe, protocol, buffers));
+        }
+    }
+
+    fn handle_connection<PRC: TProcessor>(
+        processor: Arc<PRC>,
+        pipe: PipeServer,
+        protocol: Protocol,
+        buffers: BufferSizes,
+    ) {
+        let pipe = Rc::new(RefCell::new(pipe));
+        let (read, write) = super::buffered(SharedPipe(pipe.clone()), SharedPipe(pipe), buffers);
+        let (mut i_prot, mut o_prot): (Box<dyn TInputProtocol>, Box<dyn TOutputProtocol>) =
+            match protocol {
+                Protocol::Binary => (
+                    Box::new(TBinaryInputProtocol::new(read, true)),
+                    Box::new(TBinaryOutputProtocol::new(write, true)),
+                ),
+                Protocol::Compact => (
+                    Box::new(TCompactInputProtocol::new(read)),
+                    Box::new(TCompactOutputProtocol::new(write)),
+                ),
+            };
+
+        loop {
+            if let Err(e) = processor.process(&mut i_prot, &mut o_prot) {
+                match e {
+                    thrift::Error::Transport(ref transport_err)
+                        if transport_err.kind == TransportErrorKind::EndOfFile => {}
+                    other => {
+                        log::warn!(
+                            target: log_target::SERVER,
+                            "processor completed with error: {other:?}"
+                        )
+                    }
+                }
+                break;
+            }
+        }
+    }
+
+    /// A named pipe server end shared between the read and write transports.
+    ///
+    /// Both transports are driven from the same connection thread, so the
+    /// `RefCell` is never borrowed twice at once.
+    struct SharedPipe(Rc<RefCell<PipeServer>>);
+
+    impl Read for SharedPipe {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().read(buf)
+        }
+    }
+
+    impl Write for SharedPipe {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.0.borrow_mut().flush()
+        }
+    }
+
+    /// Connect to our own pipe to unblock a listener waiting for a client.
+    pub(crate) fn wake(path: &str) {
+        let _ = open(path);
+    }
+
+    /// Named pipes disappear once the last handle is closed; nothing to remove.
+    pub(crate) fn cleanup(_path: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Named pipes have no permission bits, so they are created in place.
+    pub(crate) fn stage(path: &str) -> Result<String, Error> {
+        Ok(path.to_string())
+    }
+
+    /// Named pipes have no permission bits; the mode is ignored.
+    pub(crate) fn publish(
+        _staged: &str,
+        _path: &str,
+        _mode: u32,
+        _timeout: Duration,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::panic)] // Tests are allowed to panic on setup failures
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn test_buffered_uses_configured_capacities() {
+        let mut source: &[u8] = &[0; 32];
+        let mut sink = Vec::new();
+        let sizes = BufferSizes { read: 8, write: 4 };
+        let (mut read, mut write) = buffered(&mut source, &mut sink, sizes);
+
+        // Reading one byte fills the whole read buffer from the connection
+        read.read_exact(&mut [0; 1]).expect("failed to read");
+        drop(read);
+        assert_eq!(source.len(), 24);
+
+        // Writes stay buffered until the write buffer is full
+        write.write_all(&[1; 4]).expect("failed to write");
+        write.write_all(&[2; 1]).expect("failed to write");
+        drop(write);
+        assert_eq!(sink, [1; 4]);
+    }
+
+    #[test]
+    fn test_buffered_treats_zero_sizes_as_one() {
+        let mut source: &[u8] = &[0; 4];
+        let mut sink = Vec::new();
+        let (mut read, mut write) =
+            buffered(&mut source, &mut sink, BufferSizes { read: 0, write: 0 });
+
+        read.read_exact(&mut [0; 1]).expect("failed to read");
+        drop(read);
+        assert_eq!(source.len(), 3);
+
+        write.write_all(&[1, 2]).expect("failed to write");
+        drop(write);
+        assert_eq!(sink, [1]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_connect_to_unix_socket() {
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("test.sock");
+        let _listener = UnixListener::bind(&path).expect("failed to bind test socket");
+
+        let result = connect(&path.to_string_lossy(), Duration::ZERO);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_connect_rejects_overlong_path() {
+        let path = format!("/tmp/{}/osquery.em", "x".repeat(MAX_PATH_LEN));
+
+        let Err(e) = connect(&path, Duration::ZERO) else {
+            panic!("connecting to an overlong path should fail");
+        };
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            e.to_string(),
+            format!("socket path too long for platform, max {MAX_PATH_LEN} bytes")
+        );
+    }
+
+    #[test]
+    fn test_listen_path_leaves_room_for_uuid_suffix() {
+        let path = "x".repeat(MAX_PATH_LEN - LISTEN_SUFFIX_LEN);
+        assert!(check_listen_path_len(&path).is_ok());
+
+        let path = format!("{path}x");
+        assert!(check_path_len(&path).is_ok());
+        assert_eq!(
+            check_listen_path_len(&path).map_err(|e| e.to_string()),
+            Err(format!(
+                "socket path too long for platform, max {} bytes to leave room for the extension's .<uuid> suffix",
+                MAX_PATH_LEN - LISTEN_SUFFIX_LEN
+            ))
+        );
+    }
+
+    #[test]
+    fn test_connect_missing_endpoint_fails() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("missing.sock");
+
+        let result = connect(&path.to_string_lossy(), Duration::ZERO);
+        assert!(result.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cleanup_removes_socket_file() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("test.sock");
+        std::fs::File::create(&path).expect("failed to create socket file");
+
+        let result = cleanup(&path.to_string_lossy());
+        assert!(result.is_ok());
+        assert!(!path.exists());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_connect_to_named_pipe() {
+        use named_pipe::PipeOptions;
+
+        let path = format!(r"\\.\pipe\osquery-rust-test-{}", std::process::id());
+        let server = PipeOptions::new(&path)
+            .single()
+            .expect("failed to create named pipe");
+
+        let result = connect(&path, Duration::ZERO);
+        assert!(result.is_ok());
+
+        let pipe = server.wait();
+        assert!(pipe.is_ok());
+    }
+}
@@ -26,60 +26,34 @@
 
 #[allow(clippy::expect_used, clippy::panic)] // Integration tests can panic on infra failures
 mod tests {
-    use std::path::Path;
     use std::time::Duration;
 
     const SOCKET_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
     const SOCKET_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
-    /// Get the osquery extensions socket path from environment or common locations.
+    /// Get the osquery extensions socket path with `Server::discover_socket()`.
     /// Waits up to SOCKET_WAIT_TIMEOUT for socket to appear.
     fn get_osquery_socket() -> String {
-        let start = std::time::Instant::now();
+        use osquery_rust_ng::Server;
 
-        // Build list of paths to check
-        let env_path = std::env::var("OSQUERY_SOCKET").ok();
-        let home = std::env::var("HOME").unwrap_or_default();
+        let start = std::time::Instant::now();
 
         loop {
-            // Check environment variable first
-            if let Some(ref path) = env_path {
-                if Path::new(path).exists() {
-                    return path.clone();
-                }
-            }
-
-            // Try common locations on macOS and Linux
-            let common_paths = [
-                "/var/osquery/osquery.em".to_string(),
-                "/tmp/osquery.em".to_string(),
-                format!("{}/.osquery/shell.em", home),
-            ];
-
-            for path in &common_paths {
-                if Path::new(path).exists() {
-                    return path.clone();
-                }
+            if let Some(path) = Server::discover_socket() {
+                return path;
             }
 
             // Check timeout
             if start.elapsed() >= SOCKET_WAIT_TIMEOUT {
-                let checked_paths: Vec<&str> = env_path
-                    .as_ref()
-                    .map(|p| vec![p.as_str()])
-                    .unwrap_or_default()
-                    .into_iter()
-                    .chain(common_paths.iter().map(|s| s.as_str()))
-                    .collect();
-
                 panic!(
-                    "No osquery socket found after {:?}. Checked paths: {:?}\n\
+                    "No osquery socket found after {:?}. Checked OSQUERY_SOCKET, \
+                     /var/osquery/osquery.em, /tmp/osquery.em and ~/.osquery/shell.em\n\
                      \n\
                      To run integration tests:\n\
                      1. Start osqueryi: osqueryi --nodisable_extensions\n\
                      2. Set OSQUERY_SOCKET env var to the socket path\n\
                      3. Or run tests inside Docker container with osqueryd",
-                    SOCKET_WAIT_TIMEOUT, checked_paths
+                    SOCKET_WAIT_TIMEOUT
                 );
             }
 
@@ -250,7 +224,9 @@ mod tests {
 
         // Wrap table in TablePlugin and register
         let plugin = TablePlugin::from_readonly_table(TestLifecycleTable);
-        server.register_plugin(plugin);
+        server
+            .register_plugin(plugin)
+            .expect("Failed to register plugin");
 
         // Get stop handle before spawning thread
         let stop_handle = server.get_stop_handle();
@@ -325,7 +301,9 @@ mod tests {
             Server::new(Some("test_e2e"), &socket_path).expect("Failed to create Server");
 
         let plugin = TablePlugin::from_readonly_table(TestEndToEndTable);
-        server.register_plugin(plugin);
+        server
+            .register_plugin(plugin)
+            .expect("Failed to register plugin");
 
         let stop_handle = server.get_stop_handle();
 
@@ -425,7 +403,9 @@ mod tests {
         let mut server = Server::new(Some("test_logger_integration"), &socket_path)
             .expect("Failed to create Server");
 
-        server.register_plugin(Plugin::logger(logger));
+        server
+            .register_plugin(Plugin::logger(logger))
+            .expect("Failed to register plugin");
 
         let stop_handle = server.get_stop_handle();
 